@@ -0,0 +1,100 @@
+use crate::diagnostics::{suggest, Diagnostic};
+use crate::parser::Stmt;
+
+// what `@cfg` can ask about the build.
+pub const FLAGS: &[&str] = &[
+    "linux", "macos", "windows", "unix", "wasm32", "debug", "release", "test",
+];
+
+// what a program is being built for, which decides the functions `@cfg`
+// leaves in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    // the OS it'll run on, nothing for a WebAssembly module.
+    pub os: Option<&'static str>,
+    pub debug: bool,
+    // built by `uma test`.
+    pub test: bool,
+}
+
+impl Default for Cfg {
+    // a debug build to run where it's built.
+    fn default() -> Cfg {
+        Cfg {
+            os: Some(std::env::consts::OS),
+            debug: true,
+            test: false,
+        }
+    }
+}
+
+impl Cfg {
+    pub fn wasm32() -> Cfg {
+        Cfg {
+            os: None,
+            ..Cfg::default()
+        }
+    }
+
+    // whether `flag` holds for this build, nothing when there's no such flag.
+    pub fn holds(&self, flag: &str) -> Option<bool> {
+        match flag {
+            "linux" | "macos" | "windows" => Some(self.os == Some(flag)),
+            "unix" => Some(self.os.is_some_and(|os| os != "windows")),
+            "wasm32" => Some(self.os.is_none()),
+            "debug" => Some(self.debug),
+            "release" => Some(!self.debug),
+            "test" => Some(self.test),
+            _ => None,
+        }
+    }
+}
+
+// `program` without the functions and types whose `@cfg`s don't all hold
+// for `cfg`.
+// it's done before anything else looks at the program, so what's left out
+// doesn't have to type-check, like an external whose header is only on
+// another OS.
+pub fn strip(program: Vec<Stmt>, cfg: &Cfg) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+    let mut errors = vec![];
+    let mut kept = vec![];
+
+    for stmt in program {
+        let mut holds = true;
+
+        if let Stmt::Function { attributes, .. } | Stmt::Type { attributes, .. } = &stmt {
+            for attribute in attributes.iter().filter(|attr| attr.name == "cfg") {
+                let Some(flag) = &attribute.value else {
+                    // the type checker says it needs one.
+                    continue;
+                };
+
+                match cfg.holds(flag) {
+                    Some(true) => (),
+                    Some(false) => holds = false,
+                    None => errors.push(
+                        Diagnostic::error(
+                            "UnknownCfg",
+                            attribute.token.span,
+                            format!("Unknown `@cfg` flag `{}`", flag),
+                        )
+                        .with_suggestion(suggest(flag, FLAGS.iter().copied()))
+                        .with_note(format!("the flags are `{}`", FLAGS.join("`, `"))),
+                    ),
+                }
+            }
+        }
+
+        if holds {
+            kept.push(stmt);
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(kept),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests;
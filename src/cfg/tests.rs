@@ -0,0 +1,57 @@
+use super::*;
+
+use crate::parse_str;
+
+// the functions of `src` that are left for `cfg`.
+fn kept(src: &str, cfg: &Cfg) -> Vec<String> {
+    strip(parse_str(src).unwrap(), cfg)
+        .unwrap()
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+const SRC: &str = r#"
+func always() {}
+func linux() @cfg("linux") {}
+func windows() @cfg("windows") {}
+func unix_debug() @cfg("unix") @cfg("debug") {}
+func release() @cfg("release") {}
+func test() @cfg("test") {}
+"#;
+
+#[test]
+fn functions_are_kept_for_their_build() {
+    let linux = Cfg {
+        os: Some("linux"),
+        ..Cfg::default()
+    };
+    assert_eq!(kept(SRC, &linux), ["always", "linux", "unix_debug"]);
+
+    let windows = Cfg {
+        os: Some("windows"),
+        debug: false,
+        test: true,
+    };
+    assert_eq!(
+        kept(SRC, &windows),
+        ["always", "windows", "release", "test"]
+    );
+
+    assert_eq!(kept(SRC, &Cfg::wasm32()), ["always"]);
+}
+
+#[test]
+fn unknown_flags_are_errors() {
+    let errors = strip(
+        parse_str(r#"func f() @cfg("linx") {}"#).unwrap(),
+        &Cfg::default(),
+    )
+    .unwrap_err();
+
+    assert_eq!(errors[0].message, "Unknown `@cfg` flag `linx`");
+    assert_eq!(errors[0].suggestion.as_deref(), Some("linux"));
+}
@@ -4,3 +4,4 @@ mod parser;
 mod utils;
 
 pub use parser::{Action, Arg, ArgParser};
+pub use utils::ArgMatches;
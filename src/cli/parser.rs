@@ -1,9 +1,8 @@
 use std::{env, process};
 
-use crate::{
-    cli::utils::{ArgMatches, ArgValue},
-    colors::*,
-};
+use uma::colors::*;
+
+use crate::cli::utils::{ArgMatches, ArgValue};
 
 #[derive(PartialEq)]
 pub enum Action {
@@ -12,6 +11,7 @@ pub enum Action {
     StoreValue,
     Append,
     Positional,
+    Trailing,
 }
 
 pub struct Arg {
@@ -22,6 +22,7 @@ pub struct Arg {
     pub(crate) action: Action,
     pub(crate) is_required: bool,
     pub(crate) default_val: Option<String>,
+    pub(crate) choices: Vec<String>,
 }
 
 impl Arg {
@@ -34,6 +35,7 @@ impl Arg {
             action: Action::StoreValue,
             is_required: false,
             default_val: None,
+            choices: Vec::new(),
         }
     }
 
@@ -66,22 +68,31 @@ impl Arg {
         self.default_val = Some(val.to_string());
         self
     }
+
+    pub fn choices(mut self, choices: &[&str]) -> Self {
+        self.choices = choices.iter().map(|c| c.to_string()).collect();
+        self
+    }
 }
 
 pub struct ArgParser {
     program_name: String,
+    parent_name: Option<String>,
     description: String,
     version: String,
     args: Vec<Arg>,
+    subcommands: Vec<ArgParser>,
 }
 
 impl ArgParser {
     pub fn new(name: &str) -> Self {
         Self {
             program_name: name.to_string(),
+            parent_name: None,
             description: String::new(),
             version: String::new(),
             args: Vec::new(),
+            subcommands: Vec::new(),
         }
     }
 
@@ -99,12 +110,27 @@ impl ArgParser {
         self.args.push(arg);
     }
 
+    pub fn add_subcommand(&mut self, mut cmd: ArgParser) {
+        cmd.parent_name = Some(self.program_name.clone());
+        cmd.version = self.version.clone();
+
+        self.subcommands.push(cmd);
+    }
+
+    pub fn has_subcommand(&self, name: &str) -> bool {
+        self.subcommands.iter().any(|cmd| cmd.program_name == name)
+    }
+
     pub fn parse(&self) -> ArgMatches {
         let args: Vec<String> = env::args().skip(1).collect();
         self.parse_from(args)
     }
 
     pub(crate) fn parse_from(&self, raw_args: Vec<String>) -> ArgMatches {
+        if !self.subcommands.is_empty() {
+            return self.parse_subcommand(raw_args);
+        }
+
         let mut matches = ArgMatches::new();
         let mut iter = raw_args.into_iter();
 
@@ -131,15 +157,57 @@ impl ArgParser {
             }
 
             if token == "--" {
+                if let Some(arg) = self.args.iter().find(|a| a.action == Action::Trailing) {
+                    matches
+                        .values
+                        .insert(arg.name.clone(), ArgValue::List(iter.by_ref().collect()));
+
+                    break;
+                }
+
                 force_positionals = true;
                 continue;
             }
 
-            if token.starts_with('-') && !force_positionals {
-                let matched_arg = self.args.iter().find(|a| {
+            // a lone `-` is a value, standing in for stdin or stdout.
+            if token.starts_with('-') && token != "-" && !force_positionals {
+                // `--long=value` is accepted as an alternative to `--long value`.
+                let (token, inline_val) = match token.split_once('=') {
+                    Some((flag, val)) if token.starts_with("--") => {
+                        (flag.to_string(), Some(val.to_string()))
+                    }
+                    _ => (token, None),
+                };
+
+                let mut matched_arg = self.args.iter().find(|a| {
                     a.short.as_deref() == Some(&token) || a.long.as_deref() == Some(&token)
                 });
 
+                // short flags can also take their value attached, like `-O2`.
+                let (token, inline_val) = match matched_arg {
+                    None if inline_val.is_none() && !token.starts_with("--") => {
+                        let attached = self.args.iter().find_map(|a| {
+                            let short = a.short.as_deref()?;
+                            let takes_value =
+                                matches!(a.action, Action::StoreValue | Action::Append);
+
+                            match token.strip_prefix(short) {
+                                Some(val) if takes_value && !val.is_empty() => Some((a, val)),
+                                _ => None,
+                            }
+                        });
+
+                        match attached {
+                            Some((arg, val)) => {
+                                matched_arg = Some(arg);
+                                (arg.short.clone().unwrap(), Some(val.to_string()))
+                            }
+                            None => (token, None),
+                        }
+                    }
+                    _ => (token, inline_val),
+                };
+
                 match matched_arg {
                     Some(arg) => match arg.action {
                         Action::StoreTrue => {
@@ -153,26 +221,30 @@ impl ArgParser {
                                 .insert(arg.name.clone(), ArgValue::Bool(false));
                         }
                         Action::StoreValue => {
-                            let val = iter.next().unwrap_or_else(|| {
+                            let val = inline_val.or_else(|| iter.next()).unwrap_or_else(|| {
                                 self.error_and_exit(&format!(
                                     "Argument '{}' requires a value.",
                                     token
                                 ));
                             });
 
+                            self.validate_choice(arg, &val);
+
                             matches
                                 .values
                                 .insert(arg.name.clone(), ArgValue::String(val));
                         }
 
                         Action::Append => {
-                            let val = iter.next().unwrap_or_else(|| {
+                            let val = inline_val.or_else(|| iter.next()).unwrap_or_else(|| {
                                 self.error_and_exit(&format!(
                                     "Argument '{}' requires a value.",
                                     token
                                 ));
                             });
 
+                            self.validate_choice(arg, &val);
+
                             let list = matches
                                 .values
                                 .entry(arg.name.clone())
@@ -182,7 +254,7 @@ impl ArgParser {
                                 vec.push(val);
                             }
                         }
-                        Action::Positional => unreachable!(),
+                        Action::Positional | Action::Trailing => unreachable!(),
                     },
                     None => self.error_and_exit(&format!("Unknown argument: {}", token)),
                 }
@@ -197,7 +269,7 @@ impl ArgParser {
             }
         }
 
-        if matches.values.len() == 0 {
+        if matches.values.is_empty() && !self.args.is_empty() {
             self.print_help();
             process::exit(0)
         }
@@ -211,33 +283,95 @@ impl ArgParser {
         matches
     }
 
+    fn parse_subcommand(&self, raw_args: Vec<String>) -> ArgMatches {
+        let mut iter = raw_args.into_iter();
+
+        let name = match iter.next() {
+            Some(name) => name,
+            None => {
+                self.print_help();
+                process::exit(0)
+            }
+        };
+
+        if name == "-h" || name == "--help" {
+            self.print_help();
+            process::exit(0)
+        }
+
+        if name == "-v" || name == "--version" {
+            println!("{}", self.version);
+            process::exit(0)
+        }
+
+        let cmd = self
+            .subcommands
+            .iter()
+            .find(|cmd| cmd.program_name == name)
+            .unwrap_or_else(|| self.error_and_exit(&format!("Unknown command: {}", name)));
+
+        let mut matches = ArgMatches::new();
+        matches.subcommand = Some((name, Box::new(cmd.parse_from(iter.collect()))));
+
+        matches
+    }
+
     fn print_help(&self) {
         if !self.description.is_empty() {
             println!("{}\n", self.description);
         }
 
+        let usage_name = match &self.parent_name {
+            Some(parent) => format!("{} {}", parent, self.program_name),
+            None => self.program_name.clone(),
+        };
+
         println!("{}", "USAGE:".green());
-        println!("    {} [OPTIONS] [ARGS]\n", self.program_name.blue());
+
+        if !self.subcommands.is_empty() {
+            println!("    {} <COMMAND> [OPTIONS] [ARGS]\n", usage_name.blue());
+
+            println!("{}", "COMMANDS:".green());
+            for cmd in &self.subcommands {
+                println!("    {:<19} {}", cmd.program_name.blue(), cmd.description);
+            }
+
+            return;
+        }
+
+        println!("    {} [OPTIONS] [ARGS]\n", usage_name.blue());
 
         println!("{}", "OPTIONS:".green());
-        for arg in self.args.iter().filter(|a| a.action != Action::Positional) {
-            let short = arg.short.as_deref().unwrap_or("  ");
+        for arg in self
+            .args
+            .iter()
+            .filter(|a| a.action != Action::Positional && a.action != Action::Trailing)
+        {
+            let short = match &arg.short {
+                Some(short) => format!("{},", short.blue()),
+                None => "   ".to_string(),
+            };
             let long = arg.long.as_deref().unwrap_or("");
-            let val_hint = if arg.action == Action::StoreValue || arg.action == Action::Append {
-                " <VAL>"
+            let val_hint = if arg.action != Action::StoreValue && arg.action != Action::Append {
+                String::new()
+            } else if arg.choices.is_empty() {
+                String::from(" <VAL>")
             } else {
-                ""
+                format!(" <{}>", arg.choices.join("|"))
             };
 
             println!(
-                "    {}, {:<15} {}",
-                short.blue(),
+                "    {} {:<15} {}",
+                short,
                 format!("{}{}", long.blue(), val_hint),
                 arg.help
             );
         }
 
-        let has_positionals = self.args.iter().any(|a| a.action == Action::Positional);
+        let has_positionals = self
+            .args
+            .iter()
+            .any(|a| a.action == Action::Positional || a.action == Action::Trailing);
         if has_positionals {
             println!("\n{}", "ARGS:".green());
             for arg in self.args.iter().filter(|a| a.action == Action::Positional) {
@@ -249,6 +383,25 @@ impl ArgParser {
                     req
                 );
             }
+
+            for arg in self.args.iter().filter(|a| a.action == Action::Trailing) {
+                println!(
+                    "    {:<19} {}",
+                    format!("-- <{}>...", arg.name).blue(),
+                    arg.help
+                );
+            }
+        }
+    }
+
+    fn validate_choice(&self, arg: &Arg, val: &str) {
+        if !arg.choices.is_empty() && !arg.choices.iter().any(|c| c == val) {
+            self.error_and_exit(&format!(
+                "Invalid value '{}' for '{}', expected one of: {}",
+                val,
+                arg.name,
+                arg.choices.join(", ")
+            ));
         }
     }
 
@@ -8,15 +8,23 @@ pub enum ArgValue {
 
 pub struct ArgMatches {
     pub(crate) values: HashMap<String, ArgValue>,
+    pub(crate) subcommand: Option<(String, Box<ArgMatches>)>,
 }
 
 impl ArgMatches {
     pub(crate) fn new() -> Self {
         Self {
             values: HashMap::new(),
+            subcommand: None,
         }
     }
 
+    pub fn subcommand(&self) -> Option<(&str, &ArgMatches)> {
+        self.subcommand
+            .as_ref()
+            .map(|(name, matches)| (name.as_str(), matches.as_ref()))
+    }
+
     pub fn get_string(&self, name: &str) -> Option<&String> {
         match self.values.get(name) {
             Some(ArgValue::String(s)) => Some(s),
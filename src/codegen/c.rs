@@ -1,259 +1,1704 @@
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::{self, Write},
-    process::{self, Command},
+    collections::{BTreeSet, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Instant,
 };
 
+use super::{
+    current_dir, emitter::Emitter, io, mangle::mangle, Artifact, Backend, CodegenError,
+    CodegenOptions, Library,
+};
 use crate::{
-    codegen::CodegenError,
-    parser::{Block, Expr, Stmt},
+    ir::{self, callgraph::exprs, Stmt},
+    lexer::{Span, Token, TokenKind},
+    timings::Timings,
+    typeck::{interpolation, Builtin, FunctionAttribute, Type, TypedExpr, TypedStmt},
 };
 
-pub(crate) struct CBackend {
-    headers: Vec<String>,
-    function_types: HashMap<String, String>,
+// the compilers tried, in order, when neither `--cc` nor `UMA_CC` is set.
+#[cfg(windows)]
+const COMPILERS: &[&str] = &["cc", "clang", "gcc", "cl"];
+#[cfg(not(windows))]
+const COMPILERS: &[&str] = &["cc", "clang", "gcc"];
+
+// the runtime every program is linked against. the declarations go at the
+// top of the generated C, the definitions are compiled next to it.
+const RUNTIME_HEADER: &str = include_str!("runtime/uma_rt.h");
+const RUNTIME: &str = include_str!("runtime/uma_rt.c");
+
+// how the generated C is compiled. `compiler` takes priority over `UMA_CC`
+// and the compilers on the `PATH`.
+#[derive(Debug, PartialEq, Clone, Default, Hash)]
+pub struct CompilerOptions {
+    pub compiler: Option<String>,
+    pub opt_level: Option<String>,
+    pub cflags: Vec<String>,
+    pub ldflags: Vec<String>,
+    // build with debug info, for gdb and the like.
+    pub debug: bool,
+    // leave out the checks the program would otherwise make as it runs,
+    // like the bounds of every index.
+    pub release: bool,
+    // check integer arithmetic for overflow and division by zero.
+    pub checks: bool,
+}
+
+#[derive(Default)]
+pub struct CBackend {
+    options: CompilerOptions,
+    // sorted, so the same program always includes them in the same order.
+    headers: BTreeSet<String>,
+    // the declarations of the temporaries the function being generated
+    // needs, which go at the top of its body.
+    temps: Vec<String>,
+    out: Emitter,
+    // the files the program's spans point into, for `#line`.
+    files: Vec<String>,
+    // the functions C declares, which take and give `char*`s, not strings.
+    externals: HashSet<String>,
+    // the strings in each scope of the function being generated, let go of
+    // when it ends.
+    scopes: Vec<Vec<String>>,
+    // the temporaries holding strings made for the statement being
+    // generated, and how many the function has declared.
+    pending: Vec<String>,
+    strings: usize,
+    timings: Timings,
+    // building a library, where only what's exported is seen outside it.
+    library: bool,
+    // generating `main`, which returns an `int` whatever it's declared as.
+    main: bool,
+}
+
+// the declaration in the runtime's header that starts with `start`, with the
+// comment above it.
+fn runtime_declaration(start: &str) -> String {
+    let lines = RUNTIME_HEADER.lines().collect::<Vec<_>>();
+    let at = lines
+        .iter()
+        .position(|line| line.starts_with(start))
+        .unwrap();
+    let from = lines[..at]
+        .iter()
+        .rposition(|line| !line.starts_with("//"))
+        .map_or(0, |line| line + 1);
+    let end = at
+        + lines[at..]
+            .iter()
+            .position(|line| !line.starts_with(char::is_whitespace) && line.ends_with(';'))
+            .unwrap();
+
+    lines[from..=end].join("\n")
+}
+
+fn in_path(program: &str) -> bool {
+    let path = match env::var_os("PATH") {
+        Some(path) => path,
+        None => return false,
+    };
+
+    env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || candidate.with_extension("exe").is_file()
+    })
+}
+
+impl Backend for CBackend {
+    fn name(&self) -> &str {
+        "c"
+    }
+
+    fn generate(
+        &mut self,
+        program: &[Stmt],
+        opts: &CodegenOptions,
+    ) -> Result<Artifact, CodegenError> {
+        self.timings = Timings::new();
+
+        let start = Instant::now();
+        characters(program)?;
+
+        self.library = opts.library.is_some();
+        let source = self.source(program, &opts.files);
+        self.timings.record("codegen", start.elapsed());
+
+        if opts.emit_source {
+            return Ok(Artifact::Source(source));
+        }
+
+        let links = Self::links(program);
+        let built = self.compile(source, &links, &opts.output, opts.timings, opts.library)?;
+
+        Ok(match opts.library {
+            Some(_) => Artifact::Library(built),
+            None => Artifact::Executable(built),
+        })
+    }
+
+    fn timings(&self) -> Timings {
+        self.timings.clone()
+    }
 }
 
 impl CBackend {
-    pub fn generate(exprs: Vec<Stmt>, out: impl ToString) -> Result<String, CodegenError> {
-        let mut code = String::new();
+    pub fn new(options: CompilerOptions) -> CBackend {
+        CBackend {
+            options,
+            ..CBackend::default()
+        }
+    }
+
+    fn compiler(&self) -> Result<String, CodegenError> {
+        let compiler = self.options.compiler.clone();
+
+        if let Some(cc) = compiler.or_else(|| env::var("UMA_CC").ok()) {
+            return Ok(cc);
+        }
+
+        match COMPILERS.iter().find(|cc| in_path(cc)) {
+            Some(cc) => Ok(cc.to_string()),
+            None => CodegenError::new(
+                "No C compiler found",
+                vec![
+                    format!("looked for {} on the `PATH`", COMPILERS.join(", ")),
+                    "install one, or point `--cc` or `UMA_CC` at a compiler".to_string(),
+                ],
+            ),
+        }
+    }
+
+    // the definitions of what the runtime declares, what has to be compiled
+    // along with the generated C.
+    pub fn runtime() -> String {
+        format!("{}\n{}", RUNTIME_HEADER, RUNTIME)
+    }
+
+    // `source` with the runtime's definitions after it, a single file that
+    // compiles on its own.
+    pub fn standalone(source: &str) -> String {
+        format!("{}\n{}", source, RUNTIME)
+    }
+
+    pub fn generate_source(stmts: &[TypedStmt]) -> String {
+        Self::generate_mapped_source(&ir::lower(stmts), &[])
+    }
+
+    // like `generate_source` for a program that's already been lowered, with
+    // a `#line` before every statement so the C compiler and debuggers point
+    // at the `files` the program came from.
+    pub fn generate_mapped_source(stmts: &[Stmt], files: &[PathBuf]) -> String {
+        CBackend::default().source(stmts, files)
+    }
+
+    // a header declaring the `@export`ed functions of `stmts`, for C that
+    // links against the library they're built into. `name` is what it's
+    // included as, for its include guard. when they take or give strings,
+    // `UmaString` is declared along with them, and so are `uma_cstr` and
+    // `uma_release` to make and let go of one. so are the `@opaque` types,
+    // in case they're pointed to.
+    pub fn header(stmts: &[TypedStmt], name: &str) -> String {
+        let stmts = mangle(&ir::lower(stmts));
+        let mut backend = CBackend::default();
+        let mut declarations = vec![];
+        let mut strings = false;
+
+        for stmt in &stmts {
+            if let Stmt::Opaque { .. } = stmt {
+                backend.stmt(stmt);
+            }
+
+            if let Stmt::Function {
+                name,
+                return_type,
+                args,
+                attributes,
+                ..
+            } = stmt
+            {
+                if !attributes.contains(&FunctionAttribute::Export) {
+                    continue;
+                }
+
+                strings |= std::iter::once(return_type)
+                    .chain(args.iter().map(|(_, ty)| ty))
+                    .any(|ty| Self::split_type(ty).0 == "UmaString");
+
+                let signature = backend.signature(name, return_type, args, attributes);
+                declarations.push(format!("{};", signature));
+            }
+        }
+
+        let guard = name
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_uppercase(),
+                false => '_',
+            })
+            .collect::<String>();
+
+        let mut out = format!("#ifndef UMA_{0}\n#define UMA_{0}\n", guard);
+
+        if !backend.headers.is_empty() {
+            out.push_str(&format!(
+                "\n{}\n",
+                Vec::from_iter(backend.headers).join("\n")
+            ));
+        }
+
+        out.push_str("\n#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+
+        if strings {
+            for start in [
+                "typedef struct {",
+                "UmaString uma_cstr(",
+                "UmaString uma_release(",
+            ] {
+                out.push_str(&format!("{}\n\n", runtime_declaration(start)));
+            }
+        }
+
+        let types = std::mem::take(&mut backend.out).finish();
+
+        if !types.is_empty() {
+            out.push_str(&format!("{}\n", types));
+        }
+
+        for declaration in declarations {
+            out.push_str(&format!("{}\n", declaration));
+        }
+
+        out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+        out
+    }
+
+    // the C for `stmts`, generated with this backend's options by a backend
+    // of its own, so nothing's left over from the last program.
+    fn source(&self, stmts: &[Stmt], files: &[PathBuf]) -> String {
+        let stmts = &mangle(stmts);
         let mut backend = CBackend {
-            headers: vec![],
-            function_types: HashMap::new(),
+            options: self.options.clone(),
+            library: self.library,
+            files: files
+                .iter()
+                .map(|file| file.to_string_lossy().to_string())
+                .collect(),
+            externals: stmts
+                .iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::External { name, .. } => Some(name.clone()),
+                    _ => None,
+                })
+                .collect(),
+            ..CBackend::default()
         };
 
-        for expr in &exprs {
+        // the types go before anything that could point to them, then the
+        // globals and constants so every function can use them.
+        let (types, stmts): (Vec<_>, Vec<_>) = stmts
+            .iter()
+            .partition(|stmt| matches!(stmt, Stmt::Opaque { .. }));
+
+        for stmt in &types {
+            backend.stmt(stmt);
+        }
+
+        if types
+            .iter()
+            .any(|stmt| matches!(stmt, Stmt::Opaque { header: None, .. }))
+        {
+            backend.out.line("");
+        }
+
+        let (globals, rest): (Vec<_>, Vec<_>) = stmts
+            .into_iter()
+            .partition(|stmt| matches!(stmt, Stmt::Variable { .. } | Stmt::Const { .. }));
+
+        for stmt in &globals {
+            backend.stmt(stmt);
+        }
+
+        if !globals.is_empty() {
+            backend.out.line("");
+        }
+
+        // every function is declared before any is defined, so they can call
+        // each other whatever order they're in.
+        for stmt in &rest {
             if let Stmt::Function {
-                name, return_type, ..
-            } = expr
+                name,
+                return_type,
+                args,
+                attributes,
+                ..
+            } = stmt
             {
-                let rt = return_type.clone().unwrap_or("void".to_string());
-                backend.function_types.insert(name.clone(), rt);
+                let signature = backend.signature(name, return_type, args, attributes);
+                backend.out.line(&format!("{};", signature));
             }
         }
 
-        for expr in exprs {
-            code.push_str(&backend.stmt(&expr, true)?);
+        for stmt in rest {
+            if matches!(stmt, Stmt::Function { .. }) {
+                backend.out.line("");
+            }
+
+            backend.stmt(stmt);
         }
 
-        Ok(backend.compile(
-            format!("{}\n\n{}", backend.headers.join("\n"), code),
-            out.to_string(),
-        ))
+        let code = std::mem::take(&mut backend.out).finish();
+
+        format!(
+            "{}\n\n{}\n{}",
+            Vec::from_iter(backend.headers).join("\n"),
+            RUNTIME_HEADER,
+            code
+        )
     }
 
-    pub fn generate_and_run(exprs: Vec<Stmt>, out: &String) -> Result<(), CodegenError> {
-        let result = Self::generate(exprs, &out)?;
+    // the libraries every `@link` asks for, each once, and `m` when `**` or
+    // the math builtins need it.
+    fn links(program: &[Stmt]) -> Vec<String> {
+        let mut links: Vec<String> = vec![];
+        let mut pow = false;
 
-        let output = Command::new(result)
-            .output()
-            .expect("failed to execute process");
+        exprs(program, &mut |expr| {
+            pow |= matches!(expr, TypedExpr::Binary { op, .. } if op.kind == TokenKind::Expo)
+                || matches!(expr, TypedExpr::Builtin { builtin, .. } if builtin.is_math());
+        });
 
-        io::stdout().write_all(&output.stdout).unwrap();
-        io::stdout().flush().unwrap();
+        if pow {
+            links.push("m".to_string());
+        }
 
-        Ok(())
+        for stmt in program {
+            if let Stmt::External { links: libs, .. } = stmt {
+                for lib in libs {
+                    if !links.contains(lib) {
+                        links.push(lib.clone());
+                    }
+                }
+            }
+        }
+
+        links
     }
 
-    fn compile(&self, source: String, out: String) -> String {
-        let tmp_dir = std::env::temp_dir();
+    // compiles `source` with the runtime into an executable at `out`, or
+    // with `library` a library of that kind.
+    fn compile(
+        &mut self,
+        source: String,
+        links: &[String],
+        out: &Path,
+        split: bool,
+        library: Option<Library>,
+    ) -> Result<PathBuf, CodegenError> {
+        let cc = self.compiler()?;
+
+        let output_fp = current_dir()?.join(out);
+        let c_buffer_fp = output_fp.with_extension("c");
 
-        let c_buffer_fp = tmp_dir.join("output.c");
-        let output_fp = std::env::current_dir().unwrap().join(out);
+        if let Some(parent) = output_fp.parent() {
+            io(fs::create_dir_all(parent), "create", parent)?;
+        }
 
-        let mut c_file = File::create(&c_buffer_fp).unwrap();
-        c_file.write_all(source.as_bytes()).unwrap();
+        io(fs::write(&c_buffer_fp, source), "write", &c_buffer_fp)?;
 
-        let output = Command::new("gcc")
-            .arg(&c_buffer_fp)
-            .arg("-o")
-            .arg(&output_fp)
-            .output()
-            .unwrap();
+        let runtime_fp = output_fp.with_extension("rt.c");
+        io(
+            fs::write(&runtime_fp, Self::runtime()),
+            "write",
+            &runtime_fp,
+        )?;
+
+        let options = &self.options;
+        let sources = [&c_buffer_fp, &runtime_fp];
+        let objects = sources.map(|source| source.with_extension("o"));
+
+        // the phase each command is timed as.
+        let mut steps = vec![];
+
+        // msvc takes its own style of flags.
+        if Path::new(&cc).file_stem().is_some_and(|stem| stem == "cl") {
+            let compile = || {
+                let mut command = Command::new(&cc);
+                command.arg("/nologo");
+
+                match options.opt_level.as_deref() {
+                    Some("0") => command.arg("/Od"),
+                    Some(_) => command.arg("/O2"),
+                    None => &mut command,
+                };
+
+                if options.debug {
+                    command.arg("/Zi");
+                }
+
+                command.args(&options.cflags);
+                command
+            };
+
+            // a static library is only the objects, put together by `lib`.
+            if library == Some(Library::Static) {
+                for (source, object) in sources.iter().zip(&objects) {
+                    let mut command = compile();
+                    command
+                        .arg("/c")
+                        .arg(source)
+                        .arg(format!("/Fo:{}", object.display()));
+                    steps.push(("cc", command));
+                }
+
+                let mut command = Command::new("lib");
+                command
+                    .arg("/nologo")
+                    .arg(format!("/OUT:{}", output_fp.display()))
+                    .args(&objects);
+                steps.push(("link", command));
+            } else {
+                let mut command = compile();
+
+                if library == Some(Library::Shared) {
+                    command.arg("/LD");
+                }
+
+                command
+                    .arg(&c_buffer_fp)
+                    .arg(&runtime_fp)
+                    .arg(format!("/Fe:{}", output_fp.display()));
+
+                if !options.ldflags.is_empty() || !links.is_empty() {
+                    // the math functions are part of msvc's C library.
+                    command.arg("/link").args(&options.ldflags).args(
+                        links
+                            .iter()
+                            .filter(|lib| *lib != "m")
+                            .map(|lib| format!("{}.lib", lib)),
+                    );
+                }
+
+                steps.push(("cc", command));
+            }
+        } else {
+            let compile = || {
+                let mut command = Command::new(&cc);
+
+                if let Some(level) = &options.opt_level {
+                    command.arg(format!("-O{}", level));
+                }
+
+                if options.debug {
+                    command.arg("-g");
+                }
+
+                // a library can be linked into a shared library or an
+                // executable that's loaded anywhere.
+                if library.is_some() {
+                    command.arg("-fPIC");
+                }
+
+                command.args(&options.cflags);
+                command
+            };
+
+            // with `split`, each file is compiled on its own and linked in a
+            // step of its own, so each can be timed. a static library is
+            // only the objects, so they're always compiled apart.
+            let split = split || library == Some(Library::Static);
+
+            if split {
+                for (source, object) in sources.iter().zip(&objects) {
+                    let mut command = compile();
+                    command.arg("-c").arg(source).arg("-o").arg(object);
+                    steps.push(("cc", command));
+                }
+            }
+
+            if library == Some(Library::Static) {
+                // an old archive would keep objects that aren't in this one.
+                let _ = fs::remove_file(&output_fp);
+
+                let mut command = Command::new(env::var("AR").unwrap_or_else(|_| "ar".into()));
+                command.arg("rcs").arg(&output_fp).args(&objects);
+                steps.push(("link", command));
+            } else {
+                let mut link = match split {
+                    true => {
+                        let mut command = Command::new(&cc);
+                        command.args(&options.cflags).args(&objects);
+                        command
+                    }
+                    false => {
+                        let mut command = compile();
+                        command.arg(&c_buffer_fp).arg(&runtime_fp);
+                        command
+                    }
+                };
+
+                if library == Some(Library::Shared) {
+                    link.arg("-shared");
+                }
+
+                // libraries have to come after the sources that use them.
+                link.arg("-o")
+                    .arg(&output_fp)
+                    .args(&options.ldflags)
+                    .args(links.iter().map(|lib| format!("-l{}", lib)));
+
+                steps.push((if split { "link" } else { "cc" }, link));
+            }
+        }
+
+        let result = steps.into_iter().try_for_each(|(phase, mut command)| {
+            let program = command.get_program().to_string_lossy().to_string();
+            self.run_compiler(&program, &mut command, phase)
+        });
+
+        // the runtime isn't the program's, so it's gone even when the
+        // program's C is kept around to look at.
+        let cleanup = io(fs::remove_file(&runtime_fp), "remove", &runtime_fp).and_then(|_| {
+            objects
+                .iter()
+                .filter(|object| object.is_file())
+                .try_for_each(|object| io(fs::remove_file(object), "remove", object))
+        });
+
+        // the C source is kept around on failure so it can be inspected.
+        if let Err(mut err) = result {
+            err.notes.push(format!(
+                "the generated C was kept at {}",
+                c_buffer_fp.display()
+            ));
+
+            return Err(err);
+        }
+
+        cleanup?;
+        io(fs::remove_file(&c_buffer_fp), "remove", &c_buffer_fp)?;
+
+        Ok(output_fp)
+    }
+
+    // runs one of the commands building the program, timed as `phase`.
+    fn run_compiler(
+        &mut self,
+        cc: &str,
+        command: &mut Command,
+        phase: &str,
+    ) -> Result<(), CodegenError> {
+        let start = Instant::now();
+        let output = command.output();
+        self.timings.record(phase, start.elapsed());
+
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => {
+                return CodegenError::new(
+                    format!("Couldn't run the C compiler `{}`", cc),
+                    vec![err.to_string()],
+                )
+            }
+        };
 
         if !output.status.success() {
-            io::stderr().write_all(&output.stderr).unwrap();
-            io::stderr().flush().unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let action = match phase {
+                "link" => "link",
+                _ => "compile",
+            };
 
-            process::exit(1);
+            return CodegenError::new(
+                format!("`{}` failed to {} the generated C", cc, action),
+                vec![stderr.trim_end().to_string()],
+            );
         }
 
-        return output_fp.display().to_string();
+        Ok(())
     }
 
     fn add_header_if_not_exist(&mut self, header: String) {
-        if !self.headers.contains(&header) {
-            self.headers.push(header);
+        self.headers.insert(header);
+    }
+
+    fn concat(&mut self, lhs: &TypedExpr, rhs: &TypedExpr) -> String {
+        let (lhs, rhs) = (self.borrow(lhs), self.borrow(rhs));
+        format!("uma_concat({}, {})", lhs, rhs)
+    }
+
+    // builds a string with `snprintf`, `args` being the format and its values.
+    fn format(&mut self, args: Vec<String>) -> String {
+        format!("uma_format({})", args.join(", "))
+    }
+
+    // whether `expr` makes a string with a reference of its own, which
+    // whoever ends up with it has to let go of.
+    fn owned(&self, expr: &TypedExpr) -> bool {
+        if expr.ty() != Type::String {
+            return false;
+        }
+
+        match expr {
+            TypedExpr::Call { name, .. } => !self.externals.contains(name),
+            TypedExpr::Index { target, .. } => target.ty() == Type::String,
+            TypedExpr::Binary { .. }
+            | TypedExpr::Builtin { .. }
+            | TypedExpr::Interpolation(_)
+            | TypedExpr::If { .. }
+            | TypedExpr::Match { .. } => true,
+            _ => false,
+        }
+    }
+
+    // `expr` with a reference of its own, for something that keeps it. a
+    // literal is never freed, and neither is a global, so neither needs one.
+    fn own(&mut self, expr: &TypedExpr) -> String {
+        let code = self.expr(expr);
+
+        if expr.ty() != Type::String
+            || self.owned(expr)
+            || self.scopes.is_empty()
+            || matches!(expr, TypedExpr::String(_))
+        {
+            return code;
+        }
+
+        format!("uma_retain({})", code)
+    }
+
+    // `expr` for something that only looks at it. a string made just for
+    // that is put in a temporary, let go of once the statement's done.
+    fn borrow(&mut self, expr: &TypedExpr) -> String {
+        let code = self.expr(expr);
+
+        if !self.owned(expr) {
+            return code;
         }
+
+        let temp = self.temporary();
+        format!("({} = uma_replace({}, {}))", temp, temp, code)
     }
 
-    fn infer_type(&self, expr: &Stmt) -> Result<String, CodegenError> {
+    // `expr` as a `const char*`, for C.
+    fn c_str(&mut self, expr: &TypedExpr) -> String {
         match expr {
-            Stmt::Expr(expr) => match expr {
-                Expr::String(_) => Ok("char*".to_string()),
-                Expr::Number(_) => Ok("int".to_string()),
-                Expr::Binary { lhs, op: _, rhs } => {
-                    let l = self.infer_type(lhs);
-
-                    if l == self.infer_type(rhs) {
-                        return l;
-                    }
+            TypedExpr::String(value) => c_string(value),
+            expr => format!("{}.data", self.borrow(expr)),
+        }
+    }
 
-                    panic!("Mismatched types found.")
-                }
-                _ => Ok("auto".to_string()),
-            },
-            Stmt::Function {
-                name,
-                return_type,
-                args,
-                ..
-            } => {
-                let func_rt = return_type.clone().unwrap_or("void".to_string());
+    // a value handed to `printf`, a `Bool` as the string `true` or `false`.
+    fn printed(&mut self, expr: &TypedExpr) -> String {
+        match expr.ty() {
+            Type::String => self.c_str(expr),
+            Type::Bool => format!("({} ? \"true\" : \"false\")", self.expr(expr)),
+            _ => self.expr(expr),
+        }
+    }
 
-                let args_str = args
-                    .iter()
-                    .map(|(arg, typ)| format!("{} {}", typ.as_ref().unwrap(), arg))
-                    .collect::<Vec<_>>()
-                    .join(", ");
+    // a string temporary for the statement being generated. once it's done
+    // with, the next statement can use it again.
+    fn temporary(&mut self) -> String {
+        let temp = format!("uma_str_{}", self.pending.len());
+
+        if self.pending.len() == self.strings {
+            self.temps.push(format!("UmaString {} = {{0}};", temp));
+            self.strings += 1;
+        }
+
+        self.pending.push(temp.clone());
+        temp
+    }
+
+    // lets go of the temporaries made since there were `mark` of them.
+    fn flush(&mut self, mark: usize) {
+        for temp in self.pending.split_off(mark) {
+            self.out.line(&format!("{} = uma_release({});", temp, temp));
+        }
+    }
 
-                Ok(format!("{} {}({})", func_rt, name, args_str))
+    // the strings in a `name` of `ty`, each element of it when it's an array
+    // of them.
+    fn strings(name: &str, ty: &Type) -> Vec<String> {
+        match ty {
+            Type::String => vec![name.to_string()],
+            Type::Array(element, len) => (0..*len)
+                .flat_map(|i| Self::strings(&format!("{}[{}]", name, i), element))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    // has the strings in `name` let go of at the end of the scope it's
+    // declared in. a global's are never let go of.
+    fn hold(&mut self, name: &str, ty: &Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.extend(Self::strings(name, ty));
+        }
+    }
+
+    // `stmts` in a scope of their own, whose strings are let go of at its end.
+    fn scoped(&mut self, stmts: &[Stmt]) {
+        self.scopes.push(vec![]);
+        self.block(stmts);
+
+        let strings = self.scopes.pop().unwrap_or_default();
+
+        // there's no end to reach after a `return`, which lets go of them
+        // itself.
+        if !matches!(stmts.last(), Some(Stmt::Return(..))) {
+            for string in strings.iter().rev() {
+                self.out.line(&format!("uma_release({});", string));
             }
-            Stmt::Call { name, token, .. } => match self.function_types.get(name) {
-                Some(return_type) => Ok(return_type.clone()),
-                None => Err(CodegenError::new(
-                    format!("Call to undefined function '{}'", name),
-                    token.clone(),
-                )),
-            },
-            stmt => unreachable!("could not infer type for: {:?}", stmt),
         }
     }
 
-    fn block(&mut self, block: &Block) -> Result<String, CodegenError> {
-        let mut code = String::new();
+    // the runtime function that works out `op` on two `ty`s when it has to
+    // be checked. the arithmetic in a global's initializer is left to the C
+    // compiler, which works it out before the program starts.
+    fn check(&self, op: &TokenKind, ty: &Type) -> Option<&'static str> {
+        if !self.options.checks || self.scopes.is_empty() {
+            return None;
+        }
 
-        for stmt in &block.stmts {
-            code.push_str(&self.stmt(stmt, true)?);
+        let (min, _) = ty.range()?;
+
+        Some(match (op, min < 0) {
+            (TokenKind::Add, true) => "uma_add",
+            (TokenKind::Sub, true) => "uma_sub",
+            (TokenKind::Multi, true) => "uma_mul",
+            (TokenKind::Div, true) => "uma_div",
+            (TokenKind::Mod, true) => "uma_mod",
+            (TokenKind::Div, false) => "uma_udiv",
+            (TokenKind::Mod, false) => "uma_umod",
+            _ => return None,
+        })
+    }
+
+    // `code` cast back to `ty` when it's narrower than an `int`, which C
+    // would otherwise work it out as, so `127i8 + 1i8` is -128 and not 128.
+    fn narrowed(code: String, ty: &Type) -> String {
+        match ty {
+            Type::Int8 | Type::Int16 | Type::UInt8 | Type::UInt16 => {
+                format!("(({}){})", Self::split_type(ty).0, code)
+            }
+            _ => code,
         }
+    }
 
-        Ok(code)
+    // the count `rhs` of a shift kept under the bits in `ty`, which the
+    // other backends wrap it to and C leaves undefined. a literal that's
+    // already under them is left alone.
+    fn shifted(rhs: String, op: &TokenKind, ty: &Type) -> String {
+        match (op, ty.bits()) {
+            (TokenKind::Shl | TokenKind::Shr, Some(bits))
+                if !rhs.parse().is_ok_and(|count: u32| count < bits) =>
+            {
+                format!("({} & {})", rhs, bits - 1)
+            }
+            _ => rhs,
+        }
+    }
+
+    // a call to `check`, cast back to the `ty` it works on. the signed ones
+    // are told how many bits `ty` has.
+    fn checked(&self, check: &str, ty: &Type, lhs: &str, rhs: &str) -> String {
+        let (base, _) = Self::split_type(ty);
+
+        match ty.range() {
+            Some((min, max)) if min < 0 => format!(
+                "(({}){}({}, {}, {}, __FILE__, __LINE__))",
+                base,
+                check,
+                lhs,
+                rhs,
+                (max + 1).trailing_zeros() + 1
+            ),
+            _ => format!(
+                "(({}){}({}, {}, __FILE__, __LINE__))",
+                base, check, lhs, rhs
+            ),
+        }
+    }
+
+    // the `<math.h>` function for `builtin`, or for integers what stands in
+    // for it. an unsigned number is its own `abs`.
+    fn math(&mut self, builtin: Builtin, args: &[TypedExpr], ty: &Type) -> String {
+        let args = args
+            .iter()
+            .map(|arg| self.expr(arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let function = match (builtin, ty.range()) {
+            (Builtin::Abs, Some((0, _))) => return args,
+            (Builtin::Abs, Some(_)) => {
+                self.add_header_if_not_exist("#include <stdlib.h>".to_string());
+
+                return match ty {
+                    Type::Int64 => format!("llabs({})", args),
+                    _ => format!("abs({})", args),
+                };
+            }
+            // the runtime's work on the widest integers, so they're cast
+            // back to the ones they were given.
+            (Builtin::Min | Builtin::Max, Some((min, _))) => {
+                let function = match (builtin, min < 0) {
+                    (Builtin::Min, true) => "uma_min",
+                    (Builtin::Min, false) => "uma_umin",
+                    (_, true) => "uma_max",
+                    (_, false) => "uma_umax",
+                };
+
+                let (base, _) = Self::split_type(ty);
+                return format!("(({}){}({}))", base, function, args);
+            }
+            (Builtin::Abs, None) => "fabs",
+            (Builtin::Min, None) => "fmin",
+            (Builtin::Max, None) => "fmax",
+            (builtin, _) => builtin.name(),
+        };
+
+        self.add_header_if_not_exist("#include <math.h>".to_string());
+
+        format!("{}({})", function, args)
+    }
+
+    // C puts array dimensions after the declared name (`int xs[3]`), so the
+    // base type and the dimensions are kept apart.
+    fn split_type(ty: &Type) -> (String, String) {
+        let base = match ty {
+            Type::Int => "int",
+            Type::Float => "double",
+            Type::Int8 => "int8_t",
+            Type::Int16 => "int16_t",
+            Type::Int32 => "int32_t",
+            Type::Int64 => "int64_t",
+            Type::UInt8 => "uint8_t",
+            Type::UInt16 => "uint16_t",
+            Type::UInt32 => "uint32_t",
+            Type::UInt64 => "uint64_t",
+            Type::Float32 => "float",
+            Type::Bool => "bool",
+            Type::Char => "char",
+            Type::String => "UmaString",
+            Type::Void => "void",
+            Type::Array(element, len) => {
+                let (base, dims) = Self::split_type(element);
+                return (base, format!("[{}]{}", len, dims));
+            }
+            Type::Pointer(ty) => return (format!("{}*", Self::split_type(ty).0), String::new()),
+            Type::Opaque(name) => name,
+        };
+
+        (base.to_string(), String::new())
     }
 
-    fn stmt(&mut self, stmt: &Stmt, with_semi: bool) -> Result<String, CodegenError> {
-        Ok(match stmt {
-            Stmt::Block(block) => format!("{{\n{}}}\n", self.block(block)?),
+    fn c_type(&mut self, ty: &Type) -> String {
+        let (base, dims) = self.base_type(ty);
+        format!("{}{}", base, dims)
+    }
+
+    fn declaration(&mut self, ty: &Type, name: &str) -> String {
+        let (base, dims) = self.base_type(ty);
+        format!("{} {}{}", base, name, dims)
+    }
+
+    fn base_type(&mut self, ty: &Type) -> (String, String) {
+        let (base, dims) = Self::split_type(ty);
+        let pointee = base.trim_end_matches('*');
+
+        if pointee == "bool" {
+            self.add_header_if_not_exist("#include <stdbool.h>".to_string());
+        }
+
+        if pointee.ends_with("_t") {
+            self.add_header_if_not_exist("#include <stdint.h>".to_string());
+        }
+
+        (base, dims)
+    }
+
+    // the strings in one that's `kept` get a reference of their own, which
+    // the variable it's declared as lets go of. the ones in an array that's
+    // only used are let go of with the statement.
+    fn initializer(&mut self, elements: &[TypedExpr], kept: bool) -> String {
+        let elements = elements
+            .iter()
+            .map(|element| match element {
+                TypedExpr::Array { elements, .. } => self.initializer(elements, kept),
+                element if kept => self.own(element),
+                element => self.borrow(element),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{{{}}}", elements)
+    }
+
+    // `int f(int x)`, what a function's prototype and definition start with.
+    // `main` takes what C gives it, for `arg`, and always returns an `int`.
+    // one that returns nothing falls off its end, which C takes as a 0.
+    fn signature(
+        &mut self,
+        name: &str,
+        return_type: &Type,
+        args: &[(String, Type)],
+        attributes: &[FunctionAttribute],
+    ) -> String {
+        if name == "main" {
+            return "int main(int uma_argc, char** uma_argv)".to_string();
+        }
+
+        // `static` so C doesn't also want a copy of it that isn't inlined,
+        // and in a library so only what's exported can clash with the names
+        // of whatever links against it.
+        let export = attributes.contains(&FunctionAttribute::Export);
+        let linkage = match attributes.contains(&FunctionAttribute::Inline) {
+            true => "static inline ",
+            false if self.library && !export => "static ",
+            false => "",
+        };
+
+        let args = args
+            .iter()
+            .map(|(arg, ty)| format!("{} {}", self.c_type(ty), arg))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("{}{} {}({})", linkage, self.c_type(return_type), name, args)
+    }
+
+    // tells the C compiler the code that follows was written at `span`.
+    fn locate(&mut self, span: Span) {
+        // the statements the checker adds itself weren't written anywhere.
+        if span.line == 0 {
+            return;
+        }
+
+        let Some(file) = self.files.get(span.file) else {
+            return;
+        };
+
+        let file = c_string(file);
+        self.out.directive(&format!("#line {} {}", span.line, file));
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            let mark = self.pending.len();
+            self.stmt(stmt);
+
+            // a `return` has already let go of everything.
+            match stmt {
+                Stmt::Return(..) => self.pending.truncate(mark),
+                _ => self.flush(mark),
+            }
+        }
+    }
+
+    // the strings a `return` has to let go of, every one still held.
+    fn live(&self) -> Vec<String> {
+        self.scopes
+            .iter()
+            .flatten()
+            .chain(&self.pending)
+            .cloned()
+            .collect()
+    }
+
+    // what `emit` writes, taken out instead of being added to the rest.
+    fn capture(&mut self, emit: impl FnOnce(&mut CBackend)) -> String {
+        let inner = self.out.nested();
+        let outer = std::mem::replace(&mut self.out, inner);
+
+        emit(self);
+
+        std::mem::replace(&mut self.out, outer).finish()
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(block, _) => {
+                self.out.open("");
+                self.scoped(block);
+                self.out.close();
+            }
+            // a `#define` can be used anywhere C wants a constant, which
+            // a `const` variable can't.
+            Stmt::Const { name, value, .. } => {
+                let value = self.expr(value);
+                self.out.line(&format!("#define {} {}", name, value));
+            }
             Stmt::Variable {
                 name,
                 value,
                 is_mut,
+                ty,
+                span,
             } => {
-                let type_decl = if *is_mut {
-                    self.infer_type(value)?
-                } else {
-                    format!("const {}", self.infer_type(value)?)
+                self.locate(*span);
+
+                // a `const` in front of a pointer would be to what it
+                // points at, which can still be changed through it.
+                let decl = match (is_mut, ty) {
+                    (true, _) => self.declaration(ty, name),
+                    (false, Type::Pointer(_)) => {
+                        let ty = self.c_type(ty);
+                        format!("{} const {}", ty, name)
+                    }
+                    (false, _) => format!("const {}", self.declaration(ty, name)),
                 };
 
-                format!("{} {} = {};\n", type_decl, name, self.stmt(value, false)?)
-            }
-            Stmt::Assignment { name, value } => {
-                format!("{} = {};\n", name, self.stmt(value, false)?)
+                match (ty, value) {
+                    (Type::Array(..), TypedExpr::Array { elements, .. }) => {
+                        let init = self.initializer(elements, true);
+                        self.out.line(&format!("{} = {};", decl, init));
+                        self.hold(name, ty);
+                    }
+                    // arrays can't be initialised from another array in C.
+                    (Type::Array(..), value) => {
+                        self.add_header_if_not_exist("#include <string.h>".to_string());
+
+                        let decl = self.declaration(ty, name);
+                        let value = self.expr(value);
+
+                        self.out.line(&format!("{};", decl));
+                        self.out
+                            .line(&format!("memcpy({}, {}, sizeof({}));", name, value, name));
+
+                        for string in Self::strings(name, ty) {
+                            self.out.line(&format!("uma_retain({});", string));
+                        }
+
+                        self.hold(name, ty);
+                    }
+                    _ => {
+                        let value = self.own(value);
+                        self.out.line(&format!("{} = {};", decl, value));
+                        self.hold(name, ty);
+                    }
+                }
             }
-            Stmt::Call { name, args, .. } => {
-                let args_str = args
-                    .iter()
-                    .map(|arg| self.stmt(arg, false))
-                    .collect::<Result<Vec<_>, _>>()?
-                    .join(", ");
+            // strings are concatenated by a helper, so only numbers get `+=`,
+            // and only when the runtime isn't checking them.
+            Stmt::Assignment {
+                name,
+                value: TypedExpr::Binary { rhs, ty, .. },
+                op: Some(op),
+                span,
+            } if ty.is_numeric() && self.check(&op.kind, ty).is_none() => {
+                self.locate(*span);
 
-                format!(
-                    "{}({}){}",
-                    name,
-                    args_str,
-                    with_semi.then(|| ";\n").unwrap_or("")
-                )
+                let rhs = self.expr(rhs);
+                self.out.line(&format!("{} {}= {};", name, op.repr(), rhs));
             }
-            Stmt::Function { external, body, .. } => {
-                if let Some(ext) = external {
-                    self.add_header_if_not_exist(format!("#include <{}>", ext));
+            Stmt::Assignment {
+                name, value, span, ..
+            } => {
+                self.locate(*span);
+
+                match value.ty() {
+                    Type::Array(..) if Self::strings(name, &value.ty()).is_empty() => {
+                        self.add_header_if_not_exist("#include <string.h>".to_string());
+
+                        let code = self.expr(value);
+                        self.out
+                            .line(&format!("memcpy({}, {}, sizeof({}));", name, code, name));
+                    }
+                    // the new strings are held before the old ones are let go
+                    // of, which they might be.
+                    ty @ Type::Array(..) => {
+                        self.add_header_if_not_exist("#include <string.h>".to_string());
+
+                        let code = self.expr(value);
+                        let old = self.declaration(&ty, "uma_old");
+
+                        self.out.open("");
+                        self.out.line(&format!("{};", old));
+                        self.out
+                            .line(&format!("memcpy(uma_old, {}, sizeof(uma_old));", name));
+                        self.out
+                            .line(&format!("memcpy({}, {}, sizeof({}));", name, code, name));
+
+                        for string in Self::strings(name, &ty) {
+                            self.out.line(&format!("uma_retain({});", string));
+                        }
+
+                        for string in Self::strings("uma_old", &ty) {
+                            self.out.line(&format!("uma_release({});", string));
+                        }
 
-                    return Ok("".to_string());
+                        self.out.close();
+                    }
+                    Type::String => {
+                        let code = self.own(value);
+                        self.out
+                            .line(&format!("{} = uma_replace({}, {});", name, name, code));
+                    }
+                    _ => {
+                        let code = self.expr(value);
+                        self.out.line(&format!("{} = {};", name, code));
+                    }
                 }
+            }
+            Stmt::External { header, .. } => {
+                self.add_header_if_not_exist(format!("#include <{}>", header));
+            }
+            // a type no header declares is one C is only ever handed
+            // pointers to, which it can take without knowing what's in it.
+            Stmt::Opaque { name, header } => match header {
+                Some(header) => self.add_header_if_not_exist(format!("#include <{}>", header)),
+                None => self.out.line(&format!("typedef struct {0} {0};", name)),
+            },
+            Stmt::Function {
+                name,
+                return_type,
+                args,
+                body,
+                attributes,
+                span,
+            } => {
+                self.locate(*span);
+
+                let signature = self.signature(name, return_type, args, attributes);
 
-                let func_proto = self.infer_type(stmt)?;
+                self.out.open(&signature);
+
+                let outer = std::mem::take(&mut self.temps);
+                self.strings = 0;
+                self.main = name == "main";
+                let body = self.capture(|backend| {
+                    if name == "main" {
+                        backend.out.line("uma_set_args(uma_argc, uma_argv);");
+                    }
 
-                format!("{} {{\n{}\n}}\n", func_proto, self.block(body)?)
+                    backend.scoped(body)
+                });
+                let temps = std::mem::replace(&mut self.temps, outer);
+
+                for temp in temps {
+                    self.out.line(&temp);
+                }
+
+                self.out.raw(&body);
+                self.out.close();
             }
             Stmt::For {
                 iterator,
                 start,
                 end,
                 body,
+                span,
             } => {
-                let start_val = self.stmt(start, false)?;
-                let end_val = self.stmt(end, false)?;
-                let body_code = self.block(body)?;
+                self.locate(*span);
 
-                format!(
-                    "for (int {it} = {start}; {it} < {end}; {it}++) {{\n{body}}}\n",
+                let start = self.expr(start);
+                let end = self.expr(end);
+
+                self.out.open(&format!(
+                    "for (int {it} = {start}; {it} < {end}; {it}++)",
                     it = iterator,
-                    start = start_val,
-                    end = end_val,
-                    body = body_code
-                )
+                    start = start,
+                    end = end,
+                ));
+                self.scoped(body);
+                self.out.close();
             }
-            Stmt::Return(stmt) => {
-                let expr = self.stmt(stmt, false)?;
+            Stmt::Return(None, span) => {
+                self.locate(*span);
+
+                for string in self.live().iter().rev() {
+                    self.out.line(&format!("uma_release({});", string));
+                }
 
-                format!("return {};", expr)
+                match self.main {
+                    true => self.out.line("return 0;"),
+                    false => self.out.line("return;"),
+                }
+            }
+            Stmt::Return(Some(value), span) => {
+                self.locate(*span);
+
+                let ty = value.ty();
+                let value = self.own(value);
+                let live = self.live();
+
+                if live.is_empty() {
+                    self.out.line(&format!("return {};", value));
+                    return;
+                }
+
+                // the value is worked out before the strings it might use
+                // are let go of.
+                let result = match ty {
+                    Type::Void => {
+                        self.out.line(&format!("{};", value));
+                        None
+                    }
+                    ty => {
+                        let decl = format!("{};", self.declaration(&ty, "uma_result"));
+
+                        if !self.temps.contains(&decl) {
+                            self.temps.push(decl);
+                        }
+
+                        self.out.line(&format!("uma_result = {};", value));
+                        Some("uma_result")
+                    }
+                };
+
+                for string in live.iter().rev() {
+                    self.out.line(&format!("uma_release({});", string));
+                }
+
+                match result {
+                    Some(result) => self.out.line(&format!("return {};", result)),
+                    None => self.out.line("return;"),
+                }
             }
             Stmt::If {
                 condition,
                 consequence,
                 alternative,
+                span,
             } => {
-                let cond_str = self.stmt(condition, false)?;
-                let consequence_str = self.block(consequence)?;
+                self.locate(*span);
+
+                let condition = self.expr(condition);
+
+                self.out.open(&format!("if ({})", condition));
+                self.scoped(consequence);
+
+                let mut alternative = alternative.as_deref();
 
-                let mut code = format!("if ({}) {{\n{}}}", cond_str, consequence_str);
+                // an `else if` chain stays flat instead of nesting each `if`
+                // in the last one's `else`.
+                while let Some(alt) = alternative {
+                    match alt {
+                        Stmt::If {
+                            condition,
+                            consequence,
+                            alternative: next,
+                            span,
+                        } => {
+                            self.locate(*span);
 
-                if let Some(alt) = alternative {
-                    match &**alt {
-                        Stmt::If { .. } => {
-                            code.push_str(&format!(" else {}", self.stmt(alt, false)?));
+                            let condition = self.expr(condition);
+
+                            self.out.reopen(&format!("else if ({})", condition));
+                            self.scoped(consequence);
+
+                            alternative = next.as_deref();
                         }
-                        Stmt::Block(block) => {
-                            code.push_str(&format!(" else {{\n{}}}\n", self.block(block)?));
+                        Stmt::Block(block, _) => {
+                            self.out.reopen("else");
+                            self.scoped(block);
+
+                            alternative = None;
                         }
                         _ => unreachable!("alternative must be an If or a Block"),
                     }
-                } else {
-                    code.push('\n');
                 }
 
-                code
+                self.out.close();
             }
-            Stmt::Expr(expr) => self.expr(expr)?,
-            Stmt::Empty => "".to_string(),
-            stmt => unimplemented!("{:?}", stmt),
-        })
+            Stmt::Expr(expr, span) => {
+                self.locate(*span);
+
+                let code = self.expr(expr);
+
+                // a string nothing keeps is let go of straight away.
+                match self.owned(expr) {
+                    true => self.out.line(&format!("uma_release({});", code)),
+                    false => self.out.line(&format!("{};", code)),
+                }
+            }
+        }
     }
 
-    fn expr(&mut self, expr: &Expr) -> Result<String, CodegenError> {
-        Ok(match expr {
-            Expr::Binary { lhs, op, rhs } => {
+    fn expr(&mut self, expr: &TypedExpr) -> String {
+        match expr {
+            TypedExpr::Binary {
+                lhs,
+                op,
+                rhs,
+                ty: result,
+            } => match (lhs.ty(), &op.kind) {
+                (Type::String, TokenKind::Add) => self.concat(lhs, rhs),
+                (Type::String, _) => {
+                    let (l, r) = (self.borrow(lhs), self.borrow(rhs));
+                    format!("(uma_compare({}, {}) {} 0)", l, r, op.repr())
+                }
+                (ty, kind) => {
+                    let (l, r) = (self.expr(lhs), Self::shifted(self.expr(rhs), kind, &ty));
+
+                    if let Some(check) = self.check(kind, &ty) {
+                        return self.checked(check, &ty, &l, &r);
+                    }
+
+                    if *kind != TokenKind::Expo {
+                        return Self::narrowed(format!("({} {} {})", l, op.repr(), r), result);
+                    }
+
+                    self.add_header_if_not_exist("#include <math.h>".to_string());
+
+                    match ty {
+                        ty if ty.is_integer() => {
+                            format!("({})pow({}, {})", Self::split_type(&ty).0, l, r)
+                        }
+                        _ => format!("pow({}, {})", l, r),
+                    }
+                }
+            },
+            TypedExpr::Cast { value, ty } => {
+                let ty = self.c_type(ty);
+                format!("(({}){})", ty, self.expr(value))
+            }
+            TypedExpr::Unary { op, operand, .. } => {
+                format!("({}{})", op.repr(), self.expr(operand))
+            }
+            TypedExpr::AddressOf { name, .. } => format!("(&{})", name),
+            TypedExpr::Deref { pointer, .. } => format!("(*{})", self.expr(pointer)),
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => format!(
+                "({} ? {} : {})",
+                self.expr(condition),
+                self.own(consequence),
+                self.own(alternative)
+            ),
+            // a chain of `?:` comparing against each pattern in turn. a
+            // subject that calls something is put in a temporary first, so
+            // it's only worked out once.
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ..
+            } => {
+                let mut calls = false;
+                exprs(
+                    &[Stmt::Expr(*subject.clone(), Span::default())],
+                    &mut |expr| {
+                        calls |= matches!(expr, TypedExpr::Call { .. } | TypedExpr::Builtin { .. })
+                    },
+                );
+
+                let (subject, assign) = if self.owned(subject) {
+                    let temp = self.temporary();
+                    let assign =
+                        format!("{} = uma_replace({}, {}), ", temp, temp, self.expr(subject));
+                    let subject = TypedExpr::Identifier {
+                        name: temp,
+                        ty: Type::String,
+                    };
+
+                    (subject, assign)
+                } else if calls {
+                    let temp = format!("uma_match_{}", self.temps.len());
+                    let decl = self.declaration(&subject.ty(), &temp);
+                    self.temps.push(format!("{};", decl));
+
+                    let assign = format!("{} = {}, ", temp, self.expr(subject));
+                    let subject = TypedExpr::Identifier {
+                        name: temp,
+                        ty: subject.ty(),
+                    };
+
+                    (subject, assign)
+                } else {
+                    (*subject.clone(), String::new())
+                };
+
+                let mut code = self.own(default);
+
+                for (pattern, value) in arms.iter().rev() {
+                    let matches = self.expr(&TypedExpr::Binary {
+                        lhs: subject.clone().into(),
+                        op: Token::new(TokenKind::BinaryEq, None, Span::default()),
+                        rhs: pattern.clone().into(),
+                        ty: Type::Bool,
+                    });
+
+                    code = format!("({} ? {} : {})", matches, self.own(value), code);
+                }
+
+                format!("({}{})", assign, code)
+            }
+            // C is handed the characters of a string, and what it gives back
+            // is never freed.
+            TypedExpr::Call { name, args, ty } if self.externals.contains(name) => {
+                let args = args
+                    .iter()
+                    .map(|arg| match arg.ty() {
+                        Type::String => self.c_str(arg),
+                        _ => self.expr(arg),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                match ty {
+                    Type::String => format!("uma_cstr({}({}))", name, args),
+                    _ => format!("{}({})", name, args),
+                }
+            }
+            TypedExpr::Call { name, args, .. } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| self.borrow(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{}({})", name, args_str)
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Len,
+                args,
+                ..
+            } => match args[0].ty() {
+                Type::Array(_, len) => len.to_string(),
+                _ => format!("(int){}.len", self.borrow(&args[0])),
+            },
+            TypedExpr::Builtin { builtin, args, ty } if builtin.is_math() => {
+                self.math(*builtin, args, ty)
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Input,
+                args,
+                ..
+            } => match args.first() {
+                Some(prompt) => format!("uma_input({})", self.c_str(prompt)),
+                None => "uma_input(NULL)".to_string(),
+            },
+            TypedExpr::Builtin {
+                builtin: builtin @ (Builtin::ParseInt | Builtin::ParseFloat),
+                args,
+                ..
+            } => {
+                let function = match builtin {
+                    Builtin::ParseInt => "uma_parse_int",
+                    _ => "uma_parse_float",
+                };
+
+                let string = self.borrow(&args[0]);
+                format!("{}({}, __FILE__, __LINE__)", function, string)
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::ReadFile,
+                args,
+                ..
+            } => match args.as_slice() {
+                [path, fallback] => {
+                    let path = self.c_str(path);
+                    format!("uma_read_file_or({}, {})", path, self.borrow(fallback))
+                }
+                _ => format!(
+                    "uma_read_file({}, __FILE__, __LINE__)",
+                    self.c_str(&args[0])
+                ),
+            },
+            TypedExpr::Builtin {
+                builtin: builtin @ (Builtin::WriteFile | Builtin::AppendFile),
+                args,
+                ..
+            } => {
+                let path = self.c_str(&args[0]);
+                let contents = self.borrow(&args[1]);
+                let append = (*builtin == Builtin::AppendFile) as i32;
+
+                format!("uma_write_file({}, {}, {})", path, contents, append)
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::ArgCount,
+                ..
+            } => "uma_arg_count()".to_string(),
+            TypedExpr::Builtin {
+                builtin: Builtin::Arg,
+                args,
+                ..
+            } => format!("uma_arg({}, __FILE__, __LINE__)", self.expr(&args[0])),
+            TypedExpr::Builtin {
+                builtin: Builtin::Exit,
+                args,
+                ..
+            } => {
+                self.add_header_if_not_exist("#include <stdlib.h>".to_string());
+                format!("exit({})", self.expr(&args[0]))
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Env,
+                args,
+                ..
+            } => format!("uma_env({})", self.c_str(&args[0])),
+            TypedExpr::Builtin {
+                builtin: Builtin::SetEnv,
+                args,
+                ..
+            } => {
+                let name = self.c_str(&args[0]);
+                let value = self.c_str(&args[1]);
+
+                format!("uma_set_env({}, {}, __FILE__, __LINE__)", name, value)
+            }
+            // like C's own `assert`, nothing is worked out once it's
+            // compiled out.
+            TypedExpr::Builtin {
+                builtin: Builtin::Assert,
+                args,
+                ..
+            } => {
+                if self.options.release {
+                    return "((void)0)".to_string();
+                }
+
+                let condition = self.expr(&args[0]);
+                let source = self.c_str(&args[1]);
+                let message = match args.get(2) {
+                    Some(message) => self.c_str(message),
+                    None => "NULL".to_string(),
+                };
+
                 format!(
-                    "({} {} {})",
-                    self.stmt(lhs, false)?,
-                    op.repr(),
-                    self.stmt(rhs, false)?
+                    "({} ? (void)0 : uma_assert({}, {}, __FILE__, __LINE__))",
+                    condition, source, message
                 )
             }
-            Expr::Identifier(name) => name.to_string(),
-            Expr::Number(num) => num.to_string(),
-            Expr::Float(num) => num.to_string(),
-            Expr::String(value) => format!("\"{}\"", value.replace("\n", r#"\n"#)),
-        })
+            // the rest of the prelude comes down to a `printf` picked by
+            // argument type.
+            TypedExpr::Builtin { builtin, args, .. } => {
+                self.add_header_if_not_exist("#include <stdio.h>".to_string());
+
+                let args = builtin
+                    .printf(args)
+                    .iter()
+                    .map(|arg| self.printed(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("printf({})", args)
+            }
+            TypedExpr::Array { elements, ty } => {
+                format!("({}){}", self.c_type(ty), self.initializer(elements, false))
+            }
+            TypedExpr::Index { target, index, .. } if target.ty() == Type::String => {
+                let (target, index) = (self.borrow(target), self.expr(index));
+                format!("uma_string_at({}, {}, __FILE__, __LINE__)", target, index)
+            }
+            // `#line` points `__FILE__` and `__LINE__` at the source, so a bad
+            // index is reported where it was written. a literal that's in
+            // bounds needs no checking.
+            TypedExpr::Index { target, index, .. } => {
+                let (array, code) = (self.expr(target), self.expr(index));
+
+                let len = match target.ty() {
+                    Type::Array(_, len) => len,
+                    ty => unreachable!("cannot index a {:?}", ty),
+                };
+
+                let in_bounds = matches!(
+                    &**index,
+                    TypedExpr::Number(n) if n.parse::<usize>().is_ok_and(|n| n < len)
+                );
+
+                match self.options.release || in_bounds {
+                    true => format!("{}[{}]", array, code),
+                    false => format!(
+                        "{}[uma_bounds({}, {}, __FILE__, __LINE__)]",
+                        array, code, len
+                    ),
+                }
+            }
+            TypedExpr::Identifier { name, .. } => name.to_string(),
+            TypedExpr::Number(num) => num.to_string(),
+            TypedExpr::Float(num) => num.to_string(),
+            TypedExpr::Bool(value) => {
+                self.add_header_if_not_exist("#include <stdbool.h>".to_string());
+
+                value.to_string()
+            }
+            TypedExpr::Char(value) => c_char(*value),
+            TypedExpr::String(value) => format!("UMA_STR({})", c_string(value)),
+            TypedExpr::Interpolation(parts) => {
+                let (format, values) = interpolation(parts);
+
+                let mut args = vec![c_string(&format)];
+
+                for value in values {
+                    args.push(self.printed(value));
+                }
+
+                self.format(args)
+            }
+        }
+    }
+}
+
+// `value` as a C string literal. whatever isn't printable is written as a
+// `\xNN` escape, and since those run on for as many hex digits as follow, a
+// hex digit right after one starts a new literal for C to join back up.
+fn c_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            // `??` followed by some characters is a trigraph in older C.
+            '?' if chars.peek() == Some(&'?') => out.push_str("\\?"),
+            c if c.is_ascii_control() => {
+                out.push_str(&format!("\\x{:02x}", c as u8));
+
+                if chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    out.push_str("\"\"");
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+// `value` as a C character literal, it's only ever ASCII.
+fn c_char(value: char) -> String {
+    match value {
+        '\'' => "'\\''".to_string(),
+        '\\' => "'\\\\'".to_string(),
+        '\n' => "'\\n'".to_string(),
+        '\t' => "'\\t'".to_string(),
+        '\r' => "'\\r'".to_string(),
+        c if c.is_ascii_control() => format!("'\\x{:02x}'", c as u8),
+        c => format!("'{}'", c),
+    }
+}
+
+// a C `char` is a byte, so the characters past ASCII have nothing to be.
+fn characters(program: &[Stmt]) -> Result<(), CodegenError> {
+    let mut wide = None;
+
+    exprs(program, &mut |expr| {
+        if let TypedExpr::Char(c) = expr {
+            if !c.is_ascii() && wide.is_none() {
+                wide = Some(*c);
+            }
+        }
+    });
+
+    match wide {
+        Some(c) => CodegenError::new(
+            format!("`{}` doesn't fit in a C `char`", c),
+            vec!["only ASCII characters can be `Char`s when compiling to C, use a `String` for the others".to_string()],
+        ),
+        None => Ok(()),
     }
 }
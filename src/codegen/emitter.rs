@@ -0,0 +1,65 @@
+// C source written a line at a time, each line indented by how many blocks
+// it's in.
+#[derive(Debug, Default)]
+pub struct Emitter {
+    out: String,
+    depth: usize,
+}
+
+const INDENT: &str = "    ";
+
+impl Emitter {
+    pub fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.out.push_str(&INDENT.repeat(self.depth));
+            self.out.push_str(text);
+        }
+
+        self.out.push('\n');
+    }
+
+    // a preprocessor line, which isn't indented with the code around it.
+    pub fn directive(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    // `head {`, with what comes after it a level deeper.
+    pub fn open(&mut self, head: &str) {
+        match head {
+            "" => self.line("{"),
+            head => self.line(&format!("{} {{", head)),
+        }
+
+        self.depth += 1;
+    }
+
+    pub fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    // `} head {`, the end of one block running into the next, like an `else`.
+    pub fn reopen(&mut self, head: &str) {
+        self.depth -= 1;
+        self.line(&format!("}} {} {{", head));
+        self.depth += 1;
+    }
+
+    // an empty emitter that starts as deep as this one is.
+    pub fn nested(&self) -> Emitter {
+        Emitter {
+            out: String::new(),
+            depth: self.depth,
+        }
+    }
+
+    // text that's already laid out, like a captured block.
+    pub fn raw(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
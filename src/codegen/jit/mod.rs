@@ -0,0 +1,1704 @@
+mod runtime;
+
+use std::collections::{HashMap, HashSet};
+
+use cranelift_codegen::{
+    ir::{
+        condcodes::{FloatCC, IntCC},
+        types, AbiParam, BlockArg, FuncRef, GlobalValue, InstBuilder, MemFlagsData, Signature,
+        StackSlotData, StackSlotKind, TrapCode, Type as ValType, UserFuncName, Value,
+    },
+    settings::{self, Configurable},
+};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{
+    default_libcall_names, DataDescription, DataId, FuncId, Linkage, Module, ModuleError,
+    ModuleRelocTarget,
+};
+
+use super::{CodegenError, CodegenOptions, CompilerOptions};
+use crate::ir::{self, callgraph::calls, Stmt};
+use crate::lexer::{Span, Token, TokenKind};
+use crate::typeck::{Builtin, Type, TypedExpr, TypedPart, TypedStmt};
+
+// machine code for the program, run in-process instead of being built into
+// an executable. a string is a pointer to NUL-terminated bytes, like the
+// wasm32 target's, and the arrays are never written to in place, so they're
+// shared instead of copied. what's made as the program runs is freed once it
+// finishes.
+#[derive(Default)]
+pub struct Jit {
+    options: CompilerOptions,
+}
+
+// a program that's been compiled, ready to have its `main` called.
+struct Compiled {
+    module: JITModule,
+    main: FuncId,
+    // whether `main` gives back a status, which a `Void` one doesn't.
+    status: bool,
+}
+
+struct External {
+    id: FuncId,
+    arity: usize,
+    is_varadic: bool,
+    // the types it was first called with, which every call is made with.
+    signature: Signature,
+}
+
+// where a variable of the function being compiled lives. the ones whose
+// address is taken are kept on the stack, so there's something to point at.
+#[derive(Clone, Copy)]
+enum Local {
+    Variable(Variable),
+    Slot(cranelift_codegen::ir::StackSlot),
+}
+
+fn val_type(ty: &Type, pointer: ValType) -> Option<ValType> {
+    match ty {
+        Type::Int | Type::Int32 | Type::UInt32 => Some(types::I32),
+        Type::Int8 | Type::UInt8 | Type::Bool | Type::Char => Some(types::I8),
+        Type::Int16 | Type::UInt16 => Some(types::I16),
+        Type::Int64 | Type::UInt64 => Some(types::I64),
+        Type::Float => Some(types::F64),
+        Type::Float32 => Some(types::F32),
+        Type::String | Type::Array(..) | Type::Pointer(_) | Type::Opaque(_) => Some(pointer),
+        Type::Void => None,
+    }
+}
+
+// whether `ty` is extended with its sign, `Char` being C's signed `char`.
+fn signed(ty: &Type) -> bool {
+    matches!(ty.range(), Some((min, _)) if min < 0) || *ty == Type::Char
+}
+
+fn unsupported<T>(what: impl ToString) -> Result<T, CodegenError> {
+    unsupported_at(what, Span::default())
+}
+
+fn unsupported_at<T>(what: impl ToString, span: Span) -> Result<T, CodegenError> {
+    CodegenError::at(
+        format!("{} is not supported by the JIT", what.to_string()),
+        vec![],
+        span,
+    )
+}
+
+// cranelift turning down what it's given.
+fn cranelift<T>(result: Result<T, ModuleError>) -> Result<T, CodegenError> {
+    result.or_else(|err| {
+        CodegenError::new(
+            format!("The JIT couldn't compile the program: {}", err),
+            vec![],
+        )
+    })
+}
+
+// the variables `&` is taken of anywhere in `stmts`.
+fn addressed(stmts: &[Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+
+    ir::callgraph::exprs(stmts, &mut |expr| {
+        if let TypedExpr::AddressOf { name, .. } = expr {
+            names.insert(name.clone());
+        }
+    });
+
+    names
+}
+
+impl Jit {
+    pub fn new(options: CompilerOptions) -> Jit {
+        Jit { options }
+    }
+
+    // compiles the program and calls its `main`, returning the status it
+    // finishes with. `args` are what `arg` gives back, the first standing in
+    // for the program's name.
+    pub fn run(
+        &self,
+        program: &[TypedStmt],
+        opts: &CodegenOptions,
+        args: &[String],
+    ) -> Result<i32, CodegenError> {
+        let compiled = self.compile(&ir::lower(program), opts)?;
+        let main = compiled.module.get_finalized_function(compiled.main);
+
+        runtime::set_args(args);
+
+        // SAFETY: `main` was compiled with the signature it's called with,
+        // and everything it calls was found before it was finalized.
+        let status = unsafe {
+            match compiled.status {
+                true => std::mem::transmute::<*const u8, extern "C" fn() -> i32>(main)(),
+                false => {
+                    std::mem::transmute::<*const u8, extern "C" fn()>(main)();
+                    0
+                }
+            }
+        };
+
+        runtime::finish();
+
+        // SAFETY: nothing the program made is used after it's finished.
+        unsafe { compiled.module.free_memory() };
+
+        Ok(status)
+    }
+
+    fn compile(&self, stmts: &[Stmt], opts: &CodegenOptions) -> Result<Compiled, CodegenError> {
+        let status = match stmts.iter().find_map(|stmt| match stmt {
+            Stmt::Function {
+                name, return_type, ..
+            } if name == "main" => Some(return_type),
+            _ => None,
+        }) {
+            Some(return_type) => *return_type != Type::Void,
+            None => {
+                return CodegenError::new("The JIT can only run a program with a `main`", vec![])
+            }
+        };
+
+        let mut program = Program {
+            module: self.module()?,
+            options: self.options.clone(),
+            files: opts
+                .files
+                .iter()
+                .map(|file| file.to_string_lossy().to_string())
+                .collect(),
+            functions: HashMap::new(),
+            externals: HashMap::new(),
+            globals: HashMap::new(),
+            consts: HashMap::new(),
+            strings: HashMap::new(),
+        };
+
+        program.declare(stmts)?;
+
+        // top-level variables are initialised when `main` starts.
+        let init = program.init(stmts)?;
+
+        for stmt in stmts {
+            if let Stmt::Function {
+                name,
+                return_type,
+                args,
+                body,
+                span,
+                ..
+            } = stmt
+            {
+                let init = init.filter(|_| name == "main");
+                program.function(name, return_type, args, body, *span, init)?;
+            }
+        }
+
+        program.resolve()?;
+        cranelift(program.module.finalize_definitions())?;
+
+        Ok(Compiled {
+            main: program.functions["main"],
+            module: program.module,
+            status,
+        })
+    }
+
+    fn module(&self) -> Result<JITModule, CodegenError> {
+        let mut flags = settings::builder();
+
+        // the runtime and the C library are called by their address in this
+        // process, wherever that is.
+        flags
+            .set("use_colocated_libcalls", "false")
+            .and_then(|_| flags.set("is_pic", "false"))
+            .expect("cranelift has these settings");
+
+        if self
+            .options
+            .opt_level
+            .as_deref()
+            .is_some_and(|level| level != "0")
+        {
+            flags
+                .set("opt_level", "speed")
+                .expect("cranelift has this setting");
+        }
+
+        let isa = cranelift_native::builder()
+            .or_else(|err| {
+                CodegenError::new(
+                    format!("The JIT can't run on this machine: {}", err),
+                    vec![],
+                )
+            })?
+            .finish(settings::Flags::new(flags))
+            .or_else(|err| {
+                CodegenError::new(
+                    format!("The JIT can't run on this machine: {}", err),
+                    vec![],
+                )
+            })?;
+
+        let mut builder = JITBuilder::with_isa(isa, default_libcall_names());
+        builder.symbols(runtime::symbols());
+
+        Ok(JITModule::new(builder))
+    }
+}
+
+// everything the program's functions are compiled against.
+struct Program {
+    module: JITModule,
+    options: CompilerOptions,
+    // the source files, for where a check that fails was written.
+    files: Vec<String>,
+    functions: HashMap<String, FuncId>,
+    externals: HashMap<String, External>,
+    globals: HashMap<String, (DataId, Type)>,
+    // top-level constants, already worked out, used where they're named.
+    consts: HashMap<String, TypedExpr>,
+    strings: HashMap<String, DataId>,
+}
+
+impl Program {
+    fn pointer(&self) -> ValType {
+        self.module.target_config().pointer_type()
+    }
+
+    fn val_type(&self, ty: &Type) -> Option<ValType> {
+        val_type(ty, self.pointer())
+    }
+
+    // the integers smaller than a register are extended to fill one, the
+    // way C passes them.
+    fn param(&self, ty: &Type) -> Option<AbiParam> {
+        let param = AbiParam::new(self.val_type(ty)?);
+
+        Some(match (param.value_type.bits(), signed(ty)) {
+            (bits, true) if bits < 32 => param.sext(),
+            (bits, false) if bits < 32 => param.uext(),
+            _ => param,
+        })
+    }
+
+    fn signature<'a>(
+        &self,
+        params: impl IntoIterator<Item = &'a Type>,
+        returns: &Type,
+    ) -> Signature {
+        let mut signature = self.module.make_signature();
+
+        signature
+            .params
+            .extend(params.into_iter().filter_map(|ty| self.param(ty)));
+        signature.returns.extend(self.param(returns));
+
+        signature
+    }
+
+    fn declare(&mut self, stmts: &[Stmt]) -> Result<(), CodegenError> {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Function {
+                    name,
+                    return_type,
+                    args,
+                    ..
+                } => {
+                    let signature = self.signature(args.iter().map(|(_, ty)| ty), return_type);
+                    let id = cranelift(self.module.declare_function(
+                        name,
+                        Linkage::Local,
+                        &signature,
+                    ))?;
+
+                    self.functions.insert(name.clone(), id);
+                }
+                Stmt::Variable { name, ty, .. } => {
+                    let size = self.val_type(ty).expect("variables aren't `Void`").bytes();
+                    let id = cranelift(self.module.declare_anonymous_data(true, false))?;
+
+                    let mut data = DataDescription::new();
+                    data.define_zeroinit(size as usize);
+                    cranelift(self.module.define_data(id, &data))?;
+
+                    self.globals.insert(name.clone(), (id, ty.clone()));
+                }
+                Stmt::Const { name, value, .. } => {
+                    self.consts.insert(name.clone(), value.clone());
+                }
+                _ => (),
+            }
+        }
+
+        // an external is called with the types of the arguments it's first
+        // called with. it's looked for in the process once the program's
+        // compiled, so one that isn't there can be reported.
+        let mut found = vec![];
+        calls(stmts, &mut found);
+
+        for stmt in stmts {
+            let Stmt::External {
+                name,
+                arity,
+                is_varadic,
+                ..
+            } = stmt
+            else {
+                continue;
+            };
+
+            let Some((_, args, ty)) = found.iter().find(|(called, ..)| called == name) else {
+                continue;
+            };
+
+            if args.len() < *arity {
+                return CodegenError::new(
+                    format!(
+                        "`{}` expects {} arguments, found {}",
+                        name,
+                        arity,
+                        args.len()
+                    ),
+                    vec![],
+                );
+            }
+
+            let signature = self.signature(
+                args[..*arity]
+                    .iter()
+                    .map(TypedExpr::ty)
+                    .collect::<Vec<_>>()
+                    .iter(),
+                ty,
+            );
+            let id = cranelift(self.module.declare_function(
+                name,
+                Linkage::Preemptible,
+                &signature,
+            ))?;
+
+            self.externals.insert(
+                name.clone(),
+                External {
+                    id,
+                    arity: *arity,
+                    is_varadic: *is_varadic,
+                    signature,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    // a function setting the top-level variables, if there are any.
+    fn init(&mut self, stmts: &[Stmt]) -> Result<Option<FuncId>, CodegenError> {
+        if !stmts
+            .iter()
+            .any(|stmt| matches!(stmt, Stmt::Variable { .. }))
+        {
+            return Ok(None);
+        }
+
+        let signature = self.module.make_signature();
+        let id = cranelift(self.module.declare_anonymous_function(&signature))?;
+
+        let body = stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Variable {
+                    name, value, span, ..
+                } => Some(Stmt::Assignment {
+                    name: name.clone(),
+                    value: value.clone(),
+                    op: None,
+                    span: *span,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        self.define(id, signature, &[], &Type::Void, &body, None)?;
+        Ok(Some(id))
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        return_type: &Type,
+        args: &[(String, Type)],
+        body: &[Stmt],
+        span: Span,
+        init: Option<FuncId>,
+    ) -> Result<(), CodegenError> {
+        let id = self.functions[name];
+        let signature = self.signature(args.iter().map(|(_, ty)| ty), return_type);
+
+        self.define(id, signature, args, return_type, body, init)
+            .map_err(|err| match err.span == Span::default() {
+                true => CodegenError { span, ..err },
+                false => err,
+            })
+    }
+
+    fn define(
+        &mut self,
+        id: FuncId,
+        signature: Signature,
+        args: &[(String, Type)],
+        return_type: &Type,
+        body: &[Stmt],
+        init: Option<FuncId>,
+    ) -> Result<(), CodegenError> {
+        let mut context = self.module.make_context();
+        let config = self.module.target_config();
+        let mut builder_context = FunctionBuilderContext::new();
+
+        context.func.signature = signature;
+        context.func.name = UserFuncName::user(0, id.as_u32());
+
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+
+        let params = builder.block_params(entry).to_vec();
+
+        let mut function = Translator {
+            addressed: addressed(body),
+            program: self,
+            builder,
+            scopes: vec![HashMap::new()],
+            imported: HashMap::new(),
+            data: HashMap::new(),
+            span: Span::default(),
+            matches: 0,
+        };
+
+        for ((name, ty), value) in args.iter().zip(params) {
+            function.declare(name, ty, value);
+        }
+
+        if let Some(init) = init {
+            let init = function.func(init);
+            function.builder.ins().call(init, &[]);
+        }
+
+        function.block(body)?;
+
+        // every path should have returned by now, which cranelift can't tell.
+        match return_type {
+            Type::Void => function.builder.ins().return_(&[]),
+            _ => function.builder.ins().trap(TrapCode::unwrap_user(1)),
+        };
+
+        function.builder.seal_all_blocks();
+        function.builder.finalize(config);
+
+        cranelift(self.module.define_function(id, &mut context))?;
+        self.module.clear_context(&mut context);
+
+        Ok(())
+    }
+
+    // every external has to be in the process before the program can run.
+    fn resolve(&self) -> Result<(), CodegenError> {
+        let mut names = self.externals.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let target = ModuleRelocTarget::from(self.externals[name].id);
+
+            if self.module.get_address(&target).is_null() {
+                return CodegenError::new(
+                    format!("Couldn't find the external `{}` to call", name),
+                    vec!["the JIT can only call what's already loaded, like the C library".into()],
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn string(&mut self, value: &str) -> Result<DataId, CodegenError> {
+        if let Some(id) = self.strings.get(value) {
+            return Ok(*id);
+        }
+
+        let id = cranelift(self.module.declare_anonymous_data(false, false))?;
+
+        let mut data = DataDescription::new();
+        data.define([value.as_bytes(), &[0]].concat().into_boxed_slice());
+        cranelift(self.module.define_data(id, &data))?;
+
+        self.strings.insert(value.to_string(), id);
+        Ok(id)
+    }
+}
+
+// the function being compiled.
+struct Translator<'a> {
+    program: &'a mut Program,
+    builder: FunctionBuilder<'a>,
+    scopes: Vec<HashMap<String, (Local, Type)>>,
+    addressed: HashSet<String>,
+    imported: HashMap<FuncId, FuncRef>,
+    data: HashMap<DataId, GlobalValue>,
+    // the statement being compiled, for where a check that fails was written.
+    span: Span,
+    matches: usize,
+}
+
+// what `print` and an interpolated string are made of.
+enum Piece<'e> {
+    Text(String),
+    Value(&'e TypedExpr),
+}
+
+// `args` one after the other, each split into its text and the values
+// between it.
+fn pieces<'e>(args: &'e [TypedExpr], separator: &str) -> Vec<Piece<'e>> {
+    let mut pieces = vec![];
+
+    let text = |pieces: &mut Vec<Piece<'e>>, text: &str| match pieces.last_mut() {
+        Some(Piece::Text(last)) => last.push_str(text),
+        _ => pieces.push(Piece::Text(text.to_string())),
+    };
+
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            text(&mut pieces, separator);
+        }
+
+        match arg {
+            TypedExpr::Interpolation(parts) => {
+                for part in parts {
+                    match part {
+                        TypedPart::Text(value) => text(&mut pieces, value),
+                        TypedPart::Expr(expr) => pieces.push(Piece::Value(expr)),
+                    }
+                }
+            }
+            TypedExpr::String(value) => text(&mut pieces, value),
+            arg => pieces.push(Piece::Value(arg)),
+        }
+    }
+
+    pieces
+}
+
+impl Translator<'_> {
+    fn pointer(&self) -> ValType {
+        self.program.pointer()
+    }
+
+    fn val_type(&self, ty: &Type) -> ValType {
+        self.program.val_type(ty).expect("values aren't `Void`")
+    }
+
+    fn func(&mut self, id: FuncId) -> FuncRef {
+        *self.imported.entry(id).or_insert_with(|| {
+            self.program
+                .module
+                .declare_func_in_func(id, self.builder.func)
+        })
+    }
+
+    fn address(&mut self, id: DataId) -> Value {
+        let pointer = self.pointer();
+        let global = *self.data.entry(id).or_insert_with(|| {
+            self.program
+                .module
+                .declare_data_in_func(id, self.builder.func)
+        });
+
+        self.builder.ins().symbol_value(pointer, global)
+    }
+
+    fn literal(&mut self, value: &str) -> Result<Value, CodegenError> {
+        let id = self.program.string(value)?;
+        Ok(self.address(id))
+    }
+
+    fn null(&mut self) -> Value {
+        let pointer = self.pointer();
+        self.builder.ins().iconst(pointer, 0)
+    }
+
+    // calls the runtime's `name`, which takes what `args` are.
+    fn runtime(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        returns: Option<ValType>,
+    ) -> Result<Option<Value>, CodegenError> {
+        let mut signature = self.program.module.make_signature();
+
+        for arg in args {
+            let ty = self.builder.func.dfg.value_type(*arg);
+            signature.params.push(AbiParam::new(ty));
+        }
+
+        signature.returns.extend(returns.map(AbiParam::new));
+
+        let id = cranelift(self.program.module.declare_function(
+            name,
+            Linkage::Import,
+            &signature,
+        ))?;
+
+        let func = self.func(id);
+        let call = self.builder.ins().call(func, args);
+
+        Ok(self.builder.inst_results(call).first().copied())
+    }
+
+    fn runtime_value(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        ty: ValType,
+    ) -> Result<Value, CodegenError> {
+        Ok(self
+            .runtime(name, args, Some(ty))?
+            .expect("it gives back a value"))
+    }
+
+    // the file and line of the statement being compiled, for a check to say
+    // where it failed.
+    fn location(&mut self) -> Result<Vec<Value>, CodegenError> {
+        let file = self
+            .program
+            .files
+            .get(self.span.file)
+            .cloned()
+            .unwrap_or_default();
+
+        let file = self.literal(&file)?;
+        let line = self.builder.ins().iconst(types::I32, self.span.line as i64);
+
+        Ok(vec![file, line])
+    }
+
+    // runs `fail` only when `failed` is set, carrying on after it otherwise.
+    fn guard(
+        &mut self,
+        failed: Value,
+        fail: impl FnOnce(&mut Self) -> Result<(), CodegenError>,
+    ) -> Result<(), CodegenError> {
+        let (fail_block, next) = (self.builder.create_block(), self.builder.create_block());
+
+        self.builder.ins().brif(failed, fail_block, &[], next, &[]);
+        self.builder.set_cold_block(fail_block);
+
+        self.builder.switch_to_block(fail_block);
+        fail(self)?;
+        self.builder.ins().jump(next, &[]);
+
+        self.builder.switch_to_block(next);
+        Ok(())
+    }
+
+    fn overflow(&mut self, failed: Value, op: &str) -> Result<(), CodegenError> {
+        self.guard(failed, |function| {
+            let mut args = vec![function.literal(op)?];
+            args.extend(function.location()?);
+
+            function.runtime("uma_jit_overflow", &args, None)?;
+            Ok(())
+        })
+    }
+
+    fn divisor(&mut self, rhs: Value) -> Result<(), CodegenError> {
+        let zero = self.builder.ins().icmp_imm_s(IntCC::Equal, rhs, 0);
+
+        self.guard(zero, |function| {
+            let args = function.location()?;
+            function.runtime("uma_jit_divisor", &args, None)?;
+            Ok(())
+        })
+    }
+
+    fn declare(&mut self, name: &str, ty: &Type, value: Value) {
+        let val_type = self.val_type(ty);
+
+        let local = match self.addressed.contains(name) {
+            true => {
+                let slot = self.builder.create_sized_stack_slot(StackSlotData::new(
+                    StackSlotKind::ExplicitSlot,
+                    val_type.bytes(),
+                    val_type.bytes().trailing_zeros() as u8,
+                ));
+
+                let pointer = self.pointer();
+                self.builder.ins().stack_store(pointer, value, slot, 0);
+                Local::Slot(slot)
+            }
+            false => {
+                let variable = self.builder.declare_var(val_type);
+                self.builder.def_var(variable, value);
+                Local::Variable(variable)
+            }
+        };
+
+        self.scopes
+            .last_mut()
+            .expect("there is always a function scope")
+            .insert(name.to_string(), (local, ty.clone()));
+    }
+
+    fn local(&self, name: &str) -> Option<(Local, Type)> {
+        self.scopes.iter().rev().find_map(|s| s.get(name)).cloned()
+    }
+
+    fn get(&mut self, name: &str, ty: &Type) -> Result<Value, CodegenError> {
+        if let Some((local, ty)) = self.local(name) {
+            return Ok(match local {
+                Local::Variable(variable) => self.builder.use_var(variable),
+                Local::Slot(slot) => {
+                    let (pointer, ty) = (self.pointer(), self.val_type(&ty));
+                    self.builder.ins().stack_load(pointer, ty, slot, 0)
+                }
+            });
+        }
+
+        if let Some(value) = self.program.consts.get(name) {
+            return self.expr(&value.clone());
+        }
+
+        let (id, _) = self.program.globals[name];
+        let address = self.address(id);
+        let ty = self.val_type(ty);
+
+        Ok(self
+            .builder
+            .ins()
+            .load(ty, MemFlagsData::trusted(), address, 0))
+    }
+
+    fn set(&mut self, name: &str, value: Value) {
+        match self.local(name) {
+            Some((Local::Variable(variable), _)) => self.builder.def_var(variable, value),
+            Some((Local::Slot(slot), _)) => {
+                let pointer = self.pointer();
+                self.builder.ins().stack_store(pointer, value, slot, 0);
+            }
+            None => {
+                let (id, _) = self.program.globals[name];
+                let address = self.address(id);
+
+                self.builder
+                    .ins()
+                    .store(MemFlagsData::trusted(), value, address, 0);
+            }
+        }
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), CodegenError> {
+        self.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CodegenError> {
+        if let Stmt::Variable { span, .. }
+        | Stmt::Assignment { span, .. }
+        | Stmt::If { span, .. }
+        | Stmt::For { span, .. }
+        | Stmt::Return(_, span)
+        | Stmt::Expr(_, span) = stmt
+        {
+            // the statements the checker adds itself weren't written anywhere.
+            if span.line != 0 {
+                self.span = *span;
+            }
+        }
+
+        match stmt {
+            Stmt::Variable {
+                name, value, ty, ..
+            }
+            | Stmt::Const {
+                name, value, ty, ..
+            } => {
+                let value = self.expr(value)?;
+                self.declare(name, ty, value);
+            }
+            Stmt::Assignment { name, value, .. } => {
+                let value = self.expr(value)?;
+                self.set(name, value);
+            }
+            Stmt::External { .. } | Stmt::Opaque { .. } => (),
+            Stmt::Function { name, .. } => {
+                return unsupported(format!("Nested function `{}`", name))
+            }
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                let condition = self.expr(condition)?;
+
+                let then = self.builder.create_block();
+                let otherwise = self.builder.create_block();
+                let next = self.builder.create_block();
+
+                self.builder
+                    .ins()
+                    .brif(condition, then, &[], otherwise, &[]);
+
+                self.builder.switch_to_block(then);
+                self.block(consequence)?;
+                self.builder.ins().jump(next, &[]);
+
+                self.builder.switch_to_block(otherwise);
+
+                if let Some(alternative) = alternative {
+                    self.stmt(alternative)?;
+                }
+
+                self.builder.ins().jump(next, &[]);
+                self.builder.switch_to_block(next);
+            }
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.scopes.push(HashMap::new());
+
+                let start = self.expr(start)?;
+                self.declare(iterator, &Type::Int, start);
+                let end = self.expr(end)?;
+
+                let header = self.builder.create_block();
+                let looped = self.builder.create_block();
+                let done = self.builder.create_block();
+
+                self.builder.ins().jump(header, &[]);
+                self.builder.switch_to_block(header);
+
+                let i = self.get(iterator, &Type::Int)?;
+                let more = self.builder.ins().icmp(IntCC::SignedLessThan, i, end);
+                self.builder.ins().brif(more, looped, &[], done, &[]);
+
+                self.builder.switch_to_block(looped);
+                self.block(body)?;
+
+                let i = self.get(iterator, &Type::Int)?;
+                let next = self.builder.ins().iadd_imm_s(i, 1);
+                self.set(iterator, next);
+                self.builder.ins().jump(header, &[]);
+
+                self.builder.switch_to_block(done);
+                self.scopes.pop();
+            }
+            Stmt::Block(stmts, _) => self.block(stmts)?,
+            Stmt::Return(value, _) => {
+                let value = match value {
+                    Some(value) => self.eval(value)?,
+                    None => None,
+                };
+
+                self.builder.ins().return_(value.as_slice());
+
+                // whatever comes after it can never run, but still needs
+                // somewhere to go.
+                let unreachable = self.builder.create_block();
+                self.builder.switch_to_block(unreachable);
+            }
+            Stmt::Expr(expr, _) => {
+                self.eval(expr)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) -> Result<Value, CodegenError> {
+        Ok(self.eval(expr)?.expect("only a `Void` call has no value"))
+    }
+
+    // the value of `expr`, which a `Void` one doesn't have.
+    fn eval(&mut self, expr: &TypedExpr) -> Result<Option<Value>, CodegenError> {
+        let value = match expr {
+            TypedExpr::Binary { lhs, op, rhs, ty } => self.binary(lhs, op, rhs, ty)?,
+            TypedExpr::Cast { value, ty } => match &**value {
+                // a literal used as a sized type is written out as one, which
+                // it might not fit in an `Int` to be converted from.
+                TypedExpr::Number(num) if ty.is_integer() => {
+                    let num = num.parse::<i128>().unwrap_or_default();
+                    let ty = self.val_type(ty);
+                    self.builder.ins().iconst(ty, num as i64)
+                }
+                value => {
+                    let from = value.ty();
+                    let value = self.expr(value)?;
+                    self.convert(value, &from, ty)
+                }
+            },
+            TypedExpr::Unary { op, operand, .. } => {
+                let operand = self.expr(operand)?;
+
+                match op.kind {
+                    TokenKind::Not => self.builder.ins().icmp_imm_s(IntCC::Equal, operand, 0),
+                    _ => self.builder.ins().bnot(operand),
+                }
+            }
+            TypedExpr::AddressOf { name, .. } => match self.local(name) {
+                Some((Local::Slot(slot), _)) => {
+                    let pointer = self.pointer();
+                    self.builder.ins().stack_addr(pointer, slot, 0)
+                }
+                Some((Local::Variable(_), _)) => unreachable!("`&` is taken of a slot"),
+                None => {
+                    let (id, _) = self.program.globals[name];
+                    self.address(id)
+                }
+            },
+            TypedExpr::Deref { pointer, ty } => {
+                let pointer = self.expr(pointer)?;
+                let ty = self.val_type(ty);
+
+                self.builder
+                    .ins()
+                    .load(ty, MemFlagsData::trusted(), pointer, 0)
+            }
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ty,
+            } => {
+                let condition = self.expr(condition)?;
+
+                let then = self.builder.create_block();
+                let otherwise = self.builder.create_block();
+                let next = self.builder.create_block();
+
+                self.builder
+                    .ins()
+                    .brif(condition, then, &[], otherwise, &[]);
+
+                for (block, value) in [(then, consequence), (otherwise, alternative)] {
+                    self.builder.switch_to_block(block);
+                    let value = self.eval(value)?;
+                    let args = value.map(BlockArg::Value);
+                    self.builder.ins().jump(next, args.as_slice());
+                }
+
+                self.builder.switch_to_block(next);
+                return Ok(self.merge(next, ty));
+            }
+            // the subject is put in a variable no program can name, and each
+            // arm is tried in turn.
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ty,
+            } => {
+                let name = format!("match#{}", self.matches);
+                self.matches += 1;
+
+                let value = self.expr(subject)?;
+                self.declare(&name, &subject.ty(), value);
+
+                let next = self.builder.create_block();
+
+                for (pattern, value) in arms {
+                    let matches = self.expr(&TypedExpr::Binary {
+                        lhs: TypedExpr::Identifier {
+                            name: name.clone(),
+                            ty: subject.ty(),
+                        }
+                        .into(),
+                        op: Token::new(TokenKind::BinaryEq, None, Span::default()),
+                        rhs: pattern.clone().into(),
+                        ty: Type::Bool,
+                    })?;
+
+                    let (arm, rest) = (self.builder.create_block(), self.builder.create_block());
+                    self.builder.ins().brif(matches, arm, &[], rest, &[]);
+
+                    self.builder.switch_to_block(arm);
+                    let value = self.eval(value)?;
+                    let args = value.map(BlockArg::Value);
+                    self.builder.ins().jump(next, args.as_slice());
+
+                    self.builder.switch_to_block(rest);
+                }
+
+                let value = self.eval(default)?;
+                let args = value.map(BlockArg::Value);
+                self.builder.ins().jump(next, args.as_slice());
+
+                self.builder.switch_to_block(next);
+                return Ok(self.merge(next, ty));
+            }
+            TypedExpr::Call { name, args, ty } if self.program.externals.contains_key(name) => {
+                return self.external(name, args, ty)
+            }
+            TypedExpr::Call { name, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let id = self.program.functions[name];
+                let func = self.func(id);
+                let call = self.builder.ins().call(func, &args);
+
+                return Ok(self.builder.inst_results(call).first().copied());
+            }
+            TypedExpr::Builtin { builtin, args, ty } => return self.builtin(*builtin, args, ty),
+            TypedExpr::Array { elements, ty } => {
+                let element = match ty {
+                    Type::Array(element, _) => self.val_type(element),
+                    ty => unreachable!("array literal of type {:?}", ty),
+                };
+
+                let size = element.bytes() as i64 * elements.len() as i64;
+                let size = self.builder.ins().iconst(types::I64, size);
+                let pointer = self.pointer();
+                let array = self.runtime_value("uma_jit_alloc", &[size], pointer)?;
+
+                for (i, value) in elements.iter().enumerate() {
+                    let value = self.expr(value)?;
+                    let offset = (i as u32 * element.bytes()) as i32;
+
+                    self.builder
+                        .ins()
+                        .store(MemFlagsData::trusted(), value, array, offset);
+                }
+
+                array
+            }
+            TypedExpr::Index { target, index, .. } if target.ty() == Type::String => {
+                let target = self.expr(target)?;
+                let index = self.expr(index)?;
+                let index = self.builder.ins().sextend(types::I64, index);
+                let pointer = self.pointer();
+
+                self.runtime_value("uma_jit_string_at", &[target, index], pointer)?
+            }
+            // a literal that's in bounds needs no checking.
+            TypedExpr::Index { target, index, ty } => {
+                let len = match target.ty() {
+                    Type::Array(_, len) => len,
+                    ty => unreachable!("cannot index a {:?}", ty),
+                };
+
+                let in_bounds = matches!(
+                    &**index,
+                    TypedExpr::Number(n) if n.parse::<usize>().is_ok_and(|n| n < len)
+                );
+
+                let array = self.expr(target)?;
+                let mut i = self.expr(index)?;
+
+                if !self.program.options.release && !in_bounds {
+                    let wide = self.builder.ins().sextend(types::I64, i);
+                    let len = self.builder.ins().iconst(types::I32, len as i64);
+
+                    let mut args = vec![wide, len];
+                    args.extend(self.location()?);
+
+                    i = self.runtime_value("uma_jit_bounds", &args, types::I32)?;
+                }
+
+                let ty = self.val_type(ty);
+                let pointer = self.pointer();
+
+                let i = self.builder.ins().sextend(pointer, i);
+                let offset = self.builder.ins().imul_imm_s(i, ty.bytes() as i64);
+                let address = self.builder.ins().iadd(array, offset);
+
+                self.builder
+                    .ins()
+                    .load(ty, MemFlagsData::trusted(), address, 0)
+            }
+            TypedExpr::Identifier { name, ty } => self.get(name, ty)?,
+            TypedExpr::Number(num) => {
+                let num = num.parse::<i128>().unwrap_or_default();
+                self.builder.ins().iconst(types::I32, num as i64)
+            }
+            TypedExpr::Float(num) => self
+                .builder
+                .ins()
+                .f64const(num.parse::<f64>().unwrap_or_default()),
+            TypedExpr::Bool(value) => self.builder.ins().iconst(types::I8, *value as i64),
+            TypedExpr::Char(value) => self.builder.ins().iconst(types::I8, *value as u8 as i64),
+            TypedExpr::String(value) => self.literal(value)?,
+            // each value is turned into a string and added onto the ones
+            // before it.
+            TypedExpr::Interpolation(parts) => {
+                let arg = TypedExpr::Interpolation(parts.clone());
+                let mut out = self.literal("")?;
+
+                for piece in pieces(std::slice::from_ref(&arg), "") {
+                    let piece = match piece {
+                        Piece::Text(text) => self.literal(&text)?,
+                        Piece::Value(value) => self.show(value, false)?,
+                    };
+
+                    let pointer = self.pointer();
+                    out = self.runtime_value("uma_jit_concat", &[out, piece], pointer)?;
+                }
+
+                out
+            }
+        };
+
+        Ok(Some(value))
+    }
+
+    // the value an `if` or `match` gives `next`, if it gives one.
+    fn merge(&mut self, next: cranelift_codegen::ir::Block, ty: &Type) -> Option<Value> {
+        let ty = self.program.val_type(ty)?;
+        Some(self.builder.append_block_param(next, ty))
+    }
+
+    fn binary(
+        &mut self,
+        lhs: &TypedExpr,
+        op: &Token,
+        rhs: &TypedExpr,
+        ty: &Type,
+    ) -> Result<Value, CodegenError> {
+        let l = self.expr(lhs)?;
+
+        // the right side of `&&` and `||` only runs when it decides the result.
+        if let TokenKind::And | TokenKind::Or = op.kind {
+            let (right, next) = (self.builder.create_block(), self.builder.create_block());
+            let result = self.builder.append_block_param(next, types::I8);
+
+            match op.kind {
+                TokenKind::And => {
+                    self.builder
+                        .ins()
+                        .brif(l, right, &[], next, &[BlockArg::Value(l)])
+                }
+                _ => self
+                    .builder
+                    .ins()
+                    .brif(l, next, &[BlockArg::Value(l)], right, &[]),
+            };
+
+            self.builder.switch_to_block(right);
+            let r = self.expr(rhs)?;
+            self.builder.ins().jump(next, &[BlockArg::Value(r)]);
+
+            self.builder.switch_to_block(next);
+            return Ok(result);
+        }
+
+        let r = self.expr(rhs)?;
+        let operands = lhs.ty();
+
+        if operands == Type::String {
+            let pointer = self.pointer();
+
+            if op.kind == TokenKind::Add {
+                return self.runtime_value("uma_jit_concat", &[l, r], pointer);
+            }
+
+            let order = self.runtime_value("uma_jit_compare", &[l, r], types::I32)?;
+            let zero = self.builder.ins().iconst(types::I32, 0);
+
+            return match int_cc(&op.kind, true) {
+                Some(cc) => Ok(self.builder.ins().icmp(cc, order, zero)),
+                None => unsupported_at(format!("The `{:?}` operator on strings", op.kind), op.span),
+            };
+        }
+
+        // through `pow` on doubles, like the C backend.
+        if op.kind == TokenKind::Expo {
+            let (l, r) = (
+                self.convert(l, &operands, &Type::Float),
+                self.convert(r, &operands, &Type::Float),
+            );
+
+            let result = self.runtime_value("uma_jit_pow", &[l, r], types::F64)?;
+            return Ok(self.convert(result, &Type::Float, ty));
+        }
+
+        if matches!(operands, Type::Float | Type::Float32) {
+            let ins = self.builder.ins();
+
+            return Ok(match op.kind {
+                TokenKind::Add => ins.fadd(l, r),
+                TokenKind::Sub => ins.fsub(l, r),
+                TokenKind::Multi => ins.fmul(l, r),
+                TokenKind::Div => ins.fdiv(l, r),
+                ref kind => match float_cc(kind) {
+                    Some(cc) => ins.fcmp(cc, l, r),
+                    None => {
+                        return unsupported_at(
+                            format!("The `{:?}` operator on floats", kind),
+                            op.span,
+                        )
+                    }
+                },
+            });
+        }
+
+        self.integer(&op.kind, &operands, l, r, op.span)
+    }
+
+    // `op` on two integers of type `ty`, checked the way the C backend
+    // checks them.
+    fn integer(
+        &mut self,
+        op: &TokenKind,
+        ty: &Type,
+        l: Value,
+        r: Value,
+        span: Span,
+    ) -> Result<Value, CodegenError> {
+        let is_signed = signed(ty);
+        let checks = self.program.options.checks;
+
+        if let Some(cc) = int_cc(op, is_signed) {
+            return Ok(self.builder.ins().icmp(cc, l, r));
+        }
+
+        Ok(match op {
+            TokenKind::Add if checks && is_signed => {
+                let (value, overflowed) = self.builder.ins().sadd_overflow(l, r);
+                self.overflow(overflowed, "+")?;
+                value
+            }
+            TokenKind::Sub if checks && is_signed => {
+                let (value, overflowed) = self.builder.ins().ssub_overflow(l, r);
+                self.overflow(overflowed, "-")?;
+                value
+            }
+            TokenKind::Multi if checks && is_signed => {
+                let (value, overflowed) = self.builder.ins().smul_overflow(l, r);
+                self.overflow(overflowed, "*")?;
+                value
+            }
+            TokenKind::Div | TokenKind::Mod if checks => {
+                self.divisor(r)?;
+
+                let (min, _) = ty.range().expect("it's an integer");
+
+                if *op == TokenKind::Div && is_signed {
+                    let smallest = self.builder.ins().icmp_imm_s(IntCC::Equal, l, min as i64);
+                    let negative = self.builder.ins().icmp_imm_s(IntCC::Equal, r, -1);
+                    let overflowed = self.builder.ins().band(smallest, negative);
+
+                    self.overflow(overflowed, "/")?;
+                }
+
+                return self.integer_op(op, is_signed, l, r, span);
+            }
+            op => return self.integer_op(op, is_signed, l, r, span),
+        })
+    }
+
+    fn integer_op(
+        &mut self,
+        op: &TokenKind,
+        is_signed: bool,
+        l: Value,
+        r: Value,
+        span: Span,
+    ) -> Result<Value, CodegenError> {
+        let ins = self.builder.ins();
+
+        Ok(match (op, is_signed) {
+            (TokenKind::Add, _) => ins.iadd(l, r),
+            (TokenKind::Sub, _) => ins.isub(l, r),
+            (TokenKind::Multi, _) => ins.imul(l, r),
+            (TokenKind::Div, true) => ins.sdiv(l, r),
+            (TokenKind::Div, false) => ins.udiv(l, r),
+            (TokenKind::Mod, true) => ins.srem(l, r),
+            (TokenKind::Mod, false) => ins.urem(l, r),
+            (TokenKind::BitAnd, _) => ins.band(l, r),
+            (TokenKind::BitOr, _) => ins.bor(l, r),
+            (TokenKind::BitXor, _) => ins.bxor(l, r),
+            (TokenKind::Shl, _) => ins.ishl(l, r),
+            (TokenKind::Shr, true) => ins.sshr(l, r),
+            (TokenKind::Shr, false) => ins.ushr(l, r),
+            (kind, _) => return unsupported_at(format!("The `{:?}` operator", kind), span),
+        })
+    }
+
+    // `value` of type `from` as a `to`, the way C converts it.
+    fn convert(&mut self, value: Value, from: &Type, to: &Type) -> Value {
+        let (from_type, to_type) = (self.val_type(from), self.val_type(to));
+        let ins = self.builder.ins();
+
+        match (from_type.is_float(), to_type.is_float()) {
+            (true, true) if from_type.bits() < to_type.bits() => ins.fpromote(to_type, value),
+            (true, true) if from_type.bits() > to_type.bits() => ins.fdemote(to_type, value),
+            (true, true) => value,
+            (false, true) if signed(from) => ins.fcvt_from_sint(to_type, value),
+            (false, true) => ins.fcvt_from_uint(to_type, value),
+            (true, false) if *to == Type::Bool => {
+                let zero = ins.f64const(0.0);
+                let zero = match from_type == types::F64 {
+                    true => zero,
+                    false => self.builder.ins().fdemote(from_type, zero),
+                };
+
+                self.builder.ins().fcmp(FloatCC::NotEqual, value, zero)
+            }
+            (true, false) => {
+                let wide = match signed(to) {
+                    true => ins.fcvt_to_sint_sat(types::I64, value),
+                    false => self.builder.ins().fcvt_to_uint_sat(types::I64, value),
+                };
+
+                match to_type {
+                    types::I64 => wide,
+                    to_type => self.builder.ins().ireduce(to_type, wide),
+                }
+            }
+            (false, false) if *to == Type::Bool && *from != Type::Bool => {
+                ins.icmp_imm_s(IntCC::NotEqual, value, 0)
+            }
+            (false, false) if from_type.bits() < to_type.bits() && signed(from) => {
+                ins.sextend(to_type, value)
+            }
+            (false, false) if from_type.bits() < to_type.bits() => ins.uextend(to_type, value),
+            (false, false) if from_type.bits() > to_type.bits() => ins.ireduce(to_type, value),
+            (false, false) => value,
+        }
+    }
+
+    // an external is handed the characters of a string, and a null one it
+    // gives back is taken as empty.
+    fn external(
+        &mut self,
+        name: &str,
+        args: &[TypedExpr],
+        ty: &Type,
+    ) -> Result<Option<Value>, CodegenError> {
+        let (id, arity, is_varadic) = {
+            let external = &self.program.externals[name];
+            (external.id, external.arity, external.is_varadic)
+        };
+
+        let mut values = vec![];
+
+        for arg in &args[..arity] {
+            values.push(self.expr(arg)?);
+        }
+
+        let func = self.func(id);
+
+        let call = match is_varadic {
+            false => self.builder.ins().call(func, &values),
+            true => {
+                let mut signature = self.program.externals[name].signature.clone();
+
+                for arg in &args[arity..] {
+                    let value = self.expr(arg)?;
+                    let value = self.variadic(value, &arg.ty())?;
+
+                    signature
+                        .params
+                        .push(AbiParam::new(self.builder.func.dfg.value_type(value)));
+                    values.push(value);
+                }
+
+                let pointer = self.pointer();
+                let address = self.builder.ins().func_addr(pointer, func);
+                let signature = self.builder.import_signature(signature);
+
+                self.builder
+                    .ins()
+                    .call_indirect(signature, address, &values)
+            }
+        };
+
+        let result = self.builder.inst_results(call).first().copied();
+
+        Ok(match (result, ty) {
+            (Some(result), Type::String) => {
+                let empty = self.literal("")?;
+                let null = self.builder.ins().icmp_imm_s(IntCC::Equal, result, 0);
+
+                Some(self.builder.ins().select(null, empty, result))
+            }
+            (result, _) => result,
+        })
+    }
+
+    // an argument passed through a `...`, promoted the way C promotes it.
+    // cranelift can't say how many of them went in vector registers, which
+    // the C ABI wants for floats, or put them on the stack like Apple's
+    // arm64 ABI does for all of them.
+    fn variadic(&mut self, value: Value, ty: &Type) -> Result<Value, CodegenError> {
+        if cfg!(all(target_vendor = "apple", target_arch = "aarch64")) {
+            return unsupported_at("Passing arguments through `...` on this machine", self.span);
+        }
+
+        if matches!(ty, Type::Float | Type::Float32) {
+            return unsupported_at(format!("Passing a `{}` through `...`", ty), self.span);
+        }
+
+        Ok(match self.val_type(ty).bits() {
+            bits if bits < 32 => self.convert(value, ty, &Type::Int),
+            _ => value,
+        })
+    }
+
+    fn builtin(
+        &mut self,
+        builtin: Builtin,
+        args: &[TypedExpr],
+        ty: &Type,
+    ) -> Result<Option<Value>, CodegenError> {
+        let pointer = self.pointer();
+
+        let value = match builtin {
+            Builtin::Print | Builtin::Println => {
+                let separator = match builtin {
+                    Builtin::Println => "\n",
+                    _ => "",
+                };
+
+                let mut pieces = pieces(args, " ");
+                pieces.push(Piece::Text(separator.to_string()));
+
+                for piece in pieces {
+                    match piece {
+                        Piece::Text(text) if text.is_empty() => (),
+                        Piece::Text(text) => {
+                            let text = self.literal(&text)?;
+                            self.runtime("uma_jit_print_str", &[text], None)?;
+                        }
+                        Piece::Value(value) => {
+                            self.show(value, true)?;
+                        }
+                    }
+                }
+
+                return Ok(None);
+            }
+            Builtin::Len => match args[0].ty() {
+                Type::Array(_, len) => self.builder.ins().iconst(types::I32, len as i64),
+                _ => {
+                    let s = self.expr(&args[0])?;
+                    self.runtime_value("uma_jit_len", &[s], types::I32)?
+                }
+            },
+            Builtin::Assert => {
+                if self.program.options.release {
+                    return Ok(None);
+                }
+
+                let condition = self.expr(&args[0])?;
+                let failed = self.builder.ins().icmp_imm_s(IntCC::Equal, condition, 0);
+
+                self.guard(failed, |function| {
+                    let source = function.expr(&args[1])?;
+                    let message = match args.get(2) {
+                        Some(message) => function.expr(message)?,
+                        None => function.null(),
+                    };
+
+                    let mut args = vec![source, message];
+                    args.extend(function.location()?);
+
+                    function.runtime("uma_jit_assert", &args, None)?;
+                    Ok(())
+                })?;
+
+                return Ok(None);
+            }
+            Builtin::Abs | Builtin::Min | Builtin::Max => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let ins = self.builder.ins();
+
+                match (builtin, ty) {
+                    (Builtin::Abs, Type::Float | Type::Float32) => ins.fabs(values[0]),
+                    (Builtin::Min, Type::Float | Type::Float32) => ins.fmin(values[0], values[1]),
+                    (Builtin::Max, Type::Float | Type::Float32) => ins.fmax(values[0], values[1]),
+                    // an unsigned number is its own `abs`.
+                    (Builtin::Abs, ty) if signed(ty) => ins.iabs(values[0]),
+                    (Builtin::Abs, _) => values[0],
+                    (Builtin::Min, ty) if signed(ty) => ins.smin(values[0], values[1]),
+                    (Builtin::Min, _) => ins.umin(values[0], values[1]),
+                    (_, ty) if signed(ty) => ins.smax(values[0], values[1]),
+                    _ => ins.umax(values[0], values[1]),
+                }
+            }
+            Builtin::Sqrt | Builtin::Floor | Builtin::Ceil => {
+                let x = self.expr(&args[0])?;
+                let ins = self.builder.ins();
+
+                match builtin {
+                    Builtin::Sqrt => ins.sqrt(x),
+                    Builtin::Floor => ins.floor(x),
+                    _ => ins.ceil(x),
+                }
+            }
+            Builtin::Round => {
+                let x = self.expr(&args[0])?;
+                self.runtime_value("uma_jit_round", &[x], types::F64)?
+            }
+            Builtin::Pow => {
+                let (x, y) = (self.expr(&args[0])?, self.expr(&args[1])?);
+                self.runtime_value("uma_jit_pow", &[x, y], types::F64)?
+            }
+            Builtin::Input => {
+                let prompt = match args.first() {
+                    Some(prompt) => self.expr(prompt)?,
+                    None => self.null(),
+                };
+
+                self.runtime_value("uma_jit_input", &[prompt], pointer)?
+            }
+            Builtin::ReadFile if args.len() == 2 => {
+                let path = self.expr(&args[0])?;
+                let fallback = self.expr(&args[1])?;
+
+                self.runtime_value("uma_jit_read_file_or", &[path, fallback], pointer)?
+            }
+            Builtin::ParseInt | Builtin::ParseFloat | Builtin::ReadFile => {
+                let mut values = vec![self.expr(&args[0])?];
+                values.extend(self.location()?);
+
+                match builtin {
+                    Builtin::ParseInt => {
+                        self.runtime_value("uma_jit_parse_int", &values, types::I32)?
+                    }
+                    Builtin::ParseFloat => {
+                        self.runtime_value("uma_jit_parse_float", &values, types::F64)?
+                    }
+                    _ => self.runtime_value("uma_jit_read_file", &values, pointer)?,
+                }
+            }
+            Builtin::WriteFile | Builtin::AppendFile => {
+                let path = self.expr(&args[0])?;
+                let contents = self.expr(&args[1])?;
+                let append = self
+                    .builder
+                    .ins()
+                    .iconst(types::I32, (builtin == Builtin::AppendFile) as i64);
+
+                let written = self.runtime_value(
+                    "uma_jit_write_file",
+                    &[path, contents, append],
+                    types::I32,
+                )?;
+
+                self.builder.ins().icmp_imm_s(IntCC::NotEqual, written, 0)
+            }
+            Builtin::ArgCount => self.runtime_value("uma_jit_arg_count", &[], types::I32)?,
+            Builtin::Arg => {
+                let index = self.expr(&args[0])?;
+                let index = self.builder.ins().sextend(types::I64, index);
+
+                let mut values = vec![index];
+                values.extend(self.location()?);
+
+                self.runtime_value("uma_jit_arg", &values, pointer)?
+            }
+            Builtin::Exit => {
+                let status = self.expr(&args[0])?;
+                self.runtime("uma_jit_exit", &[status], None)?;
+
+                return Ok(None);
+            }
+            Builtin::Env => {
+                let name = self.expr(&args[0])?;
+                self.runtime_value("uma_jit_env", &[name], pointer)?
+            }
+            Builtin::SetEnv => {
+                let mut values = vec![self.expr(&args[0])?, self.expr(&args[1])?];
+                values.extend(self.location()?);
+
+                self.runtime("uma_jit_set_env", &values, None)?;
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(value))
+    }
+
+    // `value` printed, or made into a string when `print` isn't set.
+    fn show(&mut self, value: &TypedExpr, print: bool) -> Result<Value, CodegenError> {
+        let ty = value.ty();
+        let value = self.expr(value)?;
+
+        let (name, value) = match ty {
+            Type::String => ("str", value),
+            Type::Bool => {
+                let (yes, no) = (self.literal("true")?, self.literal("false")?);
+                ("str", self.builder.ins().select(value, yes, no))
+            }
+            Type::Char => ("char", self.convert(value, &Type::Char, &Type::Int)),
+            Type::Float => ("float", value),
+            Type::Float32 => ("float", self.convert(value, &Type::Float32, &Type::Float)),
+            ty if signed(&ty) => ("int", self.convert(value, &ty, &Type::Int64)),
+            ty => ("uint", self.convert(value, &ty, &Type::UInt64)),
+        };
+
+        let pointer = self.pointer();
+
+        match (print, name) {
+            (true, name) => {
+                self.runtime(&format!("uma_jit_print_{}", name), &[value], None)?;
+                Ok(value)
+            }
+            (false, "str") => Ok(value),
+            (false, name) => {
+                self.runtime_value(&format!("uma_jit_{}_string", name), &[value], pointer)
+            }
+        }
+    }
+}
+
+// the comparison `op` is on integers, if it's one.
+fn int_cc(op: &TokenKind, is_signed: bool) -> Option<IntCC> {
+    Some(match (op, is_signed) {
+        (TokenKind::BinaryEq, _) => IntCC::Equal,
+        (TokenKind::BinaryNeq, _) => IntCC::NotEqual,
+        (TokenKind::BinaryGt, true) => IntCC::SignedGreaterThan,
+        (TokenKind::BinaryGte, true) => IntCC::SignedGreaterThanOrEqual,
+        (TokenKind::BinaryLt, true) => IntCC::SignedLessThan,
+        (TokenKind::BinaryLte, true) => IntCC::SignedLessThanOrEqual,
+        (TokenKind::BinaryGt, false) => IntCC::UnsignedGreaterThan,
+        (TokenKind::BinaryGte, false) => IntCC::UnsignedGreaterThanOrEqual,
+        (TokenKind::BinaryLt, false) => IntCC::UnsignedLessThan,
+        (TokenKind::BinaryLte, false) => IntCC::UnsignedLessThanOrEqual,
+        _ => return None,
+    })
+}
+
+fn float_cc(op: &TokenKind) -> Option<FloatCC> {
+    Some(match op {
+        TokenKind::BinaryEq => FloatCC::Equal,
+        TokenKind::BinaryNeq => FloatCC::NotEqual,
+        TokenKind::BinaryGt => FloatCC::GreaterThan,
+        TokenKind::BinaryGte => FloatCC::GreaterThanOrEqual,
+        TokenKind::BinaryLt => FloatCC::LessThan,
+        TokenKind::BinaryLte => FloatCC::LessThanOrEqual,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,374 @@
+// what the code the JIT generates calls into, for what the C backend has
+// `uma_rt.c` for. the messages are the same as that runtime's. strings are
+// pointers to NUL-terminated bytes, and what's made while the program runs
+// is only freed once it's finished.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fs,
+    io::{self, BufRead},
+    ptr,
+};
+
+use crate::interpreter::Value;
+
+// the program's output goes through C's `stdout`, the same as whatever the
+// externals it calls print.
+extern "C" {
+    fn putchar(c: c_int) -> c_int;
+    fn fflush(stream: *mut c_void) -> c_int;
+}
+
+thread_local! {
+    static ARENA: RefCell<Vec<Box<[u64]>>> = const { RefCell::new(vec![]) };
+    static ARGS: RefCell<Vec<CString>> = const { RefCell::new(vec![]) };
+}
+
+// the functions the generated code calls, under the names it calls them by.
+pub fn symbols() -> Vec<(&'static str, *const u8)> {
+    vec![
+        ("uma_jit_alloc", uma_jit_alloc as *const u8),
+        ("uma_jit_print_str", uma_jit_print_str as *const u8),
+        ("uma_jit_print_int", uma_jit_print_int as *const u8),
+        ("uma_jit_print_uint", uma_jit_print_uint as *const u8),
+        ("uma_jit_print_float", uma_jit_print_float as *const u8),
+        ("uma_jit_print_char", uma_jit_print_char as *const u8),
+        ("uma_jit_int_string", uma_jit_int_string as *const u8),
+        ("uma_jit_uint_string", uma_jit_uint_string as *const u8),
+        ("uma_jit_float_string", uma_jit_float_string as *const u8),
+        ("uma_jit_char_string", uma_jit_char_string as *const u8),
+        ("uma_jit_concat", uma_jit_concat as *const u8),
+        ("uma_jit_compare", uma_jit_compare as *const u8),
+        ("uma_jit_len", uma_jit_len as *const u8),
+        ("uma_jit_string_at", uma_jit_string_at as *const u8),
+        ("uma_jit_bounds", uma_jit_bounds as *const u8),
+        ("uma_jit_overflow", uma_jit_overflow as *const u8),
+        ("uma_jit_divisor", uma_jit_divisor as *const u8),
+        ("uma_jit_assert", uma_jit_assert as *const u8),
+        ("uma_jit_exit", uma_jit_exit as *const u8),
+        ("uma_jit_pow", uma_jit_pow as *const u8),
+        ("uma_jit_round", uma_jit_round as *const u8),
+        ("uma_jit_input", uma_jit_input as *const u8),
+        ("uma_jit_parse_int", uma_jit_parse_int as *const u8),
+        ("uma_jit_parse_float", uma_jit_parse_float as *const u8),
+        ("uma_jit_read_file", uma_jit_read_file as *const u8),
+        ("uma_jit_read_file_or", uma_jit_read_file_or as *const u8),
+        ("uma_jit_write_file", uma_jit_write_file as *const u8),
+        ("uma_jit_arg_count", uma_jit_arg_count as *const u8),
+        ("uma_jit_arg", uma_jit_arg as *const u8),
+        ("uma_jit_env", uma_jit_env as *const u8),
+        ("uma_jit_set_env", uma_jit_set_env as *const u8),
+    ]
+}
+
+// what `arg` gives back, the first being the program itself.
+pub fn set_args(args: &[String]) {
+    let args = args
+        .iter()
+        .map(|arg| CString::new(arg.as_str()).unwrap_or_default())
+        .collect();
+
+    ARGS.with(|cell| *cell.borrow_mut() = args);
+}
+
+// once the program has finished, what it printed is written out and what it
+// made is freed.
+pub fn finish() {
+    flush();
+    ARENA.with(|arena| arena.borrow_mut().clear());
+}
+
+fn flush() {
+    // SAFETY: a null stream flushes every one C has open.
+    unsafe { fflush(ptr::null_mut()) };
+}
+
+fn panic(message: String) -> ! {
+    // whatever the program printed before it panicked comes first.
+    flush();
+    eprintln!("panic: {}", message);
+    std::process::exit(101)
+}
+
+fn bytes<'a>(s: *const c_char) -> &'a [u8] {
+    // SAFETY: every string the generated code passes is NUL-terminated, and
+    // lives until the program finishes.
+    unsafe { CStr::from_ptr(s) }.to_bytes()
+}
+
+fn text(s: *const c_char) -> String {
+    String::from_utf8_lossy(bytes(s)).into_owned()
+}
+
+fn print(bytes: &[u8]) {
+    for byte in bytes {
+        // SAFETY: `putchar` takes any byte.
+        unsafe { putchar(*byte as c_int) };
+    }
+}
+
+// a string that lasts until the program finishes. a NUL in `bytes` ends it
+// early, like it would in C.
+fn string(bytes: &[u8]) -> *const c_char {
+    let out = uma_jit_alloc(bytes.len() as i64 + 1) as *mut u8;
+
+    // SAFETY: `out` has room for `bytes` and the NUL after them, which is
+    // already there.
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len()) };
+
+    out as *const c_char
+}
+
+// `size` zeroed bytes, aligned for anything the program keeps in them.
+extern "C" fn uma_jit_alloc(size: i64) -> *mut c_void {
+    let mut block = vec![0u64; (size.max(1) as usize).div_ceil(8)].into_boxed_slice();
+    let out = block.as_mut_ptr() as *mut c_void;
+
+    ARENA.with(|arena| arena.borrow_mut().push(block));
+    out
+}
+
+extern "C" fn uma_jit_print_str(s: *const c_char) {
+    print(bytes(s));
+}
+
+extern "C" fn uma_jit_print_int(n: i64) {
+    print(n.to_string().as_bytes());
+}
+
+extern "C" fn uma_jit_print_uint(n: u64) {
+    print(n.to_string().as_bytes());
+}
+
+extern "C" fn uma_jit_print_float(x: f64) {
+    print(Value::Float(x).to_string().as_bytes());
+}
+
+extern "C" fn uma_jit_print_char(c: c_int) {
+    print(&[c as u8]);
+}
+
+extern "C" fn uma_jit_int_string(n: i64) -> *const c_char {
+    string(n.to_string().as_bytes())
+}
+
+extern "C" fn uma_jit_uint_string(n: u64) -> *const c_char {
+    string(n.to_string().as_bytes())
+}
+
+extern "C" fn uma_jit_float_string(x: f64) -> *const c_char {
+    string(Value::Float(x).to_string().as_bytes())
+}
+
+extern "C" fn uma_jit_char_string(c: c_int) -> *const c_char {
+    string(&[c as u8])
+}
+
+extern "C" fn uma_jit_concat(a: *const c_char, b: *const c_char) -> *const c_char {
+    string(&[bytes(a), bytes(b)].concat())
+}
+
+extern "C" fn uma_jit_compare(a: *const c_char, b: *const c_char) -> c_int {
+    bytes(a).cmp(bytes(b)) as c_int
+}
+
+extern "C" fn uma_jit_len(s: *const c_char) -> c_int {
+    bytes(s).len() as c_int
+}
+
+extern "C" fn uma_jit_string_at(s: *const c_char, index: i64) -> *const c_char {
+    let s = bytes(s);
+
+    match usize::try_from(index).ok().and_then(|i| s.get(i)) {
+        Some(byte) => string(&[*byte]),
+        None => panic(format!(
+            "index {} is out of bounds for a string of length {}",
+            index,
+            s.len()
+        )),
+    }
+}
+
+extern "C" fn uma_jit_bounds(index: i64, len: c_int, file: *const c_char, line: c_int) -> c_int {
+    if index < 0 || index >= len as i64 {
+        panic(format!(
+            "index {} out of bounds for length {} at {}:{}",
+            index,
+            len,
+            text(file),
+            line
+        ));
+    }
+
+    index as c_int
+}
+
+extern "C" fn uma_jit_overflow(op: *const c_char, file: *const c_char, line: c_int) {
+    panic(format!(
+        "integer overflow in `{}` at {}:{}",
+        text(op),
+        text(file),
+        line
+    ));
+}
+
+extern "C" fn uma_jit_divisor(file: *const c_char, line: c_int) {
+    panic(format!("division by zero at {}:{}", text(file), line));
+}
+
+extern "C" fn uma_jit_assert(
+    source: *const c_char,
+    message: *const c_char,
+    file: *const c_char,
+    line: c_int,
+) {
+    let failed = format!(
+        "assertion `{}` failed at {}:{}",
+        text(source),
+        text(file),
+        line
+    );
+
+    match message.is_null() {
+        true => panic(failed),
+        false => panic(format!("{}: {}", failed, text(message))),
+    }
+}
+
+extern "C" fn uma_jit_exit(status: c_int) {
+    flush();
+    std::process::exit(status);
+}
+
+extern "C" fn uma_jit_pow(x: f64, y: f64) -> f64 {
+    x.powf(y)
+}
+
+// away from zero, like C's `round`.
+extern "C" fn uma_jit_round(x: f64) -> f64 {
+    x.round()
+}
+
+extern "C" fn uma_jit_input(prompt: *const c_char) -> *const c_char {
+    if !prompt.is_null() {
+        print(bytes(prompt));
+    }
+
+    // whatever was printed before has to be seen before waiting on a reply.
+    flush();
+
+    let mut line = vec![];
+    let _ = io::stdin().lock().read_until(b'\n', &mut line);
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    string(&line)
+}
+
+extern "C" fn uma_jit_parse_int(s: *const c_char, file: *const c_char, line: c_int) -> c_int {
+    match text(s).trim().parse::<i32>() {
+        Ok(n) => n,
+        Err(_) => panic(format!(
+            "cannot parse \"{}\" as an `Int` at {}:{}",
+            text(s),
+            text(file),
+            line
+        )),
+    }
+}
+
+extern "C" fn uma_jit_parse_float(s: *const c_char, file: *const c_char, line: c_int) -> f64 {
+    match text(s).trim().parse::<f64>() {
+        Ok(x) => x,
+        Err(_) => panic(format!(
+            "cannot parse \"{}\" as a `Float` at {}:{}",
+            text(s),
+            text(file),
+            line
+        )),
+    }
+}
+
+extern "C" fn uma_jit_read_file(
+    path: *const c_char,
+    file: *const c_char,
+    line: c_int,
+) -> *const c_char {
+    match fs::read(text(path)) {
+        Ok(contents) => string(&contents),
+        Err(err) => panic(format!(
+            "cannot read `{}`: {} at {}:{}",
+            text(path),
+            err,
+            text(file),
+            line
+        )),
+    }
+}
+
+extern "C" fn uma_jit_read_file_or(path: *const c_char, fallback: *const c_char) -> *const c_char {
+    match fs::read(text(path)) {
+        Ok(contents) => string(&contents),
+        Err(_) => fallback,
+    }
+}
+
+extern "C" fn uma_jit_write_file(
+    path: *const c_char,
+    contents: *const c_char,
+    append: c_int,
+) -> c_int {
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append != 0)
+        .truncate(append == 0)
+        .open(text(path))
+        .and_then(|mut f| io::Write::write_all(&mut f, bytes(contents)));
+
+    written.is_ok() as c_int
+}
+
+extern "C" fn uma_jit_arg_count() -> c_int {
+    ARGS.with(|args| args.borrow().len() as c_int)
+}
+
+extern "C" fn uma_jit_arg(index: i64, file: *const c_char, line: c_int) -> *const c_char {
+    let len = ARGS.with(|args| args.borrow().len());
+    let index = uma_jit_bounds(index, len as c_int, file, line);
+
+    ARGS.with(|args| string(args.borrow()[index as usize].as_bytes()))
+}
+
+extern "C" fn uma_jit_env(name: *const c_char) -> *const c_char {
+    let value = std::env::var_os(text(name)).unwrap_or_default();
+    string(value.as_encoded_bytes())
+}
+
+extern "C" fn uma_jit_set_env(
+    name: *const c_char,
+    value: *const c_char,
+    file: *const c_char,
+    line: c_int,
+) {
+    let (name, value) = (text(name), text(value));
+
+    // what `set_var` would panic on.
+    if name.is_empty() || name.contains('=') {
+        panic(format!(
+            "cannot set `{}`: Invalid argument at {}:{}",
+            name,
+            text(file),
+            line
+        ));
+    }
+
+    std::env::set_var(name, value);
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typeck::TypeChecker;
+
+fn typed(src: &str) -> Vec<TypedStmt> {
+    let tokens = Lexer::new(src).lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let (typed, _) = TypeChecker::check(ast).unwrap();
+
+    typed
+}
+
+fn run(src: &str) -> Result<i32, CodegenError> {
+    Jit::default().run(&typed(src), &CodegenOptions::new(""), &["test".into()])
+}
+
+#[test]
+fn main_gives_the_status() {
+    let src = "func fib(n: int): int {
+        if (n < 2) { return n; }
+        return fib(n - 1) + fib(n - 2);
+    }
+
+    func main(): int { return fib(10); }";
+
+    assert_eq!(run(src), Ok(55));
+    assert_eq!(run("func main(): void { let x = 1; }"), Ok(0));
+}
+
+#[test]
+fn globals_are_set_before_main() {
+    let src = "let mut total = 40;
+    const STEP = 2;
+
+    func bump(): void { total += STEP; }
+    func main(): int { bump(); return total; }";
+
+    assert_eq!(run(src), Ok(42));
+}
+
+#[test]
+fn sized_integers_wrap_like_c() {
+    let src = "func main(): int {
+        let small: UInt8 = 250;
+        let max: Int8 = 127;
+        let big = 5000000000i64;
+        let s = 40;
+        return (small + 10u8) as int + (max + 1i8) as int + (big / 1000000000i64) as int + (1 << s);
+    }";
+
+    // 4 - 128 + 5 + 256, with the shift's count wrapped to 8.
+    assert_eq!(run(src), Ok(137));
+}
+
+#[test]
+fn pointers_are_to_the_stack() {
+    let src = r#"func sscanf(s: String, format: String, ...): int @requires("stdio.h")
+    func next(p: Ptr<Int>): int { return *p + 1; }
+
+    func main(): int {
+        let mut n = 0;
+        sscanf("41", "%d", &n);
+        return next(&n);
+    }"#;
+
+    assert_eq!(run(src), Ok(42));
+}
+
+#[test]
+fn missing_externals() {
+    let src = r#"func uma_no_such_function(x: int): int @requires("stdio.h")
+    func main(): int { return uma_no_such_function(1); }"#;
+
+    let err = run(src).unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "Couldn't find the external `uma_no_such_function` to call"
+    );
+}
+
+#[test]
+fn needs_a_main() {
+    let err = run("func helper(): int { return 1; }").unwrap_err();
+    assert_eq!(err.message, "The JIT can only run a program with a `main`");
+}
@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+
+use crate::ir::Stmt;
+use crate::typeck::{TypedExpr, TypedPart};
+
+// the names C or the headers the generated code includes already use.
+const RESERVED: &[&str] = &[
+    // keywords, up to C23.
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "nullptr",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "struct",
+    "switch",
+    "thread_local",
+    "true",
+    "typedef",
+    "typeof",
+    "typeof_unqual",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    // what the helpers and the prelude call.
+    "printf",
+    "puts",
+    "malloc",
+    "free",
+    "strlen",
+    "strcpy",
+    "strcat",
+    "strcmp",
+    "memcpy",
+    "pow",
+    "abs",
+    "llabs",
+    "fabs",
+    "fmin",
+    "fmax",
+    "sqrt",
+    "floor",
+    "ceil",
+    "round",
+    "vsnprintf",
+    "va_list",
+    "va_start",
+    "va_end",
+    "va_arg",
+    // and what the runtime calls.
+    "exit",
+    "fflush",
+    "fputs",
+    "fputc",
+    "vfprintf",
+    "fgetc",
+    "realloc",
+    "strtoll",
+    "strtod",
+    "isspace",
+    "fopen",
+    "fclose",
+    "fread",
+    "fwrite",
+    "ferror",
+    "strerror",
+    "getenv",
+    "setenv",
+    "_putenv_s",
+    "UmaString",
+    "UMA_STR",
+    // and some of the macros and types that come with their headers.
+    "NULL",
+    "EOF",
+    "BUFSIZ",
+    "RAND_MAX",
+    "EXIT_SUCCESS",
+    "EXIT_FAILURE",
+    "stdin",
+    "stdout",
+    "stderr",
+    "errno",
+    "size_t",
+    "int8_t",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "uint8_t",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+    "INFINITY",
+    "NAN",
+    "HUGE_VAL",
+];
+
+// `program` with every name it declares made safe to use in C. a name that's
+// reserved, starts with `_` (C keeps those for itself), has `__` in it or
+// starts like an escaped name is escaped with `u_`, so a function named
+// `double` becomes `u_double` and a variable named `café` `u_caf_xe9_`. the externals keep their names, they're what
+// C calls them, and names starting with `uma_` are left for the backend.
+// so are the `@opaque` types, which anything else with their name is
+// escaped around.
+pub fn mangle(program: &[Stmt]) -> Vec<Stmt> {
+    let names = |opaque: bool| {
+        program
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::External { name, .. } if !opaque => Some(name.clone()),
+                Stmt::Opaque { name, .. } if opaque => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mangler = Mangler {
+        externals: names(false),
+        types: names(true),
+    };
+
+    program
+        .iter()
+        .cloned()
+        .map(|mut stmt| {
+            mangler.stmt(&mut stmt);
+            stmt
+        })
+        .collect()
+}
+
+struct Mangler {
+    externals: HashSet<String>,
+    types: HashSet<String>,
+}
+
+impl Mangler {
+    fn name(&self, name: &mut String) {
+        let escaped = RESERVED.contains(&name.as_str())
+            || self.externals.contains(name)
+            || self.types.contains(name)
+            || name.starts_with('_')
+            || name.starts_with("u_")
+            || name.starts_with("uma_")
+            || name.contains("__")
+            || !name.is_ascii();
+
+        // C can't be relied on to take names outside ASCII, the characters
+        // are spelt out as `_x` and their code point. `_` is doubled so the
+        // spelt out ones can't be mistaken for part of the name.
+        if !name.is_ascii() {
+            *name = name
+                .chars()
+                .map(|c| match c {
+                    '_' => "__".to_string(),
+                    c if c.is_ascii() => c.to_string(),
+                    c => format!("_x{:x}_", c as u32),
+                })
+                .collect();
+        }
+
+        if escaped {
+            name.insert_str(0, "u_");
+        }
+    }
+
+    fn block(&self, stmts: &mut [Stmt]) {
+        stmts.iter_mut().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Variable { name, value, .. }
+            | Stmt::Const { name, value, .. }
+            | Stmt::Assignment { name, value, .. } => {
+                self.name(name);
+                self.expr(value);
+            }
+            Stmt::Function {
+                name, args, body, ..
+            } => {
+                self.name(name);
+                args.iter_mut().for_each(|(arg, _)| self.name(arg));
+                self.block(body);
+            }
+            Stmt::External { .. } | Stmt::Opaque { .. } => (),
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                self.expr(condition);
+                self.block(consequence);
+
+                if let Some(alt) = alternative {
+                    self.stmt(alt);
+                }
+            }
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.name(iterator);
+                self.expr(start);
+                self.expr(end);
+                self.block(body);
+            }
+            Stmt::Block(body, _) => self.block(body),
+            Stmt::Return(Some(value), _) | Stmt::Expr(value, _) => self.expr(value),
+            Stmt::Return(None, _) => (),
+        }
+    }
+
+    fn expr(&self, expr: &mut TypedExpr) {
+        match expr {
+            TypedExpr::Identifier { name, .. } | TypedExpr::AddressOf { name, .. } => {
+                self.name(name)
+            }
+            TypedExpr::Call { name, args, .. } => {
+                if !self.externals.contains(name) {
+                    self.name(name);
+                }
+
+                args.iter_mut().for_each(|arg| self.expr(arg));
+            }
+            TypedExpr::Builtin { args, .. } => args.iter_mut().for_each(|arg| self.expr(arg)),
+            TypedExpr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            TypedExpr::Unary { operand, .. }
+            | TypedExpr::Cast { value: operand, .. }
+            | TypedExpr::Deref {
+                pointer: operand, ..
+            } => self.expr(operand),
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                self.expr(condition);
+                self.expr(consequence);
+                self.expr(alternative);
+            }
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ..
+            } => {
+                self.expr(subject);
+
+                for (pattern, value) in arms {
+                    self.expr(pattern);
+                    self.expr(value);
+                }
+
+                self.expr(default);
+            }
+            TypedExpr::Array { elements, .. } => elements.iter_mut().for_each(|e| self.expr(e)),
+            TypedExpr::Index { target, index, .. } => {
+                self.expr(target);
+                self.expr(index);
+            }
+            TypedExpr::Interpolation(parts) => {
+                for part in parts {
+                    if let TypedPart::Expr(e) = part {
+                        self.expr(e);
+                    }
+                }
+            }
+            TypedExpr::Number(_)
+            | TypedExpr::Float(_)
+            | TypedExpr::Bool(_)
+            | TypedExpr::Char(_)
+            | TypedExpr::String(_) => {}
+        }
+    }
+}
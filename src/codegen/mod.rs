@@ -1,39 +1,216 @@
 pub mod c;
+mod emitter;
+#[cfg(feature = "jit")]
+pub mod jit;
+mod mangle;
+pub mod wasm;
 
-use crate::{lexer::Token, parser::Stmt};
-use c::CBackend;
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+use crate::ir::{self, Stmt};
+use crate::lexer::Span;
+use crate::timings::Timings;
+use crate::typeck::TypedStmt;
+
+pub use self::{
+    c::{CBackend, CompilerOptions},
+    wasm::WasmBackend,
+};
+
+#[cfg(feature = "jit")]
+pub use self::jit::Jit;
 
 #[derive(Debug, PartialEq)]
 pub struct CodegenError {
     pub message: String,
-    pub token: Token,
+    pub notes: Vec<String>,
+    // where in the source the problem is, left as the default for the ones
+    // about the program as a whole.
+    pub span: Span,
 }
 
 impl CodegenError {
-    pub fn new(message: impl Into<String>, token: Token) -> Self {
-        Self {
-            message: message.into(),
-            token,
+    pub fn new<T>(message: impl ToString, notes: Vec<String>) -> Result<T, CodegenError> {
+        Self::at(message, notes, Span::default())
+    }
+
+    pub fn at<T>(
+        message: impl ToString,
+        notes: Vec<String>,
+        span: Span,
+    ) -> Result<T, CodegenError> {
+        Err(CodegenError {
+            message: message.to_string(),
+            notes,
+            span,
+        })
+    }
+}
+
+// what couldn't be done to `path`, for the files a backend writes and cleans
+// up after.
+fn io<T>(result: std::io::Result<T>, action: &str, path: &Path) -> Result<T, CodegenError> {
+    result.or_else(|err| {
+        CodegenError::new(
+            format!("Couldn't {} `{}`: {}", action, path.display(), err),
+            vec![],
+        )
+    })
+}
+
+// where the outputs are relative to.
+fn current_dir() -> Result<PathBuf, CodegenError> {
+    std::env::current_dir().or_else(|err| {
+        CodegenError::new(
+            format!("Couldn't find the current directory: {}", err),
+            vec![],
+        )
+    })
+}
+
+// the output of a backend.
+#[derive(Debug, PartialEq)]
+pub enum Artifact {
+    Executable(PathBuf),
+    // a library for C to link against, with what it exports declared by
+    // `CBackend::header`.
+    Library(PathBuf),
+    // a file that needs a host to run, like a wasm module.
+    File(PathBuf),
+    Source(String),
+}
+
+// the kinds of library the C backend can build instead of an executable.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Library {
+    Static,
+    Shared,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodegenOptions {
+    pub output: PathBuf,
+    // return the generated source instead of compiling it.
+    pub emit_source: bool,
+    // the source files, indexed by `Span::file`, for a backend that can
+    // point its output back at them.
+    pub files: Vec<PathBuf>,
+    // split the build into steps that can be timed on their own, like
+    // compiling and linking the C.
+    pub timings: bool,
+    // build a library of the `@export`ed functions instead, for a program
+    // without a `main`.
+    pub library: Option<Library>,
+}
+
+impl CodegenOptions {
+    pub fn new(output: impl Into<PathBuf>) -> CodegenOptions {
+        CodegenOptions {
+            output: output.into(),
+            emit_source: false,
+            files: vec![],
+            timings: false,
+            library: None,
         }
     }
 }
 
+// a compilation target. the built-in ones are picked with `CodegenBackend`,
+// anything else can implement this and be passed to `Codegen` directly.
+// `Codegen` lowers the checked program to the IR before a backend is handed
+// it.
+pub trait Backend {
+    fn name(&self) -> &str;
+
+    fn generate(
+        &mut self,
+        program: &[Stmt],
+        opts: &CodegenOptions,
+    ) -> Result<Artifact, CodegenError>;
+
+    // how long the phases of the last `generate` took, for a backend that
+    // keeps track of them.
+    fn timings(&self) -> Timings {
+        Timings::default()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum CodegenBackend {
+    #[default]
     C,
+    Wasm,
+}
+
+impl CodegenBackend {
+    pub fn backend(self) -> Box<dyn Backend> {
+        match self {
+            CodegenBackend::C => Box::<CBackend>::default(),
+            CodegenBackend::Wasm => Box::<WasmBackend>::default(),
+        }
+    }
 }
 
 pub struct Codegen;
 
 impl Codegen {
     pub fn generate(
-        backend: CodegenBackend,
-        exprs: Vec<Stmt>,
-        out: &String,
-    ) -> Result<(), CodegenError> {
-        match backend {
-            CodegenBackend::C => CBackend::generate_and_run(exprs, out)?,
-        };
+        backend: &mut dyn Backend,
+        program: &[TypedStmt],
+        opts: &CodegenOptions,
+    ) -> Result<Artifact, CodegenError> {
+        backend.generate(&ir::lower(program), opts)
+    }
 
-        Ok(())
+    // builds the program and runs it, returning its exit code.
+    pub fn run(
+        backend: &mut dyn Backend,
+        program: &[TypedStmt],
+        opts: &CodegenOptions,
+        args: &[String],
+    ) -> Result<i32, CodegenError> {
+        match Self::generate(backend, program, opts)? {
+            Artifact::Executable(path) => Self::execute(&path, args),
+            _ => CodegenError::new(
+                format!("The {} target can't be run directly", backend.name()),
+                vec!["build it with `uma build` and load it from a host".into()],
+            ),
+        }
     }
+
+    // the program shares our stdio, so its output shows up as it's written
+    // and it can read from the terminal.
+    pub fn execute(path: &Path, args: &[String]) -> Result<i32, CodegenError> {
+        let status = Command::new(path)
+            .args(args)
+            .spawn()
+            .and_then(|mut child| child.wait());
+
+        match status {
+            Ok(status) => Ok(exit_code(status)),
+            Err(err) => CodegenError::new(
+                format!("Failed to run `{}`", path.display()),
+                vec![err.to_string()],
+            ),
+        }
+    }
+}
+
+// a program killed by a signal exits like it would from a shell.
+#[cfg(unix)]
+fn exit_code(status: ExitStatus) -> i32 {
+    use std::os::unix::process::ExitStatusExt;
+
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn exit_code(status: ExitStatus) -> i32 {
+    status.code().unwrap_or(1)
 }
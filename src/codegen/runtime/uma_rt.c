@@ -0,0 +1,429 @@
+// compiled after `uma_rt.h`, which declares everything here.
+#include <ctype.h>
+#include <errno.h>
+#include <limits.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+void uma_panic(const char* format, ...) {
+    va_list args;
+
+    // whatever the program printed before it panicked comes first.
+    fflush(stdout);
+    fputs("panic: ", stderr);
+
+    va_start(args, format);
+    vfprintf(stderr, format, args);
+    va_end(args);
+
+    fputc('\n', stderr);
+    exit(101);
+}
+
+void uma_assert(const char* source, const char* message, const char* file, int line) {
+    if (message == NULL) {
+        uma_panic("assertion `%s` failed at %s:%d", source, file, line);
+    }
+
+    uma_panic("assertion `%s` failed at %s:%d: %s", source, file, line, message);
+}
+
+void* uma_alloc(size_t size) {
+    void* out = malloc(size);
+
+    if (out == NULL) {
+        uma_panic("out of memory allocating %zu bytes", size);
+    }
+
+    return out;
+}
+
+int uma_bounds(long long index, int len, const char* file, int line) {
+    if (index < 0 || index >= len) {
+        uma_panic("index %lld out of bounds for length %d at %s:%d", index, len, file, line);
+    }
+
+    return (int)index;
+}
+
+// the largest and smallest values a signed integer of `bits` bits holds.
+static long long uma_largest(int bits) {
+    return bits == 64 ? LLONG_MAX : (1LL << (bits - 1)) - 1;
+}
+
+static long long uma_smallest(int bits) {
+    return -uma_largest(bits) - 1;
+}
+
+static void uma_overflow(const char* op, const char* file, int line) {
+    uma_panic("integer overflow in `%s` at %s:%d", op, file, line);
+}
+
+static void uma_divisor(unsigned long long b, const char* file, int line) {
+    if (b == 0) {
+        uma_panic("division by zero at %s:%d", file, line);
+    }
+}
+
+long long uma_add(long long a, long long b, int bits, const char* file, int line) {
+    if ((b > 0 && a > uma_largest(bits) - b) || (b < 0 && a < uma_smallest(bits) - b)) {
+        uma_overflow("+", file, line);
+    }
+
+    return a + b;
+}
+
+long long uma_sub(long long a, long long b, int bits, const char* file, int line) {
+    if ((b < 0 && a > uma_largest(bits) + b) || (b > 0 && a < uma_smallest(bits) + b)) {
+        uma_overflow("-", file, line);
+    }
+
+    return a - b;
+}
+
+long long uma_mul(long long a, long long b, int bits, const char* file, int line) {
+    long long max = uma_largest(bits);
+    long long min = uma_smallest(bits);
+
+    // each sign works out differently, so none of the checks overflow.
+    int overflows = a > 0 ? (b > 0 ? a > max / b : b < min / a)
+                          : (b > 0 ? a < min / b : a != 0 && b < max / a);
+
+    if (overflows) {
+        uma_overflow("*", file, line);
+    }
+
+    return a * b;
+}
+
+long long uma_div(long long a, long long b, int bits, const char* file, int line) {
+    uma_divisor(b, file, line);
+
+    if (a == uma_smallest(bits) && b == -1) {
+        uma_overflow("/", file, line);
+    }
+
+    return a / b;
+}
+
+long long uma_mod(long long a, long long b, int bits, const char* file, int line) {
+    uma_divisor(b, file, line);
+
+    // C leaves the smallest value `%` -1 undefined, even though it's 0.
+    if (a == uma_smallest(bits) && b == -1) {
+        return 0;
+    }
+
+    return a % b;
+}
+
+unsigned long long uma_udiv(unsigned long long a, unsigned long long b, const char* file, int line) {
+    uma_divisor(b, file, line);
+    return a / b;
+}
+
+unsigned long long uma_umod(unsigned long long a, unsigned long long b, const char* file, int line) {
+    uma_divisor(b, file, line);
+    return a % b;
+}
+
+long long uma_min(long long a, long long b) {
+    return a < b ? a : b;
+}
+
+long long uma_max(long long a, long long b) {
+    return a > b ? a : b;
+}
+
+unsigned long long uma_umin(unsigned long long a, unsigned long long b) {
+    return a < b ? a : b;
+}
+
+unsigned long long uma_umax(unsigned long long a, unsigned long long b) {
+    return a > b ? a : b;
+}
+
+// a string on the heap with room for `len` bytes and its NUL, with one
+// reference. its count sits right before its bytes, so both are freed at once.
+static UmaString uma_string_new(long long len, char** data) {
+    long long* refs = uma_alloc(sizeof(long long) + len + 1);
+    *refs = 1;
+
+    *data = (char*)(refs + 1);
+    (*data)[len] = '\0';
+
+    UmaString out = {len, *data, refs};
+    return out;
+}
+
+UmaString uma_cstr(const char* data) {
+    if (data == NULL) {
+        data = "";
+    }
+
+    UmaString out = {(long long)strlen(data), data, NULL};
+    return out;
+}
+
+UmaString uma_retain(UmaString s) {
+    if (s.refs != NULL) {
+        *s.refs += 1;
+    }
+
+    return s;
+}
+
+UmaString uma_release(UmaString s) {
+    if (s.refs != NULL && --*s.refs == 0) {
+        free(s.refs);
+    }
+
+    UmaString empty = {0, "", NULL};
+    return empty;
+}
+
+UmaString uma_replace(UmaString old, UmaString next) {
+    uma_release(old);
+    return next;
+}
+
+int uma_compare(UmaString a, UmaString b) {
+    size_t len = (size_t)(a.len < b.len ? a.len : b.len);
+    int order = memcmp(a.data, b.data, len);
+
+    if (order != 0) {
+        return order;
+    }
+
+    return (a.len > b.len) - (a.len < b.len);
+}
+
+UmaString uma_string_at(UmaString s, long long index, const char* file, int line) {
+    if (index < 0 || index >= s.len) {
+        uma_panic("index %lld is out of bounds for a string of length %lld at %s:%d", index,
+                  s.len, file, line);
+    }
+
+    char* data;
+    UmaString out = uma_string_new(1, &data);
+    data[0] = s.data[index];
+
+    return out;
+}
+
+UmaString uma_concat(UmaString a, UmaString b) {
+    char* data;
+    UmaString out = uma_string_new(a.len + b.len, &data);
+
+    memcpy(data, a.data, a.len);
+    memcpy(data + a.len, b.data, b.len);
+
+    return out;
+}
+
+UmaString uma_format(const char* format, ...) {
+    va_list args;
+
+    va_start(args, format);
+    int len = vsnprintf(NULL, 0, format, args);
+    va_end(args);
+
+    char* data;
+    UmaString out = uma_string_new(len, &data);
+
+    va_start(args, format);
+    vsnprintf(data, len + 1, format, args);
+    va_end(args);
+
+    return out;
+}
+
+UmaString uma_input(const char* prompt) {
+    if (prompt != NULL) {
+        fputs(prompt, stdout);
+    }
+
+    // whatever was printed before has to be seen before waiting on a reply.
+    fflush(stdout);
+
+    size_t cap = 64;
+    size_t len = 0;
+    char* buffer = uma_alloc(cap);
+    int c;
+
+    while ((c = fgetc(stdin)) != EOF && c != '\n') {
+        if (len == cap) {
+            cap *= 2;
+            buffer = realloc(buffer, cap);
+
+            if (buffer == NULL) {
+                uma_panic("out of memory reading a line of %zu bytes", len);
+            }
+        }
+
+        buffer[len++] = (char)c;
+    }
+
+    if (len > 0 && buffer[len - 1] == '\r') {
+        len--;
+    }
+
+    char* data;
+    UmaString out = uma_string_new((long long)len, &data);
+    memcpy(data, buffer, len);
+    free(buffer);
+
+    return out;
+}
+
+// whether `end`, where parsing `s` stopped, leaves only spaces after it.
+static int uma_parsed(UmaString s, const char* end) {
+    while (end < s.data + s.len && isspace((unsigned char)*end)) {
+        end++;
+    }
+
+    return end == s.data + s.len;
+}
+
+int uma_parse_int(UmaString s, const char* file, int line) {
+    char* end;
+
+    errno = 0;
+    long long value = strtoll(s.data, &end, 10);
+
+    if (end == s.data || !uma_parsed(s, end) || errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+        uma_panic("cannot parse \"%s\" as an `Int` at %s:%d", s.data, file, line);
+    }
+
+    return (int)value;
+}
+
+double uma_parse_float(UmaString s, const char* file, int line) {
+    char* end;
+    double value = strtod(s.data, &end);
+
+    if (end == s.data || !uma_parsed(s, end)) {
+        uma_panic("cannot parse \"%s\" as a `Float` at %s:%d", s.data, file, line);
+    }
+
+    return value;
+}
+
+// reads the file at `path` into `out`, or leaves `errno` saying why it
+// couldn't.
+static int uma_read(const char* path, UmaString* out) {
+    FILE* f = fopen(path, "rb");
+
+    if (f == NULL) {
+        return 0;
+    }
+
+    size_t cap = 4096;
+    size_t len = 0;
+    char* buffer = uma_alloc(cap);
+    size_t read;
+
+    while ((read = fread(buffer + len, 1, cap - len, f)) > 0) {
+        len += read;
+
+        if (len == cap) {
+            cap *= 2;
+            buffer = realloc(buffer, cap);
+
+            if (buffer == NULL) {
+                uma_panic("out of memory reading `%s`", path);
+            }
+        }
+    }
+
+    if (ferror(f)) {
+        int error = errno;
+        fclose(f);
+        free(buffer);
+        errno = error;
+
+        return 0;
+    }
+
+    fclose(f);
+
+    char* data;
+    *out = uma_string_new((long long)len, &data);
+    memcpy(data, buffer, len);
+    free(buffer);
+
+    return 1;
+}
+
+UmaString uma_read_file(const char* path, const char* file, int line) {
+    UmaString out;
+
+    if (!uma_read(path, &out)) {
+        uma_panic("cannot read `%s`: %s at %s:%d", path, strerror(errno), file, line);
+    }
+
+    return out;
+}
+
+UmaString uma_read_file_or(const char* path, UmaString fallback) {
+    UmaString out;
+    return uma_read(path, &out) ? out : uma_retain(fallback);
+}
+
+int uma_write_file(const char* path, UmaString contents, int append) {
+    FILE* f = fopen(path, append ? "ab" : "wb");
+
+    if (f == NULL) {
+        return 0;
+    }
+
+    size_t written = fwrite(contents.data, 1, (size_t)contents.len, f);
+
+    // what's buffered only fails to be written when it's closed.
+    return fclose(f) == 0 && written == (size_t)contents.len;
+}
+
+static int uma_argc;
+static char** uma_argv;
+
+void uma_set_args(int argc, char** argv) {
+    uma_argc = argc;
+    uma_argv = argv;
+}
+
+int uma_arg_count(void) {
+    return uma_argc;
+}
+
+UmaString uma_arg(long long index, const char* file, int line) {
+    return uma_cstr(uma_argv[uma_bounds(index, uma_argc, file, line)]);
+}
+
+UmaString uma_env(const char* name) {
+    const char* value = getenv(name);
+
+    if (value == NULL) {
+        value = "";
+    }
+
+    // setting it again can free what `getenv` gave back, so it's copied.
+    long long len = (long long)strlen(value);
+    char* data;
+    UmaString out = uma_string_new(len, &data);
+    memcpy(data, value, (size_t)len);
+
+    return out;
+}
+
+void uma_set_env(const char* name, const char* value, const char* file, int line) {
+#ifdef _WIN32
+    int failed = _putenv_s(name, value);
+#else
+    int failed = setenv(name, value, 1);
+#endif
+
+    if (failed) {
+        uma_panic("cannot set `%s`: %s at %s:%d", name, strerror(errno), file, line);
+    }
+}
@@ -0,0 +1,217 @@
+// the parts of the WebAssembly binary format the backend needs.
+
+pub const MAGIC: &[u8] = b"\0asm\x01\0\0\0";
+
+pub mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0b;
+    pub const BR: u8 = 0x0c;
+    pub const BR_IF: u8 = 0x0d;
+    pub const RETURN: u8 = 0x0f;
+    pub const CALL: u8 = 0x10;
+    pub const DROP: u8 = 0x1a;
+
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+
+    pub const I32_LOAD: u8 = 0x28;
+    pub const F64_LOAD: u8 = 0x2b;
+    pub const I32_LOAD8_U: u8 = 0x2d;
+    pub const I32_STORE: u8 = 0x36;
+    pub const I64_STORE: u8 = 0x37;
+    pub const F64_STORE: u8 = 0x39;
+    pub const I32_STORE8: u8 = 0x3a;
+    pub const MEMORY_SIZE: u8 = 0x3f;
+    pub const MEMORY_GROW: u8 = 0x40;
+
+    pub const I32_CONST: u8 = 0x41;
+    pub const F64_CONST: u8 = 0x44;
+
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_NE: u8 = 0x47;
+    pub const I32_LT_S: u8 = 0x48;
+    pub const I32_GT_S: u8 = 0x4a;
+    pub const I32_GT_U: u8 = 0x4b;
+    pub const I32_LE_S: u8 = 0x4c;
+    pub const I32_GE_S: u8 = 0x4e;
+    pub const F64_EQ: u8 = 0x61;
+    pub const F64_NE: u8 = 0x62;
+    pub const F64_LT: u8 = 0x63;
+    pub const F64_GT: u8 = 0x64;
+    pub const F64_LE: u8 = 0x65;
+    pub const F64_GE: u8 = 0x66;
+
+    pub const I32_ADD: u8 = 0x6a;
+    pub const I32_SUB: u8 = 0x6b;
+    pub const I32_MUL: u8 = 0x6c;
+    pub const I32_DIV_S: u8 = 0x6d;
+    pub const I32_DIV_U: u8 = 0x6e;
+    pub const I32_REM_S: u8 = 0x6f;
+    pub const I32_REM_U: u8 = 0x70;
+    pub const I32_AND: u8 = 0x71;
+    pub const I32_OR: u8 = 0x72;
+    pub const I32_XOR: u8 = 0x73;
+    pub const I32_SHL: u8 = 0x74;
+    pub const I32_SHR_S: u8 = 0x75;
+    pub const I32_SHR_U: u8 = 0x76;
+    pub const F64_ABS: u8 = 0x99;
+    pub const F64_CEIL: u8 = 0x9b;
+    pub const F64_FLOOR: u8 = 0x9c;
+    pub const F64_SQRT: u8 = 0x9f;
+    pub const F64_ADD: u8 = 0xa0;
+    pub const F64_SUB: u8 = 0xa1;
+    pub const F64_MUL: u8 = 0xa2;
+    pub const F64_DIV: u8 = 0xa3;
+    pub const F64_MIN: u8 = 0xa4;
+    pub const F64_MAX: u8 = 0xa5;
+    pub const I32_TRUNC_F64_S: u8 = 0xaa;
+    pub const I64_EXTEND_I32_S: u8 = 0xac;
+    pub const F64_CONVERT_I32_S: u8 = 0xb7;
+
+    // prefix for `memory.copy` and friends.
+    pub const MISC: u8 = 0xfc;
+    pub const MEMORY_COPY: u32 = 10;
+
+    pub const EMPTY_BLOCK: u8 = 0x40;
+}
+
+pub mod section {
+    pub const TYPE: u8 = 1;
+    pub const IMPORT: u8 = 2;
+    pub const FUNCTION: u8 = 3;
+    pub const MEMORY: u8 = 5;
+    pub const GLOBAL: u8 = 6;
+    pub const EXPORT: u8 = 7;
+    pub const CODE: u8 = 10;
+    pub const DATA: u8 = 11;
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ValType {
+    I32,
+    F64,
+}
+
+impl ValType {
+    pub fn byte(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7f,
+            ValType::F64 => 0x7c,
+        }
+    }
+}
+
+pub fn unsigned(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+pub fn signed(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        let sign_bit = byte & 0x40 != 0;
+
+        if (value == 0 && !sign_bit) || (value == -1 && sign_bit) {
+            out.push(byte);
+            return;
+        }
+
+        out.push(byte | 0x80);
+    }
+}
+
+pub fn name(out: &mut Vec<u8>, name: &str) {
+    unsigned(out, name.len() as u64);
+    out.extend_from_slice(name.as_bytes());
+}
+
+pub fn vector(out: &mut Vec<u8>, items: Vec<Vec<u8>>) {
+    unsigned(out, items.len() as u64);
+
+    for item in items {
+        out.extend(item);
+    }
+}
+
+pub fn section(out: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+    out.push(id);
+    unsigned(out, contents.len() as u64);
+    out.extend(contents);
+}
+
+// an instruction sequence, built up one instruction at a time.
+#[derive(Default)]
+pub struct Code(pub Vec<u8>);
+
+impl Code {
+    pub fn op(&mut self, op: u8) -> &mut Code {
+        self.0.push(op);
+        self
+    }
+
+    // instructions taking a single index: locals, globals, calls and branches.
+    pub fn op_index(&mut self, op: u8, index: u32) -> &mut Code {
+        self.0.push(op);
+        unsigned(&mut self.0, index as u64);
+        self
+    }
+
+    pub fn block(&mut self, op: u8) -> &mut Code {
+        self.0.extend([op, op::EMPTY_BLOCK]);
+        self
+    }
+
+    pub fn memory(&mut self, op: u8, align: u32, offset: u32) -> &mut Code {
+        self.0.push(op);
+        unsigned(&mut self.0, align as u64);
+        unsigned(&mut self.0, offset as u64);
+        self
+    }
+
+    pub fn memory_copy(&mut self) -> &mut Code {
+        self.0.push(op::MISC);
+        unsigned(&mut self.0, op::MEMORY_COPY as u64);
+        self.0.extend([0, 0]);
+        self
+    }
+
+    pub fn memory_size(&mut self) -> &mut Code {
+        self.0.extend([op::MEMORY_SIZE, 0]);
+        self
+    }
+
+    pub fn memory_grow(&mut self) -> &mut Code {
+        self.0.extend([op::MEMORY_GROW, 0]);
+        self
+    }
+
+    pub fn i32_const(&mut self, value: i32) -> &mut Code {
+        self.0.push(op::I32_CONST);
+        signed(&mut self.0, value as i64);
+        self
+    }
+
+    pub fn f64_const(&mut self, value: f64) -> &mut Code {
+        self.0.push(op::F64_CONST);
+        self.0.extend(value.to_le_bytes());
+        self
+    }
+}
@@ -0,0 +1,1374 @@
+mod encoder;
+
+use std::{collections::HashMap, fs};
+
+use self::encoder::{op, section, Code, ValType, MAGIC};
+use super::{current_dir, io, Artifact, Backend, CodegenError, CodegenOptions};
+use crate::ir::{
+    callgraph::{calls, exprs},
+    Stmt,
+};
+use crate::lexer::{Span, Token, TokenKind};
+use crate::typeck::{Builtin, Type, TypedExpr, TypedPart};
+
+// string literals live NUL-terminated in a data segment from `DATA_START`,
+// leaving 0 free as a null pointer. everything allocated at runtime (arrays,
+// concatenated strings, variadic arguments) comes after them from a bump
+// allocator that never frees.
+const DATA_START: u32 = 8;
+const PAGE_SIZE: u32 = 65536;
+
+// the heap pointer is always the first global.
+const HEAP: u32 = 0;
+
+// runtime helpers, in the order they're defined after the imports.
+const ALLOC: u32 = 0;
+const STRLEN: u32 = 1;
+const STRCMP: u32 = 2;
+const CONCAT: u32 = 3;
+const ITOA: u32 = 4;
+const HELPERS: u32 = 5;
+
+struct Import {
+    name: String,
+    signature: u32,
+}
+
+struct Function {
+    signature: u32,
+    locals: Vec<ValType>,
+    code: Code,
+}
+
+struct External {
+    arity: usize,
+    is_varadic: bool,
+}
+
+// the function currently being generated.
+#[derive(Default)]
+struct Frame {
+    params: usize,
+    locals: Vec<ValType>,
+    scopes: Vec<HashMap<String, u32>>,
+    code: Code,
+}
+
+#[derive(Default)]
+pub struct WasmBackend {
+    signatures: Vec<(Vec<ValType>, Vec<ValType>)>,
+    imports: Vec<Import>,
+    externals: HashMap<String, External>,
+    functions: HashMap<String, u32>,
+    globals: HashMap<String, u32>,
+    global_types: Vec<ValType>,
+    strings: HashMap<String, u32>,
+    data: Vec<u8>,
+    frame: Frame,
+    // where the statement being generated was written, for the errors about
+    // what's in it.
+    span: Span,
+}
+
+fn val_type(ty: &Type) -> Option<ValType> {
+    match ty {
+        Type::Float => Some(ValType::F64),
+        Type::Int | Type::Bool | Type::String | Type::Array(..) => Some(ValType::I32),
+        Type::Void => None,
+        ty => unreachable!("`{}` is rejected before codegen", ty),
+    }
+}
+
+fn size_of(ty: &Type) -> u32 {
+    match ty {
+        Type::Float => 8,
+        _ => 4,
+    }
+}
+
+// the first of the sized types, like `Int8`, `Char` or a pointer used
+// anywhere in `stmts`, which there's no wasm for yet, with the innermost
+// statement it's used in.
+fn sized(stmts: &[Stmt]) -> Option<(Type, Span)> {
+    stmts.iter().find_map(|stmt| {
+        let mut found = vec![];
+
+        exprs(std::slice::from_ref(stmt), &mut |expr| {
+            found.push(expr.ty())
+        });
+
+        if let Stmt::Function {
+            args, return_type, ..
+        } = stmt
+        {
+            found.extend(args.iter().map(|(_, ty)| ty.clone()));
+            found.push(return_type.clone());
+        }
+
+        let ty = found
+            .into_iter()
+            .find(|ty| ty.is_sized() || matches!(ty, Type::Char | Type::Pointer(_)))?;
+
+        let inner = match stmt {
+            Stmt::Function { body, .. } | Stmt::For { body, .. } | Stmt::Block(body, _) => {
+                sized(body)
+            }
+            Stmt::If {
+                consequence,
+                alternative,
+                ..
+            } => {
+                sized(consequence).or_else(|| sized(std::slice::from_ref(alternative.as_deref()?)))
+            }
+            _ => None,
+        };
+
+        Some(inner.unwrap_or((ty, location(stmt))))
+    })
+}
+
+// where `stmt` was written. the ones the checker adds itself, and the
+// declarations that don't keep one, weren't written anywhere.
+fn location(stmt: &Stmt) -> Span {
+    match stmt {
+        Stmt::Variable { span, .. }
+        | Stmt::Function { span, .. }
+        | Stmt::If { span, .. }
+        | Stmt::For { span, .. }
+        | Stmt::Block(_, span)
+        | Stmt::Assignment { span, .. }
+        | Stmt::Return(_, span)
+        | Stmt::Expr(_, span) => *span,
+        Stmt::Const { .. } | Stmt::External { .. } | Stmt::Opaque { .. } => Span::default(),
+    }
+}
+
+// a `Bool` is printed as the string `true` or `false`.
+fn shown(expr: &TypedExpr) -> TypedExpr {
+    match expr.ty() {
+        Type::Bool => TypedExpr::If {
+            condition: expr.clone().into(),
+            consequence: TypedExpr::String("true".into()).into(),
+            alternative: TypedExpr::String("false".into()).into(),
+            ty: Type::String,
+        },
+        _ => expr.clone(),
+    }
+}
+
+fn unsupported_at<T>(what: impl ToString, span: Span) -> Result<T, CodegenError> {
+    CodegenError::at(
+        format!("The wasm32 target doesn't support {}", what.to_string()),
+        vec![],
+        span,
+    )
+}
+
+impl Backend for WasmBackend {
+    fn name(&self) -> &str {
+        "wasm32"
+    }
+
+    fn generate(
+        &mut self,
+        program: &[Stmt],
+        opts: &CodegenOptions,
+    ) -> Result<Artifact, CodegenError> {
+        if opts.emit_source {
+            return CodegenError::new("The wasm32 target has no source to emit", vec![]);
+        }
+
+        let module = Self::assemble(program)?;
+        let path = current_dir()?.join(opts.output.with_extension("wasm"));
+
+        if let Some(parent) = path.parent() {
+            io(fs::create_dir_all(parent), "create", parent)?;
+        }
+
+        io(fs::write(&path, module), "write", &path)?;
+
+        Ok(Artifact::File(path))
+    }
+}
+
+impl WasmBackend {
+    pub fn assemble(stmts: &[Stmt]) -> Result<Vec<u8>, CodegenError> {
+        if let Some((ty, span)) = sized(stmts) {
+            return unsupported_at(format!("`{}` values", ty), span);
+        }
+
+        let mut backend = WasmBackend::default();
+        backend.global_types.push(ValType::I32);
+
+        backend.declare_imports(stmts)?;
+
+        let base = backend.imports.len() as u32 + HELPERS;
+        let mut functions = vec![];
+
+        for stmt in stmts {
+            match stmt {
+                Stmt::Function { name, .. } => {
+                    let index = base + backend.functions.len() as u32;
+                    backend.functions.insert(name.clone(), index);
+                }
+                Stmt::Variable { name, ty, .. } | Stmt::Const { name, ty, .. } => {
+                    let index = backend.global_types.len() as u32;
+                    backend.globals.insert(name.clone(), index);
+                    backend
+                        .global_types
+                        .push(val_type(ty).expect("variables aren't `Void`"));
+                }
+                _ => (),
+            }
+        }
+
+        // top-level variables and constants are initialised when `main`
+        // starts.
+        let init = stmts
+            .iter()
+            .any(|stmt| matches!(stmt, Stmt::Variable { .. } | Stmt::Const { .. }))
+            .then(|| base + backend.functions.len() as u32);
+
+        for stmt in stmts {
+            if let Stmt::Function {
+                name,
+                return_type,
+                args,
+                body,
+                ..
+            } = stmt
+            {
+                functions.push(backend.function(name, return_type, args, body, init)?);
+            }
+        }
+
+        if init.is_some() {
+            backend.begin(&[]);
+
+            for stmt in stmts {
+                if let Stmt::Variable { name, value, .. } | Stmt::Const { name, value, .. } = stmt {
+                    backend.span = location(stmt);
+                    backend.expr(value)?;
+
+                    let index = backend.globals[name];
+                    backend.code().op_index(op::GLOBAL_SET, index);
+                }
+            }
+
+            let signature = backend.signature(vec![], vec![]);
+            functions.push(backend.end(signature));
+        }
+
+        Ok(backend.module(functions, stmts))
+    }
+
+    // externals become imports from `env`, typed after the arguments they're
+    // first called with. arguments passed through a `...` are written to
+    // 8-byte slots in memory (ints sign-extended to 64 bits) and the import
+    // receives a pointer to the first slot, like the C ABI for wasm32.
+    fn declare_imports(&mut self, stmts: &[Stmt]) -> Result<(), CodegenError> {
+        for stmt in stmts {
+            if let Stmt::External {
+                name,
+                arity,
+                is_varadic,
+                ..
+            } = stmt
+            {
+                self.externals.insert(
+                    name.clone(),
+                    External {
+                        arity: *arity,
+                        is_varadic: *is_varadic,
+                    },
+                );
+            }
+        }
+
+        let mut found = vec![];
+        calls(stmts, &mut found);
+
+        for (name, args, ty) in found {
+            let external = match self.externals.get(name) {
+                Some(external) => external,
+                None => continue,
+            };
+
+            if self.imports.iter().any(|import| import.name == name) {
+                continue;
+            }
+
+            if args.len() < external.arity {
+                return CodegenError::new(
+                    format!(
+                        "`{}` expects {} arguments, found {}",
+                        name,
+                        external.arity,
+                        args.len()
+                    ),
+                    vec![],
+                );
+            }
+
+            let mut params = args[..external.arity]
+                .iter()
+                .map(|arg| val_type(&arg.ty()))
+                .collect::<Option<Vec<_>>>()
+                .expect("arguments aren't `Void`");
+
+            if external.is_varadic {
+                params.push(ValType::I32);
+            }
+
+            let signature = self.signature(params, val_type(ty).into_iter().collect());
+
+            self.imports.push(Import {
+                name: name.to_string(),
+                signature,
+            });
+        }
+
+        // the prelude prints through the host's `printf`.
+        let mut builtins = false;
+        exprs(stmts, &mut |expr| {
+            builtins |= matches!(expr, TypedExpr::Builtin { builtin, .. } if builtin.prints());
+        });
+
+        if builtins && !self.imports.iter().any(|import| import.name == "printf") {
+            self.externals.insert(
+                "printf".to_string(),
+                External {
+                    arity: 1,
+                    is_varadic: true,
+                },
+            );
+
+            let signature = self.signature(vec![ValType::I32, ValType::I32], vec![]);
+
+            self.imports.push(Import {
+                name: "printf".to_string(),
+                signature,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn signature(&mut self, params: Vec<ValType>, results: Vec<ValType>) -> u32 {
+        let signature = (params, results);
+
+        match self.signatures.iter().position(|s| *s == signature) {
+            Some(index) => index as u32,
+            None => {
+                self.signatures.push(signature);
+                self.signatures.len() as u32 - 1
+            }
+        }
+    }
+
+    fn helper(&self, helper: u32) -> u32 {
+        self.imports.len() as u32 + helper
+    }
+
+    fn string(&mut self, value: &str) -> u32 {
+        if let Some(offset) = self.strings.get(value) {
+            return *offset;
+        }
+
+        let offset = DATA_START + self.data.len() as u32;
+
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+        self.strings.insert(value.to_string(), offset);
+
+        offset
+    }
+
+    fn code(&mut self) -> &mut Code {
+        &mut self.frame.code
+    }
+
+    fn begin(&mut self, params: &[(String, Type)]) {
+        self.frame = Frame {
+            params: params.len(),
+            locals: params
+                .iter()
+                .map(|(_, ty)| val_type(ty).expect("arguments aren't `Void`"))
+                .collect(),
+            scopes: vec![params
+                .iter()
+                .enumerate()
+                .map(|(i, (name, _))| (name.clone(), i as u32))
+                .collect()],
+            code: Code::default(),
+        };
+    }
+
+    fn end(&mut self, signature: u32) -> Function {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.code.op(op::END);
+
+        Function {
+            signature,
+            locals: frame.locals.split_off(frame.params),
+            code: frame.code,
+        }
+    }
+
+    fn local(&mut self, ty: ValType) -> u32 {
+        self.frame.locals.push(ty);
+        self.frame.locals.len() as u32 - 1
+    }
+
+    fn declare(&mut self, name: &str, ty: &Type) -> u32 {
+        let index = self.local(val_type(ty).expect("variables aren't `Void`"));
+
+        self.frame
+            .scopes
+            .last_mut()
+            .expect("there is always a function scope")
+            .insert(name.to_string(), index);
+
+        index
+    }
+
+    fn function(
+        &mut self,
+        name: &str,
+        return_type: &Type,
+        args: &[(String, Type)],
+        body: &[Stmt],
+        init: Option<u32>,
+    ) -> Result<Function, CodegenError> {
+        self.begin(args);
+
+        if let (Some(init), "main") = (init, name) {
+            self.code().op_index(op::CALL, init);
+        }
+
+        self.block(body)?;
+
+        let results: Vec<_> = val_type(return_type).into_iter().collect();
+
+        // every path should have returned by now, which wasm can't tell.
+        if !results.is_empty() {
+            self.code().op(op::UNREACHABLE);
+        }
+
+        let params = self.frame.locals[..self.frame.params].to_vec();
+        let signature = self.signature(params, results);
+
+        Ok(self.end(signature))
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), CodegenError> {
+        self.frame.scopes.push(HashMap::new());
+
+        for stmt in stmts {
+            self.stmt(stmt)?;
+        }
+
+        self.frame.scopes.pop();
+        Ok(())
+    }
+
+    fn get(&mut self, name: &str) {
+        match self.frame.scopes.iter().rev().find_map(|s| s.get(name)) {
+            Some(index) => {
+                let index = *index;
+                self.code().op_index(op::LOCAL_GET, index)
+            }
+            None => {
+                let index = self.globals[name];
+                self.code().op_index(op::GLOBAL_GET, index)
+            }
+        };
+    }
+
+    fn set(&mut self, name: &str) {
+        match self.frame.scopes.iter().rev().find_map(|s| s.get(name)) {
+            Some(index) => {
+                let index = *index;
+                self.code().op_index(op::LOCAL_SET, index)
+            }
+            None => {
+                let index = self.globals[name];
+                self.code().op_index(op::GLOBAL_SET, index)
+            }
+        };
+    }
+
+    fn load(&mut self, ty: &Type, offset: u32) {
+        match val_type(ty) {
+            Some(ValType::F64) => self.code().memory(op::F64_LOAD, 3, offset),
+            _ => self.code().memory(op::I32_LOAD, 2, offset),
+        };
+    }
+
+    fn store(&mut self, ty: &Type, offset: u32) {
+        match val_type(ty) {
+            Some(ValType::F64) => self.code().memory(op::F64_STORE, 3, offset),
+            _ => self.code().memory(op::I32_STORE, 2, offset),
+        };
+    }
+
+    // leaves an `i32` that's non-zero when `ty` is truthy.
+    fn condition(&mut self, condition: &TypedExpr) -> Result<(), CodegenError> {
+        self.expr(condition)?;
+
+        if condition.ty() == Type::Float {
+            self.code().f64_const(0.0).op(op::F64_NE);
+        }
+
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Result<(), CodegenError> {
+        if location(stmt) != Span::default() {
+            self.span = location(stmt);
+        }
+
+        match stmt {
+            // arrays are never written to in place, so sharing them is the
+            // same as copying them.
+            Stmt::Variable {
+                name, value, ty, ..
+            } => {
+                self.expr(value)?;
+
+                let index = self.declare(name, ty);
+                self.code().op_index(op::LOCAL_SET, index);
+            }
+            Stmt::Assignment { name, value, .. } => {
+                self.expr(value)?;
+                self.set(name);
+            }
+            Stmt::External { .. } | Stmt::Opaque { .. } | Stmt::Const { .. } => (),
+            Stmt::Function { name, span, .. } => {
+                return unsupported_at(format!("nested functions, like `{}`", name), *span)
+            }
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                self.condition(condition)?;
+                self.code().block(op::IF);
+                self.block(consequence)?;
+
+                if let Some(alt) = alternative {
+                    self.code().op(op::ELSE);
+                    self.stmt(alt)?;
+                }
+
+                self.code().op(op::END);
+            }
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                self.frame.scopes.push(HashMap::new());
+
+                self.expr(start)?;
+                let i = self.declare(iterator, &Type::Int);
+                self.code().op_index(op::LOCAL_SET, i);
+
+                self.expr(end)?;
+                let end = self.local(ValType::I32);
+                self.code().op_index(op::LOCAL_SET, end);
+
+                self.code()
+                    .block(op::BLOCK)
+                    .block(op::LOOP)
+                    .op_index(op::LOCAL_GET, i)
+                    .op_index(op::LOCAL_GET, end)
+                    .op(op::I32_GE_S)
+                    .op_index(op::BR_IF, 1);
+
+                self.block(body)?;
+
+                self.code()
+                    .op_index(op::LOCAL_GET, i)
+                    .i32_const(1)
+                    .op(op::I32_ADD)
+                    .op_index(op::LOCAL_SET, i)
+                    .op_index(op::BR, 0)
+                    .op(op::END)
+                    .op(op::END);
+
+                self.frame.scopes.pop();
+            }
+            Stmt::Block(stmts, _) => self.block(stmts)?,
+            Stmt::Return(value, _) => {
+                if let Some(value) = value {
+                    self.expr(value)?;
+                }
+
+                self.code().op(op::RETURN);
+            }
+            Stmt::Expr(expr, _) => {
+                self.expr(expr)?;
+
+                if expr.ty() != Type::Void {
+                    self.code().op(op::DROP);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) -> Result<(), CodegenError> {
+        match expr {
+            TypedExpr::Binary {
+                lhs,
+                op: token,
+                rhs,
+                ..
+            } => {
+                self.expr(lhs)?;
+
+                // the right side of `&&` and `||` only runs when it decides the result.
+                if let TokenKind::And | TokenKind::Or = token.kind {
+                    self.code().op(op::IF).op(ValType::I32.byte());
+
+                    if token.kind == TokenKind::And {
+                        self.expr(rhs)?;
+                        self.code().op(op::ELSE).i32_const(0);
+                    } else {
+                        self.code().i32_const(1).op(op::ELSE);
+                        self.expr(rhs)?;
+                    }
+
+                    self.code().op(op::END);
+                    return Ok(());
+                }
+
+                self.expr(rhs)?;
+
+                let ty = lhs.ty();
+
+                if ty == Type::String {
+                    match token.kind {
+                        TokenKind::Add => {
+                            let concat = self.helper(CONCAT);
+                            self.code().op_index(op::CALL, concat);
+                        }
+                        TokenKind::BinaryEq => {
+                            let strcmp = self.helper(STRCMP);
+                            self.code().op_index(op::CALL, strcmp).op(op::I32_EQZ);
+                        }
+                        TokenKind::BinaryNeq => {
+                            let strcmp = self.helper(STRCMP);
+                            self.code()
+                                .op_index(op::CALL, strcmp)
+                                .i32_const(0)
+                                .op(op::I32_NE);
+                        }
+                        _ => unreachable!("checked by the type checker"),
+                    }
+
+                    return Ok(());
+                }
+
+                let instruction = match (val_type(&ty), &token.kind) {
+                    (Some(ValType::I32), TokenKind::Add) => op::I32_ADD,
+                    (Some(ValType::I32), TokenKind::Sub) => op::I32_SUB,
+                    (Some(ValType::I32), TokenKind::Multi) => op::I32_MUL,
+                    (Some(ValType::I32), TokenKind::Div) => op::I32_DIV_S,
+                    (Some(ValType::I32), TokenKind::Mod) => op::I32_REM_S,
+                    (Some(ValType::I32), TokenKind::BitAnd) => op::I32_AND,
+                    (Some(ValType::I32), TokenKind::BitOr) => op::I32_OR,
+                    (Some(ValType::I32), TokenKind::BitXor) => op::I32_XOR,
+                    (Some(ValType::I32), TokenKind::Shl) => op::I32_SHL,
+                    (Some(ValType::I32), TokenKind::Shr) => op::I32_SHR_S,
+                    (Some(ValType::I32), TokenKind::BinaryEq) => op::I32_EQ,
+                    (Some(ValType::I32), TokenKind::BinaryNeq) => op::I32_NE,
+                    (Some(ValType::I32), TokenKind::BinaryGt) => op::I32_GT_S,
+                    (Some(ValType::I32), TokenKind::BinaryGte) => op::I32_GE_S,
+                    (Some(ValType::I32), TokenKind::BinaryLt) => op::I32_LT_S,
+                    (Some(ValType::I32), TokenKind::BinaryLte) => op::I32_LE_S,
+                    (Some(ValType::F64), TokenKind::Add) => op::F64_ADD,
+                    (Some(ValType::F64), TokenKind::Sub) => op::F64_SUB,
+                    (Some(ValType::F64), TokenKind::Multi) => op::F64_MUL,
+                    (Some(ValType::F64), TokenKind::Div) => op::F64_DIV,
+                    (Some(ValType::F64), TokenKind::BinaryEq) => op::F64_EQ,
+                    (Some(ValType::F64), TokenKind::BinaryNeq) => op::F64_NE,
+                    (Some(ValType::F64), TokenKind::BinaryGt) => op::F64_GT,
+                    (Some(ValType::F64), TokenKind::BinaryGte) => op::F64_GE,
+                    (Some(ValType::F64), TokenKind::BinaryLt) => op::F64_LT,
+                    (Some(ValType::F64), TokenKind::BinaryLte) => op::F64_LE,
+                    (_, kind) => {
+                        return unsupported_at(format!("the `{:?}` operator", kind), token.span)
+                    }
+                };
+
+                self.code().op(instruction);
+            }
+            TypedExpr::Cast { value, ty } => {
+                self.expr(value)?;
+
+                // the integers and `Bool` are all an `i32` already.
+                match (value.ty(), ty) {
+                    (Type::Float, Type::Float) => (),
+                    (Type::Float, _) => {
+                        self.code().op(op::I32_TRUNC_F64_S);
+                    }
+                    (_, Type::Float) => {
+                        self.code().op(op::F64_CONVERT_I32_S);
+                    }
+                    _ => (),
+                }
+            }
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ty,
+            } => {
+                self.condition(condition)?;
+
+                let block = val_type(ty).map_or(op::EMPTY_BLOCK, ValType::byte);
+                self.code().op(op::IF).op(block);
+
+                self.expr(consequence)?;
+                self.code().op(op::ELSE);
+                self.expr(alternative)?;
+                self.code().op(op::END);
+            }
+            // the subject is put in a local no program can name, and each arm
+            // is an `if` in the `else` of the one before.
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ty,
+            } => {
+                let name = format!("match#{}", self.frame.locals.len());
+                let local = self.declare(&name, &subject.ty());
+
+                self.expr(subject)?;
+                self.code().op_index(op::LOCAL_SET, local);
+
+                let block = val_type(ty).map_or(op::EMPTY_BLOCK, ValType::byte);
+
+                for (pattern, value) in arms {
+                    self.expr(&TypedExpr::Binary {
+                        lhs: TypedExpr::Identifier {
+                            name: name.clone(),
+                            ty: subject.ty(),
+                        }
+                        .into(),
+                        op: Token::new(TokenKind::BinaryEq, None, Span::default()),
+                        rhs: pattern.clone().into(),
+                        ty: Type::Bool,
+                    })?;
+
+                    self.code().op(op::IF).op(block);
+                    self.expr(value)?;
+                    self.code().op(op::ELSE);
+                }
+
+                self.expr(default)?;
+
+                for _ in arms {
+                    self.code().op(op::END);
+                }
+            }
+            // `~x` is `x ^ -1`, and `!x` is whether `x` is zero.
+            TypedExpr::Unary {
+                op: token, operand, ..
+            } => {
+                self.expr(operand)?;
+
+                match token.kind {
+                    TokenKind::Not => self.code().op(op::I32_EQZ),
+                    _ => self.code().i32_const(-1).op(op::I32_XOR),
+                };
+            }
+            TypedExpr::AddressOf { .. } | TypedExpr::Deref { .. } => {
+                unreachable!("pointers are rejected before codegen")
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Len,
+                args,
+                ..
+            } => match args[0].ty() {
+                Type::Array(_, len) => {
+                    self.code().i32_const(len as i32);
+                }
+                ty => return unsupported_at(format!("`len` of a `{}`", ty), self.span),
+            },
+            // the ones wasm has an instruction for.
+            TypedExpr::Builtin { builtin, args, ty } if builtin.is_math() => {
+                let instruction = match (builtin, ty) {
+                    (Builtin::Abs, Type::Float) => op::F64_ABS,
+                    (Builtin::Min, Type::Float) => op::F64_MIN,
+                    (Builtin::Max, Type::Float) => op::F64_MAX,
+                    (Builtin::Sqrt, _) => op::F64_SQRT,
+                    (Builtin::Floor, _) => op::F64_FLOOR,
+                    (Builtin::Ceil, _) => op::F64_CEIL,
+                    (builtin, ty) => {
+                        return unsupported_at(
+                            format!("`{}` of a `{}`", builtin.name(), ty),
+                            self.span,
+                        )
+                    }
+                };
+
+                for arg in args {
+                    self.expr(arg)?;
+                }
+
+                self.code().op(instruction);
+            }
+            TypedExpr::Builtin {
+                builtin:
+                    builtin @ (Builtin::Input
+                    | Builtin::ParseInt
+                    | Builtin::ParseFloat
+                    | Builtin::ReadFile
+                    | Builtin::WriteFile
+                    | Builtin::AppendFile
+                    | Builtin::ArgCount
+                    | Builtin::Arg
+                    | Builtin::Exit
+                    | Builtin::Env
+                    | Builtin::SetEnv),
+                ..
+            } => return unsupported_at(format!("`{}`", builtin.name()), self.span),
+            // there's nowhere to print why, so a failed `assert` just traps.
+            TypedExpr::Builtin {
+                builtin: Builtin::Assert,
+                args,
+                ..
+            } => {
+                self.condition(&args[0])?;
+                self.code()
+                    .op(op::I32_EQZ)
+                    .block(op::IF)
+                    .op(op::UNREACHABLE)
+                    .op(op::END);
+            }
+            TypedExpr::Builtin { builtin, args, .. } => {
+                self.expr(&TypedExpr::Call {
+                    name: "printf".to_string(),
+                    args: builtin.printf(args).iter().map(shown).collect(),
+                    ty: Type::Void,
+                })?;
+            }
+            TypedExpr::Call { name, args, .. } => {
+                let external = self.externals.get(name).map(|e| (e.arity, e.is_varadic));
+
+                let (fixed, index) = match external {
+                    Some((arity, _)) => {
+                        let index = self
+                            .imports
+                            .iter()
+                            .position(|import| import.name == *name)
+                            .expect("every called external is imported");
+
+                        (arity, index as u32)
+                    }
+                    None => (args.len(), self.functions[name]),
+                };
+
+                for arg in &args[..fixed] {
+                    self.expr(arg)?;
+                }
+
+                if let Some((_, true)) = external {
+                    self.varargs(&args[fixed..])?;
+                }
+
+                self.code().op_index(op::CALL, index);
+            }
+            TypedExpr::Array { elements, ty } => {
+                let element = match ty {
+                    Type::Array(element, _) => element,
+                    ty => unreachable!("array literal of type {:?}", ty),
+                };
+
+                let size = size_of(element);
+                let array = self.alloc(size * elements.len() as u32);
+
+                for (i, value) in elements.iter().enumerate() {
+                    self.code().op_index(op::LOCAL_GET, array);
+                    self.expr(value)?;
+                    self.store(element, i as u32 * size);
+                }
+
+                self.code().op_index(op::LOCAL_GET, array);
+            }
+            TypedExpr::Index { target, .. } if target.ty() == Type::String => {
+                return unsupported_at("indexing a `String`", self.span)
+            }
+            TypedExpr::Index { target, index, ty } => {
+                self.expr(target)?;
+                self.expr(index)?;
+
+                self.code()
+                    .i32_const(size_of(ty) as i32)
+                    .op(op::I32_MUL)
+                    .op(op::I32_ADD);
+
+                self.load(ty, 0);
+            }
+            TypedExpr::Identifier { name, .. } => self.get(name),
+            TypedExpr::Number(num) => match num.parse::<i32>() {
+                Ok(num) => {
+                    self.code().i32_const(num);
+                }
+                Err(_) => {
+                    return CodegenError::at(
+                        format!("Integer `{}` doesn't fit in a 32-bit `Int`", num),
+                        vec![],
+                        self.span,
+                    )
+                }
+            },
+            TypedExpr::Bool(value) => {
+                self.code().i32_const(*value as i32);
+            }
+            TypedExpr::Char(_) => return unsupported_at("`Char` values", self.span),
+            TypedExpr::Float(num) => {
+                self.code().f64_const(num.parse().unwrap());
+            }
+            TypedExpr::String(value) => {
+                let offset = self.string(value);
+                self.code().i32_const(offset as i32);
+            }
+            // each part is turned into a string and concatenated onto the
+            // ones before it.
+            TypedExpr::Interpolation(parts) => {
+                let concat = self.helper(CONCAT);
+                self.expr(&TypedExpr::String(String::new()))?;
+
+                for part in parts {
+                    match part {
+                        TypedPart::Text(text) => self.expr(&TypedExpr::String(text.clone()))?,
+                        TypedPart::Expr(expr) => {
+                            let expr = &shown(expr);
+                            self.expr(expr)?;
+
+                            match expr.ty() {
+                                Type::Int => {
+                                    let itoa = self.helper(ITOA);
+                                    self.code().op_index(op::CALL, itoa);
+                                }
+                                Type::String => (),
+                                ty => {
+                                    return unsupported_at(
+                                        format!("interpolating a `{}` outside of `print`", ty),
+                                        self.span,
+                                    )
+                                }
+                            }
+                        }
+                    }
+
+                    self.code().op_index(op::CALL, concat);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // allocates `size` bytes, returning the local the pointer is stored in.
+    fn alloc(&mut self, size: u32) -> u32 {
+        let alloc = self.helper(ALLOC);
+        let pointer = self.local(ValType::I32);
+
+        self.code()
+            .i32_const(size as i32)
+            .op_index(op::CALL, alloc)
+            .op_index(op::LOCAL_SET, pointer);
+
+        pointer
+    }
+
+    fn varargs(&mut self, args: &[TypedExpr]) -> Result<(), CodegenError> {
+        if args.is_empty() {
+            self.code().i32_const(0);
+            return Ok(());
+        }
+
+        let buffer = self.alloc(8 * args.len() as u32);
+
+        for (i, arg) in args.iter().enumerate() {
+            self.code().op_index(op::LOCAL_GET, buffer);
+            self.expr(arg)?;
+
+            match val_type(&arg.ty()) {
+                Some(ValType::F64) => self.code().memory(op::F64_STORE, 3, i as u32 * 8),
+                _ => self
+                    .code()
+                    .op(op::I64_EXTEND_I32_S)
+                    .memory(op::I64_STORE, 3, i as u32 * 8),
+            };
+        }
+
+        self.code().op_index(op::LOCAL_GET, buffer);
+        Ok(())
+    }
+
+    fn helpers(&mut self) -> Vec<Function> {
+        let i32_to_i32 = self.signature(vec![ValType::I32], vec![ValType::I32]);
+        let i32s_to_i32 = self.signature(vec![ValType::I32; 2], vec![ValType::I32]);
+        let (strlen, alloc) = (self.helper(STRLEN), self.helper(ALLOC));
+
+        // alloc(size) bumps the heap pointer to the next 8-byte boundary,
+        // growing memory when it runs past the end.
+        let mut code = Code::default();
+        code.op_index(op::GLOBAL_GET, HEAP)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::GLOBAL_GET, HEAP)
+            .op_index(op::LOCAL_GET, 0)
+            .op(op::I32_ADD)
+            .i32_const(7)
+            .op(op::I32_ADD)
+            .i32_const(-8)
+            .op(op::I32_AND)
+            .op_index(op::GLOBAL_SET, HEAP)
+            .op_index(op::GLOBAL_GET, HEAP)
+            .memory_size()
+            .i32_const(16)
+            .op(op::I32_SHL)
+            .op(op::I32_GT_U)
+            .block(op::IF)
+            .op_index(op::GLOBAL_GET, HEAP)
+            .i32_const(16)
+            .op(op::I32_SHR_U)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .memory_size()
+            .op(op::I32_SUB)
+            .memory_grow()
+            .op(op::DROP)
+            .op(op::END)
+            .op_index(op::LOCAL_GET, 1)
+            .op(op::END);
+
+        let alloc_fn = Function {
+            signature: i32_to_i32,
+            locals: vec![ValType::I32],
+            code,
+        };
+
+        // strlen(s)
+        let mut code = Code::default();
+        code.block(op::BLOCK)
+            .block(op::LOOP)
+            .op_index(op::LOCAL_GET, 0)
+            .op_index(op::LOCAL_GET, 1)
+            .op(op::I32_ADD)
+            .memory(op::I32_LOAD8_U, 0, 0)
+            .op(op::I32_EQZ)
+            .op_index(op::BR_IF, 1)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::BR, 0)
+            .op(op::END)
+            .op(op::END)
+            .op_index(op::LOCAL_GET, 1)
+            .op(op::END);
+
+        let strlen_fn = Function {
+            signature: i32_to_i32,
+            locals: vec![ValType::I32],
+            code,
+        };
+
+        // strcmp(a, b), only ever compared against 0.
+        let mut code = Code::default();
+        code.block(op::BLOCK)
+            .block(op::LOOP)
+            .op_index(op::LOCAL_GET, 0)
+            .memory(op::I32_LOAD8_U, 0, 0)
+            .op_index(op::LOCAL_SET, 2)
+            .op_index(op::LOCAL_GET, 1)
+            .memory(op::I32_LOAD8_U, 0, 0)
+            .op_index(op::LOCAL_SET, 3)
+            .op_index(op::LOCAL_GET, 2)
+            .op_index(op::LOCAL_GET, 3)
+            .op(op::I32_NE)
+            .op_index(op::BR_IF, 1)
+            .op_index(op::LOCAL_GET, 2)
+            .op(op::I32_EQZ)
+            .op_index(op::BR_IF, 1)
+            .op_index(op::LOCAL_GET, 0)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .op_index(op::LOCAL_SET, 0)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::BR, 0)
+            .op(op::END)
+            .op(op::END)
+            .op_index(op::LOCAL_GET, 2)
+            .op_index(op::LOCAL_GET, 3)
+            .op(op::I32_SUB)
+            .op(op::END);
+
+        let strcmp_fn = Function {
+            signature: i32s_to_i32,
+            locals: vec![ValType::I32; 2],
+            code,
+        };
+
+        // concat(a, b) copies both strings into a new allocation.
+        let mut code = Code::default();
+        code.op_index(op::LOCAL_GET, 0)
+            .op_index(op::CALL, strlen)
+            .op_index(op::LOCAL_SET, 2)
+            .op_index(op::LOCAL_GET, 1)
+            .op_index(op::CALL, strlen)
+            .op_index(op::LOCAL_SET, 3)
+            .op_index(op::LOCAL_GET, 2)
+            .op_index(op::LOCAL_GET, 3)
+            .op(op::I32_ADD)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .op_index(op::CALL, alloc)
+            .op_index(op::LOCAL_SET, 4)
+            .op_index(op::LOCAL_GET, 4)
+            .op_index(op::LOCAL_GET, 0)
+            .op_index(op::LOCAL_GET, 2)
+            .memory_copy()
+            .op_index(op::LOCAL_GET, 4)
+            .op_index(op::LOCAL_GET, 2)
+            .op(op::I32_ADD)
+            .op_index(op::LOCAL_GET, 1)
+            .op_index(op::LOCAL_GET, 3)
+            .i32_const(1)
+            .op(op::I32_ADD)
+            .memory_copy()
+            .op_index(op::LOCAL_GET, 4)
+            .op(op::END);
+
+        let concat_fn = Function {
+            signature: i32s_to_i32,
+            locals: vec![ValType::I32; 3],
+            code,
+        };
+
+        // itoa(n) writes the digits backwards from the end of a new buffer,
+        // negating through unsigned arithmetic so the smallest int survives.
+        let mut code = Code::default();
+        code.op_index(op::LOCAL_GET, 0)
+            .i32_const(0)
+            .op(op::I32_LT_S)
+            .op_index(op::LOCAL_SET, 2)
+            .op_index(op::LOCAL_GET, 2)
+            .block(op::IF)
+            .i32_const(0)
+            .op_index(op::LOCAL_GET, 0)
+            .op(op::I32_SUB)
+            .op_index(op::LOCAL_SET, 0)
+            .op(op::END)
+            .i32_const(12)
+            .op_index(op::CALL, alloc)
+            .i32_const(11)
+            .op(op::I32_ADD)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(0)
+            .memory(op::I32_STORE8, 0, 0)
+            .block(op::LOOP)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(1)
+            .op(op::I32_SUB)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::LOCAL_GET, 1)
+            .op_index(op::LOCAL_GET, 0)
+            .i32_const(10)
+            .op(op::I32_REM_U)
+            .i32_const(48)
+            .op(op::I32_ADD)
+            .memory(op::I32_STORE8, 0, 0)
+            .op_index(op::LOCAL_GET, 0)
+            .i32_const(10)
+            .op(op::I32_DIV_U)
+            .op_index(op::LOCAL_SET, 0)
+            .op_index(op::LOCAL_GET, 0)
+            .op_index(op::BR_IF, 0)
+            .op(op::END)
+            .op_index(op::LOCAL_GET, 2)
+            .block(op::IF)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(1)
+            .op(op::I32_SUB)
+            .op_index(op::LOCAL_SET, 1)
+            .op_index(op::LOCAL_GET, 1)
+            .i32_const(45)
+            .memory(op::I32_STORE8, 0, 0)
+            .op(op::END)
+            .op_index(op::LOCAL_GET, 1)
+            .op(op::END);
+
+        let itoa_fn = Function {
+            signature: i32_to_i32,
+            locals: vec![ValType::I32; 2],
+            code,
+        };
+
+        vec![alloc_fn, strlen_fn, strcmp_fn, concat_fn, itoa_fn]
+    }
+
+    fn module(&mut self, functions: Vec<Function>, stmts: &[Stmt]) -> Vec<u8> {
+        let mut functions_with_helpers = self.helpers();
+        functions_with_helpers.extend(functions);
+
+        let functions = functions_with_helpers;
+
+        let heap_start = (DATA_START + self.data.len() as u32 + 7) & !7;
+        let pages = heap_start / PAGE_SIZE + 1;
+
+        let mut module = MAGIC.to_vec();
+
+        let types = self
+            .signatures
+            .iter()
+            .map(|(params, results)| {
+                let mut out = vec![0x60];
+                encoder::vector(&mut out, params.iter().map(|t| vec![t.byte()]).collect());
+                encoder::vector(&mut out, results.iter().map(|t| vec![t.byte()]).collect());
+                out
+            })
+            .collect();
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, types);
+        encoder::section(&mut module, section::TYPE, contents);
+
+        let imports = self
+            .imports
+            .iter()
+            .map(|import| {
+                let mut out = vec![];
+                encoder::name(&mut out, "env");
+                encoder::name(&mut out, &import.name);
+                out.push(0x00);
+                encoder::unsigned(&mut out, import.signature as u64);
+                out
+            })
+            .collect();
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, imports);
+        encoder::section(&mut module, section::IMPORT, contents);
+
+        let signatures = functions
+            .iter()
+            .map(|function| {
+                let mut out = vec![];
+                encoder::unsigned(&mut out, function.signature as u64);
+                out
+            })
+            .collect();
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, signatures);
+        encoder::section(&mut module, section::FUNCTION, contents);
+
+        let mut contents = vec![];
+        let mut limits = vec![0x00];
+        encoder::unsigned(&mut limits, pages as u64);
+        encoder::vector(&mut contents, vec![limits]);
+        encoder::section(&mut module, section::MEMORY, contents);
+
+        let globals = self
+            .global_types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let mut init = Code::default();
+
+                match ty {
+                    ValType::I32 if i as u32 == HEAP => init.i32_const(heap_start as i32),
+                    ValType::I32 => init.i32_const(0),
+                    ValType::F64 => init.f64_const(0.0),
+                };
+
+                let mut out = vec![ty.byte(), 0x01];
+                out.extend(init.0);
+                out.push(op::END);
+                out
+            })
+            .collect();
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, globals);
+        encoder::section(&mut module, section::GLOBAL, contents);
+
+        let mut exports = vec![];
+
+        let mut memory = vec![];
+        encoder::name(&mut memory, "memory");
+        memory.extend([0x02, 0x00]);
+        exports.push(memory);
+
+        for stmt in stmts {
+            if let Stmt::Function { name, .. } = stmt {
+                let mut export = vec![];
+                encoder::name(&mut export, name);
+                export.push(0x00);
+                encoder::unsigned(&mut export, self.functions[name] as u64);
+                exports.push(export);
+            }
+        }
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, exports);
+        encoder::section(&mut module, section::EXPORT, contents);
+
+        let bodies = functions
+            .into_iter()
+            .map(|function| {
+                let mut body = vec![];
+                let locals = function
+                    .locals
+                    .iter()
+                    .map(|ty| vec![0x01, ty.byte()])
+                    .collect();
+
+                encoder::vector(&mut body, locals);
+                body.extend(function.code.0);
+
+                let mut out = vec![];
+                encoder::unsigned(&mut out, body.len() as u64);
+                out.extend(body);
+                out
+            })
+            .collect();
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, bodies);
+        encoder::section(&mut module, section::CODE, contents);
+
+        let mut segment = vec![0x00];
+        let mut offset = Code::default();
+        offset.i32_const(DATA_START as i32).op(op::END);
+        segment.extend(offset.0);
+        encoder::unsigned(&mut segment, self.data.len() as u64);
+        segment.extend(&self.data);
+
+        let mut contents = vec![];
+        encoder::vector(&mut contents, vec![segment]);
+        encoder::section(&mut module, section::DATA, contents);
+
+        module
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,133 @@
+use super::*;
+
+use crate::ir;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typeck::TypeChecker;
+
+fn generate(src: &str) -> Result<Vec<u8>, CodegenError> {
+    let tokens = Lexer::new(src).lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let (typed, _) = TypeChecker::check(ast).unwrap();
+
+    WasmBackend::assemble(&ir::lower(&typed))
+}
+
+#[test]
+fn leb128() {
+    let mut out = vec![];
+    encoder::unsigned(&mut out, 624485);
+    assert_eq!(out, [0xe5, 0x8e, 0x26]);
+
+    let mut out = vec![];
+    encoder::signed(&mut out, -123456);
+    assert_eq!(out, [0xc0, 0xbb, 0x78]);
+
+    let mut out = vec![];
+    encoder::signed(&mut out, 64);
+    assert_eq!(out, [0xc0, 0x00]);
+}
+
+#[test]
+fn module_header() {
+    let module = generate("func main(): int { return 0; }").unwrap();
+
+    assert_eq!(&module[..8], MAGIC);
+    assert_eq!(module[8], section::TYPE);
+}
+
+#[test]
+fn externals_are_imported() {
+    let module = generate(
+        r#"func printf(format, ...) @requires("stdio.h")
+
+        func main(): int {
+            printf("%d\n", 42);
+            return 0;
+        }"#,
+    )
+    .unwrap();
+
+    let import = [&[3][..], b"env", &[6], b"printf"].concat();
+    assert!(module.windows(import.len()).any(|w| w == import));
+}
+
+#[test]
+fn prelude_imports_printf() {
+    let module = generate("func main(): int { println(42); return 0; }").unwrap();
+
+    let import = [&[3][..], b"env", &[6], b"printf"].concat();
+    assert!(module.windows(import.len()).any(|w| w == import));
+}
+
+#[test]
+fn interpolation() {
+    assert!(generate(r#"func main(): int { let s = "{1} {"a"}"; return 0; }"#).is_ok());
+
+    let err = generate(r#"func main(): int { let s = "{1.5}"; return 0; }"#).unwrap_err();
+    assert!(err.message.contains("interpolating a `Float`"));
+}
+
+#[test]
+fn unsupported_operator() {
+    let err = generate("func main(): int { return 2 ** 3; }").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "The wasm32 target doesn't support the `Expo` operator"
+    );
+}
+
+#[test]
+fn sized_types_are_unsupported() {
+    let err = generate("func main(): int {\n    let x: Int8 = 1;\n    return 0;\n}").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "The wasm32 target doesn't support `Int8` values"
+    );
+    assert_eq!(err.span.line, 2);
+}
+
+#[test]
+fn errors_point_at_the_source() {
+    let err = generate("func main(): int { return 2 ** 3; }").unwrap_err();
+
+    assert_eq!(err.span.start, 28);
+
+    // the ones inside an expression point at the statement it's in.
+    let err =
+        generate("func main(): int {\n    let s = \"ab\";\n    print(s[0]);\n    return 0;\n}")
+            .unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "The wasm32 target doesn't support indexing a `String`"
+    );
+    assert_eq!(err.span.line, 3);
+}
+
+#[test]
+fn if_expressions() {
+    assert!(
+        generate("func main(): int { let x = if 1 > 2 { 1.5 } else { 2 }; return 0; }").is_ok()
+    );
+}
+
+#[test]
+fn match_expressions() {
+    assert!(generate(
+        r#"func main(): int { let s = match "b" { "a" => 1, _ => 2.5 }; return match 1 { 1 => 0, _ => 1 }; }"#
+    )
+    .is_ok());
+}
+
+#[test]
+fn math_on_floats() {
+    assert!(
+        generate("func main(): int { let x = sqrt(2) + floor(max(1.5, 2)); return 0; }").is_ok()
+    );
+
+    let err = generate("func main(): int { return abs(2); }").unwrap_err();
+    assert!(err.message.contains("`abs` of a `Int`"));
+}
@@ -28,6 +28,10 @@ where
     fn green(&self) -> String {
         colour_my_pencils!("0;1;32", self)
     }
+
+    fn yellow(&self) -> String {
+        colour_my_pencils!("0;1;33", self)
+    }
 }
 
 impl Colour for &str {}
@@ -0,0 +1,69 @@
+use super::{Diagnostic, Severity};
+
+fn string(value: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+impl Diagnostic {
+    // a single line of JSON, for `--error-format=json`. the position fields
+    // are `null` when the diagnostic isn't tied to a place in the source.
+    pub fn to_json(&self, file_name: &str) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        let (line, column, span) = if self.span.line == 0 {
+            ("null".to_string(), "null".to_string(), "null".to_string())
+        } else {
+            (
+                self.span.line.to_string(),
+                self.span.column.to_string(),
+                format!(
+                    "{{\"start\":{},\"end\":{}}}",
+                    self.span.start, self.span.end
+                ),
+            )
+        };
+
+        let notes = self
+            .notes
+            .iter()
+            .map(|note| string(note))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let suggestion = match &self.suggestion {
+            Some(suggestion) => string(suggestion),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"type\":{},\"severity\":\"{}\",\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"span\":{},\"notes\":[{}],\"suggestion\":{}}}",
+            string(&self.code),
+            severity,
+            string(&self.message),
+            string(file_name),
+            line,
+            column,
+            span,
+            notes,
+            suggestion
+        )
+    }
+}
@@ -0,0 +1,91 @@
+mod json;
+mod render;
+mod suggest;
+
+use crate::codegen::CodegenError;
+use crate::lexer::{LexerError, Span};
+use crate::parser::ParserError;
+use crate::typeck::TypeError;
+
+pub use self::suggest::suggest;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: String,
+    pub span: Span,
+    pub message: String,
+    pub notes: Vec<String>,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: impl ToString, span: Span, message: impl ToString) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: code.to_string(),
+            span,
+            message: message.to_string(),
+            notes: vec![],
+            suggestion: None,
+        }
+    }
+
+    pub fn warning(code: impl ToString, span: Span, message: impl ToString) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, span, message)
+        }
+    }
+
+    pub fn with_note(mut self, note: impl ToString) -> Diagnostic {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<String>) -> Diagnostic {
+        self.suggestion = suggestion;
+        self
+    }
+
+    pub fn emit(&self, source: &str, file_name: &str) {
+        eprint!("{}", self.render(source, file_name));
+    }
+}
+
+impl From<LexerError> for Diagnostic {
+    fn from(err: LexerError) -> Diagnostic {
+        Diagnostic::error(format!("{:?}", err.kind), err.span, err.message)
+    }
+}
+
+impl From<ParserError> for Diagnostic {
+    fn from(err: ParserError) -> Diagnostic {
+        Diagnostic::error(format!("{:?}", err.r#type), err.token.span, err.message)
+    }
+}
+
+impl From<TypeError> for Diagnostic {
+    fn from(err: TypeError) -> Diagnostic {
+        Diagnostic::error("TypeError", err.token.span, err.message).with_suggestion(err.suggestion)
+    }
+}
+
+impl From<CodegenError> for Diagnostic {
+    fn from(err: CodegenError) -> Diagnostic {
+        let diagnostic = Diagnostic::error("CompileError", err.span, err.message);
+
+        err.notes
+            .into_iter()
+            .fold(diagnostic, Diagnostic::with_note)
+    }
+}
+
+#[cfg(test)]
+mod tests;
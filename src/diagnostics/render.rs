@@ -0,0 +1,166 @@
+use std::fmt::Write;
+
+use super::{Diagnostic, Severity};
+use crate::colors::*;
+
+// how many columns a tab stop is apart when a line with tabs is shown.
+pub const TAB_WIDTH: usize = 4;
+
+impl Diagnostic {
+    pub fn render(&self, source: &str, file_name: &str) -> String {
+        self.render_with_tabs(source, file_name, TAB_WIDTH)
+    }
+
+    // renders the diagnostic with up to two lines of context either side of
+    // the offending line, underlining the span. tabs are laid out to every
+    // `tab_width`th column so the underline stays under what it points at.
+    pub fn render_with_tabs(&self, source: &str, file_name: &str, tab_width: usize) -> String {
+        let mut out = String::new();
+
+        // a byte order mark isn't shown with the first line.
+        let lines: Vec<&str> = source.trim_start_matches('\u{feff}').lines().collect();
+        let (error_line, column) = (self.span.line, self.span.column);
+
+        // the underline stops at the end of the line the span starts on.
+        let width = source
+            .get(self.span.start..self.span.end)
+            .and_then(|text| text.lines().next())
+            .map_or(0, |text| text.chars().count())
+            .max(1);
+
+        let max_line_num = (error_line + 2).min(lines.len());
+        let line_num_width = max_line_num.to_string().len();
+
+        let paint = |text: &str| match self.severity {
+            Severity::Error => text.red(),
+            Severity::Warning => text.yellow(),
+        };
+
+        let severity = paint(match self.severity {
+            Severity::Error => "error:",
+            Severity::Warning => "warning:",
+        });
+
+        writeln!(out, "   {} {}", severity, self.code).unwrap();
+
+        // diagnostics without a location point at the whole file.
+        if error_line == 0 || error_line > lines.len() {
+            writeln!(out, "    {} {}", "-->".blue(), file_name).unwrap();
+            writeln!(out, "     {}", paint(&self.message)).unwrap();
+        } else {
+            writeln!(
+                out,
+                "    {} {}:{}:{}",
+                "-->".blue(),
+                file_name,
+                error_line,
+                column
+            )
+            .unwrap();
+
+            let context = error_line.saturating_sub(2).max(1)..=max_line_num;
+
+            for line_num in context {
+                let line = lines[line_num - 1];
+                let gutter = format!("{:width$} |", line_num, width = line_num_width);
+                writeln!(out, "  {} {}", gutter.blue(), expand(line, tab_width)).unwrap();
+
+                if line_num == error_line {
+                    // where the span starts and ends on the screen, once the
+                    // tabs and the wide or zero width characters before it and
+                    // in it are laid out.
+                    let before: String = line.chars().take(column.saturating_sub(1)).collect();
+                    let through: String =
+                        line.chars().take(before.chars().count() + width).collect();
+
+                    let start = text_width(&expand(&before, tab_width));
+                    let end = text_width(&expand(&through, tab_width));
+
+                    let underline =
+                        format!("{} {}", "^".repeat((end - start).max(1)), self.message);
+
+                    writeln!(
+                        out,
+                        "   {} {}",
+                        " ".repeat(start + 1 + line_num_width),
+                        paint(&underline)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        for note in &self.notes {
+            writeln!(out, "   {} {}", "= note:".blue(), note).unwrap();
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            writeln!(
+                out,
+                "   {} did you mean `{}`?",
+                "= help:".blue(),
+                suggestion
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+// `line` with each tab swapped for the spaces up to the next tab stop.
+fn expand(line: &str, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+
+    let mut out = String::new();
+    let mut column = 0;
+
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+
+            out.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            out.push(c);
+            column += char_width(c);
+        }
+    }
+
+    out
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+// how many columns a terminal gives `c`: none for the marks that go on the
+// character before them and the invisible ones, two for the wide East Asian
+// characters and most emoji, and one for everything else.
+fn char_width(c: char) -> usize {
+    match c {
+        '\u{300}'..='\u{36f}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{200b}'..='\u{200f}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}'
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{feff}' => 0,
+        '\u{1100}'..='\u{115f}'
+        | '\u{2e80}'..='\u{303e}'
+        | '\u{3041}'..='\u{33ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{a000}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{1f300}'..='\u{1f64f}'
+        | '\u{1f900}'..='\u{1f9ff}'
+        | '\u{20000}'..='\u{3fffd}' => 2,
+        _ => 1,
+    }
+}
@@ -0,0 +1,159 @@
+use super::*;
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+#[test]
+fn render_parser_error() {
+    let src = "let x = 1;\nlet = 5;\nlet y = 2;\n";
+    let tokens = Lexer::new(src).lex().unwrap();
+
+    let errors = Parser::new(tokens).parse().err().unwrap();
+    let diagnostic = Diagnostic::from(errors.into_iter().next().unwrap());
+
+    assert_eq!(
+        diagnostic.render(src, "main.uma"),
+        [
+            "   error: ExpectedToken",
+            "    --> main.uma:2:5",
+            "  1 | let x = 1;",
+            "  2 | let = 5;",
+            "          ^ Expected `Identifier` but found `Equals`",
+            "  3 | let y = 2;",
+            "",
+        ]
+        .join("\n")
+    )
+}
+
+#[test]
+fn render_underline_and_notes() {
+    let src = "let total = count + 1;";
+    let diagnostic = Diagnostic::error("TypeError", Span::new(12, 17, 1, 13), "Unknown")
+        .with_note("declare it with `let`");
+
+    assert_eq!(
+        diagnostic.render(src, "main.uma"),
+        [
+            "   error: TypeError",
+            "    --> main.uma:1:13",
+            "  1 | let total = count + 1;",
+            "                  ^^^^^ Unknown",
+            "   = note: declare it with `let`",
+            "",
+        ]
+        .join("\n")
+    )
+}
+
+#[test]
+fn render_under_tabs() {
+    let src = "func main(): int {\n\tlet x = $;\n}";
+    let err = Lexer::new(src).lex().err().unwrap();
+
+    assert_eq!(
+        Diagnostic::from(err).render_with_tabs(src, "main.uma", 4),
+        [
+            "   error: UnexpectedCharacter",
+            "    --> main.uma:2:10",
+            "  1 | func main(): int {",
+            "  2 |     let x = $;",
+            "                  ^ Unexpected character `$`",
+            "  3 | }",
+            "",
+        ]
+        .join("\n")
+    )
+}
+
+#[test]
+fn render_windows_line_endings() {
+    let src = "\u{feff}let x = 1;\r\nlet = 5;\r\n";
+    let tokens = Lexer::new(src).lex().unwrap();
+
+    let errors = Parser::new(tokens).parse().err().unwrap();
+    let diagnostic = Diagnostic::from(errors.into_iter().next().unwrap());
+
+    assert_eq!(
+        diagnostic.render(src, "main.uma"),
+        [
+            "   error: ExpectedToken",
+            "    --> main.uma:2:5",
+            "  1 | let x = 1;",
+            "  2 | let = 5;",
+            "          ^ Expected `Identifier` but found `Equals`",
+            "",
+        ]
+        .join("\n")
+    )
+}
+
+#[test]
+fn render_under_wide_characters() {
+    let src = "let 名前 = \"🎉\" + $;";
+    let err = Lexer::new(src).lex().err().unwrap();
+
+    assert_eq!(
+        Diagnostic::from(err).render(src, "main.uma"),
+        [
+            "   error: UnexpectedCharacter",
+            "    --> main.uma:1:16",
+            "  1 | let 名前 = \"🎉\" + $;",
+            "                        ^ Unexpected character `$`",
+            "",
+        ]
+        .join("\n")
+    )
+}
+
+#[test]
+fn render_unlocated() {
+    let diagnostic = Diagnostic::error("TypeError", Span::default(), "Missing `main`");
+
+    assert_eq!(
+        diagnostic.render("", "main.uma"),
+        "   error: TypeError\n    --> main.uma\n     Missing `main`\n"
+    )
+}
+
+#[test]
+fn suggestions() {
+    assert_eq!(suggest::edit_distance("kitten", "sitting"), 3);
+    assert_eq!(suggest::edit_distance("cuont", "count"), 1);
+    assert_eq!(
+        suggest("prnt", ["print", "main"]),
+        Some(String::from("print"))
+    );
+    assert_eq!(suggest("x", ["count"]), None);
+}
+
+#[test]
+fn json() {
+    let diagnostic = Diagnostic::warning(
+        "UnusedVariable",
+        Span::new(4, 5, 1, 4),
+        "Variable `x` is never used",
+    )
+    .with_note("a \"note\"");
+
+    assert_eq!(
+        diagnostic.to_json("main.uma"),
+        r#"{"type":"UnusedVariable","severity":"warning","message":"Variable `x` is never used","file":"main.uma","line":1,"column":4,"span":{"start":4,"end":5},"notes":["a \"note\""],"suggestion":null}"#
+    );
+
+    let unlocated = Diagnostic::error("CompileError", Span::default(), "failed\n");
+
+    assert_eq!(
+        unlocated.to_json("main.uma"),
+        r#"{"type":"CompileError","severity":"error","message":"failed\n","file":"main.uma","line":null,"column":null,"span":null,"notes":[],"suggestion":null}"#
+    );
+}
+
+#[test]
+fn lexer_errors_keep_their_offsets() {
+    let src = "let x = 1;\nlet s = \"ok \\q\";\n";
+    let diagnostic = Diagnostic::from(Lexer::new(src).lex().unwrap_err());
+
+    assert_eq!(diagnostic.span, Span::new(23, 25, 2, 13));
+    assert_eq!(&src[diagnostic.span.start..diagnostic.span.end], "\\q");
+}
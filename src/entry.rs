@@ -1,104 +1,964 @@
-use std::fs;
+use std::{
+    cell::Cell,
+    collections::HashSet,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use crate::{
-    codegen::{Codegen, CodegenBackend},
+use uma::{
     colors::*,
-    lexer::{Lexer, Token},
-    parser::Parser,
+    interpreter::{Interpreter, RuntimeError, Value},
+    lints::{Level, LintConfig},
+    timings::Timed,
+    Artifact, Backend, CBackend, Cfg, Codegen, CodegenBackend, CodegenError, CodegenOptions,
+    CompilerOptions, Diagnostic, Lexer, Library, Parser, Severity, Span, Stmt, Timings, Token,
+    TokenKind, TypeChecker, TypedStmt,
 };
 
-macro_rules! print_line {
-    ($line_num:expr, $msg:expr, $width:expr) => {{
-        let line_num = $line_num;
+// a command that stopped short, once it's printed why. `main` exits with a
+// failing status for it.
+pub struct Failed;
 
-        println!(
-            "  {} {}",
-            format!("{:width$} |", line_num, width = $width).blue(),
-            $msg
-        );
-    }};
+// what a command finishes with, the status `main` exits with.
+pub type Status = Result<i32, Failed>;
+
+// an error that isn't about any of the sources, like a file that can't be
+// written.
+fn failed(message: impl std::fmt::Display) -> Failed {
+    eprintln!("{} {}", "error:".red(), message);
+    Failed
+}
 
-    ($msg:expr, $width:expr) => {{
-        println!("{} {}", " ".repeat($width), $msg);
-    }};
+// how the commands that check a program go about it.
+#[derive(Default)]
+pub struct CheckOptions {
+    pub deny_warnings: bool,
+    // the lints `--allow` and `--deny` set, over what `uma.toml` sets.
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    // print diagnostics as JSON.
+    pub json: bool,
+    // print how long each phase took.
+    pub timings: bool,
+    // what the program's built for, for its `@cfg`s.
+    pub cfg: Cfg,
+    // it's built as a library, without a `main`.
+    pub library: Option<Library>,
+}
 
-    ($msg:expr) => {{
-        println!("   {}", $msg);
-    }};
+// every file a program is made of, indexed by `Span::file`. diagnostics are
+// printed as JSON instead of rendered with `json`.
+struct Sources {
+    files: Vec<(PathBuf, String)>,
+    json: bool,
 }
 
-fn error(token: &Token, err_type: &str, message: &str, source: &str, file_name: &str) {
-    let lines: Vec<&str> = source.lines().collect();
-    let error_line = token.line;
+impl Sources {
+    fn new(json: bool) -> Sources {
+        Sources {
+            files: vec![],
+            json,
+        }
+    }
+
+    fn single(path: &str, source: String, json: bool) -> Sources {
+        Sources {
+            files: vec![(PathBuf::from(path), source)],
+            json,
+        }
+    }
 
-    let max_line_num = (error_line + 2).min(lines.len());
-    let line_num_width = max_line_num.to_string().len();
+    fn paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|(path, _)| path.clone()).collect()
+    }
 
-    print_line!(format!("{} {}", "error:".red(), err_type));
+    fn emit(&self, diagnostic: &Diagnostic) {
+        let (path, source) = self
+            .files
+            .get(diagnostic.span.file)
+            .unwrap_or(&self.files[0]);
 
-    print_line!(format!(
-        " {} {}:{}:{}",
-        "-->".blue(),
-        file_name,
-        error_line,
-        token.column,
-    ));
+        let file_name = path.to_string_lossy();
 
-    if error_line > 2 {
-        print_line!(error_line - 2, lines[error_line - 3], line_num_width)
+        if self.json {
+            eprintln!("{}", diagnostic.to_json(&file_name));
+        } else {
+            diagnostic.emit(source, &file_name);
+        }
     }
 
-    if error_line > 1 {
-        print_line!(error_line - 1, lines[error_line - 2], line_num_width);
+    fn fail(&self, diagnostics: Vec<Diagnostic>) -> Failed {
+        for diagnostic in diagnostics {
+            self.emit(&diagnostic);
+        }
+
+        Failed
     }
+}
+
+struct Loader {
+    sources: Sources,
+    // the files being loaded, to catch import cycles.
+    stack: Vec<PathBuf>,
+    loaded: HashSet<PathBuf>,
+    program: Vec<Stmt>,
+    timings: Timings,
+}
+
+impl Loader {
+    fn new(json: bool) -> Loader {
+        Loader {
+            sources: Sources::new(json),
+            stack: vec![],
+            loaded: HashSet::new(),
+            program: vec![],
+            timings: Timings::new(),
+        }
+    }
+
+    // lexes and parses `path`, merging in the files it imports where they're
+    // imported. a file imported more than once is only loaded the first time.
+    fn load(&mut self, path: PathBuf, source: String) -> Result<(), Vec<Diagnostic>> {
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        self.stack.push(canonical.clone());
+        self.loaded.insert(canonical);
+
+        let file = self.sources.files.len();
+        self.sources.files.push((path.clone(), source));
+
+        // the parser borrows the source, so it's done with before the imports
+        // add theirs.
+        let ast = {
+            let source = &self.sources.files[file].1;
+
+            // the lexer runs as the parser goes, the time spent in it is
+            // taken out of the parser's.
+            let lexing = Rc::new(Cell::new(Duration::ZERO));
+            let lexer = Timed::new(Lexer::with_file(source, file), lexing.clone());
+
+            let mut parser = Parser::from_stream(lexer);
+            let start = Instant::now();
+            let result = parser.parse();
+
+            self.timings.record("lex", lexing.get());
+            self.timings
+                .record("parse", start.elapsed().saturating_sub(lexing.get()));
+
+            if let Some(err) = parser.lexer_error() {
+                let mut diagnostic = Diagnostic::from(err);
+                diagnostic.span.file = file;
+                return Err(vec![diagnostic]);
+            }
+
+            result.map_err(|errors| errors.into_iter().map(Diagnostic::from).collect::<Vec<_>>())?
+        };
+
+        for stmt in ast {
+            match stmt {
+                Stmt::Import {
+                    path: import,
+                    token,
+                } => {
+                    let imported = path.parent().unwrap_or(Path::new("")).join(&import);
+                    let error = |message: String| {
+                        vec![Diagnostic::error("ImportError", token.span, message)]
+                    };
+
+                    let canonical = fs::canonicalize(&imported)
+                        .map_err(|err| error(format!("Couldn't import `{}`: {}", import, err)))?;
 
-    print_line!(error_line, lines[error_line - 1], line_num_width);
+                    if let Some(start) = self.stack.iter().position(|file| *file == canonical) {
+                        let cycle = self.stack[start..]
+                            .iter()
+                            .chain([&canonical])
+                            .map(|file| file.file_name().unwrap().to_string_lossy())
+                            .collect::<Vec<_>>();
 
-    print_line!(format!(
-        "{} {}",
-        " ".repeat(token.column + line_num_width),
-        format!("^ {}", message).red()
-    ));
+                        return Err(error(format!("Import cycle: {}", cycle.join(" -> "))));
+                    }
 
-    if error_line < lines.len() {
-        print_line!(error_line + 1, lines[error_line], line_num_width);
+                    if self.loaded.contains(&canonical) {
+                        continue;
+                    }
+
+                    let source = fs::read_to_string(&imported)
+                        .map_err(|err| error(format!("Couldn't import `{}`: {}", import, err)))?;
+
+                    self.load(imported, source)?;
+                }
+                stmt => self.program.push(stmt),
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+// the input file `-` is read from stdin, and the output file `-` is written
+// to stdout.
+const STDIO: &str = "-";
+
+// what `input_file` is called in diagnostics.
+fn file_name(input_file: &str) -> &str {
+    match input_file {
+        STDIO => "<stdin>",
+        file => file,
     }
+}
+
+fn read(input_file: &str) -> Result<String, Failed> {
+    if input_file == STDIO {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|err| failed(format!("Couldn't read stdin: {}", err)))?;
 
-    if error_line + 1 < lines.len() {
-        print_line!(error_line + 2, lines[error_line + 1], line_num_width);
+        return Ok(source);
     }
 
-    std::process::exit(1);
+    fs::read_to_string(input_file)
+        .map_err(|err| failed(format!("Couldn't read `{}`: {}", input_file, err)))
+}
+
+fn load(input_file: &str, json: bool) -> Result<Loader, Failed> {
+    let mut loader = Loader::new(json);
+
+    // what stdin imports is found from the current directory.
+    match loader.load(PathBuf::from(file_name(input_file)), read(input_file)?) {
+        Ok(()) => Ok(loader),
+        Err(errors) => Err(loader.sources.fail(errors)),
+    }
 }
 
-pub fn compile(input_file: &String, output_file: Option<&String>) {
-    let src = match fs::read_to_string(&input_file) {
-        Ok(src) => src,
-        Err(e) => panic!("{}", e),
+// the levels of the lints, from the `uma.toml` nearest to `input_file` and
+// then `--allow` and `--deny`.
+fn lint_config(input_file: &str, options: &CheckOptions) -> Result<LintConfig, Failed> {
+    let dir = match input_file {
+        STDIO => PathBuf::from("."),
+        _ => Path::new(input_file)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf(),
     };
+    let dir = fs::canonicalize(&dir).unwrap_or(dir);
+
+    let mut config = match dir
+        .ancestors()
+        .map(|dir| dir.join("uma.toml"))
+        .find(|path| path.is_file())
+    {
+        Some(path) => {
+            let source = fs::read_to_string(&path)
+                .map_err(|err| failed(format!("Couldn't read `{}`: {}", path.display(), err)))?;
+
+            LintConfig::from_toml(&source)
+                .map_err(|err| failed(format!("{}, {}", path.display(), err)))?
+        }
+        None => LintConfig::new(),
+    };
+
+    let levels = [(&options.allow, Level::Allow), (&options.deny, Level::Deny)];
+
+    for (lints, level) in levels {
+        for lint in lints {
+            config.set(lint, level).map_err(failed)?;
+        }
+    }
+
+    Ok(config)
+}
+
+// the checked program, with how long loading and checking it took added to
+// `timings`.
+fn analyze(
+    input_file: &str,
+    options: &CheckOptions,
+    timings: &mut Timings,
+) -> Result<(Vec<TypedStmt>, Sources), Failed> {
+    let config = lint_config(input_file, options)?;
+    let loader = load(input_file, options.json)?;
+
+    analyze_loaded(loader, &config, options, timings)
+}
+
+// `analyze`, for a program that's already been loaded.
+fn analyze_loaded(
+    loader: Loader,
+    config: &LintConfig,
+    options: &CheckOptions,
+    timings: &mut Timings,
+) -> Result<(Vec<TypedStmt>, Sources), Failed> {
+    timings.extend(&loader.timings);
+
+    let program = uma::cfg::strip(loader.program, &options.cfg)
+        .map_err(|errors| loader.sources.fail(errors))?;
+
+    let lints = timings.time("lint", || uma::lints::lint(&program));
+
+    let (typed_ast, warnings) = timings
+        .time("typecheck", || uma::check_program(program, false))
+        .map_err(|err| loader.sources.fail(err.diagnostics))?;
+
+    let mut warnings = config.apply(warnings.into_iter().chain(lints).collect());
+
+    if options.deny_warnings {
+        for warning in &mut warnings {
+            warning.severity = Severity::Error;
+        }
+    }
+
+    if warnings
+        .iter()
+        .any(|warning| warning.severity == Severity::Error)
+    {
+        return Err(loader.sources.fail(warnings));
+    }
+
+    for warning in warnings {
+        loader.sources.emit(&warning);
+    }
+
+    let entry = match options.library {
+        Some(_) => uma::typeck::check_library,
+        None => uma::typeck::check_main,
+    };
+
+    if let Err(err) = timings.time("typecheck", || entry(&typed_ast)) {
+        return Err(loader.sources.fail(err.diagnostics));
+    }
+
+    Ok((typed_ast, loader.sources))
+}
+
+// the phases the build went through, when `--timings` asks for them. it goes
+// to stderr, out of the way of what a program run after it prints.
+fn report(timings: &Timings, options: &CheckOptions) {
+    if options.timings && !timings.is_empty() {
+        eprint!("{}", timings.report());
+    }
+}
+
+// `Codegen::generate`, adding the phases `backend` went through to `timings`.
+// a backend that doesn't time them counts as one phase.
+fn generate(
+    backend: &mut dyn Backend,
+    typed_ast: &[TypedStmt],
+    opts: &CodegenOptions,
+    timings: &mut Timings,
+) -> Result<Artifact, CodegenError> {
+    let start = Instant::now();
+    let artifact = Codegen::generate(backend, typed_ast, opts);
+
+    match backend.timings() {
+        phases if phases.is_empty() => timings.record("codegen", start.elapsed()),
+        phases => timings.extend(&phases),
+    }
+
+    artifact
+}
+
+fn output_path(input_file: &str, output_file: Option<&String>) -> PathBuf {
+    match (output_file, input_file) {
+        (Some(out), _) => PathBuf::from(out),
+        // there's no file to name it after.
+        (None, STDIO) => PathBuf::from("main"),
+        (None, _) => Path::new(input_file).with_extension(""),
+    }
+}
+
+// what `input_file` is called without its extension, `main` for stdin.
+fn stem(input_file: &str) -> String {
+    match input_file {
+        STDIO => "main".into(),
+        _ => Path::new(input_file)
+            .file_stem()
+            .map_or_else(|| "main".into(), |stem| stem.to_string_lossy().to_string()),
+    }
+}
+
+// `libname.a` next to `input_file`, or whatever this platform calls a
+// library of that kind.
+fn library_path(input_file: &str, output_file: Option<&String>, library: Library) -> PathBuf {
+    if let Some(out) = output_file {
+        return PathBuf::from(out);
+    }
+
+    let (prefix, extension) = match library {
+        Library::Static if cfg!(windows) => ("", "lib"),
+        Library::Static => ("lib", "a"),
+        Library::Shared if cfg!(windows) => ("", "dll"),
+        Library::Shared if cfg!(target_os = "macos") => ("lib", "dylib"),
+        Library::Shared => ("lib", "so"),
+    };
+
+    let name = format!("{}{}.{}", prefix, stem(input_file), extension);
+
+    match input_file {
+        STDIO => PathBuf::from(name),
+        _ => Path::new(input_file).with_file_name(name),
+    }
+}
+
+// only the tokens of `input_file` itself, imports aren't followed.
+fn lex(input_file: &str, json: bool) -> Result<Vec<Token>, Failed> {
+    let src = read(input_file)?;
 
     let tokens = Lexer::new(&src).lex();
 
-    let ast = match Parser::new(tokens).parse() {
-        Ok(ast) => ast,
+    tokens.map_err(|err| Sources::single(file_name(input_file), src, json).fail(vec![err.into()]))
+}
+
+fn dump_tokens(input_file: &str, json: bool) -> Status {
+    for token in lex(input_file, json)? {
+        let position = format!("{}:{}", token.span.line, token.span.column);
+
+        match token.value {
+            Some(value) => println!("{:<8}{:?} {:?}", position, token.kind, value),
+            None => println!("{:<8}{:?}", position, token.kind),
+        }
+    }
+
+    Ok(0)
+}
+
+// the tokens or the AST as JSON, for tools that read them.
+#[cfg(feature = "serde")]
+fn dump_json(value: &impl serde::Serialize) -> Status {
+    println!("{}", serde_json::to_string(value).map_err(failed)?);
+    Ok(0)
+}
+
+// the AST with every import merged in, before it's type-checked.
+fn dump_ast(input_file: &str, json: bool) -> Status {
+    print!("{}", uma::parser::dump(&load(input_file, json)?.program));
+    Ok(0)
+}
+
+pub fn fmt(input_file: &str, check: bool) -> Status {
+    let src = read(input_file)?;
+
+    let formatted = match uma::format_str(&src) {
+        Ok(formatted) => formatted,
         Err(err) => {
-            error(
-                &err.token,
-                &format!("{:?}", err.r#type),
-                &err.message,
-                &src,
-                &input_file,
-            );
-            return;
+            return Err(Sources::single(file_name(input_file), src, false).fail(err.diagnostics))
         }
     };
 
-    match Codegen::generate(
-        CodegenBackend::C,
-        ast,
-        output_file.unwrap_or(&input_file.replace(".uma", "")),
-    ) {
-        Err(err) => error(&err.token, "SemanticError", &err.message, &src, &input_file),
+    // stdin can't be formatted in place, it's printed formatted instead.
+    if input_file == STDIO && !check {
+        print!("{}", formatted);
+        return Ok(0);
+    }
+
+    if formatted == src {
+        return Ok(0);
+    }
+
+    if check {
+        println!(
+            "{} isn't formatted, run `uma fmt {}`",
+            file_name(input_file),
+            input_file
+        );
+        return Ok(1);
+    }
+
+    fs::write(input_file, formatted)
+        .map_err(|err| failed(format!("Couldn't write `{}`: {}", input_file, err)))?;
+
+    Ok(0)
+}
+
+pub fn check(input_file: &str, options: CheckOptions) -> Status {
+    let mut timings = Timings::new();
+    analyze(input_file, &options, &mut timings)?;
+
+    report(&timings, &options);
+    Ok(0)
+}
+
+pub fn build(
+    input_file: &str,
+    output_file: Option<&String>,
+    emit: Option<&String>,
+    format: Option<&String>,
+    target: Option<&String>,
+    compiler: CompilerOptions,
+    options: CheckOptions,
+) -> Status {
+    match (emit.map(String::as_str), format.map(String::as_str)) {
+        #[cfg(feature = "serde")]
+        (Some("tokens"), Some("json")) => return dump_json(&lex(input_file, options.json)?),
+        #[cfg(feature = "serde")]
+        (Some("ast"), Some("json")) => return dump_json(&load(input_file, options.json)?.program),
+        (Some("tokens"), _) => return dump_tokens(input_file, options.json),
+        (Some("ast"), _) => return dump_ast(input_file, options.json),
         _ => (),
+    }
+
+    // only the C is text, the rest is written to files.
+    let stdout = output_file.is_some_and(|out| out == STDIO);
+
+    if stdout && emit.is_none() {
+        return Err(failed(
+            "Only the generated C can be written to stdout, with `--emit=c`",
+        ));
+    }
+
+    let library = options.library;
+    let wasm = target.is_some_and(|target| target == "wasm32");
+
+    if library.is_some() && wasm {
+        return Err(failed("Only the C target can build a library"));
+    }
+
+    let mut timings = Timings::new();
+    let (typed_ast, sources) = analyze(input_file, &options, &mut timings)?;
+    let output = match (library, emit) {
+        (Some(library), None) => library_path(input_file, output_file, library),
+        _ => output_path(input_file, output_file),
+    };
+
+    let mut backend: Box<dyn Backend> = match wasm {
+        true => CodegenBackend::Wasm.backend(),
+        false => Box::new(CBackend::new(compiler)),
+    };
+
+    let opts = CodegenOptions {
+        emit_source: emit.is_some(),
+        files: sources.paths(),
+        timings: options.timings,
+        library,
+        ..CodegenOptions::new(&output)
+    };
+
+    let artifact = generate(backend.as_mut(), &typed_ast, &opts, &mut timings);
+    report(&timings, &options);
+
+    // what the library exports is declared next to it, for the C that
+    // links against it.
+    if library.is_some() && !stdout && artifact.is_ok() {
+        let name = format!("{}.h", stem(input_file));
+        let header = output.with_file_name(&name);
+
+        fs::write(&header, CBackend::header(&typed_ast, &name))
+            .map_err(|err| failed(format!("Couldn't write `{}`: {}", header.display(), err)))?;
+    }
+
+    match artifact {
+        // there's only the one file to write to, the runtime goes after the
+        // program.
+        Ok(Artifact::Source(source)) if stdout => {
+            print!("{}", CBackend::standalone(&source));
+            Ok(0)
+        }
+        // the runtime is written next to it, it has to be compiled along with
+        // the program.
+        Ok(Artifact::Source(source)) => {
+            fs::write(output.with_extension("c"), source)
+                .and_then(|_| fs::write(output.with_extension("rt.c"), CBackend::runtime()))
+                .map_err(|err| failed(format!("Couldn't write the C source: {}", err)))?;
+
+            Ok(0)
+        }
+        Ok(_) => Ok(0),
+        Err(err) => Err(sources.fail(vec![err.into()])),
+    }
+}
+
+// with `cache`, the program is run from the cache and only copied to
+// `output_file` when there is one.
+pub fn run(
+    input_file: &str,
+    output_file: Option<&String>,
+    args: &[String],
+    backend: Option<&String>,
+    compiler: CompilerOptions,
+    options: CheckOptions,
+    cache: bool,
+) -> Status {
+    let mut timings = Timings::new();
+    let (typed_ast, sources) = analyze(input_file, &options, &mut timings)?;
+
+    if let Some("interpreter") = backend.map(String::as_str) {
+        report(&timings, &options);
+        return interpret(&typed_ast, &sources, input_file, args);
+    }
+
+    #[cfg(feature = "jit")]
+    if let Some("jit") = backend.map(String::as_str) {
+        report(&timings, &options);
+        return jit(&typed_ast, &sources, input_file, args, compiler);
+    }
+
+    let path = match cache {
+        true => {
+            let path = cached(
+                &typed_ast,
+                &sources,
+                input_file,
+                compiler,
+                &options,
+                &mut timings,
+            );
+            report(&timings, &options);
+            let path = path?;
+
+            if let Some(output) = output_file {
+                fs::copy(&path, output)
+                    .map_err(|err| failed(format!("Couldn't write `{}`: {}", output, err)))?;
+            }
+
+            path
+        }
+        false => {
+            let mut backend = CBackend::new(compiler);
+            let opts = CodegenOptions {
+                files: sources.paths(),
+                timings: options.timings,
+                ..CodegenOptions::new(output_path(input_file, output_file))
+            };
+
+            let artifact = generate(&mut backend, &typed_ast, &opts, &mut timings);
+            report(&timings, &options);
+
+            match artifact {
+                Ok(Artifact::Executable(path)) => path,
+                Ok(_) => unreachable!("the C backend builds an executable"),
+                Err(err) => return Err(sources.fail(vec![err.into()])),
+            }
+        }
     };
+
+    Codegen::execute(&path, args).map_err(|err| sources.fail(vec![err.into()]))
+}
+
+// builds `input_file` with a `main` that runs one of its `@test` functions
+// in place of its own, then runs each test on its own so one that fails
+// doesn't take the others down with it.
+pub fn test(input_file: &str, compiler: CompilerOptions, mut options: CheckOptions) -> Status {
+    options.cfg.test = true;
+
+    // the program's `main` is swapped for one that runs the tests, which
+    // would leave what only `main` calls looking like it's never called.
+    let mut config = lint_config(input_file, &options)?;
+    config.set("UnusedFunction", Level::Allow).map_err(failed)?;
+    let mut loader = load(input_file, options.json)?;
+    loader.program = uma::cfg::strip(std::mem::take(&mut loader.program), &options.cfg)
+        .map_err(|errors| loader.sources.fail(errors))?;
+    let tests = uma::test_names(&loader.program);
+
+    println!(
+        "running {} test{}",
+        tests.len(),
+        if tests.len() == 1 { "" } else { "s" }
+    );
+
+    if tests.is_empty() {
+        return Ok(0);
+    }
+
+    loader
+        .program
+        .retain(|stmt| !matches!(stmt, Stmt::Function { name, .. } if name == "main"));
+
+    if let Err(errors) = loader.load(PathBuf::from("<tests>"), uma::test_harness(&tests)) {
+        return Err(loader.sources.fail(errors));
+    }
+
+    let mut timings = Timings::new();
+    let (typed_ast, sources) = analyze_loaded(loader, &config, &options, &mut timings)?;
+    let path = cached(
+        &typed_ast,
+        &sources,
+        input_file,
+        compiler,
+        &options,
+        &mut timings,
+    );
+    report(&timings, &options);
+    let path = path?;
+
+    let mut failures = vec![];
+
+    for (i, test) in tests.iter().enumerate() {
+        let output = Command::new(&path)
+            .arg(i.to_string())
+            .output()
+            .map_err(|err| failed(format!("Couldn't run `{}`: {}", test, err)))?;
+
+        if output.status.success() {
+            println!("test {} ... {}", test, "ok".green());
+        } else {
+            println!("test {} ... {}", test, "FAILED".red());
+            failures.push((test, output));
+        }
+    }
+
+    for (test, output) in &failures {
+        println!("\n---- {} ----", test);
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        print!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let result = match failures.is_empty() {
+        true => "ok".green(),
+        false => "FAILED".red(),
+    };
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        result,
+        tests.len() - failures.len(),
+        failures.len()
+    );
+
+    Ok(if failures.is_empty() { 0 } else { 1 })
+}
+
+// `uma script.uma`, built the way `uma run` builds it and run from the cache.
+pub fn script(input_file: &str, args: &[String]) -> Status {
+    let compiler = CompilerOptions {
+        checks: true,
+        ..CompilerOptions::default()
+    };
+
+    run(
+        input_file,
+        None,
+        args,
+        None,
+        compiler,
+        CheckOptions::default(),
+        true,
+    )
+}
+
+// where built programs are kept between runs, `$XDG_CACHE_HOME/uma` or
+// `~/.cache/uma`.
+fn cache_dir() -> PathBuf {
+    let env = |name| std::env::var_os(name).map(PathBuf::from);
+
+    env("XDG_CACHE_HOME")
+        .or_else(|| env("HOME").map(|home| home.join(".cache")))
+        .or_else(|| env("LOCALAPPDATA"))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("uma")
+}
+
+// the program built into the cache, or the one already there. it's keyed by
+// every file it's built from, how it's compiled and the version of uma, so
+// changing any of them builds it again.
+fn cached(
+    typed_ast: &[TypedStmt],
+    sources: &Sources,
+    input_file: &str,
+    compiler: CompilerOptions,
+    options: &CheckOptions,
+    timings: &mut Timings,
+) -> Result<PathBuf, Failed> {
+    let mut hasher = DefaultHasher::new();
+    sources.files.hash(&mut hasher);
+    compiler.hash(&mut hasher);
+    std::env::var("UMA_CC").ok().hash(&mut hasher);
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+
+    let name = format!("{}-{:016x}", stem(input_file), hasher.finish());
+    let dir = cache_dir();
+    let output = dir.join(&name);
+
+    if output.is_file() {
+        return Ok(output);
+    }
+
+    fs::create_dir_all(&dir)
+        .map_err(|err| failed(format!("Couldn't create `{}`: {}", dir.display(), err)))?;
+
+    // it's built under another name and moved into place, so a build that
+    // stops halfway isn't taken for a finished one.
+    let partial = dir.join(format!("{}.{}.tmp", name, std::process::id()));
+
+    let mut backend = CBackend::new(compiler);
+    let opts = CodegenOptions {
+        files: sources.paths(),
+        timings: options.timings,
+        ..CodegenOptions::new(&partial)
+    };
+
+    let built = match generate(&mut backend, typed_ast, &opts, timings) {
+        Ok(Artifact::Executable(path)) => path,
+        Ok(_) => unreachable!("the C backend builds an executable"),
+        Err(err) => return Err(sources.fail(vec![err.into()])),
+    };
+
+    fs::rename(built, &output)
+        .map_err(|err| failed(format!("Couldn't write `{}`: {}", output.display(), err)))?;
+
+    Ok(output)
+}
+
+// runs the program in-process instead of compiling it, finishing with the
+// status `main` returns.
+fn interpret(
+    typed_ast: &[TypedStmt],
+    sources: &Sources,
+    input_file: &str,
+    args: &[String],
+) -> Status {
+    let mut interpreter = Interpreter::new();
+    // the file stands in for the program's name.
+    interpreter.set_args([&[file_name(input_file).to_string()], args].concat());
+
+    let result = interpreter
+        .run(typed_ast)
+        .and_then(|_| interpreter.call("main", vec![]));
+
+    print!("{}", interpreter.take_output());
+    io::stdout().flush().unwrap();
+
+    match result {
+        Ok(Value::Int(status)) => Ok(status as i32),
+        Ok(_) => Ok(0),
+        Err(RuntimeError {
+            exit: Some(status), ..
+        }) => Ok(status),
+        Err(err) => {
+            let err = Diagnostic::error("RuntimeError", Span::default(), err.message);
+            Err(sources.fail(vec![err]))
+        }
+    }
+}
+
+#[cfg(feature = "jit")]
+fn jit(
+    typed_ast: &[TypedStmt],
+    sources: &Sources,
+    input_file: &str,
+    args: &[String],
+    compiler: CompilerOptions,
+) -> Status {
+    let opts = CodegenOptions {
+        files: sources.paths(),
+        ..CodegenOptions::new(PathBuf::new())
+    };
+
+    // what's been printed so far comes before what the program prints.
+    io::stdout().flush().unwrap();
+
+    uma::Jit::new(compiler)
+        .run(
+            typed_ast,
+            &opts,
+            &[&[file_name(input_file).to_string()], args].concat(),
+        )
+        .map_err(|err| sources.fail(vec![err.into()]))
+}
+
+// a block is only complete once every `{` and `(` opened in it is closed.
+fn is_incomplete(src: &str) -> bool {
+    // let lexer errors through so they're reported like any other.
+    let tokens = match Lexer::new(src).lex() {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+
+    let depth = tokens.iter().fold(0, |depth, token| match token.kind {
+        TokenKind::BraceL | TokenKind::PareL => depth + 1,
+        TokenKind::BraceR | TokenKind::PareR => depth - 1,
+        _ => depth,
+    });
+
+    depth > 0
+}
+
+#[cfg(feature = "lsp")]
+pub fn lsp() -> Status {
+    uma::lsp::run(io::stdin().lock(), io::stdout()).map_err(failed)?;
+    Ok(0)
+}
+
+pub fn repl() -> Status {
+    let mut checker = TypeChecker::new();
+    let mut interpreter = Interpreter::new();
+
+    let stdin = io::stdin();
+
+    loop {
+        let mut src = String::new();
+
+        loop {
+            print!("{}", if src.is_empty() { ">>> " } else { "... " });
+            io::stdout().flush().unwrap();
+
+            let mut line = String::new();
+            if stdin.read_line(&mut line).map_err(failed)? == 0 {
+                println!();
+                return Ok(0);
+            }
+
+            src.push_str(&line);
+
+            if !is_incomplete(&src) {
+                break;
+            }
+        }
+
+        let tokens = match Lexer::new(&src).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                Diagnostic::from(err).emit(&src, "<repl>");
+                continue;
+            }
+        };
+
+        let ast = match Parser::new(tokens).parse() {
+            Ok(ast) => ast,
+            Err(errors) => {
+                for err in errors {
+                    Diagnostic::from(err).emit(&src, "<repl>");
+                }
+
+                continue;
+            }
+        };
+
+        // a failed check shouldn't leave half of the input declared.
+        let snapshot = checker.clone();
+
+        let typed_ast = match checker.check_incremental(ast) {
+            Ok(typed_ast) => typed_ast,
+            Err(err) => {
+                checker = snapshot;
+                Diagnostic::from(err).emit(&src, "<repl>");
+                continue;
+            }
+        };
+
+        for warning in checker.take_warnings() {
+            warning.emit(&src, "<repl>");
+        }
+
+        let result = interpreter.run(&typed_ast);
+        print!("{}", interpreter.take_output());
+
+        match result {
+            Ok(value) => {
+                let value = value.to_string();
+
+                if !value.is_empty() {
+                    println!("{}", value);
+                }
+            }
+            Err(RuntimeError {
+                exit: Some(status), ..
+            }) => return Ok(status),
+            Err(err) => println!("{} {}", "error:".red(), err.message),
+        }
+    }
 }
@@ -0,0 +1,492 @@
+use crate::lexer::{Span, TokenKind};
+use crate::parser::{Block, Expr, Layout, Part, Pattern, Spanned, Stmt};
+
+const INDENT: &str = "    ";
+
+// prints an AST back out as source. blank lines between statements are kept
+// (more than one becomes one), everything else is laid out from scratch.
+pub struct Formatter<'a> {
+    text: &'a str,
+    source: Vec<&'a str>,
+    layout: Layout,
+    // how far into `layout.lines` we are.
+    line: usize,
+    depth: usize,
+    out: String,
+}
+
+// the text of a string literal between its quotes. a `{` would start an
+// interpolation, so it's escaped.
+fn escape(value: &str) -> String {
+    let mut out = String::new();
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '{' => out.push_str("\\{"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn character(value: char) -> String {
+    match value {
+        '\'' => "'\\''".to_string(),
+        '"' => "'\"'".to_string(),
+        '{' => "'{'".to_string(),
+        c => format!("'{}'", escape(&c.to_string())),
+    }
+}
+
+fn string(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+// how tightly `as` binds, above every binary operator.
+const CAST: i8 = 11;
+
+fn operator(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Expo => "**",
+        TokenKind::Add => "+",
+        TokenKind::Sub => "-",
+        TokenKind::Multi => "*",
+        TokenKind::Div => "/",
+        TokenKind::Mod => "%",
+        TokenKind::BinaryEq => "==",
+        TokenKind::BinaryNeq => "!=",
+        TokenKind::BinaryGt => ">",
+        TokenKind::BinaryGte => ">=",
+        TokenKind::BinaryLt => "<",
+        TokenKind::BinaryLte => "<=",
+        TokenKind::And => "&&",
+        TokenKind::Or => "||",
+        TokenKind::BitAnd => "&",
+        TokenKind::BitOr => "|",
+        TokenKind::BitXor => "^",
+        TokenKind::BitNot => "~",
+        TokenKind::Not => "!",
+        TokenKind::Shl => "<<",
+        TokenKind::Shr => ">>",
+        kind => unreachable!("`{:?}` isn't a binary operator", kind),
+    }
+}
+
+fn has_body(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Function { external: None, .. })
+}
+
+// `expr` laid out the way it would be in a formatted file.
+pub fn format_expr(expr: &Spanned<Expr>) -> String {
+    Formatter::new("", Layout::default()).expr(expr)
+}
+
+impl<'a> Formatter<'a> {
+    pub fn new(source: &'a str, layout: Layout) -> Formatter<'a> {
+        Formatter {
+            text: source,
+            source: source.lines().collect(),
+            layout,
+            line: 0,
+            depth: 0,
+            out: String::new(),
+        }
+    }
+
+    pub fn format(mut self, stmts: &[Stmt]) -> String {
+        // a `#!` line is kept as it is, along with a blank line after it.
+        if let Some(shebang) = self.source.first().filter(|line| line.starts_with("#!")) {
+            self.out.push_str(shebang);
+            self.out.push('\n');
+
+            if !stmts.is_empty() && self.follows_blank_line(3) {
+                self.out.push('\n');
+            }
+        }
+
+        self.stmts(stmts, true);
+        self.out
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    // whether the line before `line` (1-based) is empty.
+    fn follows_blank_line(&self, line: usize) -> bool {
+        line >= 2
+            && self
+                .source
+                .get(line - 2)
+                .is_some_and(|l| l.trim().is_empty())
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt], top_level: bool) {
+        let mut previous: Option<&Stmt> = None;
+
+        for stmt in stmts {
+            let line = self.layout.lines[self.line];
+            self.line += 1;
+
+            if *stmt == Stmt::Empty {
+                continue;
+            }
+
+            if let Some(previous) = previous {
+                // functions with a body are always set apart at the top level.
+                let around_function = top_level && (has_body(previous) || has_body(stmt));
+
+                if around_function || self.follows_blank_line(line) {
+                    self.out.push('\n');
+                }
+            }
+
+            self.indent();
+            self.stmt(stmt);
+            self.out.push('\n');
+
+            previous = Some(stmt);
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.out.push_str("{\n");
+
+        self.depth += 1;
+        self.stmts(&block.stmts, false);
+        self.depth -= 1;
+
+        self.indent();
+        self.out.push('}');
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Variable {
+                name,
+                value,
+                is_mut,
+                ty,
+                ..
+            } => {
+                let kw = if *is_mut { "let mut" } else { "let" };
+                let ty = ty.as_ref().map_or(String::new(), |ty| format!(": {}", ty));
+                let value = self.expr(value);
+
+                self.out
+                    .push_str(&format!("{} {}{} = {};", kw, name, ty, value));
+            }
+            Stmt::Const { name, value, ty } => {
+                let ty = ty.as_ref().map_or(String::new(), |ty| format!(": {}", ty));
+                let value = self.expr(value);
+
+                self.out
+                    .push_str(&format!("const {}{} = {};", name, ty, value));
+            }
+            Stmt::Function {
+                name,
+                return_type,
+                args,
+                external,
+                links,
+                attributes,
+                is_varadic,
+                body,
+                ..
+            } => {
+                let mut params = args
+                    .iter()
+                    .map(|param| {
+                        let mut out = param.name.clone();
+
+                        if let Some(ty) = &param.ty {
+                            out.push_str(&format!(": {}", ty));
+                        }
+
+                        if let Some(default) = &param.default {
+                            out.push_str(&format!(" = {}", self.expr(default)));
+                        }
+
+                        out
+                    })
+                    .collect::<Vec<_>>();
+
+                if *is_varadic {
+                    params.push("...".to_string());
+                }
+
+                let mut header = format!("func {}({})", name, params.join(", "));
+
+                if let Some(ty) = return_type {
+                    header.push_str(&format!(": {}", ty));
+                }
+
+                for attribute in attributes {
+                    match &attribute.value {
+                        Some(value) => {
+                            header.push_str(&format!(" @{}({})", attribute.name, string(value)))
+                        }
+                        None => header.push_str(&format!(" @{}", attribute.name)),
+                    }
+                }
+
+                self.out.push_str(&header);
+
+                if let Some(external) = external {
+                    self.out
+                        .push_str(&format!(" @requires({})", string(external)));
+
+                    for link in links {
+                        self.out.push_str(&format!(" @link({})", string(link)));
+                    }
+
+                    return;
+                }
+
+                self.out.push(' ');
+                self.block(body);
+            }
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                let condition = self.expr(condition);
+                self.out.push_str(&format!("if ({}) ", condition));
+                self.block(consequence);
+
+                match alternative.as_deref() {
+                    Some(Stmt::Block(block, _)) => {
+                        self.out.push_str(" else ");
+                        self.block(block);
+                    }
+                    Some(alt) => {
+                        self.out.push_str(" else ");
+                        self.stmt(alt);
+                    }
+                    None => (),
+                }
+            }
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                let (start, end) = (self.expr(start), self.expr(end));
+                self.out
+                    .push_str(&format!("for {} in {}..{} ", iterator, start, end));
+                self.block(body);
+            }
+            Stmt::ForEach {
+                iterator,
+                iterable,
+                body,
+                ..
+            } => {
+                let iterable = self.expr(iterable);
+                self.out
+                    .push_str(&format!("for {} in {} ", iterator, iterable));
+                self.block(body);
+            }
+            Stmt::Block(block, _) => self.block(block),
+            Stmt::Assignment { name, value, op } => {
+                let value = self.expr(value);
+                let op = op.as_ref().map_or("", |op| operator(&op.kind));
+
+                self.out.push_str(&format!("{} {}= {};", name, op, value));
+            }
+            Stmt::Import { path, .. } => {
+                self.out.push_str(&format!("import {};", string(path)));
+            }
+            Stmt::Type {
+                name,
+                external,
+                attributes,
+                ..
+            } => {
+                self.out.push_str(&format!("type {}", name));
+
+                for attribute in attributes {
+                    match &attribute.value {
+                        Some(value) => {
+                            self.out
+                                .push_str(&format!(" @{}({})", attribute.name, string(value)))
+                        }
+                        None => self.out.push_str(&format!(" @{}", attribute.name)),
+                    }
+                }
+
+                if let Some(external) = external {
+                    self.out
+                        .push_str(&format!(" @requires({})", string(external)));
+                }
+
+                self.out.push(';');
+            }
+            Stmt::Return(Some(value), _) => {
+                let value = self.expr(value);
+                self.out.push_str(&format!("return {};", value));
+            }
+            Stmt::Return(None, _) => self.out.push_str("return;"),
+            Stmt::Expr(expr) => {
+                let expr = self.expr(expr);
+                self.out.push_str(&format!("{};", expr));
+            }
+            Stmt::Empty => {
+                unreachable!("{:?} is never a statement on its own", stmt)
+            }
+        }
+    }
+
+    fn expr(&self, expr: &Spanned<Expr>) -> String {
+        match &expr.node {
+            Expr::Call { name, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| match &arg.name {
+                        Some(name) => format!(
+                            "{}: {}",
+                            name.value.as_deref().unwrap_or_default(),
+                            self.expr(&arg.value)
+                        ),
+                        None => self.expr(&arg.value),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("{}({})", name, args)
+            }
+            Expr::Binary { lhs, op, rhs } => {
+                let precedence = op.kind.precedence();
+
+                // an operand of the same precedence only needs parentheses
+                // on the side the operator doesn't group towards.
+                let (lhs, rhs) = if op.kind.is_right_associative() {
+                    (
+                        self.operand(lhs, |p| p <= precedence),
+                        self.operand(rhs, |p| p < precedence),
+                    )
+                } else {
+                    (
+                        self.operand(lhs, |p| p < precedence),
+                        self.operand(rhs, |p| p <= precedence),
+                    )
+                };
+
+                format!("{} {} {}", lhs, operator(&op.kind), rhs)
+            }
+            Expr::Unary { op, operand } => {
+                format!("{}{}", operator(&op.kind), self.operand(operand, |_| true))
+            }
+            Expr::Cast { value, ty } => {
+                let value = self.operand(value, |p| p < CAST);
+                format!("{} as {}", value, ty.value.as_deref().unwrap_or_default())
+            }
+            Expr::If {
+                condition,
+                consequence,
+                alternative,
+            } => format!(
+                "if {} {{ {} }} else {}",
+                self.expr(condition),
+                self.expr(consequence),
+                match &alternative.node {
+                    Expr::If { .. } => self.expr(alternative),
+                    _ => format!("{{ {} }}", self.expr(alternative)),
+                }
+            ),
+            Expr::Match { subject, arms } => {
+                let arms = arms
+                    .iter()
+                    .map(|(pattern, value)| {
+                        let pattern = match pattern {
+                            Pattern::Literal(literal) => self.expr(literal),
+                            Pattern::Wildcard(_) => "_".to_string(),
+                        };
+
+                        format!("{} => {}", pattern, self.expr(value))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                if arms.is_empty() {
+                    format!("match {} {{}}", self.expr(subject))
+                } else {
+                    format!("match {} {{ {} }}", self.expr(subject), arms)
+                }
+            }
+            Expr::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.expr(element))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("[{}]", elements)
+            }
+            Expr::Index { target, index } => {
+                let target = self.operand(target, |_| true);
+                format!("{}[{}]", target, self.expr(index))
+            }
+            Expr::Identifier(name) => name.clone(),
+            Expr::Number(num) | Expr::Float(num) => num.clone(),
+            Expr::Bool(value) => value.to_string(),
+            Expr::Char(value) => character(*value),
+            Expr::String(value) => match self.written(expr.span) {
+                Some(text) => text.to_string(),
+                None => string(value),
+            },
+            Expr::Interpolation(parts) => {
+                let parts = parts
+                    .iter()
+                    .map(|part| match part {
+                        Part::Text(text) => escape(text),
+                        Part::Expr(expr) => format!("{{{}}}", self.expr(expr)),
+                    })
+                    .collect::<String>();
+
+                format!("\"{}\"", parts)
+            }
+        }
+    }
+
+    // an operand of a binary expression or index, wrapped in parentheses when
+    // it's a binary expression `needs_parens` holds for the precedence of, or
+    // an `if`.
+    // a raw string or a text block as it's written in the source, they'd
+    // lose what they're for if they were escaped like the other strings.
+    fn written(&self, span: Span) -> Option<&'a str> {
+        let text = self.text.get(span.start..span.end)?;
+        let kept = text.starts_with('r') || text.starts_with("\"\"\"");
+
+        kept.then_some(text)
+    }
+
+    fn operand(&self, expr: &Spanned<Expr>, needs_parens: impl Fn(i8) -> bool) -> String {
+        match &expr.node {
+            Expr::Binary { op, .. } if needs_parens(op.kind.precedence()) => {
+                format!("({})", self.expr(expr))
+            }
+            Expr::Cast { .. } if needs_parens(CAST) => format!("({})", self.expr(expr)),
+            // an `if` doesn't need them, but without them it reads like
+            // the operator belongs in the `else`.
+            Expr::If { .. } => format!("({})", self.expr(expr)),
+            _ => self.expr(expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
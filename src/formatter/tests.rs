@@ -0,0 +1,209 @@
+use crate::format_str;
+
+fn format(src: &str) -> String {
+    format_str(src).unwrap()
+}
+
+#[test]
+fn spacing_and_indentation() {
+    assert_eq!(
+        format("func  sum(y:int,x : int):int{return y+x*2}\nfunc main():int{\nlet mut a=sum(1,2);a=a-1;;\n\n\n\nreturn a}"),
+        [
+            "func sum(y: int, x: int): int {",
+            "    return y + x * 2;",
+            "}",
+            "",
+            "func main(): int {",
+            "    let mut a = sum(1, 2);",
+            "    a = a - 1;",
+            "",
+            "    return a;",
+            "}",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn control_flow() {
+    assert_eq!(
+        format("func main() { for i in 0..10 { if (i == 1) { f(\"a\\n\"); } else if (i > 2) { } else { g(xs[i]); } } for x in [1, 2] { } }"),
+        [
+            "func main() {",
+            "    for i in 0..10 {",
+            "        if (i == 1) {",
+            "            f(\"a\\n\");",
+            "        } else if (i > 2) {",
+            "        } else {",
+            "            g(xs[i]);",
+            "        }",
+            "    }",
+            "    for x in [1, 2] {",
+            "    }",
+            "}",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn parentheses() {
+    assert_eq!(
+        format("let x = (1 - (2 - 3)) * (4 + 5) - (6 * 7);"),
+        "let x = (1 - (2 - 3)) * (4 + 5) - 6 * 7;\n"
+    );
+}
+
+#[test]
+fn externals_and_imports() {
+    let src = [
+        "import \"utils.uma\";",
+        "type FILE @opaque @requires(\"stdio.h\");",
+        "func printf(fmt, ...) @requires(\"stdio.h\")",
+        "func fclose(file: Ptr<FILE>): int @requires(\"stdio.h\")",
+        "func sqrt(x: float): float @requires(\"math.h\") @link(\"m\")",
+        "func main(): int {",
+        "    return 0;",
+        "}",
+        "",
+    ]
+    .join("\n");
+
+    assert_eq!(
+        format(&src),
+        src.replace("@link(\"m\")\n", "@link(\"m\")\n\n")
+    );
+}
+
+#[test]
+fn idempotent() {
+    for example in ["fib", "functions", "variables"] {
+        let path = format!("{}/examples/{}.uma", env!("CARGO_MANIFEST_DIR"), example);
+        let src = std::fs::read_to_string(path).unwrap();
+
+        assert_eq!(format(&src), src, "{} isn't formatted", example);
+    }
+}
+
+#[test]
+fn interpolated_strings() {
+    assert_eq!(
+        format(r#"let s = "{ a+1 } \{b} {"{c}"}";"#),
+        "let s = \"{a + 1} \\{b} {\"{c}\"}\";\n"
+    );
+}
+
+#[test]
+fn escapes_are_kept() {
+    assert_eq!(
+        format(r#"let s = "\x41\r\u{1}\'\u{e9}"; let c = '\0';"#),
+        "let s = \"A\\r\\u{1}'é\";\nlet c = '\\0';\n"
+    );
+}
+
+#[test]
+fn characters() {
+    assert_eq!(
+        format(r"let c = 'a'; let q = '\''; let t = '\t'; let e = 'é';"),
+        "let c = 'a';\nlet q = '\\'';\nlet t = '\\t';\nlet e = 'é';\n"
+    );
+}
+
+#[test]
+fn shebang_is_kept() {
+    assert_eq!(
+        format("#!/usr/bin/env uma\n\nfunc main() {\nf();\n}"),
+        "#!/usr/bin/env uma\n\nfunc main() {\n    f();\n}\n"
+    );
+}
+
+#[test]
+fn raw_strings_and_text_blocks_are_kept() {
+    let src = "func main() {\nlet p = r\"a\\{b}\";\nlet t = \"\"\"\n  hi\n  \"\"\";\n}\n";
+
+    assert_eq!(
+        format(src),
+        "func main() {\n    let p = r\"a\\{b}\";\n    let t = \"\"\"\n  hi\n  \"\"\";\n}\n"
+    );
+}
+
+#[test]
+fn logical_operators() {
+    assert_eq!(
+        format("let x = (a||b)&&true == false;"),
+        "let x = (a || b) && true == false;\n"
+    );
+}
+
+#[test]
+fn compound_assignment() {
+    assert_eq!(format("x+=1; y %=2*3;"), "x += 1;\ny %= 2 * 3;\n");
+}
+
+#[test]
+fn exponent_parentheses() {
+    assert_eq!(
+        format("let x = (2 ** 3) ** 2 + 2 ** (3 ** 2);"),
+        "let x = (2 ** 3) ** 2 + 2 ** 3 ** 2;\n"
+    );
+}
+
+#[test]
+fn bitwise_operators() {
+    assert_eq!(
+        format("let x = ~(a|b)&c<<1;"),
+        "let x = ~(a | b) & c << 1;\n"
+    );
+}
+
+#[test]
+fn logical_not() {
+    assert_eq!(format("let x = !(a&&b)||!c;"), "let x = !(a && b) || !c;\n");
+}
+
+#[test]
+fn annotations() {
+    assert_eq!(format("let mut x:Float=1.5;"), "let mut x: Float = 1.5;\n");
+}
+
+#[test]
+fn casts() {
+    assert_eq!(
+        format("let x = (a+1) as Float*~b as Int8;"),
+        "let x = (a + 1) as Float * ~b as Int8;\n"
+    );
+}
+
+#[test]
+fn constants() {
+    assert_eq!(
+        format("const  N:Int=2*3\nconst NAME = \"uma\";"),
+        "const N: Int = 2 * 3;\nconst NAME = \"uma\";\n"
+    );
+}
+
+#[test]
+fn if_expressions() {
+    assert_eq!(
+        format("let x = if a>0 {1} else if a<0 {2} else {3};\nlet y = (if a { 1 } else { 2 })+1;"),
+        "let x = if a > 0 { 1 } else if a < 0 { 2 } else { 3 };\nlet y = (if a { 1 } else { 2 }) + 1;\n"
+    );
+}
+
+#[test]
+fn match_expressions() {
+    assert_eq!(
+        format("let x = match a {1=>\"one\",\n2 => \"two\",_=>\"many\",};"),
+        "let x = match a { 1 => \"one\", 2 => \"two\", _ => \"many\" };\n"
+    );
+}
+
+#[test]
+fn default_and_named_arguments() {
+    assert_eq!(
+        format("func greet(name:String,greeting:String=\"Hi\"){}\ngreet(name:\"Ana\");"),
+        "func greet(name: String, greeting: String = \"Hi\") {\n}\n\ngreet(name: \"Ana\");\n"
+    );
+}
@@ -0,0 +1,756 @@
+mod value;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::lexer::TokenKind;
+use crate::typeck::{Builtin, Type, TypedExpr, TypedPart, TypedStmt};
+
+pub use self::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    // the status the program ended itself with through `exit`, which stops
+    // it the same way an error does.
+    pub exit: Option<i32>,
+}
+
+impl RuntimeError {
+    pub fn new<T>(message: impl Into<String>) -> Result<T, RuntimeError> {
+        Err(RuntimeError {
+            message: message.into(),
+            exit: None,
+        })
+    }
+
+    pub fn exit<T>(status: i32) -> Result<T, RuntimeError> {
+        Err(RuntimeError {
+            message: format!("Exited with status {}", status),
+            exit: Some(status),
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Function {
+    args: Vec<String>,
+    body: Vec<TypedStmt>,
+}
+
+enum Flow {
+    Next,
+    Return(Value),
+}
+
+pub struct Interpreter {
+    functions: HashMap<String, Function>,
+    scopes: Vec<HashMap<String, Value>>,
+    output: String,
+    // where `input` reads from, stdin when it's `None`.
+    input: Option<Box<dyn BufRead>>,
+    // what `arg` gives back, the program's name first.
+    args: Vec<String>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            functions: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            output: String::new(),
+            input: None,
+            args: vec![],
+        }
+    }
+
+    // an interpreter whose `input` reads from `input` instead of stdin.
+    pub fn with_input(input: impl BufRead + 'static) -> Interpreter {
+        Interpreter {
+            input: Some(Box::new(input)),
+            ..Interpreter::new()
+        }
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    // runs top-level statements, returning the value of the last one if it
+    // was an expression.
+    pub fn run(&mut self, stmts: &[TypedStmt]) -> Result<Value, RuntimeError> {
+        let mut last = Value::Void;
+
+        for stmt in stmts {
+            last = match stmt {
+                TypedStmt::Expr(expr, _) => self.expr(expr)?,
+                stmt => {
+                    self.stmt(stmt)?;
+                    Value::Void
+                }
+            };
+        }
+
+        Ok(last)
+    }
+
+    // everything written by the program since the last call.
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    // variables are resolved by the type checker, but a function can be
+    // called before a global it uses has been declared.
+    fn lookup(&self, name: &str) -> Result<&Value, RuntimeError> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(value) => Ok(value),
+            None => RuntimeError::new(format!("`{}` is used before it's declared", name)),
+        }
+    }
+
+    fn assign(&mut self, name: &str, value: Value) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(name) {
+                *slot = value;
+                return;
+            }
+        }
+    }
+
+    fn block(
+        &mut self,
+        stmts: &[TypedStmt],
+        bindings: HashMap<String, Value>,
+    ) -> Result<Flow, RuntimeError> {
+        self.scopes.push(bindings);
+
+        let mut flow = Ok(Flow::Next);
+
+        for stmt in stmts {
+            flow = self.stmt(stmt);
+
+            if !matches!(flow, Ok(Flow::Next)) {
+                break;
+            }
+        }
+
+        self.scopes.pop();
+        flow
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) -> Result<Flow, RuntimeError> {
+        match stmt {
+            TypedStmt::Variable { name, value, .. } | TypedStmt::Const { name, value, .. } => {
+                let value = self.expr(value)?;
+
+                self.scopes
+                    .last_mut()
+                    .expect("there is always a global scope")
+                    .insert(name.clone(), value);
+            }
+            TypedStmt::Assignment { name, value, .. } => {
+                let value = self.expr(value)?;
+                self.assign(name, value);
+            }
+            TypedStmt::Function {
+                name, args, body, ..
+            } => {
+                self.functions.insert(
+                    name.clone(),
+                    Function {
+                        args: args.iter().map(|(arg, _)| arg.clone()).collect(),
+                        body: body.clone(),
+                    },
+                );
+            }
+            TypedStmt::External { .. } | TypedStmt::Opaque { .. } => (),
+            TypedStmt::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                if self.expr(condition)?.is_truthy() {
+                    return self.block(consequence, HashMap::new());
+                }
+
+                if let Some(alt) = alternative {
+                    return self.stmt(alt);
+                }
+            }
+            TypedStmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                ..
+            } => {
+                let (start, end) = match (self.expr(start)?, self.expr(end)?) {
+                    (Value::Int(start), Value::Int(end)) => (start, end),
+                    _ => unreachable!("ranges are checked to be `Int`"),
+                };
+
+                for i in start..end {
+                    let bindings = HashMap::from([(iterator.clone(), Value::Int(i))]);
+
+                    if let Flow::Return(value) = self.block(body, bindings)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            TypedStmt::ForEach {
+                iterator,
+                iterable,
+                body,
+                ..
+            } => {
+                let elements = match self.expr(iterable)? {
+                    Value::Array(elements) => elements,
+                    _ => unreachable!("iterables are checked to be arrays"),
+                };
+
+                for element in elements {
+                    let bindings = HashMap::from([(iterator.clone(), element)]);
+
+                    if let Flow::Return(value) = self.block(body, bindings)? {
+                        return Ok(Flow::Return(value));
+                    }
+                }
+            }
+            TypedStmt::Block(stmts, _) => return self.block(stmts, HashMap::new()),
+            TypedStmt::Return(Some(value), _) => return Ok(Flow::Return(self.expr(value)?)),
+            TypedStmt::Return(None, _) => return Ok(Flow::Return(Value::Void)),
+            TypedStmt::Expr(expr, _) => {
+                self.expr(expr)?;
+            }
+        }
+
+        Ok(Flow::Next)
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) -> Result<Value, RuntimeError> {
+        Ok(match expr {
+            TypedExpr::Binary { lhs, op, rhs, ty } => {
+                let lhs = self.expr(lhs)?;
+
+                // `&&` and `||` only look at the right side if they have to.
+                match (&op.kind, &lhs) {
+                    (TokenKind::And, Value::Bool(false)) | (TokenKind::Or, Value::Bool(true)) => {
+                        return Ok(lhs)
+                    }
+                    _ => (),
+                }
+
+                // the count of a shift wraps to the bits in `ty`, like the
+                // C and JIT backends.
+                let rhs = match (&op.kind, self.expr(rhs)?, ty.bits()) {
+                    (TokenKind::Shl | TokenKind::Shr, Value::Int(count), Some(bits)) => {
+                        Value::Int(count & (bits as i128 - 1))
+                    }
+                    (_, rhs, _) => rhs,
+                };
+
+                fit(Self::binary(lhs, &op.kind, rhs)?, ty)
+            }
+            TypedExpr::Cast { value, ty } => match (self.expr(value)?, ty.is_integer()) {
+                (Value::Int(num), false) => fit(Value::Float(num as f64), ty),
+                // towards zero, like C.
+                (Value::Float(num), true) => fit(Value::Int(num as i128), ty),
+                (Value::Bool(value), true) => Value::Int(value as i128),
+                (Value::Char(value), true) => fit(Value::Int(value as i128), ty),
+                (value, _) => fit(value, ty),
+            },
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                if self.expr(condition)?.is_truthy() {
+                    self.expr(consequence)?
+                } else {
+                    self.expr(alternative)?
+                }
+            }
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ..
+            } => {
+                let subject = self.expr(subject)?;
+
+                for (pattern, value) in arms {
+                    let pattern = self.expr(pattern)?;
+
+                    if Self::binary(subject.clone(), &TokenKind::BinaryEq, pattern)?.is_truthy() {
+                        return self.expr(value);
+                    }
+                }
+
+                self.expr(default)?
+            }
+            // there's nothing the interpreter's values live at to point to.
+            TypedExpr::AddressOf { .. } | TypedExpr::Deref { .. } => {
+                return RuntimeError::new("Pointers can only be used in a built program")
+            }
+            TypedExpr::Unary { operand, ty, .. } => match self.expr(operand)? {
+                Value::Int(num) => fit(Value::Int(!num), ty),
+                Value::Bool(value) => Value::Bool(!value),
+                value => unreachable!("`~` and `!` are checked: {:?}", value),
+            },
+            TypedExpr::Call { name, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.call(name, args)?
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Len,
+                args,
+                ..
+            } => match self.expr(&args[0])? {
+                Value::String(s) => Value::Int(s.len() as i128),
+                Value::Array(elements) => Value::Int(elements.len() as i128),
+                _ => unreachable!("`len` is checked by the type checker"),
+            },
+            TypedExpr::Builtin {
+                builtin, args, ty, ..
+            } if builtin.is_math() => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                fit(math(*builtin, &values), ty)
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Input,
+                args,
+                ..
+            } => {
+                if let Some(prompt) = args.first() {
+                    let prompt = self.expr(prompt)?;
+                    self.output.push_str(&prompt.to_string());
+                }
+
+                Value::String(self.read_line()?)
+            }
+            TypedExpr::Builtin {
+                builtin: builtin @ (Builtin::ParseInt | Builtin::ParseFloat),
+                args,
+                ..
+            } => {
+                let Value::String(s) = self.expr(&args[0])? else {
+                    unreachable!("`{}` is checked by the type checker", builtin.name())
+                };
+
+                let value = match builtin {
+                    Builtin::ParseInt => {
+                        s.trim().parse::<i32>().ok().map(|n| Value::Int(n as i128))
+                    }
+                    _ => s.trim().parse::<f64>().ok().map(Value::Float),
+                };
+
+                match value {
+                    Some(value) => value,
+                    None if *builtin == Builtin::ParseInt => {
+                        return RuntimeError::new(format!("Cannot parse \"{}\" as an `Int`", s))
+                    }
+                    None => {
+                        return RuntimeError::new(format!("Cannot parse \"{}\" as a `Float`", s))
+                    }
+                }
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::ReadFile,
+                args,
+                ..
+            } => {
+                let path = self.expr(&args[0])?.to_string();
+                // worked out either way, like the compiled code does.
+                let fallback = args.get(1).map(|arg| self.expr(arg)).transpose()?;
+
+                match (std::fs::read_to_string(&path), fallback) {
+                    (Ok(contents), _) => Value::String(contents),
+                    (Err(_), Some(fallback)) => fallback,
+                    (Err(err), None) => {
+                        return RuntimeError::new(format!("Cannot read `{}`: {}", path, err))
+                    }
+                }
+            }
+            TypedExpr::Builtin {
+                builtin: builtin @ (Builtin::WriteFile | Builtin::AppendFile),
+                args,
+                ..
+            } => {
+                let path = self.expr(&args[0])?.to_string();
+                let contents = self.expr(&args[1])?.to_string();
+
+                let written = std::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(*builtin == Builtin::AppendFile)
+                    .truncate(*builtin == Builtin::WriteFile)
+                    .open(path)
+                    .and_then(|mut file| file.write_all(contents.as_bytes()));
+
+                Value::Bool(written.is_ok())
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::ArgCount,
+                ..
+            } => Value::Int(self.args.len() as i128),
+            TypedExpr::Builtin {
+                builtin: Builtin::Arg,
+                args,
+                ..
+            } => {
+                let Value::Int(index) = self.expr(&args[0])? else {
+                    unreachable!("`arg` is checked by the type checker")
+                };
+
+                match usize::try_from(index).ok().and_then(|i| self.args.get(i)) {
+                    Some(arg) => Value::String(arg.clone()),
+                    None => {
+                        return RuntimeError::new(format!(
+                            "Index {} is out of bounds for {} arguments",
+                            index,
+                            self.args.len()
+                        ))
+                    }
+                }
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Exit,
+                args,
+                ..
+            } => {
+                let Value::Int(status) = self.expr(&args[0])? else {
+                    unreachable!("`exit` is checked by the type checker")
+                };
+
+                return RuntimeError::exit(status as i32);
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Env,
+                args,
+                ..
+            } => {
+                let name = self.expr(&args[0])?.to_string();
+                Value::String(std::env::var(name).unwrap_or_default())
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::SetEnv,
+                args,
+                ..
+            } => {
+                let name = self.expr(&args[0])?.to_string();
+                let value = self.expr(&args[1])?.to_string();
+
+                // what `set_var` would panic on.
+                if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+                    return RuntimeError::new(format!("Cannot set `{}`", name));
+                }
+
+                std::env::set_var(name, value);
+                Value::Void
+            }
+            TypedExpr::Builtin {
+                builtin: Builtin::Assert,
+                args,
+                ..
+            } => {
+                if self.expr(&args[0])?.is_truthy() {
+                    return Ok(Value::Void);
+                }
+
+                let source = match &args[1] {
+                    TypedExpr::String(source) => source,
+                    arg => unreachable!("the checker puts the source here, found {:?}", arg),
+                };
+
+                return match args.get(2).map(|message| self.expr(message)).transpose()? {
+                    Some(Value::String(message)) => {
+                        RuntimeError::new(format!("Assertion `{}` failed: {}", source, message))
+                    }
+                    _ => RuntimeError::new(format!("Assertion `{}` failed", source)),
+                };
+            }
+            TypedExpr::Builtin { builtin, args, .. } => {
+                let values = builtin
+                    .printf(args)
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.printf(values)?
+            }
+            TypedExpr::Array { elements, .. } => Value::Array(
+                elements
+                    .iter()
+                    .map(|element| self.expr(element))
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypedExpr::Index { target, index, .. } => {
+                match (self.expr(target)?, self.expr(index)?) {
+                    (Value::Array(mut elements), Value::Int(i)) => {
+                        if i < 0 || i as usize >= elements.len() {
+                            return RuntimeError::new(format!(
+                                "Index {} is out of bounds for an array of length {}",
+                                i,
+                                elements.len()
+                            ));
+                        }
+
+                        elements.swap_remove(i as usize)
+                    }
+                    // strings are indexed by byte, like they are in C.
+                    (Value::String(s), Value::Int(i)) => match s.as_bytes().get(i as usize) {
+                        Some(byte) if i >= 0 => {
+                            Value::String(String::from_utf8_lossy(&[*byte]).to_string())
+                        }
+                        _ => {
+                            return RuntimeError::new(format!(
+                                "Index {} is out of bounds for a string of length {}",
+                                i,
+                                s.len()
+                            ))
+                        }
+                    },
+                    _ => unreachable!("indexing is checked by the type checker"),
+                }
+            }
+            TypedExpr::Identifier { name, .. } => self.lookup(name)?.clone(),
+            TypedExpr::Number(num) => match num.parse() {
+                Ok(num) => Value::Int(num),
+                Err(_) => return RuntimeError::new(format!("Integer `{}` is too large", num)),
+            },
+            TypedExpr::Float(num) => Value::Float(num.parse().unwrap()),
+            TypedExpr::Bool(value) => Value::Bool(*value),
+            TypedExpr::Char(value) => Value::Char(*value),
+            TypedExpr::String(value) => Value::String(value.clone()),
+            TypedExpr::Interpolation(parts) => {
+                let mut out = String::new();
+
+                for part in parts {
+                    match part {
+                        TypedPart::Text(text) => out.push_str(text),
+                        TypedPart::Expr(expr) => out.push_str(&self.expr(expr)?.to_string()),
+                    }
+                }
+
+                Value::String(out)
+            }
+        })
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let function = match self.functions.get(name) {
+            Some(function) => function.clone(),
+            None if name == "printf" => return self.printf(args),
+            None => {
+                return RuntimeError::new(format!(
+                    "External function `{}` cannot be called by the interpreter",
+                    name
+                ))
+            }
+        };
+
+        let bindings = function.args.into_iter().zip(args).collect();
+
+        // a function body only sees its own arguments and the globals.
+        let caller_scopes = self.scopes.split_off(1);
+        let flow = self.block(&function.body, bindings);
+        self.scopes.extend(caller_scopes);
+
+        Ok(match flow? {
+            Flow::Return(value) => value,
+            Flow::Next => Value::Void,
+        })
+    }
+
+    // a line from the input without its newline, empty once it's all read.
+    fn read_line(&mut self) -> Result<String, RuntimeError> {
+        let mut line = String::new();
+
+        let read = match &mut self.input {
+            Some(input) => input.read_line(&mut line),
+            // what's been printed so far has to be seen before the program
+            // waits on a reply.
+            None => {
+                print!("{}", self.take_output());
+                io::stdout()
+                    .flush()
+                    .and_then(|_| io::stdin().lock().read_line(&mut line))
+            }
+        };
+
+        if let Err(err) = read {
+            return RuntimeError::new(format!("Couldn't read from stdin: {}", err));
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(line)
+    }
+
+    fn printf(&mut self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut args = args.into_iter();
+
+        let fmt = match args.next() {
+            Some(Value::String(fmt)) => fmt,
+            _ => return RuntimeError::new("`printf` expects a format string"),
+        };
+
+        let mut chars = fmt.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                self.output.push(c);
+                continue;
+            }
+
+            let mut spec = chars.next();
+
+            // every value is as wide as it gets already, so the `ll` in
+            // `%lld` doesn't change anything.
+            while spec == Some('l') {
+                spec = chars.next();
+            }
+
+            match spec {
+                Some('%') => self.output.push('%'),
+                Some('d' | 'i' | 'u' | 's' | 'f' | 'c') => match args.next() {
+                    Some(value) => self.output.push_str(&value.to_string()),
+                    None => return RuntimeError::new("Not enough arguments passed to `printf`"),
+                },
+                Some(other) => {
+                    return RuntimeError::new(format!("Unsupported format specifier `%{}`", other))
+                }
+                None => self.output.push('%'),
+            }
+        }
+
+        Ok(Value::Void)
+    }
+
+    fn binary(lhs: Value, op: &TokenKind, rhs: Value) -> Result<Value, RuntimeError> {
+        use TokenKind as TT;
+
+        Ok(match (lhs, rhs) {
+            (Value::Int(l), Value::Int(r)) => match op {
+                TT::Add => Value::Int(l.wrapping_add(r)),
+                TT::Sub => Value::Int(l.wrapping_sub(r)),
+                TT::Multi => Value::Int(l.wrapping_mul(r)),
+                TT::Div if r == 0 => return RuntimeError::new("Division by zero"),
+                TT::Div => Value::Int(l.wrapping_div(r)),
+                TT::Mod if r == 0 => return RuntimeError::new("Division by zero"),
+                TT::Mod => Value::Int(l.wrapping_rem(r)),
+                // through `pow` on doubles, like the C backend.
+                TT::Expo => Value::Int((l as f64).powf(r as f64) as i128),
+                TT::BitAnd => Value::Int(l & r),
+                TT::BitOr => Value::Int(l | r),
+                TT::BitXor => Value::Int(l ^ r),
+                TT::Shl => Value::Int(l.wrapping_shl(r as u32)),
+                TT::Shr => Value::Int(l.wrapping_shr(r as u32)),
+                TT::BinaryEq => Value::Bool(l == r),
+                TT::BinaryNeq => Value::Bool(l != r),
+                TT::BinaryGt => Value::Bool(l > r),
+                TT::BinaryGte => Value::Bool(l >= r),
+                TT::BinaryLt => Value::Bool(l < r),
+                TT::BinaryLte => Value::Bool(l <= r),
+                op => return RuntimeError::new(format!("Unsupported operator `{:?}`", op)),
+            },
+            (Value::Float(l), Value::Float(r)) => match op {
+                TT::Add => Value::Float(l + r),
+                TT::Sub => Value::Float(l - r),
+                TT::Multi => Value::Float(l * r),
+                TT::Div => Value::Float(l / r),
+                TT::Expo => Value::Float(l.powf(r)),
+                TT::BinaryEq => Value::Bool(l == r),
+                TT::BinaryNeq => Value::Bool(l != r),
+                TT::BinaryGt => Value::Bool(l > r),
+                TT::BinaryGte => Value::Bool(l >= r),
+                TT::BinaryLt => Value::Bool(l < r),
+                TT::BinaryLte => Value::Bool(l <= r),
+                op => return RuntimeError::new(format!("Unsupported operator `{:?}`", op)),
+            },
+            (Value::String(l), Value::String(r)) => match op {
+                TT::Add => Value::String(l + &r),
+                TT::BinaryEq => Value::Bool(l == r),
+                TT::BinaryNeq => Value::Bool(l != r),
+                op => return RuntimeError::new(format!("Unsupported operator `{:?}`", op)),
+            },
+            (Value::Char(l), Value::Char(r)) => Value::Bool(match op {
+                TT::BinaryEq => l == r,
+                TT::BinaryNeq => l != r,
+                TT::BinaryGt => l > r,
+                TT::BinaryGte => l >= r,
+                TT::BinaryLt => l < r,
+                TT::BinaryLte => l <= r,
+                op => return RuntimeError::new(format!("Unsupported operator `{:?}`", op)),
+            }),
+            (Value::Bool(l), Value::Bool(r)) => Value::Bool(match op {
+                TT::BinaryEq => l == r,
+                TT::BinaryNeq => l != r,
+                // the left side has already been found not to decide it.
+                TT::And | TT::Or => r,
+                op => return RuntimeError::new(format!("Unsupported operator `{:?}`", op)),
+            }),
+            (lhs, rhs) => unreachable!("operands are checked to match: {:?}, {:?}", lhs, rhs),
+        })
+    }
+}
+
+// `value` wrapped around to the width of `ty`, the way C stores it.
+fn fit(value: Value, ty: &Type) -> Value {
+    match (value, ty) {
+        (Value::Int(num), Type::Int | Type::Int32) => Value::Int(num as i32 as i128),
+        (Value::Int(num), Type::Int8) => Value::Int(num as i8 as i128),
+        (Value::Int(num), Type::Int16) => Value::Int(num as i16 as i128),
+        (Value::Int(num), Type::Int64) => Value::Int(num as i64 as i128),
+        (Value::Int(num), Type::UInt8) => Value::Int(num as u8 as i128),
+        (Value::Int(num), Type::UInt16) => Value::Int(num as u16 as i128),
+        (Value::Int(num), Type::UInt32) => Value::Int(num as u32 as i128),
+        (Value::Int(num), Type::UInt64) => Value::Int(num as u64 as i128),
+        (Value::Float(num), Type::Float32) => Value::Float(num as f32 as f64),
+        (value, _) => value,
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// a math builtin on the values it was given, which the checker made sure are
+// numbers of the same kind.
+fn math(builtin: Builtin, values: &[Value]) -> Value {
+    match (builtin, values) {
+        (Builtin::Abs, [Value::Int(n)]) => Value::Int(n.wrapping_abs()),
+        (Builtin::Abs, [Value::Float(n)]) => Value::Float(n.abs()),
+        (Builtin::Min, [Value::Int(a), Value::Int(b)]) => Value::Int(*a.min(b)),
+        (Builtin::Max, [Value::Int(a), Value::Int(b)]) => Value::Int(*a.max(b)),
+        (Builtin::Min, [Value::Float(a), Value::Float(b)]) => Value::Float(a.min(*b)),
+        (Builtin::Max, [Value::Float(a), Value::Float(b)]) => Value::Float(a.max(*b)),
+        (Builtin::Sqrt, [Value::Float(n)]) => Value::Float(n.sqrt()),
+        (Builtin::Pow, [Value::Float(a), Value::Float(b)]) => Value::Float(a.powf(*b)),
+        (Builtin::Floor, [Value::Float(n)]) => Value::Float(n.floor()),
+        (Builtin::Ceil, [Value::Float(n)]) => Value::Float(n.ceil()),
+        (Builtin::Round, [Value::Float(n)]) => Value::Float(n.round()),
+        (builtin, values) => unreachable!("`{}` of {:?}", builtin.name(), values),
+    }
+}
@@ -0,0 +1,570 @@
+use super::*;
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typeck::TypeChecker;
+
+fn eval(interpreter: &mut Interpreter, checker: &mut TypeChecker, src: &str) -> Value {
+    let tokens = Lexer::new(src).lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    interpreter.run(&typed).unwrap()
+}
+
+#[test]
+fn arithmetic() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "(4 + 5) + 10 * 3"),
+        Value::Int(39)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "17 % 5 * 2 + 7 % 3"),
+        Value::Int(5)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "2 ** 3 ** 2 - 2 ** 2 ** 3"),
+        Value::Int(256)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "2.0 ** 0.5 * 2.0 ** 0.5"),
+        Value::Float(2.0000000000000004)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "(6 & 3 | 8 ^ 1) << 2 >> 1"),
+        Value::Int(22)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "~5 & 255"),
+        Value::Int(250)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "7 / 2 + 1 / 2.0"),
+        Value::Float(3.5)
+    );
+}
+
+#[test]
+fn string_operators() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#""a" + "b" == "ab""#),
+        Value::Bool(true)
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        r#"
+            func loud(value: bool): bool {
+                print("!");
+                return value;
+            }
+        "#,
+    );
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            "1 < 2 && loud(false) || false && loud(true)"
+        ),
+        Value::Bool(false)
+    );
+    assert_eq!(interpreter.take_output(), "!");
+}
+
+#[test]
+fn logical_not() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "!(1 < 2) || !false"),
+        Value::Bool(true)
+    );
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        r#"println(true, !true); print("{1 == 1}");"#,
+    );
+    assert_eq!(interpreter.take_output(), "true false\ntrue");
+}
+
+#[test]
+fn compound_assignment() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        "let mut x = 7; x *= 3; x -= 1; x /= 4;",
+    );
+
+    assert_eq!(eval(&mut interpreter, &mut checker, "x"), Value::Int(5));
+}
+
+#[test]
+fn state_persists_between_runs() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        r#"
+            func fibonacci(n: int): int {
+                if (n <= 1) {
+                    return n;
+                }
+
+                return fibonacci(n - 1) + fibonacci(n - 2);
+            }
+        "#,
+    );
+
+    eval(&mut interpreter, &mut checker, "let mut x = 10;");
+    eval(&mut interpreter, &mut checker, "x = fibonacci(x);");
+
+    assert_eq!(eval(&mut interpreter, &mut checker, "x"), Value::Int(55));
+}
+
+#[test]
+fn arguments_bind_in_order() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        "func digits(a: Int, b: Int, c: Int, d: Int, e: Int): Int { return a * 10000 + b * 1000 + c * 100 + d * 10 + e; }",
+    );
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "digits(1, 2, 3, 4, 5)"),
+        Value::Int(12345)
+    );
+}
+
+#[test]
+fn printf_output() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        r#"
+            func printf(fmt, ...) @requires("stdio.h")
+
+            for i in 0..3 {
+                printf("%d, ", i);
+            }
+
+            printf("%s 100%%\n", "done");
+        "#,
+    );
+
+    assert_eq!(interpreter.take_output(), "0, 1, 2, done 100%\n");
+}
+
+#[test]
+fn prelude_output() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        r#"
+            print("total:");
+            println(1 + 2, "100%");
+            println();
+
+            let name = "uma";
+            println("hi {name}, {1 + 1}%");
+        "#,
+    );
+
+    assert_eq!(interpreter.take_output(), "total:3 100%\n\nhi uma, 2%\n");
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#""{name}: {[1, 2][1]}""#),
+        Value::String(String::from("uma: 2"))
+    );
+}
+
+#[test]
+fn sized_types_wrap() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        "let x: Int8 = 127; let y: UInt8 = 0; let big: Int64 = 1;",
+    );
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "x + 1"),
+        Value::Int(-128)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "y - 1"),
+        Value::Int(255)
+    );
+
+    eval(&mut interpreter, &mut checker, "println(big, y);");
+    assert_eq!(interpreter.take_output(), "1 0\n");
+}
+
+#[test]
+fn casts() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "(0.5 - 3.2) as Int"),
+        Value::Int(-2)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "300 as UInt8"),
+        Value::Int(44)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "3 as Float / 2"),
+        Value::Float(1.5)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "(1 < 2) as Int"),
+        Value::Int(1)
+    );
+}
+
+#[test]
+fn characters() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(&mut interpreter, &mut checker, "let c = 'é';");
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "c as Int"),
+        Value::Int(0xe9)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "c > 'a'"),
+        Value::Bool(true)
+    );
+
+    eval(&mut interpreter, &mut checker, r#"println(c, "[{c}]");"#);
+    assert_eq!(interpreter.take_output(), "é [é]\n");
+}
+
+#[test]
+fn division_by_zero() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    let tokens = Lexer::new("1 / 0").lex().unwrap();
+    let typed = checker
+        .check_incremental(Parser::new(tokens).parse().unwrap())
+        .unwrap();
+
+    assert_eq!(
+        interpreter.run(&typed).err().unwrap().message,
+        "Division by zero"
+    );
+}
+
+#[test]
+fn call_main() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        "func main(): int { return 6 * 7; }",
+    );
+
+    assert_eq!(interpreter.call("main", vec![]).unwrap(), Value::Int(42));
+}
+
+#[test]
+fn globals() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    eval(
+        &mut interpreter,
+        &mut checker,
+        "func scaled(): int { return base * 2; } let base = 21;",
+    );
+
+    assert_eq!(interpreter.call("scaled", vec![]).unwrap(), Value::Int(42));
+
+    let tokens = Lexer::new("func late(): int { return later; } late(); let later = 1;")
+        .lex()
+        .unwrap();
+    let typed = checker
+        .check_incremental(Parser::new(tokens).parse().unwrap())
+        .unwrap();
+
+    assert_eq!(
+        interpreter.run(&typed).err().unwrap().message,
+        "`later` is used before it's declared"
+    );
+}
+
+#[test]
+fn if_expressions() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            r#"(if 1 > 2 { "a" } else if 2 > 1 { "b" } else { "c" })"#
+        ),
+        Value::String("b".into())
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            "(if true { 1 } else { 0.5 }) * 2"
+        ),
+        Value::Float(2.0)
+    );
+}
+
+#[test]
+fn match_expressions() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            r#"match "b" { "a" => 1, "b" => 2, _ => 3 }"#
+        ),
+        Value::Int(2)
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            "match 2.0 { 1 => true, _ => false }"
+        ),
+        Value::Bool(false)
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            r#"match 1 < 2 { false => "no", true => "yes" }"#
+        ),
+        Value::String("yes".into())
+    );
+}
+
+#[test]
+fn strings() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#"len("uma" + "!")"#),
+        Value::Int(4)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#""uma"[1]"#),
+        Value::String("m".into())
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "len([1, 2, 3])"),
+        Value::Int(3)
+    );
+}
+
+#[test]
+fn failed_asserts() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "assert(1 < 2)"),
+        Value::Void
+    );
+
+    let tokens = Lexer::new(r#"assert(2 < 1, "backwards")"#).lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    assert_eq!(
+        interpreter.run(&typed).unwrap_err().message,
+        "Assertion `2 < 1` failed: backwards"
+    );
+}
+
+#[test]
+fn math_builtins() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "abs(0 - 4) + max(2, 3)"),
+        Value::Int(7)
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            "sqrt(16) + round(0.5) + min(1, 0.5)"
+        ),
+        Value::Float(5.5)
+    );
+}
+
+#[test]
+fn reading_input() {
+    let mut interpreter = Interpreter::with_input("Ada\n 42 \r\nlots\n".as_bytes());
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#"input("name? ")"#),
+        Value::String("Ada".into())
+    );
+    assert_eq!(interpreter.take_output(), "name? ");
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "parse_int(input()) + 1"),
+        Value::Int(43)
+    );
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, r#"parse_float("2.5")"#),
+        Value::Float(2.5)
+    );
+
+    let tokens = Lexer::new("parse_int(input())").lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    assert_eq!(
+        interpreter.run(&typed).unwrap_err().message,
+        "Cannot parse \"lots\" as an `Int`"
+    );
+}
+
+#[test]
+fn files() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+    let path = std::env::temp_dir().join("uma_interpreter_files.txt");
+    let path = path.to_str().unwrap();
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            &format!(r#"write_file("{path}", "a") && append_file("{path}", "b")"#)
+        ),
+        Value::Bool(true)
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            &format!(r#"read_file("{path}")"#)
+        ),
+        Value::String("ab".into())
+    );
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            &format!(r#"read_file("{path}", "none")"#)
+        ),
+        Value::String("ab".into())
+    );
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            &format!(r#"read_file("{path}", "none")"#)
+        ),
+        Value::String("none".into())
+    );
+
+    let tokens = Lexer::new(&format!(r#"read_file("{path}")"#))
+        .lex()
+        .unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    assert!(interpreter
+        .run(&typed)
+        .unwrap_err()
+        .message
+        .starts_with("Cannot read `"));
+}
+
+#[test]
+fn program_arguments() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+    interpreter.set_args(vec!["main.uma".into(), "one".into()]);
+
+    assert_eq!(
+        eval(&mut interpreter, &mut checker, "arg(arg_count() - 1)"),
+        Value::String("one".into())
+    );
+
+    let tokens = Lexer::new("arg(2)").lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    assert_eq!(
+        interpreter.run(&typed).unwrap_err().message,
+        "Index 2 is out of bounds for 2 arguments"
+    );
+}
+
+#[test]
+fn exit_and_env() {
+    let mut interpreter = Interpreter::new();
+    let mut checker = TypeChecker::new();
+
+    assert_eq!(
+        eval(
+            &mut interpreter,
+            &mut checker,
+            r#"set_env("UMA_INTERPRETER_ENV", "on"); env("UMA_INTERPRETER_ENV") + env("UMA_UNSET")"#
+        ),
+        Value::String("on".into())
+    );
+
+    let tokens = Lexer::new("exit(4); println(1);").lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let typed = checker.check_incremental(ast).unwrap();
+
+    assert_eq!(interpreter.run(&typed).unwrap_err().exit, Some(4));
+    assert_eq!(interpreter.take_output(), "");
+}
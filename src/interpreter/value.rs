@@ -0,0 +1,49 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Array(Vec<Value>),
+    Void,
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Int(num) => *num != 0,
+            Value::Float(num) => *num != 0.0,
+            Value::Bool(value) => *value,
+            Value::Char(_) | Value::String(_) | Value::Array(_) => true,
+            Value::Void => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(num) => write!(f, "{}", num),
+            // the same as C's `%f`, which the other backends print them with.
+            Value::Float(num) if num.is_nan() => {
+                write!(f, "{}nan", if num.is_sign_negative() { "-" } else { "" })
+            }
+            Value::Float(num) => write!(f, "{:.6}", num),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_string())
+                    .collect::<Vec<_>>();
+
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Void => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use super::Stmt;
+use crate::typeck::{FunctionAttribute, Type, TypedExpr, TypedPart};
+
+// calls `visit` with every expression in `stmts`, including the ones nested
+// in other expressions.
+pub fn exprs<'a>(stmts: &'a [Stmt], visit: &mut impl FnMut(&'a TypedExpr)) {
+    fn expr<'a>(e: &'a TypedExpr, visit: &mut impl FnMut(&'a TypedExpr)) {
+        visit(e);
+
+        match e {
+            TypedExpr::Binary { lhs, rhs, .. } => {
+                expr(lhs, visit);
+                expr(rhs, visit);
+            }
+            TypedExpr::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                expr(condition, visit);
+                expr(consequence, visit);
+                expr(alternative, visit);
+            }
+            TypedExpr::Match {
+                subject,
+                arms,
+                default,
+                ..
+            } => {
+                expr(subject, visit);
+
+                for (pattern, value) in arms {
+                    expr(pattern, visit);
+                    expr(value, visit);
+                }
+
+                expr(default, visit);
+            }
+            TypedExpr::Unary { operand, .. }
+            | TypedExpr::Cast { value: operand, .. }
+            | TypedExpr::Deref {
+                pointer: operand, ..
+            } => expr(operand, visit),
+            TypedExpr::Call { args, .. } | TypedExpr::Builtin { args, .. } => {
+                args.iter().for_each(|arg| expr(arg, visit))
+            }
+            TypedExpr::Array { elements, .. } => elements.iter().for_each(|e| expr(e, visit)),
+            TypedExpr::Interpolation(parts) => {
+                for part in parts {
+                    if let TypedPart::Expr(e) = part {
+                        expr(e, visit);
+                    }
+                }
+            }
+            TypedExpr::Index { target, index, .. } => {
+                expr(target, visit);
+                expr(index, visit);
+            }
+            _ => (),
+        }
+    }
+
+    for stmt in stmts {
+        match stmt {
+            Stmt::Variable { value, .. }
+            | Stmt::Const { value, .. }
+            | Stmt::Assignment { value, .. }
+            | Stmt::Return(Some(value), _)
+            | Stmt::Expr(value, _) => expr(value, visit),
+            Stmt::Return(None, _) => (),
+            Stmt::Function { body, .. } | Stmt::Block(body, _) => exprs(body, visit),
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+                ..
+            } => {
+                expr(condition, visit);
+                exprs(consequence, visit);
+
+                if let Some(alt) = alternative {
+                    exprs(std::slice::from_ref(&**alt), visit);
+                }
+            }
+            Stmt::For {
+                start, end, body, ..
+            } => {
+                expr(start, visit);
+                expr(end, visit);
+                exprs(body, visit);
+            }
+            Stmt::External { .. } | Stmt::Opaque { .. } => (),
+        }
+    }
+}
+
+// every call in `stmts`, including the ones nested in other expressions.
+pub fn calls<'a>(stmts: &'a [Stmt], out: &mut Vec<(&'a str, &'a [TypedExpr], &'a Type)>) {
+    exprs(stmts, &mut |expr| {
+        if let TypedExpr::Call { name, args, ty } = expr {
+            out.push((name, args, ty));
+        }
+    });
+}
+
+// the program without the functions and externals `main` can never end up
+// calling. top-level statements always run, so what they call is kept too, as
+// are the `@export`ed functions, and a program without a `main` is left as it
+// is.
+pub fn reachable(program: &[Stmt]) -> Vec<Stmt> {
+    let mut bodies = HashMap::new();
+    let mut roots = vec![];
+    let mut exports = vec![];
+
+    for stmt in program {
+        match stmt {
+            Stmt::Function {
+                name,
+                body,
+                attributes,
+                ..
+            } => {
+                bodies.insert(name.as_str(), body.as_slice());
+
+                if attributes.contains(&FunctionAttribute::Export) {
+                    exports.push(name.as_str());
+                }
+            }
+            Stmt::External { .. } => (),
+            stmt => roots.push(stmt.clone()),
+        }
+    }
+
+    if !bodies.contains_key("main") {
+        return program.to_vec();
+    }
+
+    let mut found = vec![];
+    calls(&roots, &mut found);
+
+    let mut queue = vec!["main"];
+    queue.extend(exports);
+    queue.extend(found.iter().map(|(name, ..)| *name));
+
+    let mut live = HashSet::new();
+
+    while let Some(name) = queue.pop() {
+        if !live.insert(name) {
+            continue;
+        }
+
+        if let Some(body) = bodies.get(name) {
+            let mut found = vec![];
+            calls(body, &mut found);
+
+            queue.extend(found.iter().map(|(name, ..)| *name));
+        }
+    }
+
+    program
+        .iter()
+        .filter(|stmt| match stmt {
+            Stmt::Function { name, .. } | Stmt::External { name, .. } => {
+                live.contains(name.as_str())
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
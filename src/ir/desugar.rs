@@ -0,0 +1,246 @@
+use std::collections::HashSet;
+
+use super::Stmt;
+use crate::lexer::Span;
+use crate::typeck::{Type, TypedExpr, TypedStmt};
+
+// `program` as the IR, with every `for x in xs` rewritten as a `for` over the
+// indices of `xs`, binding `x` at the top of each iteration.
+pub fn desugar(program: &[TypedStmt]) -> Vec<Stmt> {
+    let mut names = HashSet::new();
+    declared(program, &mut names);
+
+    let mut desugarer = Desugarer { names };
+    desugarer.block(program)
+}
+
+// every name `stmts` declare, so the ones made up here can't shadow them.
+fn declared(stmts: &[TypedStmt], names: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            TypedStmt::Variable { name, .. }
+            | TypedStmt::Const { name, .. }
+            | TypedStmt::External { name, .. }
+            | TypedStmt::Opaque { name, .. } => {
+                names.insert(name.clone());
+            }
+            TypedStmt::Function {
+                name, args, body, ..
+            } => {
+                names.insert(name.clone());
+                names.extend(args.iter().map(|(arg, _)| arg.clone()));
+                declared(body, names);
+            }
+            TypedStmt::If {
+                consequence,
+                alternative,
+                ..
+            } => {
+                declared(consequence, names);
+
+                if let Some(alt) = alternative {
+                    declared(std::slice::from_ref(&**alt), names);
+                }
+            }
+            TypedStmt::For { iterator, body, .. } | TypedStmt::ForEach { iterator, body, .. } => {
+                names.insert(iterator.clone());
+                declared(body, names);
+            }
+            TypedStmt::Block(body, _) => declared(body, names),
+            TypedStmt::Assignment { .. } | TypedStmt::Return(..) | TypedStmt::Expr(..) => (),
+        }
+    }
+}
+
+struct Desugarer {
+    names: HashSet<String>,
+}
+
+impl Desugarer {
+    // `base`, or `base` with a number after it if the program already uses it.
+    fn fresh(&mut self, base: String) -> String {
+        let mut name = base.clone();
+        let mut n = 0;
+
+        while self.names.contains(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+
+        self.names.insert(name.clone());
+        name
+    }
+
+    fn block(&mut self, stmts: &[TypedStmt]) -> Vec<Stmt> {
+        stmts.iter().map(|stmt| self.stmt(stmt)).collect()
+    }
+
+    fn stmt(&mut self, stmt: &TypedStmt) -> Stmt {
+        match stmt {
+            TypedStmt::Function {
+                name,
+                return_type,
+                args,
+                body,
+                attributes,
+                span,
+            } => Stmt::Function {
+                name: name.clone(),
+                return_type: return_type.clone(),
+                args: args.clone(),
+                body: self.block(body),
+                attributes: attributes.clone(),
+                span: *span,
+            },
+            TypedStmt::If {
+                condition,
+                consequence,
+                alternative,
+                span,
+            } => Stmt::If {
+                condition: condition.clone(),
+                consequence: self.block(consequence),
+                alternative: alternative.as_ref().map(|alt| Box::new(self.stmt(alt))),
+                span: *span,
+            },
+            TypedStmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                span,
+            } => Stmt::For {
+                iterator: iterator.clone(),
+                start: start.clone(),
+                end: end.clone(),
+                body: self.block(body),
+                span: *span,
+            },
+            TypedStmt::ForEach {
+                iterator,
+                iterable,
+                body,
+                span,
+            } => self.for_each(iterator, iterable, body, *span),
+            TypedStmt::Block(body, span) => Stmt::Block(self.block(body), *span),
+            TypedStmt::Variable {
+                name,
+                value,
+                is_mut,
+                ty,
+                span,
+            } => Stmt::Variable {
+                name: name.clone(),
+                value: value.clone(),
+                is_mut: *is_mut,
+                ty: ty.clone(),
+                span: *span,
+            },
+            TypedStmt::Const { name, value, ty } => Stmt::Const {
+                name: name.clone(),
+                value: value.clone(),
+                ty: ty.clone(),
+            },
+            TypedStmt::External {
+                name,
+                header,
+                links,
+                arity,
+                is_varadic,
+            } => Stmt::External {
+                name: name.clone(),
+                header: header.clone(),
+                links: links.clone(),
+                arity: *arity,
+                is_varadic: *is_varadic,
+            },
+            TypedStmt::Opaque { name, header } => Stmt::Opaque {
+                name: name.clone(),
+                header: header.clone(),
+            },
+            TypedStmt::Assignment {
+                name,
+                value,
+                op,
+                span,
+            } => Stmt::Assignment {
+                name: name.clone(),
+                value: value.clone(),
+                op: op.clone(),
+                span: *span,
+            },
+            TypedStmt::Return(value, span) => Stmt::Return(value.clone(), *span),
+            TypedStmt::Expr(value, span) => Stmt::Expr(value.clone(), *span),
+        }
+    }
+
+    fn for_each(
+        &mut self,
+        iterator: &str,
+        iterable: &TypedExpr,
+        body: &[TypedStmt],
+        span: Span,
+    ) -> Stmt {
+        let (element, len) = match iterable.ty() {
+            Type::Array(element, len) => (*element, len),
+            ty => unreachable!("cannot iterate over {:?}", ty),
+        };
+
+        // the array is only worked out once, before the loop starts.
+        let mut setup = None;
+
+        let array = match iterable {
+            TypedExpr::Identifier { .. } => iterable.clone(),
+            iterable => {
+                let name = self.fresh(format!("{}_items", iterator));
+
+                setup = Some(Stmt::Variable {
+                    name: name.clone(),
+                    value: iterable.clone(),
+                    is_mut: false,
+                    ty: iterable.ty(),
+                    span: Span::default(),
+                });
+
+                TypedExpr::Identifier {
+                    name,
+                    ty: iterable.ty(),
+                }
+            }
+        };
+
+        let index = self.fresh(format!("{}_i", iterator));
+
+        // each element is bound like a `let`, so arrays of arrays are copied
+        // the same way.
+        let mut stmts = vec![Stmt::Variable {
+            name: iterator.to_string(),
+            value: TypedExpr::Index {
+                target: array.into(),
+                index: TypedExpr::Identifier {
+                    name: index.clone(),
+                    ty: Type::Int,
+                }
+                .into(),
+                ty: element.clone(),
+            },
+            is_mut: false,
+            ty: element,
+            span: Span::default(),
+        }];
+        stmts.extend(self.block(body));
+
+        let lowered = Stmt::For {
+            iterator: index,
+            start: TypedExpr::Number("0".to_string()),
+            end: TypedExpr::Number(len.to_string()),
+            body: stmts,
+            span,
+        };
+
+        match setup {
+            Some(setup) => Stmt::Block(vec![setup, lowered], span),
+            None => lowered,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+pub mod callgraph;
+mod desugar;
+mod types;
+
+use crate::typeck::TypedStmt;
+
+use self::{callgraph::reachable, desugar::desugar};
+
+pub use self::types::Stmt;
+
+// the checked program the way the backends take it: the sugar that every
+// backend would otherwise lower on its own is taken out, so a `for x in xs`
+// is left as a counted `for` over `xs`, and the functions that can never run
+// are dropped. a pass that doesn't need to know about the target goes here.
+// `Codegen::generate` lowers the program before a backend is handed it.
+pub fn lower(program: &[TypedStmt]) -> Vec<Stmt> {
+    reachable(&desugar(program))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,90 @@
+use super::*;
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typeck::{Type, TypeChecker, TypedExpr};
+
+fn lowered(src: &str) -> Vec<Stmt> {
+    let tokens = Lexer::new(src).lex().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    let (typed, _) = TypeChecker::check(ast).unwrap();
+
+    lower(&typed)
+}
+
+fn body(program: &[Stmt]) -> &[Stmt] {
+    match program.last() {
+        Some(Stmt::Function { body, .. }) => body,
+        stmt => panic!("expected a function, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn for_each_becomes_a_counted_for() {
+    let program =
+        lowered("func main(): int { let xs = [1, 2, 3]; for x in xs { println(x); } return 0; }");
+
+    match &body(&program)[1] {
+        Stmt::For {
+            iterator,
+            start,
+            end,
+            body,
+            ..
+        } => {
+            assert_eq!(iterator, "x_i");
+            assert_eq!(*start, TypedExpr::Number("0".to_string()));
+            assert_eq!(*end, TypedExpr::Number("3".to_string()));
+
+            match &body[0] {
+                Stmt::Variable {
+                    name, value, ty, ..
+                } => {
+                    assert_eq!(name, "x");
+                    assert_eq!(*ty, Type::Int);
+                    assert!(matches!(value, TypedExpr::Index { .. }));
+                }
+                stmt => panic!("expected the element to be bound, found {:?}", stmt),
+            }
+        }
+        stmt => panic!("expected a for loop, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn for_each_works_out_its_array_once() {
+    let program = lowered(
+        "func main(): int { let grid = [[1, 2], [3, 4]]; for x in grid[1] { println(x); } return 0; }",
+    );
+
+    match &body(&program)[1] {
+        Stmt::Block(stmts, _) => {
+            assert!(matches!(
+                &stmts[0],
+                Stmt::Variable { name, value: TypedExpr::Index { .. }, .. } if name == "x_items"
+            ));
+            assert!(matches!(&stmts[1], Stmt::For { .. }));
+        }
+        stmt => panic!("expected the array to be bound first, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn made_up_names_dont_shadow_the_programs() {
+    let program =
+        lowered("func main(): int { let x_i = 5; for x in [1, 2] { println(x, x_i); } return 0; }");
+
+    match &body(&program)[1] {
+        Stmt::Block(stmts, _) => {
+            assert!(matches!(&stmts[1], Stmt::For { iterator, .. } if iterator == "x_i_1"))
+        }
+        stmt => panic!("expected a block, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn lowering_drops_unreachable_functions() {
+    let program = lowered("func unused(): int { return 1; } func main(): int { return 0; }");
+
+    assert_eq!(program.len(), 1);
+}
@@ -0,0 +1,69 @@
+use crate::lexer::{Span, Token};
+use crate::typeck::{FunctionAttribute, Type, TypedExpr};
+
+// a statement of the lowered program, what the backends generate code from.
+// it's the checked `TypedStmt` without the sugar `lower` takes out, so a
+// `for x in xs` is only ever a counted `For`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Stmt {
+    Variable {
+        name: String,
+        value: TypedExpr,
+        is_mut: bool,
+        ty: Type,
+        span: Span,
+    },
+    // `value` is always a literal, the constant already worked out.
+    Const {
+        name: String,
+        value: TypedExpr,
+        ty: Type,
+    },
+    Function {
+        name: String,
+        return_type: Type,
+        args: Vec<(String, Type)>,
+        body: Vec<Stmt>,
+        attributes: Vec<FunctionAttribute>,
+        span: Span,
+    },
+    // `arity` only counts the arguments before a `...`.
+    External {
+        name: String,
+        header: String,
+        links: Vec<String>,
+        arity: usize,
+        is_varadic: bool,
+    },
+    // an `@opaque` type, declared by `header` or by nothing it's told of.
+    Opaque {
+        name: String,
+        header: Option<String>,
+    },
+    If {
+        condition: TypedExpr,
+        consequence: Vec<Stmt>,
+        alternative: Option<Box<Stmt>>,
+        span: Span,
+    },
+    // `iterator` counts up from `start` to just before `end`.
+    For {
+        iterator: String,
+        start: TypedExpr,
+        end: TypedExpr,
+        body: Vec<Stmt>,
+        span: Span,
+    },
+    Block(Vec<Stmt>, Span),
+    // `value` is the whole new value, `x + 1` for `x += 1`, with `op` set
+    // when it was written as a compound assignment.
+    Assignment {
+        name: String,
+        value: TypedExpr,
+        op: Option<Token>,
+        span: Span,
+    },
+    // nothing for a bare `return`.
+    Return(Option<TypedExpr>, Span),
+    Expr(TypedExpr, Span),
+}
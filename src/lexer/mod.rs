@@ -1,16 +1,23 @@
 mod tokens;
 mod utils;
 
-pub use self::tokens::{Token, TokenKind};
-pub use self::utils::Buffer;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
 
-fn match_keyword_to_token(keyword: &str, line: usize, column: usize) -> Option<Token> {
+pub use self::tokens::{Span, Token, TokenKind};
+pub use self::utils::{ErrorKind, LexerError};
+
+use self::utils::Buffer;
+
+fn match_keyword_to_token(keyword: &str, span: Span) -> Option<Token> {
     let token = {
         match keyword {
             "let" => Some(TokenKind::Let),
+            "const" => Some(TokenKind::Const),
             "mut" => Some(TokenKind::Mut),
             "if" => Some(TokenKind::If),
             "else" => Some(TokenKind::Else),
+            "match" => Some(TokenKind::Match),
             "func" => Some(TokenKind::Func),
             "for" => Some(TokenKind::For),
             "in" => Some(TokenKind::In),
@@ -18,44 +25,170 @@ fn match_keyword_to_token(keyword: &str, line: usize, column: usize) -> Option<T
             "true" => Some(TokenKind::True),
             "false" => Some(TokenKind::False),
             "none" => Some(TokenKind::None),
+            "import" => Some(TokenKind::Import),
+            "as" => Some(TokenKind::As),
+            "type" => Some(TokenKind::Type),
             _ => None,
         }
     };
 
-    token.map(|kind| Token::new(kind, None, line, column))
+    token.map(|kind| Token::new(kind, None, span))
+}
+
+// what can be written after a number to pin its type, `100u64` or `1.5f`.
+const INTEGER_SUFFIXES: &[&str] = &["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64"];
+const FLOAT_SUFFIXES: &[&str] = &["f", "f32", "f64"];
+
+// how deep strings can be interpolated inside each other's `{}`s, any deeper
+// is an error rather than the lexer running out of stack.
+const MAX_INTERPOLATIONS: usize = 64;
+
+// names follow XID_Start and XID_Continue as near as the standard library
+// gets: a name starts with a letter or `_`, and goes on with letters, digits
+// and `_`, in any script. `café` and `名前` are names, `1x` and `-x` aren't.
+fn starts_identifier(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn continues_identifier(c: char) -> bool {
+    // and the combining marks, so an accent written after its letter is
+    // part of the name too.
+    c.is_alphanumeric()
+        || c == '_'
+        || matches!(
+            c,
+            '\u{300}'..='\u{36f}'
+                | '\u{1ab0}'..='\u{1aff}'
+                | '\u{1dc0}'..='\u{1dff}'
+                | '\u{20d0}'..='\u{20ff}'
+                | '\u{fe20}'..='\u{fe2f}'
+        )
+}
+
+// a line of a text block as it's lexed. `indent` is how much whitespace it
+// starts with, and `blank` says there's been nothing else on it yet.
+struct BlockLine {
+    text: String,
+    indent: usize,
+    blank: bool,
+}
+
+impl Default for BlockLine {
+    fn default() -> Self {
+        BlockLine {
+            text: String::new(),
+            indent: 0,
+            blank: true,
+        }
+    }
 }
 
 pub struct Lexer<'a> {
+    source: &'a str,
     buffer: Buffer<'a>,
+    file: usize,
+    // a string can come out as several tokens at once, the ones that haven't
+    // been asked for yet wait here.
+    pending: VecDeque<Token>,
+    // nothing more is lexed after an error.
+    failed: bool,
+    // every name and literal seen so far. the tokens with the same text share
+    // one copy of it, so cloning a token doesn't copy its text.
+    symbols: HashSet<Rc<str>>,
+    // how many interpolated strings the lexer is inside of.
+    interpolations: usize,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(cnt: &'a str) -> Lexer<'a> {
-        Lexer {
+        Self::with_file(cnt, 0)
+    }
+
+    // tokens get spans pointing into `file`.
+    pub fn with_file(cnt: &'a str, file: usize) -> Lexer<'a> {
+        let mut lexer = Lexer {
+            source: cnt,
             buffer: Buffer::new(cnt),
+            file,
+            pending: VecDeque::new(),
+            failed: false,
+            symbols: HashSet::new(),
+            interpolations: 0,
+        };
+
+        // a `#!/usr/bin/env uma` line is for the shell running the file as a
+        // script, it isn't part of the program.
+        if lexer.rest().starts_with("#!") {
+            while !lexer.buffer.eof && lexer.buffer.current != '\n' {
+                lexer.buffer.next();
+            }
         }
+
+        lexer
     }
 
-    fn ident_or_keyword(&mut self) -> Token {
-        let mut out = String::new();
+    // the span of a token from `start` up to the current character.
+    fn span(&self, start: usize, line: usize, column: usize) -> Span {
+        Span::new(start, self.buffer.offset, line, column).in_file(self.file)
+    }
 
+    // the span from `start` through the current character, for an error
+    // found at it.
+    fn through(&self, start: usize, line: usize, column: usize) -> Span {
+        let end = match self.buffer.eof {
+            true => self.buffer.offset,
+            false => self.buffer.offset + self.buffer.current.len_utf8(),
+        };
+
+        Span::new(start, end, line, column).in_file(self.file)
+    }
+
+    // the span of the current character.
+    fn here(&self) -> Span {
+        self.through(self.buffer.offset, self.buffer.line, self.buffer.column)
+    }
+
+    // a token carrying `text`, shared with the other tokens with the same.
+    fn valued(&mut self, kind: TokenKind, text: &str, span: Span) -> Token {
+        let symbol = match self.symbols.get(text) {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let symbol: Rc<str> = text.into();
+                self.symbols.insert(symbol.clone());
+                symbol
+            }
+        };
+
+        Token {
+            kind,
+            value: Some(symbol),
+            span,
+        }
+    }
+
+    fn ident_or_keyword(&mut self) -> Token {
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
         let column = self.buffer.column;
 
-        while self.buffer.current.is_alphanumeric() || self.buffer.current == '_' {
-            out.push(self.buffer.current);
+        while !self.buffer.eof && continues_identifier(self.buffer.current) {
             self.buffer.next();
         }
 
-        match_keyword_to_token(&out, self.buffer.line, column).unwrap_or(Token::new(
-            TokenKind::Identifier,
-            Some(out),
-            self.buffer.line,
-            column,
-        ))
+        let span = self.span(start, line, column);
+        let name = &self.source[start..self.buffer.offset];
+
+        match match_keyword_to_token(name, span) {
+            Some(keyword) => keyword,
+            None => self.valued(TokenKind::Identifier, name, span),
+        }
     }
 
-    fn number(&mut self) -> Token {
+    fn number(&mut self) -> Result<Token, LexerError> {
         let mut out = String::new();
+
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
         let column = self.buffer.column;
 
         while self.buffer.current.is_ascii_digit()
@@ -79,7 +212,11 @@ impl<'a> Lexer<'a> {
                 }
 
                 if out.contains('.') {
-                    panic!("More than one decimal point found.");
+                    return LexerError::new(
+                        ErrorKind::InvalidNumber,
+                        self.here(),
+                        "More than one decimal point found",
+                    );
                 }
             }
 
@@ -87,32 +224,136 @@ impl<'a> Lexer<'a> {
             self.buffer.next();
         }
 
-        if out.contains('.') {
-            Token::new(TokenKind::Float, Some(out), self.buffer.line, column)
+        let mut is_float = out.contains('.');
+
+        // `6.02e23` and `2.5e-3`, a number with an exponent is a float.
+        if !self.buffer.eof && matches!(self.buffer.current, 'e' | 'E') {
+            let after = &self.rest()[1..];
+            let digits = after.strip_prefix(['+', '-']).unwrap_or(after);
+
+            if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                return LexerError::new(
+                    ErrorKind::InvalidNumber,
+                    self.here(),
+                    "Expected digits after the exponent",
+                );
+            }
+
+            out.push(self.buffer.current);
+            self.buffer.next();
+
+            if matches!(self.buffer.current, '+' | '-') {
+                out.push(self.buffer.current);
+                self.buffer.next();
+            }
+
+            while !self.buffer.eof
+                && (self.buffer.current.is_ascii_digit() || self.buffer.current == '_')
+            {
+                if self.buffer.current != '_' {
+                    out.push(self.buffer.current);
+                }
+
+                self.buffer.next();
+            }
+
+            is_float = true;
+        }
+
+        // anything left running into the number is its suffix, which has to
+        // be one there is.
+        if !self.buffer.eof && continues_identifier(self.buffer.current) {
+            let (line, column) = (self.buffer.line, self.buffer.column);
+            let suffix_start = self.buffer.offset;
+
+            while !self.buffer.eof && continues_identifier(self.buffer.current) {
+                self.buffer.next();
+            }
+
+            let suffix = &self.source[suffix_start..self.buffer.offset];
+
+            if FLOAT_SUFFIXES.contains(&suffix) {
+                is_float = true;
+            } else if !INTEGER_SUFFIXES.contains(&suffix) {
+                return LexerError::new(
+                    ErrorKind::InvalidNumber,
+                    self.span(suffix_start, line, column),
+                    format!("Unknown suffix `{}` on a number", suffix),
+                );
+            } else if is_float {
+                return LexerError::new(
+                    ErrorKind::InvalidNumber,
+                    self.span(suffix_start, line, column),
+                    format!("`{}` is for integers, `{}` is a float", suffix, out),
+                );
+            }
+
+            out.push_str(suffix);
+        }
+
+        let span = self.span(start, line, column);
+
+        if is_float {
+            Ok(self.valued(TokenKind::Float, &out, span))
         } else {
-            Token::new(TokenKind::Number, Some(out), self.buffer.line, column)
+            Ok(self.valued(TokenKind::Number, &out, span))
         }
     }
 
-    fn string(&mut self, delimeter: char) -> Token {
+    fn string(&mut self, delimeter: char) -> Result<(), LexerError> {
         let mut out = String::new();
+
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
+        let column = self.buffer.column;
+
+        // where the text since the last interpolated expression starts.
+        let mut part = (start, line, column);
+        let mut interpolated = false;
+
         self.buffer.next();
 
-        let column = self.buffer.column - 1;
+        loop {
+            // `current` is left on the last character at EOF, so that has to be
+            // checked before it's compared against the delimeter.
+            if self.buffer.eof || self.buffer.current == '\n' {
+                return LexerError::new(
+                    ErrorKind::UnterminatedString,
+                    self.span(start, line, column),
+                    "Unterminated string literal",
+                );
+            }
+
+            if self.buffer.current == delimeter {
+                break;
+            }
 
-        while self.buffer.current != delimeter {
             if self.buffer.current == '\\' {
+                let escaped = self.string_escape()?;
+
+                out.push(escaped);
                 self.buffer.next();
 
-                let curr = match self.buffer.current {
-                    'n' => '\n',
-                    't' => '\t',
-                    '\\' => '\\',
-                    other => other,
-                };
+                continue;
+            }
 
-                out.push(curr);
-                self.buffer.next();
+            if self.buffer.current == '{' {
+                if !interpolated {
+                    let span = Span::new(start, start + 1, line, column).in_file(self.file);
+                    self.pending
+                        .push_back(Token::new(TokenKind::InterpolationStart, None, span));
+                    interpolated = true;
+                }
+
+                if !out.is_empty() {
+                    let span = self.span(part.0, part.1, part.2);
+                    let text = self.valued(TokenKind::String, &out, span);
+                    self.pending.push_back(text);
+                    out.clear();
+                }
+
+                self.interpolation((start, line, column))?;
+                part = (self.buffer.offset, self.buffer.line, self.buffer.column);
 
                 continue;
             }
@@ -121,12 +362,393 @@ impl<'a> Lexer<'a> {
             self.buffer.next();
         }
 
+        if !interpolated {
+            self.buffer.next();
+
+            let span = self.span(start, line, column);
+            let string = self.valued(TokenKind::String, &out, span);
+            self.pending.push_back(string);
+
+            return Ok(());
+        }
+
+        if !out.is_empty() {
+            let span = self.span(part.0, part.1, part.2);
+            let text = self.valued(TokenKind::String, &out, span);
+            self.pending.push_back(text);
+        }
+
+        let end = (self.buffer.offset, self.buffer.line, self.buffer.column);
         self.buffer.next();
 
-        Token::new(TokenKind::String, Some(out), self.buffer.line, column)
+        let span = self.span(end.0, end.1, end.2);
+        self.pending
+            .push_back(Token::new(TokenKind::InterpolationEnd, None, span));
+
+        Ok(())
+    }
+
+    // the source from the current character on.
+    fn rest(&self) -> &'a str {
+        &self.source[self.buffer.offset..]
     }
 
-    fn parse_character(&mut self) -> Token {
+    // `r"..."`, a string taken as it's written, with no escapes and no
+    // interpolation. `r"""` starts a raw text block.
+    fn raw_string(&mut self) -> Result<(), LexerError> {
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
+        let column = self.buffer.column;
+
+        self.buffer.next();
+        let delimeter = self.buffer.current;
+
+        if self.rest().starts_with(&delimeter.to_string().repeat(3)) {
+            return self.text_block((start, line, column), true);
+        }
+
+        let mut out = String::new();
+        self.buffer.next();
+
+        loop {
+            if self.buffer.eof || self.buffer.current == '\n' {
+                return LexerError::new(
+                    ErrorKind::UnterminatedString,
+                    self.span(start, line, column),
+                    "Unterminated string literal",
+                );
+            }
+
+            if self.buffer.current == delimeter {
+                break;
+            }
+
+            out.push(self.buffer.current);
+            self.buffer.next();
+        }
+
+        self.buffer.next();
+
+        let span = self.span(start, line, column);
+        let string = self.valued(TokenKind::String, &out, span);
+        self.pending.push_back(string);
+
+        Ok(())
+    }
+
+    // a string between triple quotes, which can go over several lines. the
+    // line it opens on is dropped if there's nothing else on it, and so is
+    // the line it closes on, and the indentation the lines share with the
+    // closing quotes is taken off them all. the escapes work like they do in
+    // any string unless it's raw, but a `{` is just a `{`. `start` is where
+    // the string starts, at its `r` if it has one.
+    fn text_block(
+        &mut self,
+        (start, line, column): (usize, usize, usize),
+        raw: bool,
+    ) -> Result<(), LexerError> {
+        let closing = self.buffer.current.to_string().repeat(3);
+
+        for _ in 0..3 {
+            self.buffer.next();
+        }
+
+        let mut lines = vec![BlockLine::default()];
+
+        loop {
+            if self.buffer.eof {
+                return LexerError::new(
+                    ErrorKind::UnterminatedString,
+                    self.span(start, line, column),
+                    "Unterminated string literal",
+                );
+            }
+
+            if self.rest().starts_with(&closing) {
+                for _ in 0..3 {
+                    self.buffer.next();
+                }
+
+                break;
+            }
+
+            let current = self.buffer.current;
+
+            if current == '\n' {
+                lines.push(BlockLine::default());
+                self.buffer.next();
+
+                continue;
+            }
+
+            let escaped = match current {
+                '\\' if !raw => Some(self.string_escape()?),
+                _ => None,
+            };
+
+            let last = lines.last_mut().unwrap();
+
+            match escaped {
+                Some(c) => {
+                    last.text.push(c);
+                    last.blank = false;
+                }
+                None => {
+                    if last.blank && (current == ' ' || current == '\t') {
+                        last.indent += 1;
+                    } else {
+                        last.blank = false;
+                    }
+
+                    last.text.push(current);
+                }
+            }
+
+            self.buffer.next();
+        }
+
+        if lines.len() > 1 && lines[0].blank {
+            lines.remove(0);
+        }
+
+        let closer = match lines.last() {
+            Some(last) if lines.len() > 1 && last.blank => lines.pop().map(|last| last.indent),
+            _ => None,
+        };
+
+        let indent = lines
+            .iter()
+            .filter(|line| !line.blank)
+            .map(|line| line.indent)
+            .chain(closer)
+            .min()
+            .unwrap_or(0);
+
+        let text = lines
+            .iter()
+            .map(|line| match line.blank {
+                true => String::new(),
+                false => line.text.chars().skip(indent).collect(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let span = self.span(start, line, column);
+        let string = self.valued(TokenKind::String, &text, span);
+        self.pending.push_back(string);
+
+        Ok(())
+    }
+
+    // `'a'`, a single character, which can be written as an escape.
+    fn character(&mut self) -> Result<Token, LexerError> {
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
+        let column = self.buffer.column;
+
+        let unterminated = |span| {
+            LexerError::new(
+                ErrorKind::UnterminatedString,
+                span,
+                "Unterminated character literal",
+            )
+        };
+
+        self.buffer.next();
+
+        let c = match self.buffer.current {
+            _ if self.buffer.eof => return unterminated(self.span(start, line, column)),
+            '\n' => return unterminated(self.span(start, line, column)),
+            '\'' => {
+                return LexerError::new(
+                    ErrorKind::InvalidChar,
+                    self.through(start, line, column),
+                    "A character literal can't be empty",
+                )
+            }
+            '\\' => self.escape()?,
+            c => c,
+        };
+
+        self.buffer.next();
+
+        if self.buffer.eof || self.buffer.current != '\'' {
+            let rest = self.rest().lines().next().unwrap_or_default();
+
+            if self.buffer.eof || !rest.contains('\'') {
+                return unterminated(self.span(start, line, column));
+            }
+
+            return LexerError::new(
+                ErrorKind::InvalidChar,
+                self.span(start, line, column),
+                "A character literal holds one character, strings go between `\"`",
+            );
+        }
+
+        self.buffer.next();
+
+        let span = self.span(start, line, column);
+        Ok(self.valued(TokenKind::Char, &c.to_string(), span))
+    }
+
+    // the character an escape in a string stands for, from its `\` up to
+    // its last character.
+    fn escape(&mut self) -> Result<char, LexerError> {
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
+        let column = self.buffer.column;
+
+        match self.escaped() {
+            Ok(c) => Ok(c),
+            Err(message) => LexerError::new(
+                ErrorKind::InvalidEscape,
+                self.through(start, line, column),
+                message,
+            ),
+        }
+    }
+
+    // an escape in a string, which can't be a NUL. C's strings end at one,
+    // so a built program would stop printing there.
+    fn string_escape(&mut self) -> Result<char, LexerError> {
+        let start = self.buffer.offset;
+        let line = self.buffer.line;
+        let column = self.buffer.column;
+
+        match self.escape()? {
+            '\0' => LexerError::new(
+                ErrorKind::InvalidEscape,
+                self.through(start, line, column),
+                "A string can't hold a NUL, only a `Char` can",
+            ),
+            c => Ok(c),
+        }
+    }
+
+    // the same, with what's wrong with it when it isn't one.
+    fn escaped(&mut self) -> Result<char, String> {
+        self.buffer.next();
+
+        if self.buffer.eof || self.buffer.current == '\n' {
+            return Err("Expected an escape after `\\`".to_string());
+        }
+
+        let c = match self.buffer.current {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c @ ('\\' | '"' | '\'' | '{' | '}') => c,
+            // `\x41`, two hex digits up to `7f`.
+            'x' => {
+                let digits = self.hex_digits(2);
+
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && value <= 0x7f => value as char,
+                    _ => {
+                        return Err(format!(
+                            "`\\x{}` isn't an escape, it takes two hex digits up to `7f`",
+                            digits
+                        ))
+                    }
+                }
+            }
+            // `\u{1F600}`, up to six hex digits naming a unicode character.
+            'u' => {
+                if self.buffer.peek() != Some(&'{') {
+                    return Err("Expected a `{` after `\\u`".to_string());
+                }
+
+                self.buffer.next();
+                let digits = self.hex_digits(6);
+
+                if self.buffer.peek() != Some(&'}') {
+                    return Err(format!("Expected a `}}` after `\\u{{{}`", digits));
+                }
+
+                self.buffer.next();
+
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(c) => c,
+                    None => return Err(format!("`\\u{{{}}}` isn't a unicode character", digits)),
+                }
+            }
+            other => return Err(format!("Unknown escape `\\{}`", other)),
+        };
+
+        Ok(c)
+    }
+
+    // up to `n` hex digits after the current character.
+    fn hex_digits(&mut self, n: usize) -> String {
+        let mut digits = String::new();
+
+        while digits.len() < n && self.buffer.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            digits.push(self.buffer.next().unwrap());
+        }
+
+        digits
+    }
+
+    // the `{expr}` of an interpolated string, lexed like any other code up to
+    // the brace that closes it.
+    fn interpolation(
+        &mut self,
+        (start, line, column): (usize, usize, usize),
+    ) -> Result<(), LexerError> {
+        if self.interpolations == MAX_INTERPOLATIONS {
+            return LexerError::new(
+                ErrorKind::NestedTooDeeply,
+                self.span(start, line, column),
+                format!(
+                    "Strings interpolated more than {} levels deep",
+                    MAX_INTERPOLATIONS
+                ),
+            );
+        }
+
+        self.interpolations += 1;
+        let result = self.interpolated((start, line, column));
+        self.interpolations -= 1;
+
+        result
+    }
+
+    fn interpolated(
+        &mut self,
+        (start, line, column): (usize, usize, usize),
+    ) -> Result<(), LexerError> {
+        let mut depth = 0;
+
+        loop {
+            if self.buffer.eof || self.buffer.current == '\n' {
+                return LexerError::new(
+                    ErrorKind::UnterminatedString,
+                    self.span(start, line, column),
+                    "Unterminated string literal",
+                );
+            }
+
+            match self.buffer.current {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => (),
+            }
+
+            self.token()?;
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_character(&mut self) -> Result<Token, LexerError> {
+        let start = self.buffer.offset;
         let line = self.buffer.line;
         let column = self.buffer.column;
 
@@ -141,49 +763,94 @@ impl<'a> Lexer<'a> {
                 ')' => TokenKind::PareR,
                 '[' => TokenKind::BracketL,
                 ']' => TokenKind::BracketR,
-                '+' => TokenKind::Add,
-                '-' => TokenKind::Sub,
-                '/' => TokenKind::Div,
-                '*' => TokenKind::Multi,
-                '^' => TokenKind::Expo,
-                '@' => TokenKind::At,
+                '+' | '-' | '*' | '/' | '%' => {
+                    let c = self.buffer.current;
 
-                '=' => {
                     if let Some('=') = self.buffer.peek() {
                         self.buffer.next();
 
-                        TokenKind::BinaryEq
+                        match c {
+                            '+' => TokenKind::AddAssign,
+                            '-' => TokenKind::SubAssign,
+                            '*' => TokenKind::MultiAssign,
+                            '/' => TokenKind::DivAssign,
+                            _ => TokenKind::ModAssign,
+                        }
+                    } else if c == '*' && self.buffer.peek() == Some(&'*') {
+                        self.buffer.next();
+
+                        TokenKind::Expo
                     } else {
-                        TokenKind::Equals
+                        match c {
+                            '+' => TokenKind::Add,
+                            '-' => TokenKind::Sub,
+                            '*' => TokenKind::Multi,
+                            '/' => TokenKind::Div,
+                            _ => TokenKind::Mod,
+                        }
                     }
                 }
-                '!' => {
-                    if let Some('=') = self.buffer.peek() {
-                        self.buffer.next();
+                '^' => TokenKind::BitXor,
+                '~' => TokenKind::BitNot,
+                '@' => TokenKind::At,
 
+                '=' => match self.buffer.peek() {
+                    Some('=') => {
+                        self.buffer.next();
+                        TokenKind::BinaryEq
+                    }
+                    Some('>') => {
+                        self.buffer.next();
+                        TokenKind::FatArrow
+                    }
+                    _ => TokenKind::Equals,
+                },
+                '!' => match self.buffer.peek() {
+                    Some('=') => {
+                        self.buffer.next();
                         TokenKind::BinaryNeq
-                    } else {
-                        panic!("Unexpected character `!` found while parsing.");
                     }
-                }
-                '<' => {
-                    if let Some('=') = self.buffer.peek() {
+                    _ => TokenKind::Not,
+                },
+                '&' | '|' => {
+                    let c = self.buffer.current;
+
+                    if self.buffer.peek() == Some(&c) {
                         self.buffer.next();
 
-                        TokenKind::BinaryLte
+                        if c == '&' {
+                            TokenKind::And
+                        } else {
+                            TokenKind::Or
+                        }
+                    } else if c == '&' {
+                        TokenKind::BitAnd
                     } else {
-                        TokenKind::BinaryLt
+                        TokenKind::BitOr
                     }
                 }
-                '>' => {
-                    if let Some('=') = self.buffer.peek() {
+                '<' => match self.buffer.peek() {
+                    Some('=') => {
+                        self.buffer.next();
+                        TokenKind::BinaryLte
+                    }
+                    Some('<') => {
+                        self.buffer.next();
+                        TokenKind::Shl
+                    }
+                    _ => TokenKind::BinaryLt,
+                },
+                '>' => match self.buffer.peek() {
+                    Some('=') => {
                         self.buffer.next();
-
                         TokenKind::BinaryGte
-                    } else {
-                        TokenKind::BinaryGt
                     }
-                }
+                    Some('>') => {
+                        self.buffer.next();
+                        TokenKind::Shr
+                    }
+                    _ => TokenKind::BinaryGt,
+                },
 
                 '.' => {
                     if let Some('.') = self.buffer.peek() {
@@ -193,50 +860,83 @@ impl<'a> Lexer<'a> {
                             self.buffer.next();
                             self.buffer.next();
 
-                            return Token::new(TokenKind::Ellipsis, None, line, column);
+                            let span = self.span(start, line, column);
+                            return Ok(Token::new(TokenKind::Ellipsis, None, span));
                         } else {
                             self.buffer.next();
 
-                            return Token::new(TokenKind::DotDot, None, line, column);
+                            let span = self.span(start, line, column);
+                            return Ok(Token::new(TokenKind::DotDot, None, span));
                         }
                     }
 
                     TokenKind::Dot
                 }
-                _ => panic!(
-                    "Unexpected character `{}` found while parsing.",
-                    self.buffer.current
-                ),
+                other => {
+                    return LexerError::new(
+                        ErrorKind::UnexpectedCharacter,
+                        self.through(start, line, column),
+                        format!("Unexpected character `{}`", other),
+                    )
+                }
             }
         };
 
-        let token = Token::new(kind, None, line, column);
         self.buffer.next();
 
-        token
+        Ok(Token::new(kind, None, self.span(start, line, column)))
     }
 
-    pub fn lex(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
+    // every token, for when they're all needed at once. a `Lexer` is also an
+    // iterator, which lexes each token as it's asked for.
+    pub fn lex(&mut self) -> Result<Vec<Token>, LexerError> {
+        self.collect()
+    }
 
-        while !self.buffer.eof {
-            let curr = self.buffer.current;
+    // lexes whatever starts at the current character onto `pending`.
+    fn token(&mut self) -> Result<(), LexerError> {
+        let curr = self.buffer.current;
 
-            let token = match curr {
-                'a'..='z' | 'A'..='Z' | '_' => self.ident_or_keyword(),
-                '0'..='9' => self.number(),
-                '\'' | '"' => self.string(curr),
-                c if c.is_whitespace() => {
-                    self.buffer.next();
-                    continue;
-                }
-                _ => self.parse_character(),
-            };
+        let token = match curr {
+            'r' if self.rest().starts_with("r\"") => return self.raw_string(),
+            c if starts_identifier(c) => self.ident_or_keyword(),
+            '0'..='9' => self.number()?,
+            '"' if self.rest().starts_with("\"\"\"") => {
+                let start = (self.buffer.offset, self.buffer.line, self.buffer.column);
+                return self.text_block(start, false);
+            }
+            '"' => return self.string(curr),
+            '\'' => self.character()?,
+            c if c.is_whitespace() => {
+                self.buffer.next();
+                return Ok(());
+            }
+            _ => self.parse_character()?,
+        };
+
+        self.pending.push_back(token);
+        Ok(())
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.buffer.eof || self.failed {
+                return None;
+            }
 
-            tokens.push(token);
+            if let Err(err) = self.token() {
+                self.failed = true;
+                self.pending.clear();
+
+                return Some(Err(err));
+            }
         }
 
-        tokens
+        self.pending.pop_front().map(Ok)
     }
 }
 
@@ -253,22 +953,35 @@ mod tests {
             }
         "#,
         )
-        .lex();
+        .lex()
+        .unwrap();
 
         assert_eq!(
             lexed,
             vec![
-                Token::new(TokenKind::Func, None, 2, 13),
-                Token::new(TokenKind::Identifier, Some("main".to_string()), 2, 18),
-                Token::new(TokenKind::PareL, None, 2, 22),
-                Token::new(TokenKind::PareR, None, 2, 23),
-                Token::new(TokenKind::BraceL, None, 2, 25),
-                Token::new(TokenKind::Identifier, Some("print".to_string()), 3, 17),
-                Token::new(TokenKind::PareL, None, 3, 22),
-                Token::new(TokenKind::String, Some("Hello, World!".to_string()), 3, 23),
-                Token::new(TokenKind::PareR, None, 3, 38),
-                Token::new(TokenKind::Semi, None, 3, 39),
-                Token::new(TokenKind::BraceR, None, 4, 13),
+                Token::new(TokenKind::Func, None, Span::new(13, 17, 2, 13)),
+                Token::new(
+                    TokenKind::Identifier,
+                    Some("main".to_string()),
+                    Span::new(18, 22, 2, 18)
+                ),
+                Token::new(TokenKind::PareL, None, Span::new(22, 23, 2, 22)),
+                Token::new(TokenKind::PareR, None, Span::new(23, 24, 2, 23)),
+                Token::new(TokenKind::BraceL, None, Span::new(25, 26, 2, 25)),
+                Token::new(
+                    TokenKind::Identifier,
+                    Some("print".to_string()),
+                    Span::new(43, 48, 3, 17)
+                ),
+                Token::new(TokenKind::PareL, None, Span::new(48, 49, 3, 22)),
+                Token::new(
+                    TokenKind::String,
+                    Some("Hello, World!".to_string()),
+                    Span::new(49, 64, 3, 23)
+                ),
+                Token::new(TokenKind::PareR, None, Span::new(64, 65, 3, 38)),
+                Token::new(TokenKind::Semi, None, Span::new(65, 66, 3, 39)),
+                Token::new(TokenKind::BraceR, None, Span::new(79, 80, 4, 13)),
             ]
         )
     }
@@ -280,16 +993,25 @@ mod tests {
             let x = "Hello, World!";
         "#,
         )
-        .lex();
+        .lex()
+        .unwrap();
 
         assert_eq!(
             lexed,
             vec![
-                Token::new(TokenKind::Let, None, 2, 13),
-                Token::new(TokenKind::Identifier, Some("x".to_string()), 2, 17),
-                Token::new(TokenKind::Equals, None, 2, 19),
-                Token::new(TokenKind::String, Some("Hello, World!".to_string()), 2, 21),
-                Token::new(TokenKind::Semi, None, 2, 36),
+                Token::new(TokenKind::Let, None, Span::new(13, 16, 2, 13)),
+                Token::new(
+                    TokenKind::Identifier,
+                    Some("x".to_string()),
+                    Span::new(17, 18, 2, 17)
+                ),
+                Token::new(TokenKind::Equals, None, Span::new(19, 20, 2, 19)),
+                Token::new(
+                    TokenKind::String,
+                    Some("Hello, World!".to_string()),
+                    Span::new(21, 36, 2, 21)
+                ),
+                Token::new(TokenKind::Semi, None, Span::new(36, 37, 2, 36)),
             ]
         )
     }
@@ -299,80 +1021,633 @@ mod tests {
         let mut parsed_int = Lexer::new("1_000_000;");
 
         assert_eq!(
-            parsed_int.number(),
-            Token::new(TokenKind::Number, Some("1000000".to_string()), 1, 0)
+            parsed_int.number().unwrap(),
+            Token::new(
+                TokenKind::Number,
+                Some("1000000".to_string()),
+                Span::new(0, 9, 1, 1)
+            )
         );
 
         // check if it consumes things that weren't an integer
         assert_eq!(parsed_int.buffer.current, ';')
     }
 
+    #[test]
+    fn every_punctuation_and_keyword() {
+        use TokenKind as TT;
+
+        let src = "( ) { } [ ] : ; . , = => += -= *= /= %= ** + - * / % @ ... \
+                   == != > >= < <= && || & | ^ ~ << >> .. \
+                   let const mut if else match return func for in import as type true false none";
+        let kinds: Vec<_> = Lexer::new(src)
+            .lex()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TT::PareL,
+                TT::PareR,
+                TT::BraceL,
+                TT::BraceR,
+                TT::BracketL,
+                TT::BracketR,
+                TT::Colon,
+                TT::Semi,
+                TT::Dot,
+                TT::Comma,
+                TT::Equals,
+                TT::FatArrow,
+                TT::AddAssign,
+                TT::SubAssign,
+                TT::MultiAssign,
+                TT::DivAssign,
+                TT::ModAssign,
+                TT::Expo,
+                TT::Add,
+                TT::Sub,
+                TT::Multi,
+                TT::Div,
+                TT::Mod,
+                TT::At,
+                TT::Ellipsis,
+                TT::BinaryEq,
+                TT::BinaryNeq,
+                TT::BinaryGt,
+                TT::BinaryGte,
+                TT::BinaryLt,
+                TT::BinaryLte,
+                TT::And,
+                TT::Or,
+                TT::BitAnd,
+                TT::BitOr,
+                TT::BitXor,
+                TT::BitNot,
+                TT::Shl,
+                TT::Shr,
+                TT::DotDot,
+                TT::Let,
+                TT::Const,
+                TT::Mut,
+                TT::If,
+                TT::Else,
+                TT::Match,
+                TT::Return,
+                TT::Func,
+                TT::For,
+                TT::In,
+                TT::Import,
+                TT::As,
+                TT::Type,
+                TT::True,
+                TT::False,
+                TT::None,
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_identifier() {
+        let parsed = Lexer::new("x").lex().unwrap();
+
+        assert_eq!(
+            parsed,
+            vec![Token::new(
+                TokenKind::Identifier,
+                Some("x".to_string()),
+                Span::new(0, 1, 1, 1)
+            )]
+        )
+    }
+
+    #[test]
+    fn columns_count_characters_from_one() {
+        let tokens = Lexer::new("\"héllo\" x\n\ty").lex().unwrap();
+        let columns: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.span.line, token.span.column))
+            .collect();
+
+        assert_eq!(columns, vec![(1, 1), (1, 9), (2, 2)]);
+    }
+
+    #[test]
+    fn windows_line_endings() {
+        let src = "let x = 1;\r\nlet y = x;\r\n";
+        let tokens = Lexer::new(src).lex().unwrap();
+
+        assert_eq!(tokens.len(), 10);
+        assert_eq!(tokens[5].span, Span::new(12, 15, 2, 1));
+        assert_eq!(tokens[6].value.as_deref(), Some("y"));
+        // the spans still point into the source as it was read.
+        assert_eq!(&src[tokens[8].span.start..tokens[8].span.end], "x");
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let tokens = Lexer::new("\u{feff}let x = 1;").lex().unwrap();
+
+        assert_eq!(
+            tokens[0],
+            Token::new(TokenKind::Let, None, Span::new(3, 6, 1, 1))
+        );
+    }
+
+    #[test]
+    fn shebang() {
+        let tokens = Lexer::new("#!/usr/bin/env uma\nlet x = 1;").lex().unwrap();
+
+        assert_eq!(
+            tokens[0],
+            Token::new(TokenKind::Let, None, Span::new(19, 22, 2, 1))
+        );
+
+        // it's only skipped on the first line.
+        let parsed = Lexer::new("let x = 1;\n#!uma").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnexpectedCharacter);
+    }
+
+    #[test]
+    fn unicode_names() {
+        let tokens = Lexer::new("let café = 名前 + cafe\u{301};").lex().unwrap();
+        let names: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Identifier)
+            .map(|token| (token.text(), token.span.column))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("café".to_string(), 5),
+                ("名前".to_string(), 12),
+                ("cafe\u{301}".to_string(), 17)
+            ]
+        );
+
+        let parsed = Lexer::new("let x = ½;").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnexpectedCharacter);
+    }
+
+    #[test]
+    fn spans() {
+        let parsed = Lexer::new("let x = 'é';").lex().unwrap();
+
+        assert_eq!(
+            parsed.iter().map(|token| token.span).collect::<Vec<_>>(),
+            vec![
+                Span::new(0, 3, 1, 1),
+                Span::new(4, 5, 1, 5),
+                Span::new(6, 7, 1, 7),
+                Span::new(8, 12, 1, 9),
+                Span::new(12, 13, 1, 12),
+            ]
+        )
+    }
+
     #[test]
     fn float_parsing() {
-        let parsed_float = Lexer::new("3.14156").number();
+        let parsed_float = Lexer::new("3.14156").number().unwrap();
 
         assert_eq!(
             parsed_float,
-            Token::new(TokenKind::Float, Some("3.14156".to_string()), 1, 0)
+            Token::new(
+                TokenKind::Float,
+                Some("3.14156".to_string()),
+                Span::new(0, 7, 1, 1)
+            )
         )
     }
 
     #[test]
-    fn string_parsing() {
-        let parsed = Lexer::new(r#"'Hello\n\\n,\'"" World!!'"#).string('\'');
+    fn multiple_decimal_points() {
+        let parsed = Lexer::new("1.2.3").lex();
+
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::InvalidNumber)
+    }
+
+    #[test]
+    fn scientific_notation() {
+        let tokens = Lexer::new("1e9 6.02E23 2.5e-3 1e+2 0..2").lex().unwrap();
+        let numbers: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.kind.clone(), token.value.as_deref()))
+            .collect();
+
+        assert_eq!(
+            numbers,
+            vec![
+                (TokenKind::Float, Some("1e9")),
+                (TokenKind::Float, Some("6.02E23")),
+                (TokenKind::Float, Some("2.5e-3")),
+                (TokenKind::Float, Some("1e+2")),
+                (TokenKind::Number, Some("0")),
+                (TokenKind::DotDot, None),
+                (TokenKind::Number, Some("2")),
+            ]
+        );
+
+        let parsed = Lexer::new("1e-x").lex();
+        assert_eq!(
+            parsed.err().unwrap().message,
+            "Expected digits after the exponent"
+        );
+    }
+
+    #[test]
+    fn number_suffixes() {
+        let tokens = Lexer::new("100u64 1.5f 1f32 2e3f64").lex().unwrap();
+        let numbers: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.kind.clone(), token.value.as_deref()))
+            .collect();
+
+        assert_eq!(
+            numbers,
+            vec![
+                (TokenKind::Number, Some("100u64")),
+                (TokenKind::Float, Some("1.5f")),
+                (TokenKind::Float, Some("1f32")),
+                (TokenKind::Float, Some("2e3f64")),
+            ]
+        );
+
+        let parsed = Lexer::new("let x = 3px;").lex();
+        let err = parsed.err().unwrap();
+        assert_eq!(err.message, "Unknown suffix `px` on a number");
+        assert_eq!(err.span, Span::new(9, 11, 1, 10));
+
+        let parsed = Lexer::new("1.5u8").lex();
+        assert_eq!(
+            parsed.err().unwrap().message,
+            "`u8` is for integers, `1.5` is a float"
+        );
+    }
+
+    #[test]
+    fn unexpected_character() {
+        let parsed = Lexer::new("let x = $;").lex();
 
         assert_eq!(
             parsed,
-            Token::new(
+            LexerError::new(
+                ErrorKind::UnexpectedCharacter,
+                Span::new(8, 9, 1, 9),
+                "Unexpected character `$`"
+            )
+        )
+    }
+
+    #[test]
+    fn string_parsing() {
+        let mut lexer = Lexer::new(r#"'Hello\n\\n,\'"" World!!'"#);
+        lexer.string('\'').unwrap();
+
+        assert_eq!(
+            Vec::from(lexer.pending),
+            vec![Token::new(
                 TokenKind::String,
                 Some("Hello\n\\n,\'\"\" World!!".to_string()),
-                1,
-                0
-            )
+                Span::new(0, 25, 1, 1)
+            )]
         )
     }
 
+    #[test]
+    fn escapes() {
+        let tokens = Lexer::new(r#""\r\"\'\x41\u{1F600}\u{e9}\{\}" '\0'"#)
+            .lex()
+            .unwrap();
+        assert_eq!(tokens[0].text(), "\r\"'A😀é{}");
+        assert_eq!(tokens[1].text(), "\0");
+
+        for src in [
+            r#""\q""#,
+            r#""\x4""#,
+            r#""\x80""#,
+            r#""\u41""#,
+            r#""\u{41""#,
+            r#""\u{}""#,
+            r#""\u{d800}""#,
+            r#""\u{1234567}""#,
+            r#""\0""#,
+            r#""\x00""#,
+            r#""\u{0}""#,
+            "\"\"\"\n    \\0\n    \"\"\"",
+            "\"\\",
+        ] {
+            let parsed = Lexer::new(src).lex();
+            assert_eq!(
+                parsed.err().unwrap().kind,
+                ErrorKind::InvalidEscape,
+                "{}",
+                src
+            );
+        }
+
+        let parsed = Lexer::new(r#"let s = "ok \q";"#).lex();
+        assert_eq!(
+            parsed,
+            LexerError::new(
+                ErrorKind::InvalidEscape,
+                Span::new(12, 14, 1, 13),
+                "Unknown escape `\\q`"
+            )
+        );
+
+        let parsed = Lexer::new(r#"let s = "a\0b";"#).lex();
+        assert_eq!(
+            parsed,
+            LexerError::new(
+                ErrorKind::InvalidEscape,
+                Span::new(10, 12, 1, 11),
+                "A string can't hold a NUL, only a `Char` can"
+            )
+        );
+    }
+
+    #[test]
+    fn raw_strings() {
+        let tokens = Lexer::new(r#"r"C:\path\{x}" r"#).lex().unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(
+                    TokenKind::String,
+                    Some(r"C:\path\{x}".to_string()),
+                    Span::new(0, 14, 1, 1)
+                ),
+                Token::new(
+                    TokenKind::Identifier,
+                    Some("r".to_string()),
+                    Span::new(15, 16, 1, 16)
+                ),
+            ]
+        );
+
+        let parsed = Lexer::new("r\"open\n\"").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnterminatedString);
+    }
+
+    #[test]
+    fn text_blocks() {
+        let src = "let s = \"\"\"\n    Dear {name},\n      \\tmore\n\n    bye\n    \"\"\";";
+        let tokens = Lexer::new(src).lex().unwrap();
+
+        assert_eq!(tokens[3].text(), "Dear {name},\n  \tmore\n\nbye");
+        assert_eq!(tokens[3].span, Span::new(8, src.len() - 1, 1, 9));
+        assert_eq!(tokens[4].kind, TokenKind::Semi);
+
+        // the closing quotes can be less indented than the text, or on its
+        // last line.
+        let tokens = Lexer::new("\"\"\"\n  a\n    b\n\"\"\"").lex().unwrap();
+        assert_eq!(tokens[0].text(), "  a\n    b");
+
+        let tokens = Lexer::new("r\"\"\"one \\n\n   two\"\"\"").lex().unwrap();
+        assert_eq!(tokens[0].text(), "one \\n\n   two");
+
+        let parsed = Lexer::new("\"\"\"\nnever closed\"\"").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnterminatedString);
+    }
+
+    #[test]
+    fn characters() {
+        let tokens = Lexer::new(r"'a' '\n' '\'' 'é' '\u{1F600}'").lex().unwrap();
+        let chars: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.kind.clone(), token.text()))
+            .collect();
+
+        assert_eq!(
+            chars,
+            ["a", "\n", "'", "é", "😀"]
+                .map(|c| (TokenKind::Char, c.to_string()))
+                .to_vec()
+        );
+        assert_eq!(tokens[1].span, Span::new(4, 8, 1, 5));
+
+        for (src, kind) in [
+            ("''", ErrorKind::InvalidChar),
+            ("'ab'", ErrorKind::InvalidChar),
+            ("'e\u{301}'", ErrorKind::InvalidChar),
+            ("'a", ErrorKind::UnterminatedString),
+            ("'ab\n'", ErrorKind::UnterminatedString),
+            ("'\\q'", ErrorKind::InvalidEscape),
+        ] {
+            assert_eq!(Lexer::new(src).lex().err().unwrap().kind, kind, "{}", src);
+        }
+    }
+
+    #[test]
+    fn tokens_are_lexed_as_theyre_asked_for() {
+        let mut lexer = Lexer::new("let s = \"a{b}\"; $");
+
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Let);
+        // nothing past the token that was asked for has been looked at.
+        assert_eq!(lexer.buffer.offset, 3);
+
+        let kinds = lexer
+            .by_ref()
+            .take(8)
+            .map(|token| token.unwrap().kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Identifier,
+                TokenKind::Equals,
+                TokenKind::InterpolationStart,
+                TokenKind::String,
+                TokenKind::BraceL,
+                TokenKind::Identifier,
+                TokenKind::BraceR,
+                TokenKind::InterpolationEnd,
+            ]
+        );
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Semi);
+        assert!(lexer.next().unwrap().is_err());
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn names_are_shared_between_tokens() {
+        let tokens = Lexer::new("let x = x + \"x\";").lex().unwrap();
+
+        let (first, second) = (tokens[1].value.clone(), tokens[3].value.clone());
+        assert!(Rc::ptr_eq(&first.unwrap(), &second.unwrap()));
+        assert_eq!(tokens[5].value.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let parsed = Lexer::new("let x = \"Hello").lex();
+
+        assert_eq!(
+            parsed,
+            LexerError::new(
+                ErrorKind::UnterminatedString,
+                Span::new(8, 14, 1, 9),
+                "Unterminated string literal"
+            )
+        );
+
+        let parsed = Lexer::new("let x = 'Hello\n';").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnterminatedString);
+
+        let parsed = Lexer::new("\"").lex();
+        assert_eq!(parsed.err().unwrap().kind, ErrorKind::UnterminatedString);
+    }
+
     #[test]
     fn binary_ops_parsing() {
-        let parsed = Lexer::new("<><=>===!=").lex();
+        let parsed = Lexer::new("<><=>===!=&&||").lex().unwrap();
 
         assert_eq!(
             parsed,
             vec![
-                Token::new(TokenKind::BinaryLt, None, 1, 0),
-                Token::new(TokenKind::BinaryGt, None, 1, 1),
-                Token::new(TokenKind::BinaryLte, None, 1, 2),
-                Token::new(TokenKind::BinaryGte, None, 1, 4),
-                Token::new(TokenKind::BinaryEq, None, 1, 6),
-                Token::new(TokenKind::BinaryNeq, None, 1, 8),
+                Token::new(TokenKind::BinaryLt, None, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::BinaryGt, None, Span::new(1, 2, 1, 2)),
+                Token::new(TokenKind::BinaryLte, None, Span::new(2, 4, 1, 3)),
+                Token::new(TokenKind::BinaryGte, None, Span::new(4, 6, 1, 5)),
+                Token::new(TokenKind::BinaryEq, None, Span::new(6, 8, 1, 7)),
+                Token::new(TokenKind::BinaryNeq, None, Span::new(8, 10, 1, 9)),
+                Token::new(TokenKind::And, None, Span::new(10, 12, 1, 11)),
+                Token::new(TokenKind::Or, None, Span::new(12, 14, 1, 13)),
             ]
-        )
+        );
+
+        assert_eq!(
+            Lexer::new("%").lex().unwrap(),
+            vec![Token::new(TokenKind::Mod, None, Span::new(0, 1, 1, 1))]
+        );
+
+        assert_eq!(
+            Lexer::new("!!=").lex().unwrap(),
+            vec![
+                Token::new(TokenKind::Not, None, Span::new(0, 1, 1, 1)),
+                Token::new(TokenKind::BinaryNeq, None, Span::new(1, 3, 1, 2)),
+            ]
+        );
+
+        let kinds = Lexer::new("& | ^ ~ << >> ** *= <<= >>= => ==>")
+            .lex()
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::BitAnd,
+                TokenKind::BitOr,
+                TokenKind::BitXor,
+                TokenKind::BitNot,
+                TokenKind::Shl,
+                TokenKind::Shr,
+                TokenKind::Expo,
+                TokenKind::MultiAssign,
+                TokenKind::Shl,
+                TokenKind::Equals,
+                TokenKind::Shr,
+                TokenKind::Equals,
+                TokenKind::FatArrow,
+                TokenKind::BinaryEq,
+                TokenKind::BinaryGt,
+            ]
+        );
     }
 
     #[test]
     fn inline_range() {
-        let parsed = Lexer::new("10..100").lex();
+        let parsed = Lexer::new("10..100").lex().unwrap();
 
         assert_eq!(
             parsed,
             vec![
-                Token::new(TokenKind::Number, Some(String::from("10")), 1, 0),
-                Token::new(TokenKind::DotDot, None, 1, 2),
-                Token::new(TokenKind::Number, Some(String::from("100")), 1, 4),
+                Token::new(
+                    TokenKind::Number,
+                    Some(String::from("10")),
+                    Span::new(0, 2, 1, 1)
+                ),
+                Token::new(TokenKind::DotDot, None, Span::new(2, 4, 1, 3)),
+                Token::new(
+                    TokenKind::Number,
+                    Some(String::from("100")),
+                    Span::new(4, 7, 1, 5)
+                ),
             ]
         )
     }
 
+    #[test]
+    fn interpolation() {
+        let kinds = |src: &str| {
+            Lexer::new(src)
+                .lex()
+                .unwrap()
+                .into_iter()
+                .map(|token| (token.kind, token.value.as_deref().map(String::from)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds(r#""hi {name}, {"{n + 1}"}!""#),
+            vec![
+                (TokenKind::InterpolationStart, None),
+                (TokenKind::String, Some(String::from("hi "))),
+                (TokenKind::BraceL, None),
+                (TokenKind::Identifier, Some(String::from("name"))),
+                (TokenKind::BraceR, None),
+                (TokenKind::String, Some(String::from(", "))),
+                (TokenKind::BraceL, None),
+                (TokenKind::InterpolationStart, None),
+                (TokenKind::BraceL, None),
+                (TokenKind::Identifier, Some(String::from("n"))),
+                (TokenKind::Add, None),
+                (TokenKind::Number, Some(String::from("1"))),
+                (TokenKind::BraceR, None),
+                (TokenKind::InterpolationEnd, None),
+                (TokenKind::BraceR, None),
+                (TokenKind::String, Some(String::from("!"))),
+                (TokenKind::InterpolationEnd, None),
+            ]
+        );
+
+        assert_eq!(
+            kinds(r#""\{not} this}""#),
+            vec![(TokenKind::String, Some(String::from("{not} this}")))]
+        );
+
+        assert_eq!(
+            Lexer::new(r#""{x"#).lex().unwrap_err().kind,
+            ErrorKind::UnterminatedString
+        );
+
+        let deep = MAX_INTERPOLATIONS + 1;
+        let src = format!("{}x{}", "\"{".repeat(deep), "}\"".repeat(deep));
+        assert_eq!(
+            Lexer::new(&src).lex().unwrap_err().kind,
+            ErrorKind::NestedTooDeeply
+        );
+    }
+
     #[test]
     fn test_buffer() {
         let mut buffer = Buffer::new("Lot");
 
-        assert_eq!(buffer.eof, false);
+        assert!(!buffer.eof);
         assert_eq!(buffer.current, 'L');
         assert_eq!(buffer.next(), Some('o'));
         assert_eq!(buffer.next(), Some('t'));
         assert_eq!(buffer.current, 't');
         assert_eq!(buffer.next(), None);
-        assert_eq!(buffer.eof, true)
+        assert!(buffer.eof)
     }
 }
@@ -1,7 +1,15 @@
+use std::rc::Rc;
+
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     Identifier,
     String,
+    Char,
+    // around the parts of a string with `{}` in it, which come as `String`
+    // tokens for the text and the tokens of each expression between braces.
+    InterpolationStart,
+    InterpolationEnd,
     Number,
     Float,
     None,
@@ -20,11 +28,19 @@ pub enum TokenKind {
     Comma,
 
     Equals,
+    FatArrow, // =>
+    // `+=` and the like.
+    AddAssign,
+    SubAssign,
+    MultiAssign,
+    DivAssign,
+    ModAssign,
     Expo,
     Add,
     Sub,
     Multi,
     Div,
+    Mod,
     At,
     Ellipsis,
 
@@ -34,49 +50,126 @@ pub enum TokenKind {
     BinaryGte,
     BinaryLt,
     BinaryLte,
+    And,
+    Or,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    // `!`, the logical not.
+    Not,
+    Shl,
+    Shr,
 
     DotDot,
 
     Let,
+    Const,
     Mut,
     If,
     Else,
+    Match,
     Return,
     Func,
     For,
     In,
+    Import,
+    As,
+    Type,
+}
+
+// `start` and `end` are byte offsets into the source, `line` and `column`
+// are where `start` is, both counted from 1 and in characters. `file` tells
+// apart the sources of a program split over several files, the first one
+// is 0.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub file: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+            file: 0,
+        }
+    }
+
+    pub fn in_file(self, file: usize) -> Span {
+        Span { file, ..self }
+    }
+
+    // covers both spans, positioned at whichever one comes first.
+    pub fn to(self, other: Span) -> Span {
+        let (first, last) = if self.start <= other.start {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        Span {
+            end: first.end.max(last.end),
+            ..first
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token {
     pub kind: TokenKind,
-    pub value: Option<String>,
-    pub line: usize,
-    pub column: usize,
+    // the text of a name or a literal, which the lexer shares between the
+    // tokens that have the same.
+    pub value: Option<Rc<str>>,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, value: Option<String>, line: usize, column: usize) -> Token {
+    pub fn new(kind: TokenKind, value: Option<String>, span: Span) -> Token {
         Token {
             kind,
-            value,
-            line,
-            column,
+            value: value.map(Rc::from),
+            span,
         }
     }
 
+    // the text of a name or a literal, or nothing for any other token.
+    pub fn text(&self) -> String {
+        self.value.as_deref().unwrap_or_default().to_string()
+    }
+
     pub fn repr(&self) -> String {
         match &self.kind {
+            TokenKind::Expo => String::from("**"),
             TokenKind::Add => String::from("+"),
             TokenKind::Sub => String::from("-"),
             TokenKind::Multi => String::from("*"),
             TokenKind::Div => String::from("/"),
+            TokenKind::Mod => String::from("%"),
             TokenKind::BinaryEq => String::from("=="),
             TokenKind::BinaryNeq => String::from("!="),
             TokenKind::BinaryGt => String::from(">"),
             TokenKind::BinaryGte => String::from(">="),
             TokenKind::BinaryLt => String::from("<"),
             TokenKind::BinaryLte => String::from("<="),
+            TokenKind::And => String::from("&&"),
+            TokenKind::Or => String::from("||"),
+            TokenKind::BitAnd => String::from("&"),
+            TokenKind::BitOr => String::from("|"),
+            TokenKind::BitXor => String::from("^"),
+            TokenKind::BitNot => String::from("~"),
+            TokenKind::Not => String::from("!"),
+            TokenKind::Shl => String::from("<<"),
+            TokenKind::Shr => String::from(">>"),
             kind => panic!("no repr found for token: {:?}", kind),
         }
     }
@@ -87,16 +180,36 @@ impl TokenKind {
         use TokenKind as TT;
 
         match *self {
-            TT::Expo => 3,
-            TT::Div | TT::Multi => 2,
-            TT::Add | TT::Sub => 1,
-            TT::BinaryEq
-            | TT::BinaryNeq
-            | TT::BinaryGt
-            | TT::BinaryGte
-            | TT::BinaryLt
-            | TT::BinaryLte => 0,
+            // the same order as in C, with `**` above everything.
+            TT::Expo => 10,
+            TT::Div | TT::Multi | TT::Mod => 9,
+            TT::Add | TT::Sub => 8,
+            TT::Shl | TT::Shr => 7,
+            TT::BinaryGt | TT::BinaryGte | TT::BinaryLt | TT::BinaryLte => 6,
+            TT::BinaryEq | TT::BinaryNeq => 5,
+            TT::BitAnd => 4,
+            TT::BitXor => 3,
+            TT::BitOr => 2,
+            TT::And => 1,
+            TT::Or => 0,
             _ => -1,
         }
     }
+
+    // `2 ** 3 ** 2` is `2 ** (3 ** 2)`, every other operator groups to the left.
+    pub fn is_right_associative(&self) -> bool {
+        *self == TokenKind::Expo
+    }
+
+    // the operator a compound assignment applies, `+` for `+=`.
+    pub fn compound(&self) -> Option<TokenKind> {
+        match self {
+            TokenKind::AddAssign => Some(TokenKind::Add),
+            TokenKind::SubAssign => Some(TokenKind::Sub),
+            TokenKind::MultiAssign => Some(TokenKind::Multi),
+            TokenKind::DivAssign => Some(TokenKind::Div),
+            TokenKind::ModAssign => Some(TokenKind::Mod),
+            _ => None,
+        }
+    }
 }
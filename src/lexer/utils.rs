@@ -1,5 +1,38 @@
 use std::{iter::Peekable, str::Chars};
 
+use super::Span;
+
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+    UnexpectedCharacter,
+    InvalidNumber,
+    UnterminatedString,
+    InvalidEscape,
+    InvalidChar,
+    NestedTooDeeply,
+}
+
+// `span` runs from where the token with the error started up to where the
+// lexer found it.
+#[derive(Debug, PartialEq)]
+pub struct LexerError {
+    pub kind: ErrorKind,
+    pub span: Span,
+    pub message: String,
+}
+
+impl LexerError {
+    pub fn new<T>(kind: ErrorKind, span: Span, message: impl ToString) -> Result<T, LexerError> {
+        Err(LexerError {
+            kind,
+            span,
+            message: message.to_string(),
+        })
+    }
+}
+
+const BOM: char = '\u{feff}';
+
 #[derive(Debug)]
 pub struct Buffer<'a> {
     pub data: Peekable<Chars<'a>>,
@@ -7,24 +40,39 @@ pub struct Buffer<'a> {
     pub current: char,
     pub line: usize,
     pub column: usize,
+    pub offset: usize,
 }
 
 impl<'a> Buffer<'a> {
     pub fn new(raw: &'a str) -> Buffer<'a> {
-        let mut data = raw.chars().peekable();
-        let current = data.next().unwrap_or('\0');
+        // a byte order mark isn't part of the source, the offsets still count
+        // it so they point into `raw`.
+        let mut offset = match raw.starts_with(BOM) {
+            true => BOM.len_utf8(),
+            false => 0,
+        };
+
+        let mut data = raw[offset..].chars().peekable();
+        let current = read(&mut data, &mut offset).unwrap_or('\0');
 
         Buffer {
             data,
             eof: current == '\0',
             current,
             line: 1 + (current == '\n') as usize,
-            column: 0,
+            // columns count characters from 1, a newline is the 0th of the
+            // line it starts.
+            column: (current != '\n') as usize,
+            offset,
         }
     }
 
     pub fn next(&mut self) -> Option<char> {
-        let c = match self.data.next() {
+        if !self.eof {
+            self.offset += self.current.len_utf8();
+        }
+
+        let c = match read(&mut self.data, &mut self.offset) {
             None => {
                 self.eof = true;
                 return None;
@@ -48,3 +96,15 @@ impl<'a> Buffer<'a> {
         self.data.peek()
     }
 }
+
+// the next character of `data`, reading a `\r\n` as the `\n` alone. `offset`
+// is moved past the `\r` that's skipped.
+fn read(data: &mut Peekable<Chars>, offset: &mut usize) -> Option<char> {
+    match data.next()? {
+        '\r' if data.peek() == Some(&'\n') => {
+            *offset += 1;
+            data.next()
+        }
+        c => Some(c),
+    }
+}
@@ -0,0 +1,190 @@
+pub mod cfg;
+pub mod codegen;
+pub mod diagnostics;
+pub mod formatter;
+pub mod interpreter;
+pub mod ir;
+pub mod lexer;
+pub mod lints;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod parser;
+pub mod timings;
+pub mod typeck;
+
+pub mod colors;
+
+use std::path::PathBuf;
+
+pub use crate::cfg::Cfg;
+#[cfg(feature = "jit")]
+pub use crate::codegen::Jit;
+pub use crate::codegen::{
+    Artifact, Backend, CBackend, Codegen, CodegenBackend, CodegenError, CodegenOptions,
+    CompilerOptions, Library, WasmBackend,
+};
+pub use crate::diagnostics::{Diagnostic, Severity};
+pub use crate::lexer::{Lexer, Span, Token, TokenKind};
+pub use crate::parser::{Expr, Parser, Stmt};
+pub use crate::timings::Timings;
+pub use crate::typeck::{TypeChecker, TypedStmt};
+
+#[derive(Debug)]
+pub struct CompileError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl From<Diagnostic> for CompileError {
+    fn from(diagnostic: Diagnostic) -> CompileError {
+        CompileError {
+            diagnostics: vec![diagnostic],
+        }
+    }
+}
+
+impl From<Vec<Diagnostic>> for CompileError {
+    fn from(diagnostics: Vec<Diagnostic>) -> CompileError {
+        CompileError { diagnostics }
+    }
+}
+
+pub struct CompileOptions {
+    pub output: PathBuf,
+    pub backend: CodegenBackend,
+    pub deny_warnings: bool,
+}
+
+impl CompileOptions {
+    pub fn new(output: impl Into<PathBuf>) -> CompileOptions {
+        CompileOptions {
+            output: output.into(),
+            backend: CodegenBackend::default(),
+            deny_warnings: false,
+        }
+    }
+}
+
+pub fn parse_str(source: &str) -> Result<Vec<Stmt>, CompileError> {
+    parse(&mut Parser::from_lexer(Lexer::new(source)))
+}
+
+// `parse_str` for input that can't be trusted, like a fuzzer's. whatever the
+// input, the result is its statements or what's wrong with it, never a panic.
+pub fn parse_source(source: &str) -> Result<Vec<Stmt>, Vec<Diagnostic>> {
+    parse_str(source).map_err(|err| err.diagnostics)
+}
+
+// the statements `parser` parses, or its errors. when the lexer gives up, its
+// error is the only one, since the rest come from the input stopping short.
+fn parse(parser: &mut Parser) -> Result<Vec<Stmt>, CompileError> {
+    let result = parser.parse();
+
+    if let Some(err) = parser.lexer_error() {
+        return Err(Diagnostic::from(err).into());
+    }
+
+    result.map_err(|errors| {
+        errors
+            .into_iter()
+            .map(Diagnostic::from)
+            .collect::<Vec<_>>()
+            .into()
+    })
+}
+
+// parses `source` and prints it back out in the canonical style.
+pub fn format_str(source: &str) -> Result<String, CompileError> {
+    let mut parser = Parser::from_lexer(Lexer::new(source));
+    let ast = parse(&mut parser)?;
+
+    Ok(formatter::Formatter::new(source, parser.take_layout()).format(&ast))
+}
+
+// parses and type-checks `source`, returning the typed IR and any warnings.
+// with `deny_warnings`, the warnings are returned as errors instead. its
+// `@cfg`s are for a debug build on this machine.
+pub fn check_str(
+    source: &str,
+    deny_warnings: bool,
+) -> Result<(Vec<TypedStmt>, Vec<Diagnostic>), CompileError> {
+    let ast = cfg::strip(parse_str(source)?, &Cfg::default())?;
+
+    check_program(ast, deny_warnings)
+}
+
+// like `check_str`, for a program that's already been parsed.
+pub fn check_program(
+    ast: Vec<Stmt>,
+    deny_warnings: bool,
+) -> Result<(Vec<TypedStmt>, Vec<Diagnostic>), CompileError> {
+    let (typed, warnings) = TypeChecker::check(ast).map_err(Diagnostic::from)?;
+
+    if deny_warnings && !warnings.is_empty() {
+        return Err(warnings
+            .into_iter()
+            .map(|warning| Diagnostic {
+                severity: Severity::Error,
+                ..warning
+            })
+            .collect::<Vec<_>>()
+            .into());
+    }
+
+    Ok((typed, warnings))
+}
+
+// the C `source` compiles to, without compiling it any further, so it can be
+// looked at where there's no C compiler.
+pub fn generate_c(source: &str) -> Result<String, CompileError> {
+    let (typed, _) = check_str(source, false)?;
+    typeck::check_main(&typed)?;
+
+    Ok(CBackend::generate_source(&typed))
+}
+
+// compiles `source` with `options.backend`, writing to `options.output`.
+pub fn compile_str(source: &str, options: &CompileOptions) -> Result<Artifact, CompileError> {
+    compile_with(source, options.backend.backend().as_mut(), options)
+}
+
+// like `compile_str`, but with a backend from outside this crate.
+pub fn compile_with(
+    source: &str,
+    backend: &mut dyn Backend,
+    options: &CompileOptions,
+) -> Result<Artifact, CompileError> {
+    let (typed, _) = check_str(source, options.deny_warnings)?;
+    typeck::check_main(&typed)?;
+
+    Codegen::generate(backend, &typed, &CodegenOptions::new(&options.output))
+        .map_err(|err| Diagnostic::from(err).into())
+}
+
+// the `@test` functions in `program`, in the order they're declared.
+pub fn test_names(program: &[Stmt]) -> Vec<String> {
+    program
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Function {
+                name, attributes, ..
+            } if attributes.iter().any(|attr| attr.name == "test") => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// the `main` `uma test` builds in place of the program's own. it runs the
+// test numbered by its first argument, a test that fails exits the program.
+pub fn test_harness(tests: &[String]) -> String {
+    let mut source = String::from("func main(): int {\n    let test = parse_int(arg(1));\n");
+
+    for (i, test) in tests.iter().enumerate() {
+        source.push_str(&format!("    if (test == {}) {{ {}(); }}\n", i, test));
+    }
+
+    source.push_str("    return 0;\n}\n");
+    source
+}
+
+#[cfg(test)]
+mod tests;
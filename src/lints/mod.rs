@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+
+use crate::diagnostics::{suggest, Diagnostic, Severity};
+use crate::lexer::{Span, TokenKind};
+use crate::parser::{
+    walk_block, walk_expr, walk_stmt, walk_stmts, Block, Expr, Spanned, Stmt, Visitor,
+};
+
+// every warning that can be allowed or denied: the ones `lint` looks for, then
+// the ones the type checker reports.
+pub const LINTS: &[&str] = &[
+    "NonSnakeCase",
+    "UnusedMut",
+    "EmptyBlock",
+    "UnusedResult",
+    "UnusedVariable",
+    "UnusedFunction",
+    "ShadowedBinding",
+    "UnreachableCode",
+    "UnreachablePattern",
+    "Deprecated",
+];
+
+// what's done about a lint's warnings.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    fn parse(level: &str) -> Option<Level> {
+        match level {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+// the level of each lint set away from `Warn`, from the `[lints]` of a
+// `uma.toml` or `--allow` and `--deny`.
+#[derive(Debug, Default, Clone)]
+pub struct LintConfig {
+    levels: HashMap<String, Level>,
+}
+
+impl LintConfig {
+    pub fn new() -> LintConfig {
+        LintConfig::default()
+    }
+
+    // reads the `[lints]` table of a `uma.toml`, the other tables are left
+    // alone:
+    //
+    //     [lints]
+    //     UnusedMut = "allow"
+    //     NonSnakeCase = "deny"
+    pub fn from_toml(source: &str) -> Result<LintConfig, String> {
+        let mut config = LintConfig::new();
+        let mut table = String::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                table = name.trim().to_string();
+                continue;
+            }
+
+            if table != "lints" {
+                continue;
+            }
+
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+
+            let Some((lint, level)) = line.split_once('=') else {
+                return Err(error(format!(
+                    "Expected `lint = \"level\"`, found `{}`",
+                    line
+                )));
+            };
+
+            let (lint, level) = (lint.trim(), level.trim());
+            let level = level
+                .strip_prefix('"')
+                .and_then(|level| level.strip_suffix('"'))
+                .and_then(Level::parse)
+                .ok_or_else(|| {
+                    error(format!(
+                        "`{}` should be \"allow\", \"warn\" or \"deny\", not {}",
+                        lint, level
+                    ))
+                })?;
+
+            config.set(lint, level).map_err(error)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn set(&mut self, lint: &str, level: Level) -> Result<(), String> {
+        if !LINTS.contains(&lint) {
+            let message = format!("Unknown lint `{}`", lint);
+
+            return Err(match suggest(lint, LINTS.iter().copied()) {
+                Some(suggestion) => format!("{}, did you mean `{}`?", message, suggestion),
+                None => message,
+            });
+        }
+
+        self.levels.insert(lint.to_string(), level);
+        Ok(())
+    }
+
+    pub fn level(&self, lint: &str) -> Level {
+        self.levels.get(lint).copied().unwrap_or(Level::Warn)
+    }
+
+    // `diagnostics` without the warnings that are allowed, and with the ones
+    // that are denied made errors.
+    pub fn apply(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic.severity {
+                Severity::Error => Some(diagnostic),
+                Severity::Warning => match self.level(&diagnostic.code) {
+                    Level::Allow => None,
+                    Level::Warn => Some(diagnostic),
+                    Level::Deny => Some(Diagnostic {
+                        severity: Severity::Error,
+                        ..diagnostic
+                    }),
+                },
+            })
+            .collect()
+    }
+}
+
+// the warnings about how `program` is written, rather than whether it's
+// correct, which the type checker sees to.
+pub fn lint(program: &[Stmt]) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        warnings: vec![],
+        scopes: vec![vec![]],
+    };
+
+    walk_stmts(&mut linter, program);
+    linter.end_scope();
+
+    linter.warnings
+}
+
+struct Binding {
+    name: String,
+    is_mut: bool,
+    assigned: bool,
+    // where it's declared.
+    span: Span,
+}
+
+struct Linter {
+    warnings: Vec<Diagnostic>,
+    scopes: Vec<Vec<Binding>>,
+}
+
+impl Linter {
+    fn warn(&mut self, code: &str, span: Span, message: String) {
+        self.warnings.push(Diagnostic::warning(code, span, message));
+    }
+
+    fn declare(&mut self, what: &str, name: &str, is_mut: bool, span: Span) {
+        self.snake_case(what, name, span);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Binding {
+                name: name.to_string(),
+                is_mut,
+                assigned: false,
+                span,
+            });
+        }
+    }
+
+    fn assign(&mut self, name: &str) {
+        let binding = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|b| b.name == name));
+
+        if let Some(binding) = binding {
+            binding.assigned = true;
+        }
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        for binding in scope
+            .iter()
+            .filter(|binding| binding.is_mut && !binding.assigned)
+        {
+            self.warn(
+                "UnusedMut",
+                binding.span,
+                format!(
+                    "Variable `{}` is declared `mut` but never assigned to",
+                    binding.name
+                ),
+            );
+        }
+    }
+
+    fn snake_case(&mut self, what: &str, name: &str, span: Span) {
+        if name.chars().any(char::is_uppercase) {
+            let message = format!("{} `{}` should have a snake_case name", what, name);
+            let warning = Diagnostic::warning("NonSnakeCase", span, message)
+                .with_suggestion(Some(snake_case(name)));
+
+            self.warnings.push(warning);
+        }
+    }
+
+    fn empty(&mut self, stmts: &[Stmt], what: &str, span: Span) {
+        if stmts.is_empty() {
+            self.warn("EmptyBlock", span, format!("{} has an empty body", what));
+        }
+    }
+}
+
+impl Visitor for Linter {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Variable {
+                name,
+                value,
+                is_mut,
+                token,
+                ..
+            } => {
+                self.visit_expr(value);
+                self.declare("Variable", name, *is_mut, token.span);
+            }
+            Stmt::Assignment { name, value, .. } => {
+                self.visit_expr(value);
+                self.assign(name);
+            }
+            Stmt::Function {
+                name,
+                args,
+                external,
+                token,
+                ..
+            } => {
+                // externals are named whatever C calls them.
+                if external.is_none() {
+                    self.snake_case("Function", name, token.span);
+                }
+
+                self.scopes.push(vec![]);
+
+                for arg in args {
+                    self.declare("Parameter", &arg.name, false, arg.span);
+                }
+
+                walk_stmt(self, stmt);
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                consequence,
+                ..
+            } => {
+                self.empty(&consequence.stmts, "This `if`", condition.span);
+                walk_stmt(self, stmt);
+            }
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                token,
+            } => {
+                self.visit_expr(start);
+                self.visit_expr(end);
+                self.empty(&body.stmts, "This `for`", start.span);
+
+                self.scopes.push(vec![]);
+                self.declare("Variable", iterator, false, token.span);
+                self.visit_block(body);
+                self.end_scope();
+            }
+            Stmt::ForEach {
+                iterator,
+                iterable,
+                body,
+                token,
+            } => {
+                self.visit_expr(iterable);
+                self.empty(&body.stmts, "This `for`", iterable.span);
+
+                self.scopes.push(vec![]);
+                self.declare("Variable", iterator, false, token.span);
+                self.visit_block(body);
+                self.end_scope();
+            }
+            Stmt::Block(block, token) => {
+                self.empty(&block.stmts, "This block", token.span);
+                self.visit_block(block);
+            }
+            Stmt::Expr(expr) => {
+                if !matches!(expr.node, Expr::Call { .. }) {
+                    self.warn(
+                        "UnusedResult",
+                        expr.span,
+                        "The value of this expression is never used".to_string(),
+                    );
+                }
+
+                self.visit_expr(expr);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.scopes.push(vec![]);
+        walk_block(self, block);
+        self.end_scope();
+    }
+
+    // whatever's given a variable's address can assign to it.
+    fn visit_expr(&mut self, expr: &Spanned<Expr>) {
+        if let Expr::Unary { op, operand } = &expr.node {
+            if let (TokenKind::BitAnd, Expr::Identifier(name)) = (&op.kind, &operand.node) {
+                self.assign(name);
+            }
+        }
+
+        walk_expr(self, expr);
+    }
+}
+
+// `name` in snake_case, `totalCount` as `total_count` and `parseURL` as
+// `parse_url`.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut previous = None;
+
+    for c in name.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p: char| p.is_lowercase() || p.is_numeric()) {
+            out.push('_');
+        }
+
+        out.extend(c.to_lowercase());
+        previous = Some(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,134 @@
+use super::*;
+
+use crate::parse_str;
+
+// the code and message of every warning `src` gets.
+fn lints(src: &str) -> Vec<(String, String)> {
+    lint(&parse_str(src).unwrap())
+        .into_iter()
+        .map(|warning| (warning.code, warning.message))
+        .collect()
+}
+
+fn warning(code: &str, message: &str) -> (String, String) {
+    (code.to_string(), message.to_string())
+}
+
+#[test]
+fn names_are_snake_case() {
+    assert_eq!(
+        lints("func addTwo(firstNumber: int): int { let Total = firstNumber; return Total; }"),
+        vec![
+            warning(
+                "NonSnakeCase",
+                "Function `addTwo` should have a snake_case name"
+            ),
+            warning(
+                "NonSnakeCase",
+                "Parameter `firstNumber` should have a snake_case name"
+            ),
+            warning(
+                "NonSnakeCase",
+                "Variable `Total` should have a snake_case name"
+            ),
+        ]
+    );
+
+    // externals keep the names C gives them.
+    assert!(lints(r#"func SDL_Init(flags: int): int @requires("SDL.h")"#).is_empty());
+
+    let warnings = lint(&parse_str("func parseURL() {}").unwrap());
+    assert_eq!(warnings[0].suggestion, Some(String::from("parse_url")));
+}
+
+#[test]
+fn mut_that_is_never_assigned() {
+    let src = "func main(): int {
+        let mut never = 1;
+        let mut counted = 0;
+        for i in 0..3 {
+            counted += i;
+        }
+        return never + counted;
+    }";
+
+    assert_eq!(
+        lints(src),
+        vec![warning(
+            "UnusedMut",
+            "Variable `never` is declared `mut` but never assigned to"
+        )]
+    );
+
+    // what it's given the address of can assign to it.
+    let src = r#"func scanf(format: String, ...): int @requires("stdio.h")
+    let mut n = 0; scanf("%d", &n);"#;
+    assert!(lints(src).is_empty());
+
+    // an assignment goes to the innermost binding with the name.
+    let src = "let mut x = 1; { let mut x = 2; x = 3; }";
+    assert_eq!(
+        lints(src),
+        vec![warning(
+            "UnusedMut",
+            "Variable `x` is declared `mut` but never assigned to"
+        )]
+    );
+}
+
+#[test]
+fn empty_blocks_and_ignored_results() {
+    let src = "func main(): int {
+        if (true) {}
+        for i in 0..3 {}
+        {}
+        1 + 2;
+        print(1);
+        return 0;
+    }";
+
+    assert_eq!(
+        lints(src),
+        vec![
+            warning("EmptyBlock", "This `if` has an empty body"),
+            warning("EmptyBlock", "This `for` has an empty body"),
+            warning("EmptyBlock", "This block has an empty body"),
+            warning("UnusedResult", "The value of this expression is never used"),
+        ]
+    );
+}
+
+#[test]
+fn levels_come_from_uma_toml() {
+    let config = LintConfig::from_toml(
+        "[package]\nname = \"x\"\n\n[lints]\nUnusedMut = \"allow\" # too noisy\nEmptyBlock = \"deny\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(config.level("UnusedMut"), Level::Allow);
+    assert_eq!(config.level("EmptyBlock"), Level::Deny);
+    assert_eq!(config.level("UnusedResult"), Level::Warn);
+
+    let warnings = lint(&parse_str("func f() { let mut x = 1; {} 1; }").unwrap());
+    let applied = config.apply(warnings);
+
+    assert_eq!(
+        applied
+            .iter()
+            .map(|diagnostic| (diagnostic.code.as_str(), diagnostic.severity))
+            .collect::<Vec<_>>(),
+        vec![
+            ("EmptyBlock", Severity::Error),
+            ("UnusedResult", Severity::Warning)
+        ]
+    );
+
+    assert_eq!(
+        LintConfig::from_toml("[lints]\nUnusedMutt = \"allow\"").unwrap_err(),
+        "line 2: Unknown lint `UnusedMutt`, did you mean `UnusedMut`?"
+    );
+    assert_eq!(
+        LintConfig::from_toml("[lints]\nUnusedMut = \"off\"").unwrap_err(),
+        "line 2: `UnusedMut` should be \"allow\", \"warn\" or \"deny\", not \"off\""
+    );
+}
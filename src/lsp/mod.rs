@@ -0,0 +1,523 @@
+mod json;
+mod symbols;
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::panic;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::lexer::{Lexer, Span};
+use crate::typeck::{Type, TypedStmt};
+
+pub use json::Json;
+use symbols::{Symbol, SymbolKind, Symbols};
+
+// a language server speaking LSP over `input` and `output`, which are stdin
+// and stdout for `uma lsp`. documents are checked on their own, so imports
+// aren't followed.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+
+    while let Some(message) = read_message(&mut input)? {
+        let Ok(message) = Json::parse(&message) else {
+            continue;
+        };
+
+        for reply in server.handle(&message) {
+            write_message(&mut output, &reply)?;
+        }
+
+        if server.exited {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// reads one `Content-Length` framed message, `None` once the input is closed.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message without a Content-Length header",
+        ));
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+struct Document {
+    text: String,
+    symbols: Symbols,
+    typed: Option<Vec<TypedStmt>>,
+}
+
+impl Document {
+    fn new(text: String) -> (Document, Vec<Diagnostic>) {
+        let symbols = Lexer::new(&text)
+            .lex()
+            .map(|tokens| Symbols::new(&tokens))
+            .unwrap_or_default();
+
+        // the parser still panics on some unfinished input, which is most
+        // input while someone's typing.
+        let (typed, diagnostics) = match panic::catch_unwind(|| crate::check_str(&text, false)) {
+            Ok(Ok((typed, warnings))) => (Some(typed), warnings),
+            Ok(Err(err)) => (None, err.diagnostics),
+            Err(_) => (None, vec![]),
+        };
+
+        (
+            Document {
+                text,
+                symbols,
+                typed,
+            },
+            diagnostics,
+        )
+    }
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn response(id: &Json, result: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.clone()),
+        ("result", result),
+    ])
+}
+
+fn error(id: &Json, code: i32, message: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.clone()),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+// the LSP position of byte `offset`, where characters are counted in UTF-16.
+fn position(text: &str, offset: usize) -> Json {
+    let offset = offset.min(text.len());
+    let before = text.get(..offset).unwrap_or(text);
+
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].encode_utf16().count();
+
+    Json::object(vec![("line", line.into()), ("character", character.into())])
+}
+
+// the byte offset of an LSP position.
+fn offset(text: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line").as_usize()?;
+    let character = position.get("character").as_usize()?;
+
+    let mut start = 0;
+
+    for _ in 0..line {
+        start += text[start..].find('\n')? + 1;
+    }
+
+    let mut units = 0;
+
+    for (i, c) in text[start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(start + i);
+        }
+
+        units += c.len_utf16();
+    }
+
+    Some(text.len())
+}
+
+fn range(text: &str, span: Span) -> Json {
+    Json::object(vec![
+        ("start", position(text, span.start)),
+        ("end", position(text, span.end)),
+    ])
+}
+
+fn diagnostic(text: &str, diagnostic: &Diagnostic) -> Json {
+    let severity = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+
+    let mut message = diagnostic.message.clone();
+
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\nnote: {}", note));
+    }
+
+    if let Some(suggestion) = &diagnostic.suggestion {
+        message.push_str(&format!("\nhelp: did you mean `{}`?", suggestion));
+    }
+
+    // diagnostics without a location go at the top of the file.
+    let range = if diagnostic.span.line == 0 {
+        range(text, Span::default())
+    } else {
+        range(text, diagnostic.span)
+    };
+
+    Json::object(vec![
+        ("range", range),
+        ("severity", Json::Number(severity as f64)),
+        ("code", diagnostic.code.as_str().into()),
+        ("source", "uma".into()),
+        ("message", message.into()),
+    ])
+}
+
+// what the typed AST says about the symbols of a document, in the order they
+// come up in the source.
+#[derive(Default)]
+struct Types<'a> {
+    functions: HashMap<&'a str, &'a TypedStmt>,
+    variables: HashMap<&'a str, Vec<(bool, &'a Type)>>,
+    consts: HashMap<&'a str, &'a Type>,
+    loops: Vec<Type>,
+}
+
+impl<'a> Types<'a> {
+    fn collect(&mut self, stmts: &'a [TypedStmt]) {
+        for stmt in stmts {
+            match stmt {
+                TypedStmt::Variable {
+                    name, is_mut, ty, ..
+                } => self.variables.entry(name).or_default().push((*is_mut, ty)),
+                TypedStmt::Const { name, ty, .. } => {
+                    self.consts.insert(name, ty);
+                }
+                TypedStmt::Function { name, body, .. } => {
+                    self.functions.entry(name).or_insert(stmt);
+                    self.collect(body);
+                }
+                TypedStmt::External { name, .. } => {
+                    self.functions.entry(name).or_insert(stmt);
+                }
+                TypedStmt::If {
+                    consequence,
+                    alternative,
+                    ..
+                } => {
+                    self.collect(consequence);
+
+                    if let Some(alt) = alternative {
+                        self.collect(std::slice::from_ref(alt));
+                    }
+                }
+                TypedStmt::For { body, .. } => {
+                    self.loops.push(Type::Int);
+                    self.collect(body);
+                }
+                TypedStmt::ForEach { iterable, body, .. } => {
+                    self.loops.push(match iterable.ty() {
+                        Type::Array(element, _) => *element,
+                        ty => ty,
+                    });
+                    self.collect(body);
+                }
+                TypedStmt::Block(body, _) => self.collect(body),
+                TypedStmt::Opaque { .. }
+                | TypedStmt::Assignment { .. }
+                | TypedStmt::Return(..)
+                | TypedStmt::Expr(..) => (),
+            }
+        }
+    }
+
+    fn describe(&self, symbol: &Symbol) -> Option<String> {
+        match &symbol.kind {
+            SymbolKind::Function { params } => match self.functions.get(symbol.name.as_str())? {
+                TypedStmt::Function {
+                    return_type, args, ..
+                } => {
+                    let params = params
+                        .iter()
+                        .map(|param| match args.iter().find(|(name, _)| name == param) {
+                            Some((_, ty)) => format!("{}: {}", param, ty),
+                            None => param.clone(),
+                        })
+                        .collect::<Vec<_>>();
+
+                    Some(format!(
+                        "func {}({}): {}",
+                        symbol.name,
+                        params.join(", "),
+                        return_type
+                    ))
+                }
+                TypedStmt::External {
+                    header, is_varadic, ..
+                } => {
+                    let mut params = params.clone();
+
+                    if *is_varadic {
+                        params.push("...".to_string());
+                    }
+
+                    Some(format!(
+                        "func {}({}) @requires({:?})",
+                        symbol.name,
+                        params.join(", "),
+                        header
+                    ))
+                }
+                _ => None,
+            },
+            SymbolKind::Variable { nth } => {
+                let (is_mut, ty) = self.variables.get(symbol.name.as_str())?.get(*nth)?;
+                let kw = if *is_mut { "let mut" } else { "let" };
+
+                Some(format!("{} {}: {}", kw, symbol.name, ty))
+            }
+            SymbolKind::Constant => {
+                let ty = self.consts.get(symbol.name.as_str())?;
+                Some(format!("const {}: {}", symbol.name, ty))
+            }
+            SymbolKind::Param { function } => match self.functions.get(function.as_str())? {
+                TypedStmt::Function { args, .. } => {
+                    let (_, ty) = args.iter().find(|(name, _)| *name == symbol.name)?;
+                    Some(format!("{}: {}", symbol.name, ty))
+                }
+                _ => None,
+            },
+            SymbolKind::Iterator { nth } => {
+                Some(format!("{}: {}", symbol.name, self.loops.get(*nth)?))
+            }
+        }
+    }
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            documents: HashMap::new(),
+            shutdown: false,
+            exited: false,
+        }
+    }
+
+    // everything that should be sent back for `message`, replies and
+    // notifications alike.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let id = message.get("id");
+        let params = message.get("params");
+
+        let Some(method) = message.get("method").as_str() else {
+            // a response to something we never asked for.
+            return vec![];
+        };
+
+        if self.shutdown && method != "exit" && *id != Json::Null {
+            return vec![error(id, -32600, "The server is shutting down")];
+        }
+
+        match method {
+            "initialize" => vec![response(id, capabilities())],
+            "initialized" => vec![],
+            "shutdown" => {
+                self.shutdown = true;
+                vec![response(id, Json::Null)]
+            }
+            "exit" => {
+                self.exited = true;
+                vec![]
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+
+                match (document.get("uri").as_str(), document.get("text").as_str()) {
+                    (Some(uri), Some(text)) => vec![self.open(uri, text.to_string())],
+                    _ => vec![],
+                }
+            }
+            "textDocument/didChange" => {
+                let uri = params.get("textDocument").get("uri").as_str();
+
+                // only full syncs are asked for, so the last change is the
+                // whole document.
+                let text = params
+                    .get("contentChanges")
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text").as_str());
+
+                match (uri, text) {
+                    (Some(uri), Some(text)) => vec![self.open(uri, text.to_string())],
+                    _ => vec![],
+                }
+            }
+            "textDocument/didClose" => match params.get("textDocument").get("uri").as_str() {
+                Some(uri) => {
+                    self.documents.remove(uri);
+                    vec![publish(uri, vec![])]
+                }
+                None => vec![],
+            },
+            "textDocument/definition" => vec![response(id, self.definition(params))],
+            "textDocument/hover" => vec![response(id, self.hover(params))],
+            // notifications we don't know about are fine to drop.
+            _ if *id == Json::Null => vec![],
+            method => vec![error(
+                id,
+                -32601,
+                &format!("Unsupported method `{}`", method),
+            )],
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: String) -> Json {
+        let (document, diagnostics) = Document::new(text);
+
+        let diagnostics = diagnostics
+            .iter()
+            .map(|diag| diagnostic(&document.text, diag))
+            .collect();
+
+        self.documents.insert(uri.to_string(), document);
+        publish(uri, diagnostics)
+    }
+
+    // the document and symbol a `TextDocumentPositionParams` points at.
+    fn lookup<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a Document, Span, &'a Symbol)> {
+        let uri = params.get("textDocument").get("uri").as_str()?;
+        let document = self.documents.get(uri)?;
+
+        let offset = offset(&document.text, params.get("position"))?;
+        let (span, symbol) = document.symbols.at(offset)?;
+
+        Some((uri, document, span, symbol))
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let Some((uri, document, _, symbol)) = self.lookup(params) else {
+            return Json::Null;
+        };
+
+        Json::object(vec![
+            ("uri", uri.into()),
+            ("range", range(&document.text, symbol.span)),
+        ])
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let Some((_, document, span, symbol)) = self.lookup(params) else {
+            return Json::Null;
+        };
+
+        let mut types = Types::default();
+
+        if let Some(typed) = &document.typed {
+            types.collect(typed);
+        }
+
+        let Some(description) = types.describe(symbol) else {
+            return Json::Null;
+        };
+
+        Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", "markdown".into()),
+                    ("value", format!("```uma\n{}\n```", description).into()),
+                ]),
+            ),
+            ("range", range(&document.text, span)),
+        ])
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", 1.into()),
+                ("definitionProvider", true.into()),
+                ("hoverProvider", true.into()),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![
+                ("name", "uma".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    notification(
+        "textDocument/publishDiagnostics",
+        Json::object(vec![
+            ("uri", uri.into()),
+            ("diagnostics", Json::Array(diagnostics)),
+        ]),
+    )
+}
+
+#[cfg(test)]
+mod tests;
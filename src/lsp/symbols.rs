@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use crate::lexer::{Span, Token, TokenKind};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SymbolKind {
+    Function { params: Vec<String> },
+    // the `nth` declaration of this name with a `let`, counting the whole file.
+    Variable { nth: usize },
+    Constant,
+    Param { function: String },
+    // the `nth` for loop in the file.
+    Iterator { nth: usize },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub span: Span,
+    pub kind: SymbolKind,
+}
+
+// every declaration in a file, and which one each identifier refers to. this
+// only needs tokens, so it keeps working while the file doesn't type-check.
+#[derive(Debug, Default)]
+pub struct Symbols {
+    pub symbols: Vec<Symbol>,
+    // the span of every identifier that resolved, with its symbol.
+    references: Vec<(Span, usize)>,
+}
+
+impl Symbols {
+    pub fn new(tokens: &[Token]) -> Symbols {
+        let mut table = Symbols::default();
+        let mut functions = HashMap::new();
+
+        // functions can be called before they're declared, so they're
+        // collected up front.
+        for (i, token) in tokens.iter().enumerate() {
+            if token.kind != TokenKind::Func {
+                continue;
+            }
+
+            let Some(name) = tokens
+                .get(i + 1)
+                .filter(|t| t.kind == TokenKind::Identifier)
+            else {
+                continue;
+            };
+
+            let name_str = name.text();
+
+            if functions.contains_key(&name_str) {
+                continue;
+            }
+
+            let params = params(&tokens[i + 2..])
+                .iter()
+                .map(|param| param.text())
+                .collect();
+
+            functions.insert(name_str.clone(), table.symbols.len());
+            table.symbols.push(Symbol {
+                name: name_str,
+                span: name.span,
+                kind: SymbolKind::Function { params },
+            });
+        }
+
+        table.resolve(tokens, &functions);
+        table
+    }
+
+    fn declare(&mut self, scope: &mut Vec<(String, usize)>, token: &Token, kind: SymbolKind) {
+        let name = token.text();
+
+        self.references.push((token.span, self.symbols.len()));
+        scope.push((name.clone(), self.symbols.len()));
+        self.symbols.push(Symbol {
+            name,
+            span: token.span,
+            kind,
+        });
+    }
+
+    fn resolve(&mut self, tokens: &[Token], functions: &HashMap<String, usize>) {
+        let mut scopes: Vec<Vec<(String, usize)>> = vec![vec![]];
+        // parameters and loop iterators belong to the block that comes next.
+        let mut pending = vec![];
+        let mut lets: HashMap<String, usize> = HashMap::new();
+        let mut loops = 0;
+
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let token = &tokens[i];
+
+            match token.kind {
+                TokenKind::Func => {
+                    pending.clear();
+
+                    let Some(name) = tokens
+                        .get(i + 1)
+                        .filter(|t| t.kind == TokenKind::Identifier)
+                    else {
+                        i += 1;
+                        continue;
+                    };
+
+                    let function = name.text();
+
+                    if let Some(&symbol) = functions.get(&function) {
+                        self.references.push((name.span, symbol));
+                    }
+
+                    let params = params(&tokens[i + 2..]);
+
+                    for param in &params {
+                        let kind = SymbolKind::Param {
+                            function: function.clone(),
+                        };
+
+                        pending.push(((*param).clone(), kind));
+                    }
+
+                    // parameters are declared when the body opens, so their
+                    // names aren't looked up here.
+                    i = tokens[i..]
+                        .iter()
+                        .position(|t| t.kind == TokenKind::PareR)
+                        .map_or(i + 2, |close| i + close + 1);
+                    continue;
+                }
+                TokenKind::Let | TokenKind::Const => {
+                    let mut next = i + 1;
+
+                    if tokens.get(next).is_some_and(|t| t.kind == TokenKind::Mut) {
+                        next += 1;
+                    }
+
+                    if let Some(name) = tokens.get(next).filter(|t| t.kind == TokenKind::Identifier)
+                    {
+                        let kind = if token.kind == TokenKind::Const {
+                            SymbolKind::Constant
+                        } else {
+                            let count = lets.entry(name.text()).or_default();
+                            *count += 1;
+
+                            SymbolKind::Variable { nth: *count - 1 }
+                        };
+
+                        // the value can't see the variable it's assigned to, so
+                        // it's only declared once the statement ends.
+                        let end = tokens[next..]
+                            .iter()
+                            .position(|t| t.kind == TokenKind::Semi)
+                            .map_or(tokens.len(), |semi| next + semi);
+
+                        self.identifiers(&tokens[next + 1..end], &scopes, functions);
+
+                        let scope = scopes.last_mut().unwrap();
+                        self.declare(scope, name, kind);
+
+                        i = end;
+                        continue;
+                    }
+                }
+                TokenKind::For => {
+                    if let Some(name) = tokens
+                        .get(i + 1)
+                        .filter(|t| t.kind == TokenKind::Identifier)
+                    {
+                        pending.push((name.clone(), SymbolKind::Iterator { nth: loops }));
+                        loops += 1;
+                        i += 2;
+                        continue;
+                    }
+                }
+                TokenKind::At | TokenKind::Semi => pending.clear(),
+                TokenKind::BraceL => {
+                    let mut scope = vec![];
+
+                    for (token, kind) in pending.drain(..) {
+                        self.declare(&mut scope, &token, kind);
+                    }
+
+                    scopes.push(scope);
+                }
+                TokenKind::BraceR if scopes.len() > 1 => {
+                    scopes.pop();
+                }
+                // the name of an argument passed by name isn't a reference.
+                TokenKind::Identifier
+                    if tokens
+                        .get(i + 1)
+                        .is_some_and(|t| t.kind == TokenKind::Colon) => {}
+                TokenKind::Identifier => {
+                    self.identifiers(std::slice::from_ref(token), &scopes, functions)
+                }
+                _ => (),
+            }
+
+            i += 1;
+        }
+    }
+
+    fn identifiers(
+        &mut self,
+        tokens: &[Token],
+        scopes: &[Vec<(String, usize)>],
+        functions: &HashMap<String, usize>,
+    ) {
+        for (i, token) in tokens.iter().enumerate() {
+            let named = tokens
+                .get(i + 1)
+                .is_some_and(|t| t.kind == TokenKind::Colon);
+
+            if token.kind != TokenKind::Identifier || named {
+                continue;
+            }
+
+            let name = token.value.as_deref().unwrap_or_default();
+
+            let symbol = scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.iter().rev().find(|(n, _)| n == name))
+                .map(|(_, symbol)| *symbol)
+                .or_else(|| functions.get(name).copied());
+
+            if let Some(symbol) = symbol {
+                self.references.push((token.span, symbol));
+            }
+        }
+    }
+
+    // the symbol whatever's at byte `offset` refers to.
+    pub fn at(&self, offset: usize) -> Option<(Span, &Symbol)> {
+        self.references
+            .iter()
+            .find(|(span, _)| span.start <= offset && offset <= span.end)
+            .map(|(span, symbol)| (*span, &self.symbols[*symbol]))
+    }
+}
+
+// the parameter names of a function, given the tokens after its name.
+fn params(tokens: &[Token]) -> Vec<&Token> {
+    let mut params = vec![];
+
+    if tokens.first().map(|t| &t.kind) != Some(&TokenKind::PareL) {
+        return params;
+    }
+
+    let mut expecting_name = true;
+
+    for token in &tokens[1..] {
+        match token.kind {
+            TokenKind::PareR => break,
+            TokenKind::Comma => expecting_name = true,
+            TokenKind::Identifier if expecting_name => {
+                params.push(token);
+                expecting_name = false;
+            }
+            _ => expecting_name = false,
+        }
+    }
+
+    params
+}
@@ -0,0 +1,213 @@
+use super::*;
+
+const URI: &str = "file:///main.uma";
+
+const SRC: &str = "func add(x: int, y: int): int {
+    return x + y;
+}
+
+func main(): int {
+    let total = add(1, 2);
+
+    for i in 0..total {
+        let total = i;
+    }
+
+    return total;
+}
+";
+
+fn message(src: &str) -> Json {
+    Json::parse(src).unwrap()
+}
+
+fn open(server: &mut Server, text: &str) -> Vec<Json> {
+    server.handle(&Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/didOpen".into()),
+        (
+            "params",
+            Json::object(vec![(
+                "textDocument",
+                Json::object(vec![
+                    ("uri", URI.into()),
+                    ("languageId", "uma".into()),
+                    ("version", 1.into()),
+                    ("text", text.into()),
+                ]),
+            )]),
+        ),
+    ]))
+}
+
+fn request(server: &mut Server, method: &str, line: usize, character: usize) -> Json {
+    let replies = server.handle(&message(&format!(
+        r#"{{"jsonrpc":"2.0","id":1,"method":"{}","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}}}"#,
+        method, URI, line, character
+    )));
+
+    replies[0].get("result").clone()
+}
+
+#[test]
+fn json_round_trip() {
+    let src = r#"{"a":[1,2.5,-3],"b":{"c":null,"d":true},"e":"tab\tquote\" é 😀"}"#;
+    let json = message(src);
+
+    assert_eq!(json.get("e").as_str(), Some("tab\tquote\" é 😀"));
+    assert_eq!(message(&json.to_string()), json);
+    assert!(Json::parse("{\"a\":}").is_err());
+}
+
+#[test]
+fn framing() {
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#;
+    let exit = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{}Content-Length: {}\r\n\r\n{}",
+        body.len(),
+        body,
+        exit.len(),
+        exit
+    );
+
+    let mut output = vec![];
+    run(input.as_bytes(), &mut output).unwrap();
+
+    let reply = r#"{"jsonrpc":"2.0","id":1,"result":null}"#;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("Content-Length: {}\r\n\r\n{}", reply.len(), reply)
+    );
+}
+
+#[test]
+fn publishes_diagnostics() {
+    let mut server = Server::new();
+
+    let replies = open(&mut server, "func main(): int {\n    return 0;\n}\n");
+    let params = replies[0].get("params");
+
+    assert_eq!(
+        replies[0].get("method").as_str(),
+        Some("textDocument/publishDiagnostics")
+    );
+    assert_eq!(params.get("diagnostics"), &Json::Array(vec![]));
+
+    let replies = open(&mut server, "let x = 1;\nlet = 5;\n");
+    let diagnostics = replies[0].get("params").get("diagnostics");
+    let diagnostic = &diagnostics.as_array().unwrap()[0];
+
+    assert_eq!(
+        diagnostic.get("range").get("start"),
+        &message(r#"{"line":1,"character":4}"#)
+    );
+    assert_eq!(
+        diagnostic.get("message").as_str(),
+        Some("Expected `Identifier` but found `Equals`")
+    );
+
+    let replies = open(&mut server, SRC);
+    let diagnostics = replies[0].get("params").get("diagnostics");
+    let warning = &diagnostics.as_array().unwrap()[0];
+
+    assert_eq!(warning.get("severity"), &Json::Number(2.0));
+    assert_eq!(warning.get("code").as_str(), Some("ShadowedBinding"));
+
+    // a suggestion is worded like it is in the terminal.
+    let replies = open(
+        &mut server,
+        "func main(): int {\n    prnt(\"x\");\n    return 0;\n}\n",
+    );
+    let diagnostics = replies[0].get("params").get("diagnostics");
+
+    assert_eq!(
+        diagnostics.as_array().unwrap()[0].get("message").as_str(),
+        Some("Call to undefined function 'prnt'\nhelp: did you mean `print`?")
+    );
+
+    // unfinished input shouldn't take the server down.
+    let replies = open(&mut server, "func main(");
+    assert_eq!(replies.len(), 1);
+}
+
+#[test]
+fn definition() {
+    let mut server = Server::new();
+    open(&mut server, SRC);
+
+    // `add` in `add(1, 2)`.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 5, 17).get("range"),
+        &message(r#"{"start":{"line":0,"character":5},"end":{"line":0,"character":8}}"#)
+    );
+
+    // the `y` in `x + y` is the parameter.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 1, 15)
+            .get("range")
+            .get("start"),
+        &message(r#"{"line":0,"character":17}"#)
+    );
+
+    // the inner `total` shadows the outer one only inside the loop.
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 7, 16)
+            .get("range")
+            .get("start"),
+        &message(r#"{"line":5,"character":8}"#)
+    );
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 11, 11)
+            .get("range")
+            .get("start"),
+        &message(r#"{"line":5,"character":8}"#)
+    );
+
+    assert_eq!(
+        request(&mut server, "textDocument/definition", 2, 0),
+        Json::Null
+    );
+}
+
+#[test]
+fn hover() {
+    let mut server = Server::new();
+    open(&mut server, SRC);
+
+    let hover = |server: &mut Server, line, character| {
+        let result = request(server, "textDocument/hover", line, character);
+        result
+            .get("contents")
+            .get("value")
+            .as_str()
+            .map(String::from)
+    };
+
+    assert_eq!(
+        hover(&mut server, 5, 17).as_deref(),
+        Some("```uma\nfunc add(x: Int, y: Int): Int\n```")
+    );
+    assert_eq!(
+        hover(&mut server, 5, 9).as_deref(),
+        Some("```uma\nlet total: Int\n```")
+    );
+    assert_eq!(
+        hover(&mut server, 8, 20).as_deref(),
+        Some("```uma\ni: Int\n```")
+    );
+}
+
+#[test]
+fn unknown_requests() {
+    let mut server = Server::new();
+
+    let replies = server.handle(&message(
+        r#"{"jsonrpc":"2.0","id":7,"method":"textDocument/rename","params":{}}"#,
+    ));
+
+    assert_eq!(replies[0].get("error").get("code"), &Json::Number(-32601.0));
+    assert!(server
+        .handle(&message(r#"{"jsonrpc":"2.0","method":"$/cancelRequest"}"#))
+        .is_empty());
+}
@@ -1,39 +1,311 @@
 mod cli;
+mod entry;
 
-mod codegen;
-mod lexer;
-mod parser;
+use uma::{Cfg, CompilerOptions, Library};
 
-mod colors;
-mod entry;
-mod utils;
+use crate::cli::{Action, Arg, ArgMatches, ArgParser};
+use crate::entry::CheckOptions;
+
+fn input_arg() -> Arg {
+    Arg::new("input")
+        .action(Action::Positional)
+        .help("The .uma source file")
+        .required(true)
+}
+
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .short("-o")
+        .long("--output")
+        .action(Action::StoreValue)
+        .help("The output executable path")
+}
+
+fn error_format_arg() -> Arg {
+    Arg::new("error-format")
+        .long("--error-format")
+        .action(Action::StoreValue)
+        .choices(&["human", "json"])
+        .help("Print diagnostics for people or as one JSON object per line")
+}
+
+fn compiler_args() -> Vec<Arg> {
+    vec![
+        Arg::new("cc")
+            .long("--cc")
+            .action(Action::StoreValue)
+            .help("The C compiler to use, instead of `UMA_CC` or the first found"),
+        Arg::new("opt-level")
+            .short("-O")
+            .action(Action::StoreValue)
+            .choices(&["0", "1", "2", "3"])
+            .help("The optimization level, like `-O2`"),
+        Arg::new("cflag")
+            .long("--cflag")
+            .action(Action::Append)
+            .help("A flag passed to the C compiler, can be repeated"),
+        Arg::new("ldflag")
+            .long("--ldflag")
+            .action(Action::Append)
+            .help("A flag passed to the linker, can be repeated"),
+        Arg::new("debug")
+            .short("-g")
+            .action(Action::StoreTrue)
+            .help("Build with debug info that points at the .uma source"),
+        Arg::new("release")
+            .long("--release")
+            .action(Action::StoreTrue)
+            .help("Leave out the runtime checks, like indexing past the end of an array"),
+        Arg::new("checks")
+            .long("--checks")
+            .action(Action::StoreValue)
+            .choices(&["on", "off"])
+            .help(
+                "Check integer arithmetic for overflow and division by zero, on unless `--release`",
+            ),
+    ]
+}
 
-use crate::cli::{Arg, ArgParser};
+fn compiler_options(matches: &ArgMatches) -> CompilerOptions {
+    let release = matches.get_bool("release");
+
+    CompilerOptions {
+        compiler: matches.get_string("cc").cloned(),
+        opt_level: matches.get_string("opt-level").cloned(),
+        cflags: matches.get_vec("cflag").cloned().unwrap_or_default(),
+        ldflags: matches.get_vec("ldflag").cloned().unwrap_or_default(),
+        debug: matches.get_bool("debug"),
+        release,
+        checks: match matches.get_string("checks").map(String::as_str) {
+            Some(checks) => checks == "on",
+            None => !release,
+        },
+    }
+}
+
+fn lint_args() -> Vec<Arg> {
+    vec![
+        Arg::new("deny-warnings")
+            .long("--deny-warnings")
+            .action(Action::StoreTrue)
+            .help("Treat warnings as errors"),
+        Arg::new("allow")
+            .long("--allow")
+            .action(Action::Append)
+            .help("Don't warn about a lint, like `UnusedMut` (repeatable)"),
+        Arg::new("deny")
+            .long("--deny")
+            .action(Action::Append)
+            .help("Make a lint's warnings errors (repeatable)"),
+    ]
+}
+
+fn timings_arg() -> Arg {
+    Arg::new("timings")
+        .long("--timings")
+        .action(Action::StoreTrue)
+        .help("Print how long each phase of the build took")
+}
+
+fn check_options(matches: &ArgMatches) -> CheckOptions {
+    CheckOptions {
+        deny_warnings: matches.get_bool("deny-warnings"),
+        allow: matches.get_vec("allow").cloned().unwrap_or_default(),
+        deny: matches.get_vec("deny").cloned().unwrap_or_default(),
+        json: matches.get_string("error-format").map(String::as_str) == Some("json"),
+        timings: matches.get_bool("timings"),
+        cfg: Cfg {
+            debug: !matches.get_bool("release"),
+            ..match matches.get_string("target").map(String::as_str) {
+                Some("wasm32") => Cfg::wasm32(),
+                _ => Cfg::default(),
+            }
+        },
+        library: match (matches.get_bool("lib"), matches.get_bool("shared")) {
+            (_, true) => Some(Library::Shared),
+            (true, false) => Some(Library::Static),
+            (false, false) => None,
+        },
+    }
+}
 
 fn main() {
     let mut parser = ArgParser::new(env!("CARGO_PKG_NAME"))
         .description(env!("CARGO_PKG_DESCRIPTION"))
         .version(env!("CARGO_PKG_VERSION"));
 
-    parser.add_arg(
-        Arg::new("input")
-            .action(cli::Action::Positional)
-            .help("The .uma source file")
-            .required(true),
+    let mut build = ArgParser::new("build").description("Compile a .uma file into an executable");
+    build.add_arg(input_arg());
+    build.add_arg(output_arg());
+    build.add_arg(
+        Arg::new("emit")
+            .long("--emit")
+            .action(Action::StoreValue)
+            .choices(&["c", "tokens", "ast"])
+            .help("Write the generated C, or print the tokens or AST, instead of an executable"),
+    );
+    #[cfg(feature = "serde")]
+    build.add_arg(
+        Arg::new("format")
+            .long("--format")
+            .action(Action::StoreValue)
+            .choices(&["text", "json"])
+            .help("Print the tokens or AST from `--emit` as text or as JSON"),
+    );
+    build.add_arg(
+        Arg::new("target")
+            .long("--target")
+            .action(Action::StoreValue)
+            .choices(&["c", "wasm32"])
+            .help("Compile to a native executable or a WebAssembly module"),
+    );
+    build.add_arg(
+        Arg::new("lib")
+            .long("--lib")
+            .action(Action::StoreTrue)
+            .help("Build a static library of the `@export`ed functions, with a .h declaring them"),
+    );
+    build.add_arg(
+        Arg::new("shared")
+            .long("--shared")
+            .action(Action::StoreTrue)
+            .help("Build a shared library instead, like `--lib`"),
+    );
+    compiler_args()
+        .into_iter()
+        .for_each(|arg| build.add_arg(arg));
+    lint_args().into_iter().for_each(|arg| build.add_arg(arg));
+    build.add_arg(error_format_arg());
+    build.add_arg(timings_arg());
+
+    let mut run = ArgParser::new("run").description("Compile and run a .uma file");
+    run.add_arg(input_arg());
+    run.add_arg(output_arg());
+    run.add_arg(
+        Arg::new("args")
+            .action(Action::Trailing)
+            .help("Arguments passed to the program"),
     );
+    run.add_arg(
+        Arg::new("backend")
+            .long("--backend")
+            .action(Action::StoreValue)
+            .choices(match cfg!(feature = "jit") {
+                true => &["c", "interpreter", "jit"],
+                false => &["c", "interpreter"],
+            })
+            .help(match cfg!(feature = "jit") {
+                true => "Compile with C, or run in-process with the interpreter or the JIT",
+                false => "Compile with C or run in-process with the interpreter",
+            }),
+    );
+    run.add_arg(
+        Arg::new("no-cache")
+            .long("--no-cache")
+            .action(Action::StoreTrue)
+            .help("Build the program again instead of reusing an earlier build"),
+    );
+    compiler_args().into_iter().for_each(|arg| run.add_arg(arg));
+    lint_args().into_iter().for_each(|arg| run.add_arg(arg));
+    run.add_arg(error_format_arg());
+    run.add_arg(timings_arg());
+
+    let mut check =
+        ArgParser::new("check").description("Parse and type-check a .uma file without compiling");
+    check.add_arg(input_arg());
+    lint_args().into_iter().for_each(|arg| check.add_arg(arg));
+    check.add_arg(error_format_arg());
+    check.add_arg(timings_arg());
 
-    parser.add_arg(
-        Arg::new("output")
-            .short("-o")
-            .long("--output")
-            .action(cli::Action::StoreValue)
-            .help("The output executable name"),
+    let mut test =
+        ArgParser::new("test").description("Build and run the `@test` functions of a .uma file");
+    test.add_arg(input_arg());
+    compiler_args()
+        .into_iter()
+        .for_each(|arg| test.add_arg(arg));
+    lint_args().into_iter().for_each(|arg| test.add_arg(arg));
+    test.add_arg(error_format_arg());
+    test.add_arg(timings_arg());
+
+    let mut fmt = ArgParser::new("fmt").description("Format a .uma file in place");
+    fmt.add_arg(input_arg());
+    fmt.add_arg(
+        Arg::new("check")
+            .long("--check")
+            .action(Action::StoreTrue)
+            .help("Fail instead of rewriting the file when it isn't formatted"),
     );
 
-    let matches = parser.parse();
+    parser.add_subcommand(build);
+    parser.add_subcommand(run);
+    let repl = ArgParser::new("repl").description("Start an interactive session");
 
-    entry::compile(
-        matches.get_string("input").unwrap(),
-        matches.get_string("output"),
+    parser.add_subcommand(check);
+    parser.add_subcommand(test);
+    parser.add_subcommand(fmt);
+    parser.add_subcommand(repl);
+
+    #[cfg(feature = "lsp")]
+    parser.add_subcommand(
+        ArgParser::new("lsp").description("Start a language server speaking LSP over stdio"),
     );
+
+    // `uma script.uma`, how a file starting with `#!/usr/bin/env uma` is run.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(script) = args.first() {
+        if !script.starts_with('-')
+            && !parser.has_subcommand(script)
+            && std::path::Path::new(script).is_file()
+        {
+            exit(entry::script(script, &args[1..]));
+        }
+    }
+
+    let matches = parser.parse();
+
+    let status = match matches.subcommand() {
+        Some(("build", matches)) => entry::build(
+            matches.get_string("input").unwrap(),
+            matches.get_string("output"),
+            matches.get_string("emit"),
+            matches.get_string("format"),
+            matches.get_string("target"),
+            compiler_options(matches),
+            check_options(matches),
+        ),
+        Some(("run", matches)) => entry::run(
+            matches.get_string("input").unwrap(),
+            matches.get_string("output"),
+            matches.get_vec("args").map_or(&[], |args| args.as_slice()),
+            matches.get_string("backend"),
+            compiler_options(matches),
+            check_options(matches),
+            !matches.get_bool("no-cache"),
+        ),
+        Some(("check", matches)) => {
+            entry::check(matches.get_string("input").unwrap(), check_options(matches))
+        }
+        Some(("test", matches)) => entry::test(
+            matches.get_string("input").unwrap(),
+            compiler_options(matches),
+            check_options(matches),
+        ),
+        Some(("fmt", matches)) => entry::fmt(
+            matches.get_string("input").unwrap(),
+            matches.get_bool("check"),
+        ),
+        Some(("repl", _)) => entry::repl(),
+        #[cfg(feature = "lsp")]
+        Some(("lsp", _)) => entry::lsp(),
+        _ => unreachable!(),
+    };
+
+    exit(status);
+}
+
+// the only place the process exits from, once a command has finished. one
+// that failed has printed why already.
+fn exit(status: entry::Status) -> ! {
+    std::process::exit(status.unwrap_or(1))
 }
@@ -0,0 +1,280 @@
+use super::{Block, Expr, Part, Pattern, Spanned, Stmt};
+
+// an indented tree of `stmts`, one node per line, for `--emit=ast`.
+pub fn dump(stmts: &[Stmt]) -> String {
+    let mut out = String::new();
+
+    for stmt in stmts {
+        dump_stmt(&mut out, stmt, 0);
+    }
+
+    out
+}
+
+fn line(out: &mut String, depth: usize, text: &str) {
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(text);
+    out.push('\n');
+}
+
+fn dump_block(out: &mut String, block: &Block, depth: usize) {
+    for stmt in &block.stmts {
+        dump_stmt(out, stmt, depth);
+    }
+}
+
+fn dump_stmt(out: &mut String, stmt: &Stmt, depth: usize) {
+    match stmt {
+        Stmt::Variable {
+            name,
+            value,
+            is_mut,
+            ty,
+            ..
+        } => {
+            let kw = if *is_mut { "Variable mut" } else { "Variable" };
+
+            match ty {
+                Some(ty) => line(out, depth, &format!("{} {}: {}", kw, name, ty)),
+                None => line(out, depth, &format!("{} {}", kw, name)),
+            }
+
+            dump_expr(out, value, depth + 1);
+        }
+        Stmt::Const { name, value, ty } => {
+            match ty {
+                Some(ty) => line(out, depth, &format!("Const {}: {}", name, ty)),
+                None => line(out, depth, &format!("Const {}", name)),
+            }
+
+            dump_expr(out, value, depth + 1);
+        }
+        Stmt::Function {
+            name,
+            return_type,
+            args,
+            external,
+            links,
+            attributes,
+            is_varadic,
+            body,
+            ..
+        } => {
+            let mut params = args
+                .iter()
+                .map(|param| match &param.ty {
+                    Some(ty) => format!("{}: {}", param.name, ty),
+                    None => param.name.clone(),
+                })
+                .collect::<Vec<_>>();
+
+            if *is_varadic {
+                params.push("...".to_string());
+            }
+
+            let mut header = format!("Function {}({})", name, params.join(", "));
+
+            if let Some(ty) = return_type {
+                header.push_str(&format!(": {}", ty));
+            }
+
+            if let Some(external) = external {
+                header.push_str(&format!(" @requires({:?})", external));
+            }
+
+            for link in links {
+                header.push_str(&format!(" @link({:?})", link));
+            }
+
+            for attribute in attributes {
+                match &attribute.value {
+                    Some(value) => header.push_str(&format!(" @{}({:?})", attribute.name, value)),
+                    None => header.push_str(&format!(" @{}", attribute.name)),
+                }
+            }
+
+            line(out, depth, &header);
+
+            for param in args {
+                if let Some(default) = &param.default {
+                    line(out, depth + 1, &format!("Default {}", param.name));
+                    dump_expr(out, default, depth + 2);
+                }
+            }
+
+            dump_block(out, body, depth + 1);
+        }
+        Stmt::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            line(out, depth, "If");
+            dump_expr(out, condition, depth + 1);
+            line(out, depth, "Then");
+            dump_block(out, consequence, depth + 1);
+
+            if let Some(alt) = alternative {
+                line(out, depth, "Else");
+                dump_stmt(out, alt, depth + 1);
+            }
+        }
+        Stmt::For {
+            iterator,
+            start,
+            end,
+            body,
+            ..
+        } => {
+            line(out, depth, &format!("For {}", iterator));
+            dump_expr(out, start, depth + 1);
+            dump_expr(out, end, depth + 1);
+            line(out, depth, "Do");
+            dump_block(out, body, depth + 1);
+        }
+        Stmt::ForEach {
+            iterator,
+            iterable,
+            body,
+            ..
+        } => {
+            line(out, depth, &format!("ForEach {}", iterator));
+            dump_expr(out, iterable, depth + 1);
+            line(out, depth, "Do");
+            dump_block(out, body, depth + 1);
+        }
+        Stmt::Block(block, _) => {
+            line(out, depth, "Block");
+            dump_block(out, block, depth + 1);
+        }
+        Stmt::Assignment { name, value, op } => {
+            match op {
+                Some(op) => line(out, depth, &format!("Assignment {} {}=", name, op.repr())),
+                None => line(out, depth, &format!("Assignment {}", name)),
+            }
+
+            dump_expr(out, value, depth + 1);
+        }
+        Stmt::Import { path, .. } => line(out, depth, &format!("Import {:?}", path)),
+        Stmt::Type {
+            name,
+            external,
+            attributes,
+            ..
+        } => {
+            let mut header = format!("Type {}", name);
+
+            if let Some(external) = external {
+                header.push_str(&format!(" @requires({:?})", external));
+            }
+
+            for attribute in attributes {
+                match &attribute.value {
+                    Some(value) => header.push_str(&format!(" @{}({:?})", attribute.name, value)),
+                    None => header.push_str(&format!(" @{}", attribute.name)),
+                }
+            }
+
+            line(out, depth, &header);
+        }
+        Stmt::Return(value, _) => {
+            line(out, depth, "Return");
+
+            if let Some(value) = value {
+                dump_expr(out, value, depth + 1);
+            }
+        }
+        Stmt::Expr(expr) => dump_expr(out, expr, depth),
+        Stmt::Empty => line(out, depth, "Empty"),
+    }
+}
+
+fn dump_expr(out: &mut String, expr: &Spanned<Expr>, depth: usize) {
+    match &expr.node {
+        Expr::Binary { lhs, op, rhs } => {
+            line(out, depth, &format!("Binary {:?}", op.kind));
+            dump_expr(out, lhs, depth + 1);
+            dump_expr(out, rhs, depth + 1);
+        }
+        Expr::Unary { op, operand } => {
+            line(out, depth, &format!("Unary {:?}", op.kind));
+            dump_expr(out, operand, depth + 1);
+        }
+        Expr::Cast { value, ty } => {
+            line(
+                out,
+                depth,
+                &format!("Cast {}", ty.value.as_deref().unwrap_or_default()),
+            );
+            dump_expr(out, value, depth + 1);
+        }
+        Expr::Call { name, args, .. } => {
+            line(out, depth, &format!("Call {}", name));
+
+            for arg in args {
+                match &arg.name {
+                    Some(name) => {
+                        let name = name.value.as_deref().unwrap_or_default();
+                        line(out, depth + 1, &format!("Named {}", name));
+                        dump_expr(out, &arg.value, depth + 2);
+                    }
+                    None => dump_expr(out, &arg.value, depth + 1),
+                }
+            }
+        }
+        Expr::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            line(out, depth, "If");
+            dump_expr(out, condition, depth + 1);
+            dump_expr(out, consequence, depth + 1);
+            dump_expr(out, alternative, depth + 1);
+        }
+        Expr::Match { subject, arms } => {
+            line(out, depth, "Match");
+            dump_expr(out, subject, depth + 1);
+
+            for (pattern, value) in arms {
+                match pattern {
+                    Pattern::Literal(literal) => {
+                        line(out, depth + 1, "Arm");
+                        dump_expr(out, literal, depth + 2);
+                    }
+                    Pattern::Wildcard(_) => line(out, depth + 1, "Arm _"),
+                }
+
+                dump_expr(out, value, depth + 2);
+            }
+        }
+        Expr::Array(elements) => {
+            line(out, depth, "Array");
+
+            for element in elements {
+                dump_expr(out, element, depth + 1);
+            }
+        }
+        Expr::Index { target, index } => {
+            line(out, depth, "Index");
+            dump_expr(out, target, depth + 1);
+            dump_expr(out, index, depth + 1);
+        }
+        Expr::Identifier(name) => line(out, depth, &format!("Identifier {}", name)),
+        Expr::Number(num) => line(out, depth, &format!("Number {}", num)),
+        Expr::Float(num) => line(out, depth, &format!("Float {}", num)),
+        Expr::Bool(value) => line(out, depth, &format!("Bool {}", value)),
+        Expr::String(value) => line(out, depth, &format!("String {:?}", value)),
+        Expr::Char(value) => line(out, depth, &format!("Char {:?}", value)),
+        Expr::Interpolation(parts) => {
+            line(out, depth, "Interpolation");
+
+            for part in parts {
+                match part {
+                    Part::Text(text) => line(out, depth + 1, &format!("Text {:?}", text)),
+                    Part::Expr(expr) => dump_expr(out, expr, depth + 1),
+                }
+            }
+        }
+    }
+}
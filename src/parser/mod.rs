@@ -1,94 +1,480 @@
+mod dump;
 mod types;
 mod utils;
 
-use std::collections::{HashMap, VecDeque};
-
+pub use dump::dump;
 pub use types::Block;
 pub use utils::{ErrorType, ParserError};
 
-use crate::lexer::{Token, TokenKind};
-use crate::mapping;
+use crate::lexer::{Lexer, LexerError, Span, Token, TokenKind};
+
+pub use self::types::{
+    walk_block, walk_block_mut, walk_expr, walk_expr_mut, walk_pattern, walk_pattern_mut,
+    walk_stmt, walk_stmt_mut, walk_stmts, walk_stmts_mut, Arg, Attribute, Expr, Layout, Param,
+    Part, Pattern, Spanned, Stmt, Visitor, VisitorMut,
+};
+use self::utils::{Buffer, Tokens};
+
+// how deep expressions and blocks can nest, and how many binary operators a
+// statement can have. input past either is an error rather than the parser,
+// or what walks the AST after it, running out of stack.
+const MAX_DEPTH: usize = 64;
+const MAX_OPERATORS: usize = 256;
+
+pub struct Parser<'a> {
+    tokens: Tokens<'a>,
+    errors: Vec<ParserError>,
+    layout: Layout,
+    depth: usize,
+    // the binary operators in the statement being parsed, each one nests the
+    // expression before it a level deeper.
+    operators: usize,
+}
 
-pub use self::types::{Expr, Stmt};
-use self::utils::Buffer;
+impl<'a> Parser<'a> {
+    pub fn new(tokens: Vec<Token>) -> Parser<'a> {
+        Self::from_stream(tokens.into_iter().map(Ok))
+    }
 
-pub struct Parser {
-    tokens: VecDeque<Token>,
-}
+    // a parser that lexes `lexer`'s tokens as it goes, instead of all of
+    // them before it starts.
+    pub fn from_lexer(lexer: Lexer<'a>) -> Parser<'a> {
+        Self::from_stream(lexer)
+    }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Parser {
+    // like `from_lexer`, for tokens that come through something else first.
+    pub fn from_stream(tokens: impl Iterator<Item = Result<Token, LexerError>> + 'a) -> Parser<'a> {
         Parser {
-            tokens: tokens.into(),
+            tokens: Tokens::new(tokens),
+            errors: Vec::new(),
+            layout: Layout::default(),
+            depth: 0,
+            operators: 0,
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, ParserError> {
+    // the error that stopped the lexer, if it was stopped. the parse fails
+    // when it is, but its errors only come from the input ending early.
+    pub fn lexer_error(&mut self) -> Option<LexerError> {
+        self.tokens.take_error()
+    }
+
+    // what was recorded about the source's layout while parsing.
+    pub fn take_layout(&mut self) -> Layout {
+        std::mem::take(&mut self.layout)
+    }
+
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParserError>> {
         let mut stmts = Vec::<Stmt>::new();
 
-        while let Some(token) = self.tokens.peek() {
-            stmts.push(self.stmt(token)?);
+        while self.tokens.peek().is_some() {
+            if let Some(stmt) = self.recover() {
+                stmts.push(stmt);
+            }
+        }
+
+        if !self.errors.is_empty() {
+            return Err(std::mem::take(&mut self.errors));
         }
 
         Ok(stmts)
     }
 
-    fn primary(&mut self) -> Result<Stmt, ParserError> {
-        let token = self.tokens.consume();
+    // parses a statement, recording the error and skipping ahead to the next
+    // statement boundary if it fails so the rest of the input is still checked.
+    fn recover(&mut self) -> Option<Stmt> {
+        self.operators = 0;
+
+        if let Some(token) = self.tokens.peek() {
+            self.layout.lines.push(token.span.line);
+        }
 
-        match token.clone().kind {
-            TokenKind::String | TokenKind::Number | TokenKind::Float => Ok(token.into()),
-            TokenKind::Identifier => {
-                if let Some(peeked) = self.tokens.peek() {
-                    if peeked.kind == TokenKind::PareL {
-                        return self.call(token);
+        match self.stmt() {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self) {
+        // blocks opened after the error are skipped as a whole, a `}` that
+        // wasn't opened here belongs to the enclosing block.
+        let mut depth = 0;
+
+        while let Some(token) = self.tokens.peek() {
+            match token.kind {
+                TokenKind::BraceL => depth += 1,
+                TokenKind::BraceR if depth == 0 => return,
+                TokenKind::BraceR => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        self.tokens.consume().ok();
+                        return;
                     }
                 }
-
-                Ok(token.into())
+                TokenKind::Semi if depth == 0 => {
+                    self.tokens.consume().ok();
+                    return;
+                }
+                TokenKind::Func
+                | TokenKind::Import
+                | TokenKind::Type
+                | TokenKind::Let
+                | TokenKind::Const
+                | TokenKind::If
+                | TokenKind::For
+                | TokenKind::Return
+                    if depth == 0 =>
+                {
+                    return
+                }
+                _ => (),
             }
+
+            self.tokens.consume().ok();
+        }
+    }
+
+    // an operand with the casts that come after it, `as` binds tighter than
+    // any binary operator.
+    fn primary(&mut self) -> Result<Spanned<Expr>, ParserError> {
+        let mut value = self.operand()?;
+
+        while self.tokens.try_expect(&TokenKind::As).is_some() {
+            let ty = self.tokens.expect(TokenKind::Identifier)?;
+            let span = value.span.to(ty.span);
+
+            value = Spanned::new(
+                Expr::Cast {
+                    value: value.into(),
+                    ty,
+                },
+                span,
+            );
+        }
+
+        Ok(value)
+    }
+
+    // runs `parse` a level deeper than the parser is.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.depth == MAX_DEPTH {
+            let Some(token) = self.tokens.peek().cloned() else {
+                return self.tokens.eof("expression");
+            };
+
+            return ParserError::new(
+                ErrorType::NestedTooDeeply,
+                token,
+                format!("Nested more than {} levels deep", MAX_DEPTH),
+            );
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn operand(&mut self) -> Result<Spanned<Expr>, ParserError> {
+        self.nested(Self::term)
+    }
+
+    fn term(&mut self) -> Result<Spanned<Expr>, ParserError> {
+        if self.tokens.peek().is_none() {
+            return self.tokens.eof("expression");
+        }
+
+        let token = self.tokens.consume()?;
+
+        let primary = match token.kind.clone() {
+            TokenKind::String
+            | TokenKind::Char
+            | TokenKind::Number
+            | TokenKind::Float
+            | TokenKind::True
+            | TokenKind::False => token.into(),
+            TokenKind::Identifier => match self.tokens.peek() {
+                Some(peeked) if peeked.kind == TokenKind::PareL => self.call(token)?,
+                _ => token.into(),
+            },
             TokenKind::PareL => {
                 let expr = self.expr()?;
                 self.tokens.expect(TokenKind::PareR)?;
 
-                Ok(expr)
+                expr
+            }
+            TokenKind::BracketL => self.array(token)?,
+            TokenKind::If => self.if_expr(token)?,
+            TokenKind::Match => self.match_expr(token)?,
+            TokenKind::BitNot | TokenKind::Not | TokenKind::BitAnd | TokenKind::Multi => {
+                let operand = self.operand()?;
+                let span = token.span.to(operand.span);
+
+                Spanned::new(
+                    Expr::Unary {
+                        op: token,
+                        operand: operand.into(),
+                    },
+                    span,
+                )
+            }
+            // `**pp` is lexed as the power operator, it's two dereferences.
+            TokenKind::Expo => {
+                let operand = self.operand()?;
+                let span = token.span.to(operand.span);
+                let deref = Token::new(TokenKind::Multi, None, token.span);
+
+                let inner = Spanned::new(
+                    Expr::Unary {
+                        op: deref.clone(),
+                        operand: operand.into(),
+                    },
+                    span,
+                );
+
+                Spanned::new(
+                    Expr::Unary {
+                        op: deref,
+                        operand: inner.into(),
+                    },
+                    span,
+                )
             }
+            TokenKind::InterpolationStart => self.interpolation(token)?,
+            kind => {
+                return ParserError::new(
+                    ErrorType::UnexpectedToken,
+                    token,
+                    format!("Unexpected token occurred `{:#?}`", kind),
+                )
+            }
+        };
+
+        self.index(primary)
+    }
+
+    // an `if` with a value, `token` is the `if`. the `else` can't be left
+    // out, there'd be nothing for the expression to be otherwise.
+    fn if_expr(&mut self, token: Token) -> Result<Spanned<Expr>, ParserError> {
+        let condition = self.expr()?;
+        let (consequence, _) = self.branch()?;
+
+        self.tokens.expect(TokenKind::Else)?;
+
+        let (alternative, end) = match self.tokens.try_expect(&TokenKind::If) {
+            Some(token) => {
+                let alternative = self.if_expr(token)?;
+                let end = alternative.span;
+
+                (alternative, end)
+            }
+            None => self.branch()?,
+        };
+
+        Ok(Spanned::new(
+            Expr::If {
+                condition: condition.into(),
+                consequence: consequence.into(),
+                alternative: alternative.into(),
+            },
+            token.span.to(end),
+        ))
+    }
+
+    // `token` is the `match`. arms are separated by commas, and the one
+    // after the last arm can be left out.
+    fn match_expr(&mut self, token: Token) -> Result<Spanned<Expr>, ParserError> {
+        let subject = self.expr()?;
+        self.tokens.expect(TokenKind::BraceL)?;
+
+        let mut arms = vec![];
+
+        let close = loop {
+            if let Some(close) = self.tokens.try_expect(&TokenKind::BraceR) {
+                break close;
+            }
+
+            let pattern = self.pattern()?;
+            self.tokens.expect(TokenKind::FatArrow)?;
+            arms.push((pattern, self.expr()?));
+
+            if self.tokens.try_expect(&TokenKind::Comma).is_none() {
+                break self.tokens.expect(TokenKind::BraceR)?;
+            }
+        };
+
+        Ok(Spanned::new(
+            Expr::Match {
+                subject: subject.into(),
+                arms,
+            },
+            token.span.to(close.span),
+        ))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, ParserError> {
+        if self.tokens.peek().is_none() {
+            return self.tokens.eof("a literal or `_` to match against");
+        }
+
+        let token = self.tokens.consume()?;
+
+        match token.kind.clone() {
+            TokenKind::Identifier if token.value.as_deref() == Some("_") => {
+                Ok(Pattern::Wildcard(token.span))
+            }
+            TokenKind::String
+            | TokenKind::Char
+            | TokenKind::Number
+            | TokenKind::Float
+            | TokenKind::True
+            | TokenKind::False => Ok(Pattern::Literal(token.into())),
             kind => ParserError::new(
                 ErrorType::UnexpectedToken,
                 token,
-                format!("Unexpected token occurred `{:#?}`", kind),
+                format!(
+                    "Expected a literal or `_` to match against but found `{:?}`",
+                    kind
+                ),
             ),
         }
     }
 
-    fn binary(&mut self, mut lhs: Stmt, precedence: i8) -> Result<Stmt, ParserError> {
+    // the `{ value }` of an `if` expression, along with where its `}` is.
+    fn branch(&mut self) -> Result<(Spanned<Expr>, Span), ParserError> {
+        self.tokens.expect(TokenKind::BraceL)?;
+        let value = self.expr()?;
+        let close = self.tokens.expect(TokenKind::BraceR)?;
+
+        Ok((value, close.span))
+    }
+
+    // the elements of an array literal, `open` is its `[`.
+    fn array(&mut self, open: Token) -> Result<Spanned<Expr>, ParserError> {
+        let mut elements = Vec::new();
+
+        let close = loop {
+            if let Some(close) = self.tokens.try_expect(&TokenKind::BracketR) {
+                break close;
+            }
+
+            elements.push(self.expr()?);
+
+            if self.tokens.try_expect(&TokenKind::Comma).is_none() {
+                break self.tokens.expect(TokenKind::BracketR)?;
+            }
+        };
+
+        Ok(Spanned::new(
+            Expr::Array(elements),
+            open.span.to(close.span),
+        ))
+    }
+
+    fn interpolation(&mut self, start: Token) -> Result<Spanned<Expr>, ParserError> {
+        let mut parts = Vec::new();
+
+        let end = loop {
+            if let Some(end) = self.tokens.try_expect(&TokenKind::InterpolationEnd) {
+                break end;
+            }
+
+            if let Some(text) = self.tokens.try_expect(&TokenKind::String) {
+                parts.push(Part::Text(text.text()));
+                continue;
+            }
+
+            self.tokens.expect(TokenKind::BraceL)?;
+            parts.push(Part::Expr(Box::new(self.expr()?)));
+            self.tokens.expect(TokenKind::BraceR)?;
+        };
+
+        Ok(Spanned::new(
+            Expr::Interpolation(parts),
+            start.span.to(end.span),
+        ))
+    }
+
+    fn index(&mut self, mut target: Spanned<Expr>) -> Result<Spanned<Expr>, ParserError> {
+        while self.tokens.try_expect(&TokenKind::BracketL).is_some() {
+            let index = self.expr()?;
+            let close = self.tokens.expect(TokenKind::BracketR)?;
+            let span = target.span.to(close.span);
+
+            target = Spanned::new(
+                Expr::Index {
+                    target: target.into(),
+                    index: index.into(),
+                },
+                span,
+            );
+        }
+
+        Ok(target)
+    }
+
+    fn binary(
+        &mut self,
+        mut lhs: Spanned<Expr>,
+        precedence: i8,
+    ) -> Result<Spanned<Expr>, ParserError> {
         while let Some(token) = self.tokens.peek() {
             if token.kind.precedence() < precedence {
                 return Ok(lhs);
             }
 
-            let op = self.tokens.consume();
+            let op = self.tokens.consume()?;
+
+            // there are no statements inside expressions, so every operator
+            // up to the next statement is part of the same one.
+            self.operators += 1;
+
+            if self.operators > MAX_OPERATORS {
+                return ParserError::new(
+                    ErrorType::NestedTooDeeply,
+                    op,
+                    format!("More than {} operators in one statement", MAX_OPERATORS),
+                );
+            }
 
             let mut rhs = self.primary()?;
 
             if let Some(token) = self.tokens.peek() {
-                if op.kind.precedence() < token.kind.precedence() {
-                    rhs = self.binary(rhs, op.kind.precedence() + 1)?;
+                let (current, next) = (op.kind.precedence(), token.kind.precedence());
+
+                if op.kind.is_right_associative() && current == next {
+                    rhs = self.nested(|parser| parser.binary(rhs, current))?;
+                } else if current < next {
+                    rhs = self.nested(|parser| parser.binary(rhs, current + 1))?;
                 }
             }
 
-            lhs = Expr::Binary {
-                lhs: lhs.into(),
-                op,
-                rhs: rhs.into(),
-            }
-            .into();
+            let span = lhs.span.to(rhs.span);
+
+            lhs = Spanned::new(
+                Expr::Binary {
+                    lhs: lhs.into(),
+                    op,
+                    rhs: rhs.into(),
+                },
+                span,
+            );
         }
 
         Ok(lhs)
     }
 
-    fn expr(&mut self) -> Result<Stmt, ParserError> {
+    fn expr(&mut self) -> Result<Spanned<Expr>, ParserError> {
         let primary = self.primary()?;
         let expr = self.binary(primary, 0)?;
 
@@ -96,6 +482,10 @@ impl Parser {
     }
 
     fn block(&mut self) -> Result<Block, ParserError> {
+        self.nested(Self::braced)
+    }
+
+    fn braced(&mut self) -> Result<Block, ParserError> {
         self.tokens.expect(TokenKind::BraceL)?;
 
         let mut stmts = Vec::<Stmt>::new();
@@ -105,36 +495,113 @@ impl Parser {
                 break;
             }
 
-            stmts.push(self.stmt(token)?);
+            if let Some(stmt) = self.recover() {
+                stmts.push(stmt);
+            }
         }
 
         self.tokens.expect(TokenKind::BraceR)?;
         Ok(Block { stmts })
     }
 
-    fn stmt(&mut self, token: Token) -> Result<Stmt, ParserError> {
-        let stmt = match token.kind {
+    fn stmt(&mut self) -> Result<Stmt, ParserError> {
+        let Some(token) = self.tokens.peek() else {
+            return self.expr_stmt();
+        };
+
+        match token.kind {
             TokenKind::Func => self.function(),
             TokenKind::Let => self.variable(),
+            TokenKind::Const => self.constant(),
             TokenKind::Return => self.return_(),
             TokenKind::Identifier => self.ident(),
             TokenKind::If => self.if_(),
             TokenKind::For => self.for_(),
-            TokenKind::Semi => Ok(Stmt::Empty),
-            _ => self.expr(),
-        };
+            TokenKind::Import => self.import(),
+            TokenKind::Type => self.type_(),
+            TokenKind::BraceL => {
+                let token = token.clone();
+                Ok(Stmt::Block(self.block()?, token))
+            }
+            TokenKind::Semi => {
+                self.tokens.consume()?;
+                Ok(Stmt::Empty)
+            }
+            _ => self.expr_stmt(),
+        }
+    }
+
+    // an expression on its own, like a call whose result isn't used.
+    fn expr_stmt(&mut self) -> Result<Stmt, ParserError> {
+        let expr = self.expr()?;
+        self.tokens.try_expect(&TokenKind::Semi);
 
-        stmt
+        Ok(Stmt::Expr(expr))
+    }
+
+    fn import(&mut self) -> Result<Stmt, ParserError> {
+        let token = self.tokens.expect(TokenKind::Import)?;
+        let path = self.tokens.expect(TokenKind::String)?.text();
+        self.tokens.expect(TokenKind::Semi)?;
+
+        Ok(Stmt::Import { path, token })
+    }
+
+    // `type FILE @opaque;`, a C type for the program to point to. the type
+    // checker sees that it's `@opaque`, the only kind there is.
+    fn type_(&mut self) -> Result<Stmt, ParserError> {
+        self.tokens.expect(TokenKind::Type)?;
+
+        let token = self.tokens.expect(TokenKind::Identifier)?;
+        let mut external = None;
+        let mut attributes = vec![];
+
+        while let Some(at) = self.tokens.try_expect(&TokenKind::At) {
+            let attribute = self.attribute()?;
+
+            match (attribute.name.as_str(), attribute.value) {
+                ("requires", Some(value)) => external = Some(value),
+                ("requires", None) => {
+                    return ParserError::new(
+                        ErrorType::InvalidAttribute,
+                        at,
+                        "`@requires` needs a value, like `@requires(\"...\")`".to_string(),
+                    )
+                }
+                (_, value) => attributes.push(Attribute { value, ..attribute }),
+            }
+        }
+
+        self.tokens.expect(TokenKind::Semi)?;
+
+        Ok(Stmt::Type {
+            name: token.text(),
+            external,
+            attributes,
+            token,
+        })
     }
 
     fn for_(&mut self) -> Result<Stmt, ParserError> {
         self.tokens.expect(TokenKind::For)?;
 
-        let iterator = self.tokens.expect(TokenKind::Identifier)?.value.unwrap();
+        let token = self.tokens.expect(TokenKind::Identifier)?;
+        let iterator = token.text();
         self.tokens.expect(TokenKind::In)?;
 
         let start = self.expr()?;
-        self.tokens.expect(TokenKind::DotDot)?;
+
+        if self.tokens.try_expect(&TokenKind::DotDot).is_none() {
+            let body = self.block()?;
+
+            return Ok(Stmt::ForEach {
+                iterator,
+                iterable: start.into(),
+                body,
+                token,
+            });
+        }
+
         let end = self.expr()?;
 
         let body = self.block()?;
@@ -144,6 +611,7 @@ impl Parser {
             start: start.into(),
             end: end.into(),
             body,
+            token,
         })
     }
 
@@ -157,17 +625,14 @@ impl Parser {
         let consequence = self.block()?;
 
         let mut alternative = None;
-        if self.tokens.try_expect(&TokenKind::Else).is_some() {
-            let is_else_if = self
-                .tokens
-                .peek()
-                .map_or(false, |t| t.kind == TokenKind::If);
+        if let Some(token) = self.tokens.try_expect(&TokenKind::Else) {
+            let is_else_if = self.tokens.peek().is_some_and(|t| t.kind == TokenKind::If);
 
             if is_else_if {
                 alternative = Some(Box::new(self.if_()?));
             } else {
                 let else_body = self.block()?;
-                alternative = Some(Box::new(Stmt::Block(else_body)));
+                alternative = Some(Box::new(Stmt::Block(else_body, token)));
             }
         }
 
@@ -180,44 +645,61 @@ impl Parser {
 
     fn ident(&mut self) -> Result<Stmt, ParserError> {
         if let Some(eq) = self.tokens.get(1) {
-            if eq.kind == TokenKind::Equals {
+            if eq.kind == TokenKind::Equals || eq.kind.compound().is_some() {
                 return self.assignment();
             }
         }
 
-        self.expr()
+        self.expr_stmt()
     }
 
     fn assignment(&mut self) -> Result<Stmt, ParserError> {
-        let token = self.tokens.consume(); // identifier
-        let name = token.value.unwrap();
+        let token = self.tokens.consume()?; // identifier
+        let name = token.text();
 
-        self.tokens.consume(); // equals
+        let eq = self.tokens.consume()?;
+        let op = eq
+            .kind
+            .compound()
+            .map(|kind| Token::new(kind, None, eq.span));
 
         let value = self.expr()?;
 
-        self.tokens.expect(TokenKind::Semi)?;
+        self.tokens.try_expect(&TokenKind::Semi);
 
-        return Ok(Stmt::Assignment {
+        Ok(Stmt::Assignment {
             name,
             value: value.into(),
-        });
+            op,
+        })
     }
 
+    // `return value;`, or `return;` from a function that doesn't give one.
     fn return_(&mut self) -> Result<Stmt, ParserError> {
-        self.tokens.expect(TokenKind::Return)?;
-        let expr = self.expr()?;
+        let token = self.tokens.expect(TokenKind::Return)?;
+
+        let value = match self.tokens.peek().map(|next| &next.kind) {
+            None | Some(TokenKind::Semi | TokenKind::BraceR) => None,
+            Some(_) => Some(self.expr()?.into()),
+        };
 
         self.tokens.try_expect(&TokenKind::Semi);
 
-        Ok(Stmt::Return(expr.into()))
+        Ok(Stmt::Return(value, token))
     }
 
     fn variable(&mut self) -> Result<Stmt, ParserError> {
         self.tokens.expect(TokenKind::Let)?;
 
         let is_mut = self.tokens.try_expect(&TokenKind::Mut).is_some();
-        let name = self.tokens.expect(TokenKind::Identifier)?.value.unwrap();
+        let token = self.tokens.expect(TokenKind::Identifier)?;
+        let name = token.text();
+
+        let mut ty = None;
+
+        if self.tokens.try_expect(&TokenKind::Colon).is_some() {
+            ty = Some(self.type_name()?);
+        }
 
         self.tokens.expect(TokenKind::Equals)?;
         let value = self.expr()?;
@@ -228,55 +710,100 @@ impl Parser {
             name,
             value: value.into(),
             is_mut,
+            ty,
+            token,
         })
     }
 
-    fn call(&mut self, token: Token) -> Result<Stmt, ParserError> {
-        let name = token.value.clone().unwrap();
-        self.tokens.expect(TokenKind::PareL)?;
+    fn constant(&mut self) -> Result<Stmt, ParserError> {
+        self.tokens.expect(TokenKind::Const)?;
 
-        if let Some(_) = self.tokens.try_expect(&TokenKind::PareR) {
-            return Ok(Stmt::Call {
-                name,
-                args: vec![],
-                token,
-            });
+        let name = self.tokens.expect(TokenKind::Identifier)?.text();
+
+        let mut ty = None;
+
+        if self.tokens.try_expect(&TokenKind::Colon).is_some() {
+            ty = Some(self.type_name()?);
         }
 
+        self.tokens.expect(TokenKind::Equals)?;
+        let value = self.expr()?;
+
+        self.tokens.try_expect(&TokenKind::Semi);
+
+        Ok(Stmt::Const {
+            name,
+            value: value.into(),
+            ty,
+        })
+    }
+
+    fn call(&mut self, token: Token) -> Result<Spanned<Expr>, ParserError> {
+        let name = token.text();
+        self.tokens.expect(TokenKind::PareL)?;
+
         let mut args = Vec::new();
 
-        loop {
-            let arg = self.expr()?;
-            args.push(arg.into());
+        let close = loop {
+            if let Some(close) = self.tokens.try_expect(&TokenKind::PareR) {
+                break close;
+            }
 
-            if let Some(_) = self.tokens.try_expect(&TokenKind::PareR) {
-                break;
+            args.push(self.arg()?);
+
+            if self.tokens.try_expect(&TokenKind::Comma).is_none() {
+                break self.tokens.expect(TokenKind::PareR)?;
             }
+        };
 
-            self.tokens.expect(TokenKind::Comma)?;
-        }
+        let span = token.span.to(close.span);
 
-        self.tokens.try_expect(&TokenKind::Semi);
+        Ok(Spanned::new(Expr::Call { name, args, token }, span))
+    }
+
+    // an argument of a call, which is passed by name when it starts with
+    // `name:`.
+    fn arg(&mut self) -> Result<Arg, ParserError> {
+        let named = self
+            .tokens
+            .peek()
+            .is_some_and(|ident| ident.kind == TokenKind::Identifier)
+            && self
+                .tokens
+                .get(1)
+                .is_some_and(|colon| colon.kind == TokenKind::Colon);
+
+        let name = if named {
+            let name = self.tokens.consume()?;
+            self.tokens.consume()?;
 
-        Ok(Stmt::Call { name, args, token })
+            Some(name)
+        } else {
+            None
+        };
+
+        Ok(Arg {
+            name,
+            value: self.expr()?,
+        })
     }
 
     fn args(
         &mut self,
         with_types: bool,
         should_be_unique: bool,
-    ) -> Result<(HashMap<String, Option<String>>, bool), ParserError> {
+    ) -> Result<(Vec<Param>, bool), ParserError> {
         self.tokens.expect(TokenKind::PareL)?;
 
-        if let Some(_) = self.tokens.try_expect(&TokenKind::PareR) {
-            return Ok((mapping!(), false));
+        if self.tokens.try_expect(&TokenKind::PareR).is_some() {
+            return Ok((vec![], false));
         }
 
-        let mut args = mapping!();
+        let mut args: Vec<Param> = vec![];
         let mut is_varadic = false;
 
         loop {
-            if let Some(_) = self.tokens.try_expect(&TokenKind::Ellipsis) {
+            if self.tokens.try_expect(&TokenKind::Ellipsis).is_some() {
                 self.tokens.try_expect(&TokenKind::Comma);
                 self.tokens.expect(TokenKind::PareR)?;
 
@@ -286,9 +813,9 @@ impl Parser {
             }
 
             let arg = self.tokens.expect(TokenKind::Identifier)?;
-            let name = arg.value.clone().unwrap();
+            let name = arg.text();
 
-            if should_be_unique && args.contains_key(arg.value.as_ref().unwrap()) {
+            if should_be_unique && args.iter().any(|param| param.name == name) {
                 return ParserError::new(
                     ErrorType::DuplicateArgument,
                     arg,
@@ -297,8 +824,8 @@ impl Parser {
             }
 
             let type_: Option<String> = if with_types {
-                if let Some(_) = self.tokens.try_expect(&TokenKind::Colon) {
-                    Some(self.tokens.expect(TokenKind::Identifier)?.value.unwrap())
+                if self.tokens.try_expect(&TokenKind::Colon).is_some() {
+                    Some(self.type_name()?)
                 } else {
                     None
                 }
@@ -306,68 +833,133 @@ impl Parser {
                 None
             };
 
-            args.insert(name, type_);
+            let default = match self.tokens.try_expect(&TokenKind::Equals) {
+                Some(_) => Some(self.expr()?),
+                None => None,
+            };
 
-            if let Some(_) = self.tokens.try_expect(&TokenKind::PareR) {
+            args.push(Param {
+                name,
+                ty: type_,
+                default,
+                span: arg.span,
+            });
+
+            if self.tokens.try_expect(&TokenKind::PareR).is_some() {
                 break;
             }
 
             self.tokens.expect(TokenKind::Comma)?;
         }
 
-        return Ok((args, is_varadic));
+        Ok((args, is_varadic))
     }
 
-    fn attribute(&mut self) -> Result<Stmt, ParserError> {
-        let name = self.tokens.expect(TokenKind::Identifier)?.value.unwrap();
+    // a type as it's written, like `Int` or `Ptr<Ptr<Int>>`. the `>>` closing
+    // two at once is read as a shift.
+    fn type_name(&mut self) -> Result<String, ParserError> {
+        let mut name = self.tokens.expect(TokenKind::Identifier)?.text();
+        let mut open = 0;
 
-        self.tokens.expect(TokenKind::PareL)?;
-        let value = self.tokens.expect(TokenKind::String)?.value.unwrap();
-        self.tokens.expect(TokenKind::PareR)?;
+        while self.tokens.try_expect(&TokenKind::BinaryLt).is_some() {
+            name.push('<');
+            name.push_str(&self.tokens.expect(TokenKind::Identifier)?.text());
+            open += 1;
+        }
+
+        while open > 0 {
+            if open > 1 && self.tokens.try_expect(&TokenKind::Shr).is_some() {
+                name.push_str(">>");
+                open -= 2;
+            } else {
+                self.tokens.expect(TokenKind::BinaryGt)?;
+                name.push('>');
+                open -= 1;
+            }
+        }
+
+        Ok(name)
+    }
+
+    // `@name` or `@name("value")`, after the `@`.
+    fn attribute(&mut self) -> Result<Attribute, ParserError> {
+        let token = self.tokens.expect(TokenKind::Identifier)?;
+        let mut value = None;
 
-        Ok(Stmt::Attribute { name, value })
+        if self.tokens.try_expect(&TokenKind::PareL).is_some() {
+            value = Some(self.tokens.expect(TokenKind::String)?.text());
+            self.tokens.expect(TokenKind::PareR)?;
+        }
+
+        Ok(Attribute {
+            name: token.text(),
+            value,
+            token,
+        })
     }
 
     fn function(&mut self) -> Result<Stmt, ParserError> {
         self.tokens.expect(TokenKind::Func)?;
 
-        let name = self.tokens.expect(TokenKind::Identifier)?.value.unwrap();
+        let token = self.tokens.expect(TokenKind::Identifier)?;
+        let name = token.text();
         let (args, is_varadic) = self.args(true, true)?;
 
         let mut return_type = None;
 
-        if let Some(_) = self.tokens.try_expect(&TokenKind::Colon) {
-            return_type = Some(self.tokens.expect(TokenKind::Identifier)?.value.unwrap());
+        if self.tokens.try_expect(&TokenKind::Colon).is_some() {
+            return_type = Some(self.type_name()?);
         }
 
-        let external = self.tokens.try_expect(&TokenKind::At);
-        if external.is_some() {
-            let attr = self.attribute()?;
-
-            if let Stmt::Attribute {
-                name: attr_name,
-                value,
-            } = attr
-            {
-                if attr_name != "requires" {
+        let mut external = None;
+        let mut links = vec![];
+        let mut link_token = None;
+        let mut attributes = vec![];
+
+        while let Some(at) = self.tokens.try_expect(&TokenKind::At) {
+            let attribute = self.attribute()?;
+
+            match (attribute.name.as_str(), attribute.value) {
+                ("requires", Some(value)) => external = Some(value),
+                ("link", Some(value)) => {
+                    links.push(value);
+                    link_token = Some(at);
+                }
+                ("requires" | "link", None) => {
                     return ParserError::new(
                         ErrorType::InvalidAttribute,
-                        external.unwrap(),
-                        format!("Invalid attribute `{}`, expected `requires`", attr_name),
-                    );
+                        at,
+                        format!("`@{0}` needs a value, like `@{0}(\"...\")`", attribute.name),
+                    )
                 }
-
-                return Ok(Stmt::Function {
-                    name,
-                    return_type,
-                    args,
-                    external: Some(value),
-                    is_varadic,
-                    body: Block { stmts: vec![] },
-                });
+                // the rest are checked along with the types.
+                (_, value) => attributes.push(Attribute { value, ..attribute }),
             }
         }
 
+        // `@link` only makes sense on an external function.
+        if let (None, Some(token)) = (&external, link_token) {
+            return ParserError::new(
+                ErrorType::InvalidAttribute,
+                token,
+                "`@link` needs a `@requires` header to go with it".to_string(),
+            );
+        }
+
+        if external.is_some() {
+            return Ok(Stmt::Function {
+                name,
+                return_type,
+                args,
+                external,
+                links,
+                attributes,
+                is_varadic,
+                body: Block { stmts: vec![] },
+                token,
+            });
+        }
+
         let body = self.block()?;
         self.tokens.try_expect(&TokenKind::Semi);
 
@@ -378,6 +970,9 @@ impl Parser {
             body,
             is_varadic,
             external: None,
+            links: vec![],
+            attributes,
+            token,
         })
     }
 }
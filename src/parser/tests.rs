@@ -1,7 +1,41 @@
 use super::*;
 
-use crate::lexer::Lexer;
-use crate::mapping;
+use crate::lexer::{Lexer, Span};
+
+// a parameter without a default, whose name is at `span`.
+fn param(name: &str, ty: &str, span: Span) -> Param {
+    Param {
+        name: String::from(name),
+        ty: Some(String::from(ty)),
+        default: None,
+        span,
+    }
+}
+
+// `node` where the parser says it is, to spell out the trees below.
+fn at<T>(node: T, start: usize, end: usize, line: usize, column: usize) -> Spanned<T> {
+    Spanned::new(node, Span::new(start, end, line, column))
+}
+
+#[test]
+fn char_variable() {
+    let tokens = Lexer::new("let c = 'x';").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).variable().unwrap(),
+        Stmt::Variable {
+            name: String::from("c"),
+            value: at(Expr::Char('x'), 8, 11, 1, 9).into(),
+            is_mut: false,
+            ty: None,
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("c")),
+                Span::new(4, 5, 1, 5)
+            )
+        }
+    )
+}
 
 #[test]
 fn mut_variable() {
@@ -10,14 +44,41 @@ fn mut_variable() {
             let mut foo = "bar";
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).variable().unwrap(),
         Stmt::Variable {
             name: String::from("foo"),
-            value: Expr::String(String::from("bar")).into(),
-            is_mut: true
+            value: at(Expr::String(String::from("bar")), 27, 32, 2, 27).into(),
+            is_mut: true,
+            ty: None,
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("foo")),
+                Span::new(21, 24, 2, 21)
+            )
+        }
+    )
+}
+
+#[test]
+fn annotated_variable() {
+    let tokens = Lexer::new("let mut n: Int = 3;").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).variable().unwrap(),
+        Stmt::Variable {
+            name: String::from("n"),
+            value: at(Expr::Number(String::from("3")), 17, 18, 1, 18).into(),
+            is_mut: true,
+            ty: Some(String::from("Int")),
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("n")),
+                Span::new(8, 9, 1, 9)
+            )
         }
     )
 }
@@ -31,24 +92,57 @@ fn variable_re_assignment() {
             foo = "baz";
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).parse().unwrap(),
         vec![
             Stmt::Variable {
                 name: String::from("foo"),
-                value: Expr::String(String::from("bar")).into(),
-                is_mut: true
+                value: at(Expr::String(String::from("bar")), 27, 32, 2, 27).into(),
+                is_mut: true,
+                ty: None,
+                token: Token::new(
+                    TokenKind::Identifier,
+                    Some(String::from("foo")),
+                    Span::new(21, 24, 2, 21)
+                )
             },
             Stmt::Assignment {
                 name: String::from("foo"),
-                value: Expr::String(String::from("baz")).into()
+                value: at(Expr::String(String::from("baz")), 53, 58, 4, 19).into(),
+                op: None
             }
         ]
     )
 }
 
+#[test]
+fn compound_assignment() {
+    let tokens = Lexer::new("x *= 2 + 1;").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap(),
+        vec![Stmt::Assignment {
+            name: String::from("x"),
+            value: at(
+                Expr::Binary {
+                    lhs: at(Expr::Number(String::from("2")), 5, 6, 1, 6).into(),
+                    op: Token::new(TokenKind::Add, None, Span::new(7, 8, 1, 8)),
+                    rhs: at(Expr::Number(String::from("1")), 9, 10, 1, 10).into(),
+                },
+                5,
+                10,
+                1,
+                6
+            )
+            .into(),
+            op: Some(Token::new(TokenKind::Multi, None, Span::new(2, 4, 1, 3)))
+        }]
+    )
+}
+
 #[test]
 fn basic_arithmetic() {
     let tokens = Lexer::new(
@@ -56,34 +150,65 @@ fn basic_arithmetic() {
             let foo = 9 + 10 * round(3.14);
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).variable().unwrap(),
         Stmt::Variable {
             name: String::from("foo"),
-            value: Expr::Binary {
-                lhs: Expr::Number(String::from("9")).into(),
-                op: Token::new(TokenKind::Add, None, 2, 25),
-                rhs: Expr::Binary {
-                    lhs: Expr::Number(String::from("10")).into(),
-                    op: Token::new(TokenKind::Multi, None, 2, 30),
-                    rhs: Stmt::Call {
-                        name: String::from("round"),
-                        args: vec![Expr::Float(String::from("3.14")).into()],
-                        token: Token::new(
-                            TokenKind::Identifier,
-                            Some(String::from("round")),
-                            2,
-                            32
-                        )
-                    }
+            value: at(
+                Expr::Binary {
+                    lhs: at(Expr::Number(String::from("9")), 23, 24, 2, 23).into(),
+                    op: Token::new(TokenKind::Add, None, Span::new(25, 26, 2, 25)),
+                    rhs: at(
+                        Expr::Binary {
+                            lhs: at(Expr::Number(String::from("10")), 27, 29, 2, 27).into(),
+                            op: Token::new(TokenKind::Multi, None, Span::new(30, 31, 2, 30)),
+                            rhs: at(
+                                Expr::Call {
+                                    name: String::from("round"),
+                                    args: vec![at(
+                                        Expr::Float(String::from("3.14")),
+                                        38,
+                                        42,
+                                        2,
+                                        38
+                                    )
+                                    .into()],
+                                    token: Token::new(
+                                        TokenKind::Identifier,
+                                        Some(String::from("round")),
+                                        Span::new(32, 37, 2, 32)
+                                    )
+                                },
+                                32,
+                                43,
+                                2,
+                                32
+                            )
+                            .into()
+                        },
+                        27,
+                        43,
+                        2,
+                        27
+                    )
                     .into()
-                }
-                .into()
-            }
+                },
+                23,
+                43,
+                2,
+                23
+            )
             .into(),
-            is_mut: false
+            is_mut: false,
+            ty: None,
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("foo")),
+                Span::new(17, 20, 2, 17)
+            )
         }
     )
 }
@@ -95,29 +220,55 @@ fn mut_variable_with_nested_arithmetic() {
             let mut x = (4 + 5) + 10 * 3;
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).variable().unwrap(),
         Stmt::Variable {
             name: String::from("x"),
-            value: Expr::Binary {
-                lhs: Expr::Binary {
-                    lhs: Expr::Number(String::from("4")).into(),
-                    op: Token::new(TokenKind::Add, None, 2, 28),
-                    rhs: Expr::Number(String::from("5")).into()
-                }
-                .into(),
-                op: Token::new(TokenKind::Add, None, 2, 33),
-                rhs: Expr::Binary {
-                    lhs: Expr::Number(String::from("10")).into(),
-                    op: Token::new(TokenKind::Multi, None, 2, 38),
-                    rhs: Expr::Number(String::from("3")).into()
-                }
-                .into()
-            }
+            value: at(
+                Expr::Binary {
+                    // the parentheses aren't part of the span.
+                    lhs: at(
+                        Expr::Binary {
+                            lhs: at(Expr::Number(String::from("4")), 26, 27, 2, 26).into(),
+                            op: Token::new(TokenKind::Add, None, Span::new(28, 29, 2, 28)),
+                            rhs: at(Expr::Number(String::from("5")), 30, 31, 2, 30).into()
+                        },
+                        26,
+                        31,
+                        2,
+                        26
+                    )
+                    .into(),
+                    op: Token::new(TokenKind::Add, None, Span::new(33, 34, 2, 33)),
+                    rhs: at(
+                        Expr::Binary {
+                            lhs: at(Expr::Number(String::from("10")), 35, 37, 2, 35).into(),
+                            op: Token::new(TokenKind::Multi, None, Span::new(38, 39, 2, 38)),
+                            rhs: at(Expr::Number(String::from("3")), 40, 41, 2, 40).into()
+                        },
+                        35,
+                        41,
+                        2,
+                        35
+                    )
+                    .into()
+                },
+                26,
+                41,
+                2,
+                26
+            )
             .into(),
-            is_mut: true
+            is_mut: true,
+            ty: None,
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("x")),
+                Span::new(21, 22, 2, 21)
+            )
         }
     )
 }
@@ -131,24 +282,44 @@ fn function_body() {
             }
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).function().unwrap(),
         Stmt::Function {
             name: String::from("main"),
-            args: mapping!(),
+            args: vec![],
             external: None,
+            links: vec![],
+            attributes: vec![],
             return_type: None,
             is_varadic: false,
             body: Block {
-                stmts: vec![Stmt::Call {
-                    name: String::from("print"),
-                    args: vec![Expr::String(String::from("Hello, World!")).into()],
-                    token: Token::new(TokenKind::Identifier, Some(String::from("print")), 3, 17)
-                }
+                stmts: vec![at(
+                    Expr::Call {
+                        name: String::from("print"),
+                        args: vec![
+                            at(Expr::String(String::from("Hello, World!")), 49, 64, 3, 23).into()
+                        ],
+                        token: Token::new(
+                            TokenKind::Identifier,
+                            Some(String::from("print")),
+                            Span::new(43, 48, 3, 17)
+                        )
+                    },
+                    43,
+                    65,
+                    3,
+                    17
+                )
                 .into()]
-            }
+            },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("main")),
+                Span::new(18, 22, 2, 18)
+            )
         }
     )
 }
@@ -162,29 +333,46 @@ fn function_args() {
             }
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).function().unwrap(),
         Stmt::Function {
             name: String::from("sum"),
-            args: mapping!(
-                String::from("x") => Some(String::from("Int")),
-                String::from("y") => Some(String::from("Int"))
-            ),
+            args: vec![
+                param("x", "Int", Span::new(22, 23, 2, 22)),
+                param("y", "Int", Span::new(30, 31, 2, 30)),
+            ],
             external: None,
+            links: vec![],
+            attributes: vec![],
             return_type: None,
             is_varadic: false,
             body: Block {
                 stmts: vec![Stmt::Return(
-                    Expr::Binary {
-                        lhs: Expr::Identifier(String::from("x")).into(),
-                        op: Token::new(TokenKind::Add, None, 3, 26),
-                        rhs: Expr::Identifier(String::from("y")).into()
-                    }
-                    .into()
+                    Some(
+                        at(
+                            Expr::Binary {
+                                lhs: at(Expr::Identifier(String::from("x")), 63, 64, 3, 24).into(),
+                                op: Token::new(TokenKind::Add, None, Span::new(65, 66, 3, 26)),
+                                rhs: at(Expr::Identifier(String::from("y")), 67, 68, 3, 28).into()
+                            },
+                            63,
+                            68,
+                            3,
+                            24
+                        )
+                        .into()
+                    ),
+                    Token::new(TokenKind::Return, None, Span::new(56, 62, 3, 17))
                 )]
-            }
+            },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("sum")),
+                Span::new(18, 21, 2, 18)
+            )
         }
     )
 }
@@ -198,35 +386,196 @@ fn attribute_funcs() {
             func println(fmt: String, ...) @requires("stdio.h")
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).parse().unwrap(),
         vec![
             Stmt::Function {
                 name: String::from("printf"),
-                args: mapping!(
-                    String::from("fmt") => Some(String::from("String"))
-                ),
+                args: vec![param("fmt", "String", Span::new(25, 28, 2, 25))],
                 external: Some(String::from("stdio.h")),
+                links: vec![],
+                attributes: vec![],
                 return_type: None,
                 is_varadic: false,
-                body: Block { stmts: vec![] }
+                body: Block { stmts: vec![] },
+                token: Token::new(
+                    TokenKind::Identifier,
+                    Some(String::from("printf")),
+                    Span::new(18, 24, 2, 18)
+                )
             },
             Stmt::Function {
                 name: String::from("println"),
-                args: mapping!(
-                    String::from("fmt") => Some(String::from("String"))
-                ),
+                args: vec![param("fmt", "String", Span::new(85, 88, 4, 26))],
                 external: Some(String::from("stdio.h")),
+                links: vec![],
+                attributes: vec![],
                 return_type: None,
                 is_varadic: true,
-                body: Block { stmts: vec![] }
+                body: Block { stmts: vec![] },
+                token: Token::new(
+                    TokenKind::Identifier,
+                    Some(String::from("println")),
+                    Span::new(77, 84, 4, 18)
+                )
             },
         ]
     )
 }
 
+#[test]
+fn link_attribute() {
+    let tokens = Lexer::new(r#"func sqrt(x: float): float @requires("math.h") @link("m")"#)
+        .lex()
+        .unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap(),
+        vec![Stmt::Function {
+            name: String::from("sqrt"),
+            args: vec![param("x", "float", Span::new(10, 11, 1, 11))],
+            external: Some(String::from("math.h")),
+            links: vec![String::from("m")],
+            attributes: vec![],
+            return_type: Some(String::from("float")),
+            is_varadic: false,
+            body: Block { stmts: vec![] },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("sqrt")),
+                Span::new(5, 9, 1, 6)
+            )
+        }]
+    );
+
+    let tokens = Lexer::new(r#"func sqrt(x: float): float @link("m")"#)
+        .lex()
+        .unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap_err()[0].r#type,
+        ErrorType::InvalidAttribute
+    );
+}
+
+#[test]
+fn attributes_are_kept() {
+    let tokens = Lexer::new(r#"func old() @test @deprecated("use new") {}"#)
+        .lex()
+        .unwrap();
+
+    let Stmt::Function { attributes, .. } = &Parser::new(tokens).parse().unwrap()[0] else {
+        panic!("expected a function");
+    };
+    assert_eq!(
+        attributes,
+        &vec![
+            Attribute {
+                name: String::from("test"),
+                value: None,
+                token: Token::new(
+                    TokenKind::Identifier,
+                    Some(String::from("test")),
+                    Span::new(12, 16, 1, 13)
+                )
+            },
+            Attribute {
+                name: String::from("deprecated"),
+                value: Some(String::from("use new")),
+                token: Token::new(
+                    TokenKind::Identifier,
+                    Some(String::from("deprecated")),
+                    Span::new(18, 28, 1, 19)
+                )
+            }
+        ]
+    );
+
+    // the rest are checked by the type checker.
+    for source in ["func f() @requires {}", "func f() @link {}"] {
+        let tokens = Lexer::new(source).lex().unwrap();
+
+        assert_eq!(
+            Parser::new(tokens).parse().unwrap_err()[0].r#type,
+            ErrorType::InvalidAttribute,
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn pointer_types() {
+    let tokens = Lexer::new("func f(p: Ptr<Ptr<Int>>): Ptr<Int> { return &*p; }")
+        .lex()
+        .unwrap();
+
+    let Stmt::Function {
+        args,
+        return_type,
+        body,
+        ..
+    } = &Parser::new(tokens).parse().unwrap()[0]
+    else {
+        panic!("expected a function");
+    };
+    assert_eq!(args[0].ty.as_deref(), Some("Ptr<Ptr<Int>>"));
+    assert_eq!(return_type.as_deref(), Some("Ptr<Int>"));
+
+    let Stmt::Return(Some(value), _) = &body.stmts[0] else {
+        panic!("expected a return");
+    };
+    let Expr::Unary { op, operand } = &value.node else {
+        panic!("expected `&`");
+    };
+    assert_eq!(op.kind, TokenKind::BitAnd);
+    assert!(matches!(&operand.node, Expr::Unary { op, .. } if op.kind == TokenKind::Multi));
+
+    // `**` is two dereferences.
+    let tokens = Lexer::new("**pp").lex().unwrap();
+    let Expr::Unary { operand, .. } = Parser::new(tokens).expr().unwrap().node else {
+        panic!("expected `*`");
+    };
+    assert!(matches!(operand.node, Expr::Unary { .. }));
+}
+
+#[test]
+fn opaque_types() {
+    let tokens = Lexer::new(r#"type FILE @opaque @requires("stdio.h");"#)
+        .lex()
+        .unwrap();
+
+    let Stmt::Type {
+        name,
+        external,
+        attributes,
+        ..
+    } = &Parser::new(tokens).parse().unwrap()[0]
+    else {
+        panic!("expected a type");
+    };
+    assert_eq!(name, "FILE");
+    assert_eq!(external.as_deref(), Some("stdio.h"));
+    assert_eq!(attributes.len(), 1);
+    assert_eq!(attributes[0].name, "opaque");
+}
+
+#[test]
+fn import() {
+    let tokens = Lexer::new(r#"import "utils.uma";"#).lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap(),
+        vec![Stmt::Import {
+            path: String::from("utils.uma"),
+            token: Token::new(TokenKind::Import, None, Span::new(0, 6, 1, 1)),
+        }]
+    );
+}
+
 #[test]
 fn syntax_error() {
     let tokens = Lexer::new(
@@ -234,7 +583,8 @@ fn syntax_error() {
             let = "Hello, World!";
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     let result = Parser::new(tokens).variable();
 
@@ -243,6 +593,52 @@ fn syntax_error() {
     assert_eq!(result.err().unwrap().r#type, ErrorType::ExpectedToken);
 }
 
+#[test]
+fn nesting_is_limited() {
+    // the `1` is a level deeper than the innermost parentheses.
+    let depth = MAX_DEPTH - 1;
+    let src = format!("let x = {}1{};", "(".repeat(depth), ")".repeat(depth));
+    assert!(Parser::from_lexer(Lexer::new(&src)).parse().is_ok());
+
+    let deep = MAX_DEPTH + 1;
+    for src in [
+        format!("let x = {}1{};", "(".repeat(deep), ")".repeat(deep)),
+        format!("let x = {}1;", "~".repeat(deep)),
+        format!("let x = {}1;", "2 ** ".repeat(deep)),
+        format!("{}{}", "{".repeat(deep), "}".repeat(deep)),
+    ] {
+        let errors = Parser::from_lexer(Lexer::new(&src)).parse().unwrap_err();
+        assert_eq!(errors[0].r#type, ErrorType::NestedTooDeeply);
+    }
+
+    let src = format!("let x = {}1;", "1 + ".repeat(MAX_OPERATORS + 1));
+    let errors = Parser::from_lexer(Lexer::new(&src)).parse().unwrap_err();
+    assert_eq!(
+        errors[0].message,
+        format!("More than {} operators in one statement", MAX_OPERATORS)
+    );
+
+    // every statement has its own.
+    let src = format!("let x = {}1;", "1 + ".repeat(MAX_OPERATORS)).repeat(2);
+    assert!(Parser::from_lexer(Lexer::new(&src)).parse().is_ok());
+}
+
+#[test]
+fn input_ending_early() {
+    let tokens = Lexer::new("let x = 1 +").lex().unwrap();
+    let errors = Parser::new(tokens).parse().unwrap_err();
+
+    assert_eq!(errors[0].r#type, ErrorType::UnexpectedEof);
+
+    let tokens = Lexer::new("foo(1, 2").lex().unwrap();
+    let errors = Parser::new(tokens).parse().unwrap_err();
+
+    assert_eq!(
+        errors[0].message,
+        "Unexpected end of file, expected `PareR`"
+    );
+}
+
 #[test]
 fn if_statement() {
     let tokens = Lexer::new(
@@ -252,27 +648,39 @@ fn if_statement() {
         }
     "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).if_().unwrap(),
         Stmt::If {
-            condition: Expr::Binary {
-                lhs: Expr::Identifier(String::from("x")).into(),
-                op: Token {
-                    kind: TokenKind::BinaryGt,
-                    value: None,
-                    line: 2,
-                    column: 15
+            condition: at(
+                Expr::Binary {
+                    lhs: at(Expr::Identifier(String::from("x")), 13, 14, 2, 13).into(),
+                    op: Token {
+                        kind: TokenKind::BinaryGt,
+                        value: None,
+                        span: Span::new(15, 16, 2, 15)
+                    },
+                    rhs: at(Expr::Number(String::from("60")), 17, 19, 2, 17).into()
                 },
-                rhs: Expr::Number(String::from("60")).into()
-            }
+                13,
+                19,
+                2,
+                13
+            )
             .into(),
             consequence: Block {
                 stmts: vec![Stmt::Variable {
                     name: String::from("x"),
-                    value: Expr::Number(String::from("1")).into(),
+                    value: at(Expr::Number(String::from("1")), 47, 48, 3, 25).into(),
                     is_mut: true,
+                    ty: None,
+                    token: Token::new(
+                        TokenKind::Identifier,
+                        Some(String::from("x")),
+                        Span::new(43, 44, 3, 21)
+                    )
                 }],
             },
             alternative: None,
@@ -293,65 +701,106 @@ fn if_else_if_else_statement() {
         }
     "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).if_().unwrap(),
         Stmt::If {
-            condition: Expr::Binary {
-                lhs: Expr::Identifier(String::from("x")).into(),
-                op: Token {
-                    kind: TokenKind::BinaryGte,
-                    value: None,
-                    line: 2,
-                    column: 15
+            condition: at(
+                Expr::Binary {
+                    lhs: at(Expr::Identifier(String::from("x")), 13, 14, 2, 13).into(),
+                    op: Token {
+                        kind: TokenKind::BinaryGte,
+                        value: None,
+                        span: Span::new(15, 17, 2, 15)
+                    },
+                    rhs: at(Expr::Number(String::from("30")), 18, 20, 2, 18).into()
                 },
-                rhs: Expr::Number(String::from("30")).into()
-            }
+                13,
+                20,
+                2,
+                13
+            )
             .into(),
             consequence: Block {
                 stmts: vec![Stmt::Variable {
                     name: String::from("x"),
-                    value: Expr::Number(String::from("1")).into(),
+                    value: at(Expr::Number(String::from("1")), 44, 45, 3, 21).into(),
                     is_mut: false,
+                    ty: None,
+                    token: Token::new(
+                        TokenKind::Identifier,
+                        Some(String::from("x")),
+                        Span::new(40, 41, 3, 17)
+                    )
                 }],
             },
             alternative: Some(Box::new(Stmt::If {
-                condition: Expr::Binary {
-                    lhs: Expr::Binary {
-                        lhs: Expr::Identifier(String::from("x")).into(),
+                condition: at(
+                    Expr::Binary {
+                        lhs: at(
+                            Expr::Binary {
+                                lhs: at(Expr::Identifier(String::from("x")), 66, 67, 4, 20).into(),
+                                op: Token {
+                                    kind: TokenKind::Add,
+                                    value: None,
+                                    span: Span::new(68, 69, 4, 22)
+                                },
+                                rhs: at(Expr::Number(String::from("10")), 70, 72, 4, 24).into()
+                            },
+                            66,
+                            72,
+                            4,
+                            20
+                        )
+                        .into(),
                         op: Token {
-                            kind: TokenKind::Add,
+                            kind: TokenKind::BinaryGte,
                             value: None,
-                            line: 4,
-                            column: 22
+                            span: Span::new(73, 75, 4, 27)
                         },
-                        rhs: Expr::Number(String::from("10")).into()
-                    }
-                    .into(),
-                    op: Token {
-                        kind: TokenKind::BinaryGte,
-                        value: None,
-                        line: 4,
-                        column: 27
+                        rhs: at(Expr::Number(String::from("60")), 76, 78, 4, 30).into()
                     },
-                    rhs: Expr::Number(String::from("60")).into()
-                }
+                    66,
+                    78,
+                    4,
+                    20
+                )
                 .into(),
                 consequence: Block {
                     stmts: vec![Stmt::Variable {
                         name: String::from("x"),
-                        value: Expr::Number(String::from("2")).into(),
+                        value: at(Expr::Number(String::from("2")), 102, 103, 5, 21).into(),
                         is_mut: false,
+                        ty: None,
+                        token: Token::new(
+                            TokenKind::Identifier,
+                            Some(String::from("x")),
+                            Span::new(98, 99, 5, 17)
+                        )
                     }],
                 },
-                alternative: Some(Box::new(Stmt::Block(Block {
-                    stmts: vec![Stmt::Variable {
-                        name: String::from("x"),
-                        value: Expr::Number(String::from("3")).into(),
-                        is_mut: false,
-                    }],
-                }))),
+                alternative: Some(Box::new(Stmt::Block(
+                    Block {
+                        stmts: vec![Stmt::Variable {
+                            name: String::from("x"),
+                            value: at(Expr::Number(String::from("3")), 142, 143, 7, 21).into(),
+                            is_mut: false,
+                            ty: None,
+                            token: Token::new(
+                                TokenKind::Identifier,
+                                Some(String::from("x")),
+                                Span::new(138, 139, 7, 17)
+                            )
+                        }],
+                    },
+                    Token {
+                        kind: TokenKind::Else,
+                        value: None,
+                        span: Span::new(115, 119, 6, 11)
+                    }
+                ))),
             })),
         }
     );
@@ -366,26 +815,38 @@ fn _inline_range_for() {
             }
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     assert_eq!(
         Parser::new(tokens).for_().unwrap(),
         Stmt::For {
             iterator: String::from("x"),
-            start: Expr::Number(String::from("0")).into(),
-            end: Expr::Number(String::from("25")).into(),
+            start: at(Expr::Number(String::from("0")), 22, 23, 2, 22).into(),
+            end: at(Expr::Number(String::from("25")), 25, 27, 2, 25).into(),
             body: Block {
-                stmts: vec![Stmt::Call {
-                    name: String::from("print"),
-                    args: vec![Expr::Identifier(String::from("x")).into()],
-                    token: Token {
-                        kind: TokenKind::Identifier,
-                        value: Some(String::from("print")),
-                        line: 3,
-                        column: 17
-                    }
-                }]
-            }
+                stmts: vec![at(
+                    Expr::Call {
+                        name: String::from("print"),
+                        args: vec![at(Expr::Identifier(String::from("x")), 52, 53, 3, 23).into()],
+                        token: Token {
+                            kind: TokenKind::Identifier,
+                            value: Some("print".into()),
+                            span: Span::new(46, 51, 3, 17)
+                        }
+                    },
+                    46,
+                    54,
+                    3,
+                    17
+                )
+                .into()]
+            },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("x")),
+                Span::new(17, 18, 2, 17)
+            )
         }
     )
 }
@@ -399,7 +860,8 @@ fn program() {
             }
         "#,
     )
-    .lex();
+    .lex()
+    .unwrap();
 
     let parsed = Parser::new(tokens).parse();
 
@@ -407,18 +869,632 @@ fn program() {
         parsed.unwrap(),
         vec![Stmt::Function {
             name: String::from("main"),
-            args: mapping!(),
+            args: vec![],
             external: None,
+            links: vec![],
+            attributes: vec![],
             return_type: None,
             is_varadic: false,
             body: Block {
-                stmts: vec![Stmt::Call {
-                    name: String::from("print"),
-                    args: vec![Expr::String(String::from("Hello, World!")).into()],
-                    token: Token::new(TokenKind::Identifier, Some(String::from("print")), 3, 17)
-                }
+                stmts: vec![at(
+                    Expr::Call {
+                        name: String::from("print"),
+                        args: vec![
+                            at(Expr::String(String::from("Hello, World!")), 49, 64, 3, 23).into()
+                        ],
+                        token: Token::new(
+                            TokenKind::Identifier,
+                            Some(String::from("print")),
+                            Span::new(43, 48, 3, 17)
+                        )
+                    },
+                    43,
+                    65,
+                    3,
+                    17
+                )
                 .into()]
-            }
+            },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("main")),
+                Span::new(18, 22, 2, 18)
+            )
         }]
     )
 }
+
+#[test]
+fn array_literal() {
+    let tokens = Lexer::new(
+        r#"
+            let xs = [1, 2, 3,];
+        "#,
+    )
+    .lex()
+    .unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).variable().unwrap(),
+        Stmt::Variable {
+            name: String::from("xs"),
+            value: at(
+                Expr::Array(vec![
+                    at(Expr::Number(String::from("1")), 23, 24, 2, 23),
+                    at(Expr::Number(String::from("2")), 26, 27, 2, 26),
+                    at(Expr::Number(String::from("3")), 29, 30, 2, 29),
+                ]),
+                22,
+                32,
+                2,
+                22
+            )
+            .into(),
+            is_mut: false,
+            ty: None,
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("xs")),
+                Span::new(17, 19, 2, 17)
+            )
+        }
+    )
+}
+
+#[test]
+fn array_index() {
+    let tokens = Lexer::new(
+        r#"
+            grid[i][0] + 1
+        "#,
+    )
+    .lex()
+    .unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap(),
+        vec![at(
+            Expr::Binary {
+                lhs: at(
+                    Expr::Index {
+                        target: at(
+                            Expr::Index {
+                                target: at(Expr::Identifier(String::from("grid")), 13, 17, 2, 13)
+                                    .into(),
+                                index: at(Expr::Identifier(String::from("i")), 18, 19, 2, 18)
+                                    .into(),
+                            },
+                            13,
+                            20,
+                            2,
+                            13
+                        )
+                        .into(),
+                        index: at(Expr::Number(String::from("0")), 21, 22, 2, 21).into(),
+                    },
+                    13,
+                    23,
+                    2,
+                    13
+                )
+                .into(),
+                op: Token::new(TokenKind::Add, None, Span::new(24, 25, 2, 24)),
+                rhs: at(Expr::Number(String::from("1")), 26, 27, 2, 26).into(),
+            },
+            13,
+            27,
+            2,
+            13
+        )
+        .into()]
+    )
+}
+
+#[test]
+fn for_each() {
+    let tokens = Lexer::new(
+        r#"
+            for x in [1, 2] {
+                print(x)
+            }
+        "#,
+    )
+    .lex()
+    .unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).for_().unwrap(),
+        Stmt::ForEach {
+            iterator: String::from("x"),
+            iterable: at(
+                Expr::Array(vec![
+                    at(Expr::Number(String::from("1")), 23, 24, 2, 23),
+                    at(Expr::Number(String::from("2")), 26, 27, 2, 26),
+                ]),
+                22,
+                28,
+                2,
+                22
+            )
+            .into(),
+            body: Block {
+                stmts: vec![at(
+                    Expr::Call {
+                        name: String::from("print"),
+                        args: vec![at(Expr::Identifier(String::from("x")), 53, 54, 3, 23).into()],
+                        token: Token::new(
+                            TokenKind::Identifier,
+                            Some(String::from("print")),
+                            Span::new(47, 52, 3, 17)
+                        )
+                    },
+                    47,
+                    55,
+                    3,
+                    17
+                )
+                .into()]
+            },
+            token: Token::new(
+                TokenKind::Identifier,
+                Some(String::from("x")),
+                Span::new(17, 18, 2, 17)
+            )
+        }
+    )
+}
+
+#[test]
+fn error_recovery() {
+    let tokens = Lexer::new(
+        r#"
+            let = 1;
+            let x = 2;
+
+            func main() {
+                let y = ;
+                let z = 3;
+            }
+
+            let w 4;
+        "#,
+    )
+    .lex()
+    .unwrap();
+
+    let errors = Parser::new(tokens).parse().err().unwrap();
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(|err| (err.token.span.line, err.token.span.column))
+            .collect::<Vec<_>>(),
+        vec![(2, 17), (6, 25), (10, 19)]
+    );
+}
+
+#[test]
+fn dump_ast() {
+    let tokens = Lexer::new("func main(): int { let x = [1, 2]; return x[0] + 1; }")
+        .lex()
+        .unwrap();
+
+    assert_eq!(
+        dump(&Parser::new(tokens).parse().unwrap()),
+        "Function main(): int\n  \
+           Variable x\n    \
+             Array\n      \
+               Number 1\n      \
+               Number 2\n  \
+           Return\n    \
+             Binary Add\n      \
+               Index\n        \
+                 Identifier x\n        \
+                 Number 0\n      \
+               Number 1\n"
+    );
+}
+
+#[test]
+fn interpolation() {
+    let tokens = Lexer::new(r#""{name} is {age + 1}""#).lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).expr().unwrap(),
+        at(
+            Expr::Interpolation(vec![
+                Part::Expr(at(Expr::Identifier(String::from("name")), 2, 6, 1, 3).into()),
+                Part::Text(String::from(" is ")),
+                Part::Expr(
+                    at(
+                        Expr::Binary {
+                            lhs: at(Expr::Identifier(String::from("age")), 12, 15, 1, 13).into(),
+                            op: Token::new(TokenKind::Add, None, Span::new(16, 17, 1, 17)),
+                            rhs: at(Expr::Number(String::from("1")), 18, 19, 1, 19).into()
+                        },
+                        12,
+                        19,
+                        1,
+                        13
+                    )
+                    .into()
+                ),
+            ]),
+            0,
+            21,
+            1,
+            1
+        )
+    );
+}
+
+#[test]
+fn logical_precedence() {
+    let tokens = Lexer::new("true || false && 1 < 2").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).expr().unwrap(),
+        at(
+            Expr::Binary {
+                lhs: at(Expr::Bool(true), 0, 4, 1, 1).into(),
+                op: Token::new(TokenKind::Or, None, Span::new(5, 7, 1, 6)),
+                rhs: at(
+                    Expr::Binary {
+                        lhs: at(Expr::Bool(false), 8, 13, 1, 9).into(),
+                        op: Token::new(TokenKind::And, None, Span::new(14, 16, 1, 15)),
+                        rhs: at(
+                            Expr::Binary {
+                                lhs: at(Expr::Number(String::from("1")), 17, 18, 1, 18).into(),
+                                op: Token::new(TokenKind::BinaryLt, None, Span::new(19, 20, 1, 20)),
+                                rhs: at(Expr::Number(String::from("2")), 21, 22, 1, 22).into(),
+                            },
+                            17,
+                            22,
+                            1,
+                            18
+                        )
+                        .into(),
+                    },
+                    8,
+                    22,
+                    1,
+                    9
+                )
+                .into(),
+            },
+            0,
+            22,
+            1,
+            1
+        )
+    );
+}
+
+#[test]
+fn right_associative_exponent() {
+    let tokens = Lexer::new("2 ** 3 ** 2 * 4").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).expr().unwrap(),
+        at(
+            Expr::Binary {
+                lhs: at(
+                    Expr::Binary {
+                        lhs: at(Expr::Number(String::from("2")), 0, 1, 1, 1).into(),
+                        op: Token::new(TokenKind::Expo, None, Span::new(2, 4, 1, 3)),
+                        rhs: at(
+                            Expr::Binary {
+                                lhs: at(Expr::Number(String::from("3")), 5, 6, 1, 6).into(),
+                                op: Token::new(TokenKind::Expo, None, Span::new(7, 9, 1, 8)),
+                                rhs: at(Expr::Number(String::from("2")), 10, 11, 1, 11).into(),
+                            },
+                            5,
+                            11,
+                            1,
+                            6
+                        )
+                        .into(),
+                    },
+                    0,
+                    11,
+                    1,
+                    1
+                )
+                .into(),
+                op: Token::new(TokenKind::Multi, None, Span::new(12, 13, 1, 13)),
+                rhs: at(Expr::Number(String::from("4")), 14, 15, 1, 15).into(),
+            },
+            0,
+            15,
+            1,
+            1
+        )
+    );
+}
+
+#[test]
+fn cast() {
+    let tokens = Lexer::new("~x as Float * 2").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).expr().unwrap(),
+        at(
+            Expr::Binary {
+                lhs: at(
+                    Expr::Cast {
+                        value: at(
+                            Expr::Unary {
+                                op: Token::new(TokenKind::BitNot, None, Span::new(0, 1, 1, 1)),
+                                operand: at(Expr::Identifier(String::from("x")), 1, 2, 1, 2).into(),
+                            },
+                            0,
+                            2,
+                            1,
+                            1
+                        )
+                        .into(),
+                        ty: Token::new(
+                            TokenKind::Identifier,
+                            Some(String::from("Float")),
+                            Span::new(6, 11, 1, 7)
+                        ),
+                    },
+                    0,
+                    11,
+                    1,
+                    1
+                )
+                .into(),
+                op: Token::new(TokenKind::Multi, None, Span::new(12, 13, 1, 13)),
+                rhs: at(Expr::Number(String::from("2")), 14, 15, 1, 15).into(),
+            },
+            0,
+            15,
+            1,
+            1
+        )
+    );
+}
+
+#[test]
+fn nested_calls() {
+    // a call to a one letter function, `end` is after its `)`.
+    let call = |name: &str, start: usize, end: usize, args: Vec<Spanned<Expr>>| {
+        let token = Token::new(
+            TokenKind::Identifier,
+            Some(String::from(name)),
+            Span::new(start, start + 1, 1, start + 1),
+        );
+
+        let name = String::from(name);
+        let args = args.into_iter().map(Arg::from).collect();
+
+        Box::new(at(
+            Expr::Call { name, args, token },
+            start,
+            end,
+            1,
+            start + 1,
+        ))
+    };
+
+    let tokens = Lexer::new("f(g(x)) + h();").lex().unwrap();
+
+    assert_eq!(
+        Parser::new(tokens).parse().unwrap(),
+        vec![at(
+            Expr::Binary {
+                lhs: call(
+                    "f",
+                    0,
+                    7,
+                    vec![*call(
+                        "g",
+                        2,
+                        6,
+                        vec![at(Expr::Identifier(String::from("x")), 4, 5, 1, 5)]
+                    )]
+                ),
+                op: Token::new(TokenKind::Add, None, Span::new(8, 9, 1, 9)),
+                rhs: call("h", 10, 13, vec![]),
+            },
+            0,
+            13,
+            1,
+            1
+        )
+        .into()]
+    );
+
+    // the `;` ends the call's statement, it isn't swallowed by the call.
+    let tokens = Lexer::new("let x = f(); - 1;").lex().unwrap();
+    assert!(Parser::new(tokens).parse().is_err());
+}
+
+#[test]
+fn block_statement() {
+    let tokens = Lexer::new("{ let x = 1; { x; } }").lex().unwrap();
+
+    assert_eq!(
+        dump(&Parser::new(tokens).parse().unwrap()),
+        "Block\n  \
+           Variable x\n    \
+             Number 1\n  \
+           Block\n    \
+             Identifier x\n"
+    );
+}
+
+#[test]
+fn constant() {
+    let tokens = Lexer::new("const N: Int = 2 * 3;").lex().unwrap();
+
+    assert_eq!(
+        dump(&Parser::new(tokens).parse().unwrap()),
+        "Const N: Int\n  \
+           Binary Multi\n    \
+             Number 2\n    \
+             Number 3\n"
+    );
+}
+
+#[test]
+fn if_expression() {
+    let tokens = Lexer::new(r#"if x > 0 { "pos" } else if x < 0 { "neg" } else { "zero" }"#)
+        .lex()
+        .unwrap();
+
+    let expr = Parser::new(tokens).expr().unwrap();
+    assert_eq!(expr.span, Span::new(0, 58, 1, 1));
+
+    assert_eq!(
+        dump(&[Stmt::Expr(expr)]),
+        "If\n  \
+           Binary BinaryGt\n    \
+             Identifier x\n    \
+             Number 0\n  \
+           String \"pos\"\n  \
+           If\n    \
+             Binary BinaryLt\n      \
+               Identifier x\n      \
+               Number 0\n    \
+             String \"neg\"\n    \
+             String \"zero\"\n"
+    );
+}
+
+#[test]
+fn match_expression() {
+    let tokens = Lexer::new(r#"match x { 1 => "one", "two" => 2, _ => x, }"#)
+        .lex()
+        .unwrap();
+
+    let expr = Parser::new(tokens).expr().unwrap();
+    assert_eq!(expr.span, Span::new(0, 43, 1, 1));
+
+    assert_eq!(
+        dump(&[Stmt::Expr(expr)]),
+        "Match\n  \
+           Identifier x\n  \
+           Arm\n    \
+             Number 1\n    \
+             String \"one\"\n  \
+           Arm\n    \
+             String \"two\"\n    \
+             Number 2\n  \
+           Arm _\n    \
+             Identifier x\n"
+    );
+
+    let tokens = Lexer::new("match x { y => 1 }").lex().unwrap();
+    assert_eq!(
+        Parser::new(tokens).expr().unwrap_err().r#type,
+        ErrorType::UnexpectedToken
+    );
+}
+
+#[test]
+fn default_and_named_arguments() {
+    let tokens = Lexer::new(
+        r#"func greet(name: String, greeting: String = "Hello") {} greet(name: "Ana");"#,
+    )
+    .lex()
+    .unwrap();
+
+    assert_eq!(
+        dump(&Parser::new(tokens).parse().unwrap()),
+        "Function greet(name: String, greeting: String)\n  \
+           Default greeting\n    \
+             String \"Hello\"\n\
+         Call greet\n  \
+           Named name\n    \
+             String \"Ana\"\n"
+    );
+}
+
+// the names a program uses, and the functions it calls.
+#[derive(Default)]
+struct Names {
+    used: Vec<String>,
+    called: Vec<String>,
+}
+
+impl Visitor for Names {
+    fn visit_expr(&mut self, expr: &Spanned<Expr>) {
+        match &expr.node {
+            Expr::Identifier(name) => self.used.push(name.clone()),
+            Expr::Call { name, .. } => self.called.push(name.clone()),
+            _ => (),
+        }
+
+        walk_expr(self, expr);
+    }
+}
+
+#[test]
+fn visitor_walks_every_node() {
+    let src = r#"func f(x: int = g(a)): int {
+        if (x > b) {
+            return match x { 1 => c, _ => h(d[e]) };
+        } else if (true) {
+            for i in 0..n { print("{x as float}"); }
+        }
+        return if (y) { z } else { 0 };
+    }"#;
+    let ast = Parser::from_lexer(Lexer::new(src)).parse().unwrap();
+
+    let mut names = Names::default();
+    walk_stmts(&mut names, &ast);
+
+    assert_eq!(
+        names.used,
+        vec!["a", "x", "b", "x", "c", "d", "e", "n", "x", "y", "z"]
+    );
+    assert_eq!(names.called, vec!["g", "h", "print"]);
+}
+
+// renames `from` to `to`, leaving function bodies alone.
+struct Rename(&'static str, &'static str);
+
+impl VisitorMut for Rename {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if !matches!(stmt, Stmt::Function { .. }) {
+            walk_stmt_mut(self, stmt);
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr>) {
+        match &mut expr.node {
+            Expr::Identifier(name) if name == self.0 => *name = self.1.to_string(),
+            _ => walk_expr_mut(self, expr),
+        }
+    }
+}
+
+#[test]
+fn visitor_mut_changes_nodes() {
+    let src = "let y = [x, x + 1];\nfunc f(): int { return x; }";
+    let mut ast = Parser::from_lexer(Lexer::new(src)).parse().unwrap();
+
+    walk_stmts_mut(&mut Rename("x", "w"), &mut ast);
+
+    let mut names = Names::default();
+    walk_stmts(&mut names, &ast);
+    assert_eq!(names.used, vec!["w", "w", "x"]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn ast_round_trips_through_json() {
+    let src =
+        r#"func f(x: int = 2): String { return match x { 1 => "one {x}", _ => 'c' as String }; }"#;
+    let ast = Parser::from_lexer(Lexer::new(src)).parse().unwrap();
+
+    let json = serde_json::to_string(&ast).unwrap();
+    assert!(json.starts_with(r#"[{"Function":{"name":"f","return_type":"String""#));
+    assert_eq!(serde_json::from_str::<Vec<Stmt>>(&json).unwrap(), ast);
+
+    let tokens = Lexer::new(src).lex().unwrap();
+    let json = serde_json::to_string(&tokens).unwrap();
+    assert_eq!(serde_json::from_str::<Vec<Token>>(&json).unwrap(), tokens);
+}
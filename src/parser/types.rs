@@ -1,96 +1,506 @@
-use std::collections::HashMap;
+use crate::lexer::{Span, Token, TokenKind};
 
-use crate::lexer::{Token, TokenKind};
+// a node and the part of the source it was parsed from.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
 
-#[derive(Debug, PartialEq)]
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Spanned<T> {
+        Spanned { node, span }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     Binary {
-        lhs: Box<Stmt>,
+        lhs: Box<Spanned<Expr>>,
+        op: Token,
+        rhs: Box<Spanned<Expr>>,
+    },
+    Unary {
         op: Token,
-        rhs: Box<Stmt>,
+        operand: Box<Spanned<Expr>>,
+    },
+    // `value as ty`, `ty` is the name of the type.
+    Cast {
+        value: Box<Spanned<Expr>>,
+        ty: Token,
+    },
+    // `token` is the function's name.
+    Call {
+        name: String,
+        args: Vec<Arg>,
+        token: Token,
+    },
+    // `if condition { consequence } else { alternative }` used for its value.
+    If {
+        condition: Box<Spanned<Expr>>,
+        consequence: Box<Spanned<Expr>>,
+        alternative: Box<Spanned<Expr>>,
+    },
+    // `match subject { pattern => value, ... }`, the arms in the order
+    // they're written.
+    Match {
+        subject: Box<Spanned<Expr>>,
+        arms: Vec<(Pattern, Spanned<Expr>)>,
+    },
+    Array(Vec<Spanned<Expr>>),
+    Index {
+        target: Box<Spanned<Expr>>,
+        index: Box<Spanned<Expr>>,
     },
     Identifier(String),
     Number(String),
     Float(String),
+    Bool(bool),
     String(std::string::String),
+    Char(char),
+    Interpolation(Vec<Part>),
+}
+
+// an argument of a call, `name` is set for one passed by name like
+// `greeting: "Hi"`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arg {
+    pub name: Option<Token>,
+    pub value: Spanned<Expr>,
+}
+
+// a parameter of a function, `ty` is the name of its type and `default` the
+// value it takes when a call leaves it out. `span` is where its name is.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Param {
+    pub name: String,
+    pub ty: Option<String>,
+    pub default: Option<Spanned<Expr>>,
+    pub span: Span,
+}
+
+// an attribute after a function's signature, like `@test`. `token` is its
+// name.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute {
+    pub name: String,
+    pub value: Option<String>,
+    pub token: Token,
+}
+
+// what an arm of a `match` compares against, a literal or `_` for anything.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Pattern {
+    Literal(Spanned<Expr>),
+    Wildcard(Span),
+}
+
+// a piece of an interpolated string.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Part {
+    Text(String),
+    Expr(Box<Spanned<Expr>>),
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
     Variable {
         name: String,
-        value: Box<Stmt>,
+        value: Box<Spanned<Expr>>,
         is_mut: bool,
+        // the type it's annotated with, `Int` in `let x: Int = 1;`.
+        ty: Option<String>,
+        // the variable's name.
+        token: Token,
+    },
+    // a value worked out before the program runs, like `const N = 3;`.
+    Const {
+        name: String,
+        value: Box<Spanned<Expr>>,
+        ty: Option<String>,
     },
     Function {
         name: String,
         return_type: Option<String>,
-        args: HashMap<String, Option<String>>,
+        args: Vec<Param>,
         external: Option<String>,
+        // libraries from `@link`, only set alongside `external`.
+        links: Vec<String>,
+        // the attributes other than `@requires` and `@link`.
+        attributes: Vec<Attribute>,
         is_varadic: bool,
         body: Block,
-    },
-    Call {
-        name: String,
-        args: Vec<Box<Stmt>>,
+        // the function's name.
         token: Token,
     },
     If {
-        condition: Box<Stmt>,
+        condition: Box<Spanned<Expr>>,
         consequence: Block,
         alternative: Option<Box<Stmt>>,
     },
+    // `token` is the iterator's name in both.
     For {
         iterator: String,
-        start: Box<Stmt>,
-        end: Box<Stmt>,
+        start: Box<Spanned<Expr>>,
+        end: Box<Spanned<Expr>>,
+        body: Block,
+        token: Token,
+    },
+    ForEach {
+        iterator: String,
+        iterable: Box<Spanned<Expr>>,
         body: Block,
+        token: Token,
     },
-    Block(Block),
+    // the `{` it opens with, or the `else` before it.
+    Block(Block, Token),
     Assignment {
         name: String,
-        value: Box<Stmt>,
+        value: Box<Spanned<Expr>>,
+        // the operator of a compound assignment, `+` for `x += 1`.
+        op: Option<Token>,
     },
-    Attribute {
+    Import {
+        path: String,
+        token: Token,
+    },
+    // `type FILE @opaque @requires("stdio.h");`, a C type that's only ever
+    // pointed to.
+    Type {
         name: String,
-        value: String,
+        external: Option<String>,
+        // the attributes other than `@requires`.
+        attributes: Vec<Attribute>,
+        // the type's name.
+        token: Token,
     },
-    Return(Box<Stmt>),
-    Expr(Expr),
+    // the `return` token is kept for where a bare `return` is.
+    Return(Option<Box<Spanned<Expr>>>, Token),
+    Expr(Spanned<Expr>),
     Empty,
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     pub stmts: Vec<Stmt>,
 }
 
-impl Into<Expr> for Token {
-    fn into(self) -> Expr {
-        match &self.kind {
-            TokenKind::String => Expr::String(self.value.unwrap()),
-            TokenKind::Number => Expr::Number(self.value.unwrap()),
-            TokenKind::Float => Expr::Float(self.value.unwrap()),
-            TokenKind::Identifier => Expr::Identifier(self.value.unwrap()),
-            other => panic!("cannot convert `{:#?}` to an `Expr`: {:#?}", self, other),
+// what the AST doesn't keep about how the source was written, for `uma fmt`.
+#[derive(Debug, PartialEq, Default)]
+pub struct Layout {
+    // the line every statement at the top level or in a block starts on, in
+    // the order they're parsed.
+    pub lines: Vec<usize>,
+}
+
+impl From<Token> for Expr {
+    fn from(token: Token) -> Self {
+        match &token.kind {
+            TokenKind::String => Expr::String(token.text()),
+            TokenKind::Char => Expr::Char(token.text().chars().next().unwrap_or_default()),
+            TokenKind::Number => Expr::Number(token.text()),
+            TokenKind::Float => Expr::Float(token.text()),
+            TokenKind::True => Expr::Bool(true),
+            TokenKind::False => Expr::Bool(false),
+            TokenKind::Identifier => Expr::Identifier(token.text()),
+            other => panic!("cannot convert `{:#?}` to an `Expr`: {:#?}", token, other),
+        }
+    }
+}
+
+impl From<Token> for Spanned<Expr> {
+    fn from(token: Token) -> Self {
+        let span = token.span;
+        Spanned::new(token.into(), span)
+    }
+}
+
+impl From<Spanned<Expr>> for Arg {
+    fn from(value: Spanned<Expr>) -> Self {
+        Arg { name: None, value }
+    }
+}
+
+impl From<Spanned<Expr>> for Stmt {
+    fn from(expr: Spanned<Expr>) -> Self {
+        Stmt::Expr(expr)
+    }
+}
+
+// walks the AST, every node of it unless a `visit_` method is overridden
+// without calling its `walk_` function. the `walk_`s match every kind of
+// node, so one added later can't be skipped by accident.
+pub trait Visitor {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_expr(&mut self, expr: &Spanned<Expr>) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern);
+    }
+}
+
+pub fn walk_stmts<V: Visitor + ?Sized>(visitor: &mut V, stmts: &[Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    walk_stmts(visitor, &block.stmts);
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Variable { value, .. }
+        | Stmt::Const { value, .. }
+        | Stmt::Assignment { value, .. }
+        | Stmt::Return(Some(value), _) => visitor.visit_expr(value),
+        Stmt::Function { args, body, .. } => {
+            for default in args.iter().filter_map(|arg| arg.default.as_ref()) {
+                visitor.visit_expr(default);
+            }
+
+            visitor.visit_block(body);
+        }
+        Stmt::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(consequence);
+
+            if let Some(alternative) = alternative {
+                visitor.visit_stmt(alternative);
+            }
+        }
+        Stmt::For {
+            start, end, body, ..
+        } => {
+            visitor.visit_expr(start);
+            visitor.visit_expr(end);
+            visitor.visit_block(body);
+        }
+        Stmt::ForEach { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            visitor.visit_block(body);
+        }
+        Stmt::Block(block, _) => visitor.visit_block(block),
+        Stmt::Expr(expr) => visitor.visit_expr(expr),
+        Stmt::Import { .. } | Stmt::Type { .. } | Stmt::Return(None, _) | Stmt::Empty => (),
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Spanned<Expr>) {
+    match &expr.node {
+        Expr::Binary { lhs, rhs, .. } => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        Expr::Unary { operand: value, .. } | Expr::Cast { value, .. } => visitor.visit_expr(value),
+        Expr::Call { args, .. } => {
+            for arg in args {
+                visitor.visit_expr(&arg.value);
+            }
         }
+        Expr::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(consequence);
+            visitor.visit_expr(alternative);
+        }
+        Expr::Match { subject, arms } => {
+            visitor.visit_expr(subject);
+
+            for (pattern, value) in arms {
+                visitor.visit_pattern(pattern);
+                visitor.visit_expr(value);
+            }
+        }
+        Expr::Array(elements) => {
+            for element in elements {
+                visitor.visit_expr(element);
+            }
+        }
+        Expr::Index { target, index } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(index);
+        }
+        Expr::Interpolation(parts) => {
+            for part in parts {
+                if let Part::Expr(expr) = part {
+                    visitor.visit_expr(expr);
+                }
+            }
+        }
+        Expr::Identifier(_)
+        | Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::Bool(_)
+        | Expr::String(_)
+        | Expr::Char(_) => (),
     }
 }
 
-impl Into<Stmt> for Token {
-    fn into(self) -> Stmt {
-        Stmt::Expr(self.into())
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &Pattern) {
+    match pattern {
+        Pattern::Literal(literal) => visitor.visit_expr(literal),
+        Pattern::Wildcard(_) => (),
     }
 }
 
-impl Into<Stmt> for Expr {
-    fn into(self) -> Stmt {
-        Stmt::Expr(self)
+// `Visitor` for a pass that changes the nodes it walks, like one renaming or
+// folding them.
+pub trait VisitorMut {
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Spanned<Expr>) {
+        walk_expr_mut(self, expr);
+    }
+
+    fn visit_pattern_mut(&mut self, pattern: &mut Pattern) {
+        walk_pattern_mut(self, pattern);
+    }
+}
+
+pub fn walk_stmts_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmts: &mut [Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt_mut(stmt);
+    }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    walk_stmts_mut(visitor, &mut block.stmts);
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Variable { value, .. }
+        | Stmt::Const { value, .. }
+        | Stmt::Assignment { value, .. }
+        | Stmt::Return(Some(value), _) => visitor.visit_expr_mut(value),
+        Stmt::Function { args, body, .. } => {
+            for default in args.iter_mut().filter_map(|arg| arg.default.as_mut()) {
+                visitor.visit_expr_mut(default);
+            }
+
+            visitor.visit_block_mut(body);
+        }
+        Stmt::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expr_mut(condition);
+            visitor.visit_block_mut(consequence);
+
+            if let Some(alternative) = alternative {
+                visitor.visit_stmt_mut(alternative);
+            }
+        }
+        Stmt::For {
+            start, end, body, ..
+        } => {
+            visitor.visit_expr_mut(start);
+            visitor.visit_expr_mut(end);
+            visitor.visit_block_mut(body);
+        }
+        Stmt::ForEach { iterable, body, .. } => {
+            visitor.visit_expr_mut(iterable);
+            visitor.visit_block_mut(body);
+        }
+        Stmt::Block(block, _) => visitor.visit_block_mut(block),
+        Stmt::Expr(expr) => visitor.visit_expr_mut(expr),
+        Stmt::Import { .. } | Stmt::Type { .. } | Stmt::Return(None, _) | Stmt::Empty => (),
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Spanned<Expr>) {
+    match &mut expr.node {
+        Expr::Binary { lhs, rhs, .. } => {
+            visitor.visit_expr_mut(lhs);
+            visitor.visit_expr_mut(rhs);
+        }
+        Expr::Unary { operand: value, .. } | Expr::Cast { value, .. } => {
+            visitor.visit_expr_mut(value)
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                visitor.visit_expr_mut(&mut arg.value);
+            }
+        }
+        Expr::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expr_mut(condition);
+            visitor.visit_expr_mut(consequence);
+            visitor.visit_expr_mut(alternative);
+        }
+        Expr::Match { subject, arms } => {
+            visitor.visit_expr_mut(subject);
+
+            for (pattern, value) in arms {
+                visitor.visit_pattern_mut(pattern);
+                visitor.visit_expr_mut(value);
+            }
+        }
+        Expr::Array(elements) => {
+            for element in elements {
+                visitor.visit_expr_mut(element);
+            }
+        }
+        Expr::Index { target, index } => {
+            visitor.visit_expr_mut(target);
+            visitor.visit_expr_mut(index);
+        }
+        Expr::Interpolation(parts) => {
+            for part in parts {
+                if let Part::Expr(expr) = part {
+                    visitor.visit_expr_mut(expr);
+                }
+            }
+        }
+        Expr::Identifier(_)
+        | Expr::Number(_)
+        | Expr::Float(_)
+        | Expr::Bool(_)
+        | Expr::String(_)
+        | Expr::Char(_) => (),
     }
 }
 
-impl Into<Box<Stmt>> for Expr {
-    fn into(self) -> Box<Stmt> {
-        Box::new(self.into())
+pub fn walk_pattern_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pattern: &mut Pattern) {
+    match pattern {
+        Pattern::Literal(literal) => visitor.visit_expr_mut(literal),
+        Pattern::Wildcard(_) => (),
     }
 }
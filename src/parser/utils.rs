@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::lexer::{Token, TokenKind};
+use crate::lexer::{LexerError, Span, Token, TokenKind};
 
 #[derive(Debug, PartialEq)]
 pub enum ErrorType {
@@ -8,6 +8,8 @@ pub enum ErrorType {
     UnexpectedToken,
     DuplicateArgument,
     InvalidAttribute,
+    UnexpectedEof,
+    NestedTooDeeply,
 }
 
 #[derive(Debug)]
@@ -32,29 +34,86 @@ impl ParserError {
 }
 
 pub trait Buffer<Item, Kind = TokenKind> {
-    fn consume(&mut self) -> Item;
-    fn peek(&self) -> Option<Item>;
+    fn consume(&mut self) -> Result<Item, ParserError>;
+    fn peek(&mut self) -> Option<&Item>;
+    // the item `n` after the next one.
+    fn get(&mut self, n: usize) -> Option<&Item>;
     fn try_expect(&mut self, kind: &Kind) -> Option<Item>;
     fn expect(&mut self, kind: Kind) -> Result<Item, ParserError>;
 }
 
-impl Buffer<Token> for VecDeque<Token> {
-    fn consume(&mut self) -> Token {
-        self.pop_front().expect("Unexpected end of tokens")
+// the tokens left to parse, lexed as the parser gets to them. the few it's
+// looked ahead at wait in `lookahead`.
+pub struct Tokens<'a> {
+    stream: Box<dyn Iterator<Item = Result<Token, LexerError>> + 'a>,
+    lookahead: VecDeque<Token>,
+    // what stopped the lexer, after which there are no more tokens.
+    error: Option<LexerError>,
+    // where the last token taken ended, what running out of them points at.
+    last: Span,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(stream: impl Iterator<Item = Result<Token, LexerError>> + 'a) -> Tokens<'a> {
+        Tokens {
+            stream: Box::new(stream),
+            lookahead: VecDeque::new(),
+            error: None,
+            last: Span::default(),
+        }
     }
 
-    fn peek(&self) -> Option<Token> {
-        self.get(0).cloned()
+    // the input ending where `expected` should've been.
+    pub fn eof<T>(&self, expected: &str) -> Result<T, ParserError> {
+        ParserError::new(
+            ErrorType::UnexpectedEof,
+            Token::new(TokenKind::None, None, self.last),
+            format!("Unexpected end of file, expected {}", expected),
+        )
     }
 
-    fn try_expect(&mut self, kind: &TokenKind) -> Option<Token> {
-        if let Some(next_token) = self.peek() {
-            if next_token.kind == *kind {
-                return Some(self.consume());
+    pub fn take_error(&mut self) -> Option<LexerError> {
+        self.error.take()
+    }
+}
+
+impl Buffer<Token> for Tokens<'_> {
+    // the next token, or an error if there are none left. when the lexer
+    // gave up the parse fails anyway, and the lexer's error is reported first.
+    fn consume(&mut self) -> Result<Token, ParserError> {
+        if self.get(0).is_none() {
+            return self.eof("more input");
+        }
+
+        let token = self.lookahead.pop_front().expect("a token was just peeked");
+        self.last = token.span;
+
+        Ok(token)
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.get(0)
+    }
+
+    // lexes up to the token if it hasn't been yet.
+    fn get(&mut self, n: usize) -> Option<&Token> {
+        while self.lookahead.len() <= n && self.error.is_none() {
+            match self.stream.next() {
+                Some(Ok(token)) => self.lookahead.push_back(token),
+                Some(Err(err)) => self.error = Some(err),
+                None => break,
             }
         }
 
-        return None;
+        self.lookahead.get(n)
+    }
+
+    fn try_expect(&mut self, kind: &TokenKind) -> Option<Token> {
+        if self.peek()?.kind == *kind {
+            return self.consume().ok();
+        }
+
+        None
     }
 
     fn expect(&mut self, kind: TokenKind) -> Result<Token, ParserError> {
@@ -62,9 +121,9 @@ impl Buffer<Token> for VecDeque<Token> {
             return Ok(next_token);
         }
 
-        let token = self
-            .peek()
-            .unwrap_or(Token::new(TokenKind::None, None, 0, 0));
+        let Some(token) = self.peek().cloned() else {
+            return self.eof(&format!("`{:#?}`", kind));
+        };
 
         Err(ParserError {
             message: format!("Expected `{:#?}` but found `{:#?}`", kind, &token.kind),
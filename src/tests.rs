@@ -0,0 +1,1512 @@
+use super::*;
+
+use std::path::Path;
+use std::process::{Command, Output};
+use std::time::Duration;
+
+// `src` built into the temp directory as `name`.
+fn built(src: &str, name: &str) -> Result<Artifact, CompileError> {
+    compile_str(src, &CompileOptions::new(std::env::temp_dir().join(name)))
+}
+
+// `typed` built by `backend`, with its errors as `compile_str` gives them.
+fn generated(
+    backend: &mut CBackend,
+    typed: &[TypedStmt],
+    options: &CodegenOptions,
+) -> Result<Artifact, CompileError> {
+    Codegen::generate(backend, typed, options).map_err(|err| Diagnostic::from(err).into())
+}
+
+// what the executable does when `exec` runs it, removing it after. nothing
+// when there's no C compiler to build it with.
+fn run_with<T>(built: Result<Artifact, CompileError>, exec: impl FnOnce(&Path) -> T) -> Option<T> {
+    let path = match built {
+        Ok(Artifact::Executable(path)) => path,
+        Ok(artifact) => panic!("expected an executable, found {:?}", artifact),
+        Err(err) if err.diagnostics[0].message == "No C compiler found" => return None,
+        Err(err) => panic!("doesn't build: {:?}", err.diagnostics),
+    };
+
+    let result = exec(&path);
+    std::fs::remove_file(path).unwrap();
+
+    Some(result)
+}
+
+// what the executable prints and exits with when it's run on its own.
+fn run(built: Result<Artifact, CompileError>) -> Option<Output> {
+    run_with(built, |path| Command::new(path).output().unwrap())
+}
+
+#[test]
+fn check_str_collects_diagnostics() {
+    let errors = check_str("let = 1;\nlet y = ;", false).err().unwrap();
+
+    assert_eq!(
+        errors
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code.as_str())
+            .collect::<Vec<_>>(),
+        vec!["ExpectedToken", "UnexpectedToken"]
+    );
+}
+
+#[test]
+fn lexer_errors_stop_the_parse() {
+    // the parser reaches the bad string before the lexer has gone any
+    // further, and only the lexer's error is reported.
+    let errors = check_str("func main(): int {\n    let s = \"open;\n}", false)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        errors
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.code.as_str())
+            .collect::<Vec<_>>(),
+        vec!["UnterminatedString"]
+    );
+}
+
+#[test]
+fn truncated_input_is_reported() {
+    let errors = check_str("func main(): int {\n    let x =", false)
+        .err()
+        .unwrap();
+
+    let diagnostic = &errors.diagnostics[0];
+    assert_eq!(diagnostic.code, "UnexpectedEof");
+    assert_eq!(
+        diagnostic.message,
+        "Unexpected end of file, expected expression"
+    );
+    // it points at the last token there was, the `=`.
+    assert_eq!((diagnostic.span.line, diagnostic.span.column), (2, 11));
+}
+
+#[test]
+fn malformed_input_is_diagnosed() {
+    let deep = "(".repeat(100_000);
+    let long = "1 + ".repeat(100_000);
+    let strings = "\"{".repeat(100_000);
+
+    for src in [
+        "func",
+        "func f(",
+        "func f(x: int = ) {",
+        "let x = [1, 2",
+        "let x = match 1 { 1 => }",
+        "let x = if (true) { 1 }",
+        "\"{",
+        "\"{}\"",
+        "'",
+        "''",
+        "1e",
+        "0x",
+        "1.5u8",
+        "@",
+        "import",
+        "#",
+        &deep,
+        &long,
+        &strings,
+    ] {
+        let errors = parse_source(src).unwrap_err();
+        assert!(!errors.is_empty());
+    }
+}
+
+#[test]
+fn check_str_denies_warnings() {
+    let src = "func main(): int { let x = 1; return 0; }";
+
+    let (_, warnings) = check_str(src, false).unwrap();
+    assert_eq!(warnings[0].severity, Severity::Warning);
+
+    let errors = check_str(src, true).err().unwrap();
+    assert_eq!(errors.diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn compile_str_builds_an_executable() {
+    let Some(output) = run(built("func main(): int { return 3; }", "uma_compile_str")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn mutually_recursive_functions_compile() {
+    let src = "func main(): int { return is_even(10) + is_odd(7) * 2; }
+        func is_even(n: int): int { if (n == 0) { return 1; } return is_odd(n - 1); }
+        func is_odd(n: int): int { if (n == 0) { return 0; } return is_even(n - 1); }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    let prototype = source.find("int is_odd(int n);").unwrap();
+    assert!(
+        prototype
+            < source
+                .find("int main(int uma_argc, char** uma_argv) {")
+                .unwrap()
+    );
+
+    let Some(output) = run(built(src, "uma_mutual_recursion")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn names_that_clash_with_c_are_escaped() {
+    let src = r#"func double(register: int): int { return register * 2; }
+        func printf(s: String): int { println(s); return 1; }
+        func main(): int {
+            let _x = 1;
+            let u_y = 2;
+            for auto in 0..2 {}
+            return double(3) + printf("hi") + _x + u_y - 8;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("int u_double(int u_register)"));
+    assert!(source.contains(r#"printf("%s\n", s.data);"#));
+    assert!(source.contains("const int u__x = 1;"));
+    assert!(source.contains("const int u_u_y = 2;"));
+
+    let Some(output) = run(built(src, "uma_mangling")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn unicode_names_are_spelt_out_for_c() {
+    let src = r#"func 倍(x: int): int { return x * 2; }
+        func main(): int {
+            let café = 2;
+            let caf_xe9_ = 1;
+            return 倍(café) - caf_xe9_;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("int u__x500d_(int x)"));
+    assert!(source.contains("const int u_caf_xe9_ = 2;"));
+    assert!(source.contains("const int caf_xe9_ = 1;"));
+
+    let Some(output) = run(built(src, "uma_unicode_names")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn characters_are_c_chars() {
+    let src = r#"func main(): int {
+            let c: Char = 'b';
+            println(c, '\'', c as Int, "<{c}>");
+            return 0;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("const char c = 'b';"));
+
+    let err = compile_str(
+        "func main(): int { let c = 'é'; return 0; }",
+        &CompileOptions::new("unused"),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.diagnostics[0].message, "`é` doesn't fit in a C `char`");
+
+    let Some(output) = run(built(src, "uma_characters")) else {
+        return;
+    };
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "b ' 98 <b>\n");
+}
+
+#[test]
+fn strings_are_escaped_for_c() {
+    let src = "func main(): int {
+            let pct = 100;
+            println(\"say \\\"hi\\\" \\\\ 100% {pct}%\", \"tab\\tbell\u{7}a ??=\");
+            return 0;
+        }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains(r#""tab\tbell\x07""a \??=""#));
+
+    let Some(output) = run(built(src, "uma_string_escapes")) else {
+        return;
+    };
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "say \"hi\" \\ 100% 100% tab\tbell\u{7}a ??=\n"
+    );
+}
+
+#[test]
+fn c_build_phases_are_timed() {
+    let (typed, _) = check_str("func main(): int { return 4; }", false).unwrap();
+    let phases = |timings: Timings| {
+        timings
+            .phases()
+            .iter()
+            .map(|(phase, _)| phase.clone())
+            .collect::<Vec<_>>()
+    };
+
+    // compiling and linking are only told apart when they're timed.
+    let mut backend = CBackend::default();
+    let options = CodegenOptions {
+        timings: true,
+        ..CodegenOptions::new(std::env::temp_dir().join("uma_timings"))
+    };
+
+    if let Some(output) = run(generated(&mut backend, &typed, &options)) {
+        assert_eq!(output.status.code(), Some(4));
+        assert_eq!(phases(backend.timings()), vec!["codegen", "cc", "link"]);
+        assert!(!options.output.with_extension("o").exists());
+    }
+
+    let options = CodegenOptions {
+        emit_source: true,
+        ..CodegenOptions::new("unused")
+    };
+
+    Codegen::generate(&mut backend, &typed, &options).unwrap();
+    assert_eq!(phases(backend.timings()), vec!["codegen"]);
+}
+
+#[test]
+fn timings_add_up_by_phase() {
+    let mut timings = Timings::new();
+    timings.record("lex", Duration::from_millis(1));
+    timings.record("parse", Duration::from_millis(2));
+    timings.record("lex", Duration::from_millis(1));
+
+    assert_eq!(
+        timings.phases(),
+        [
+            ("lex".to_string(), Duration::from_millis(2)),
+            ("parse".to_string(), Duration::from_millis(2)),
+        ]
+    );
+    assert_eq!(
+        timings.report(),
+        "lex        2.00ms   50.0%\nparse      2.00ms   50.0%\ntotal      4.00ms\n"
+    );
+}
+
+struct Counter(usize);
+
+impl Backend for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn generate(
+        &mut self,
+        program: &[ir::Stmt],
+        _: &CodegenOptions,
+    ) -> Result<Artifact, CodegenError> {
+        self.0 = program.len();
+        Ok(Artifact::Source(format!("{} statements", self.0)))
+    }
+}
+
+#[test]
+fn compile_with_a_custom_backend() {
+    let mut backend = Counter(0);
+    let options = CompileOptions::new("unused");
+
+    let src = "let x = 1;\nfunc main(): int { return x; }";
+    let artifact = compile_with(src, &mut backend, &options);
+
+    assert_eq!(artifact.unwrap(), Artifact::Source("2 statements".into()));
+    assert_eq!(backend.0, 2);
+}
+
+// the functions `main` never calls are gone by the time a backend sees the
+// program.
+#[test]
+fn custom_backends_get_the_lowered_program() {
+    let mut backend = Counter(0);
+    let options = CompileOptions::new("unused");
+
+    let src = "func unused(): int { return 1; }\nfunc main(): int { return 0; }";
+    compile_with(src, &mut backend, &options).unwrap();
+
+    assert_eq!(backend.0, 1);
+}
+
+#[test]
+fn execute_forwards_the_exit_code() {
+    let output = std::env::temp_dir().join("uma_execute");
+    let options = CompileOptions::new(&output);
+
+    let built = compile_str("func main(): int { return 5; }", &options);
+    let Some(status) = run_with(built, |path| Codegen::execute(path, &[])) else {
+        return;
+    };
+
+    assert_eq!(status, Ok(5));
+}
+
+#[test]
+fn missing_c_compiler() {
+    let mut backend = CBackend::new(CompilerOptions {
+        compiler: Some("uma-no-such-cc".into()),
+        ..CompilerOptions::default()
+    });
+    let options = CompileOptions::new(std::env::temp_dir().join("uma_missing_cc"));
+
+    let errors = compile_with("func main(): int { return 0; }", &mut backend, &options)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        errors.diagnostics[0].message,
+        "Couldn't run the C compiler `uma-no-such-cc`"
+    );
+}
+
+#[test]
+fn unreachable_functions_are_dropped() {
+    let src = "func puts(s) @requires(\"stdio.h\")
+func abs(x) @requires(\"stdlib.h\")
+func helper(): int { return 1; }
+func unused(): int { return helper(); }
+func used(): int { return helper(); }
+func main(): int { puts(\"hi\"); return used(); }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let mut options = CodegenOptions::new("unused");
+    options.emit_source = true;
+
+    let source = match Codegen::generate(&mut CBackend::default(), &typed, &options).unwrap() {
+        Artifact::Source(source) => source,
+        artifact => panic!("expected source, found {:?}", artifact),
+    };
+
+    assert!(source.contains("int helper()"));
+    assert!(source.contains("int used()"));
+    assert!(source.contains("#include <stdio.h>"));
+    assert!(!source.contains("int unused()"));
+    assert!(!source.contains("stdlib.h"));
+}
+
+#[test]
+fn prelude_picks_printf_formats() {
+    let (typed, _) = check_str(
+        r#"func main(): int { println("pi is", 3.14, "or", 3); return 0; }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("#include <stdio.h>"));
+    assert!(source.contains(r#"printf("%s %f %s %d\n", "pi is", 3.14, "or", 3)"#));
+}
+
+#[test]
+fn interpolation_lowers_to_printf_and_uma_format() {
+    let (typed, _) = check_str(
+        r#"func main(): int {
+            let n = 3;
+            let s = "n = {n}";
+            println("got {s} and {n * 2}", 1);
+            return 0;
+        }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains(r#"uma_format("n = %d", n)"#));
+    assert!(source.contains(r#"printf("got %s and %d %d\n", s.data, (n * 2), 1)"#));
+}
+
+#[test]
+fn booleans_include_stdbool() {
+    let (typed, _) = check_str(
+        r#"func main(): int {
+            let ok = 1 < 2 && true;
+            if (ok) {
+                return 1;
+            }
+            return 0;
+        }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("#include <stdbool.h>"));
+    assert!(source.contains("const bool ok = ((1 < 2) && true);"));
+}
+
+#[test]
+fn compound_assignments_stay_compact() {
+    let (typed, _) = check_str(
+        r#"func main(): int {
+            let mut n = 1;
+            let mut s = "a";
+            n *= 2 + 1;
+            n %= 2;
+            s += "b";
+            println(n, s);
+            return 0;
+        }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("n *= (2 + 1);"));
+    assert!(source.contains("n %= 2;"));
+    assert!(source.contains(r#"s = uma_replace(s, uma_concat(s, UMA_STR("b")));"#));
+}
+
+#[test]
+fn exponent_lowers_to_pow() {
+    let (typed, _) = check_str(
+        r#"func main(): int { let x = 2 ** 10; let y = 1.5 ** 2.0; return x; }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("#include <math.h>"));
+    assert!(source.contains("const int x = (int)pow(2, 10);"));
+    assert!(source.contains("const double y = pow(1.5, 2.0);"));
+
+    // `pow` needs `-lm` on some platforms.
+    let src = "func main(): int { let y = 3; return 2 ** y ** 1 + 1; }";
+    let Some(output) = run(built(src, "uma_exponent")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(9));
+}
+
+#[test]
+fn bitwise_operators_keep_c_precedence() {
+    let (typed, _) = check_str(
+        "func main(): int { let x = 1 | 2 ^ 3 & ~4 << 1; return x; }",
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("const int x = (1 | (2 ^ (3 & ((~4) << 1))));"));
+}
+
+#[test]
+fn bools_print_as_words() {
+    let src = r#"func main(): int {
+    let ok = !(1 == 2);
+    println(ok, !ok);
+    println("ok is {ok}");
+    return 0;
+}"#;
+    let (typed, _) = check_str(src, false).unwrap();
+
+    let source = CBackend::generate_source(&typed);
+    assert!(source.contains("const bool ok = (!(1 == 2));"));
+    assert!(source
+        .contains(r#"printf("%s %s\n", (ok ? "true" : "false"), ((!ok) ? "true" : "false"))"#));
+
+    let Some(output) = run(built(src, "uma_bools")) else {
+        return;
+    };
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "true false\nok is true\n"
+    );
+}
+
+#[test]
+fn floats_widen_ints() {
+    let (typed, _) = check_str(
+        "func main(): int { let pi = 3.14; let n = 2; let area = pi * n ** 2; return 0; }",
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("const double pi = 3.14;"));
+    assert!(source.contains("const double area = (pi * ((double)(int)pow(n, 2)));"));
+}
+
+#[test]
+fn sized_types_use_stdint() {
+    let (typed, _) = check_str(
+        "func main(): int { let x: Int8 = 5; let big: UInt64 = 1; println(big); return 0; }",
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("#include <stdint.h>"));
+    assert!(source.contains("const int8_t x = ((int8_t)5);"));
+    assert!(source.contains("\"%llu\\n\""));
+}
+
+#[test]
+fn casts_lower_to_c_casts() {
+    let (typed, _) = check_str(
+        "func main(): int { let f = 2.5; let n = f as Int; let small = n as UInt8; return 0; }",
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("const int n = ((int)f);"));
+    assert!(source.contains("const uint8_t small = ((uint8_t)n);"));
+}
+
+#[test]
+fn globals_come_before_functions() {
+    let src = "func main(): int { return limit + step; }
+let limit = 10 * 2;
+let mut step = 1;";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    let main = source
+        .find("int main(int uma_argc, char** uma_argv)")
+        .unwrap();
+    assert!(source.find("const int limit = (10 * 2);").unwrap() < main);
+    assert!(source.find("int step = 1;").unwrap() < main);
+}
+
+#[test]
+fn globals_must_be_constant() {
+    let error = |src| {
+        let err = check_str(src, false).unwrap_err();
+        let diagnostic = &err.diagnostics[0];
+
+        let span = diagnostic.span;
+
+        (diagnostic.message.clone(), span.line, span.column)
+    };
+
+    // the checker finds them, at the name they're declared under.
+    assert_eq!(
+        error("func two(): int { return 2; }\nlet x = two();\nfunc main(): int { return x; }"),
+        (
+            "Global `x` must be initialised with a constant".to_string(),
+            2,
+            5
+        )
+    );
+    assert_eq!(
+        error("let x = 1;\nlet x = 2;\nfunc main(): int { return x; }"),
+        ("Global `x` is declared more than once".to_string(), 2, 5)
+    );
+
+    let (typed, _) = check_str(
+        "let xs = [1, 2 << 3];\nfunc main(): int { return xs[0]; }",
+        false,
+    )
+    .unwrap();
+    let mut options = CodegenOptions::new("unused");
+    options.emit_source = true;
+
+    assert!(Codegen::generate(&mut CBackend::default(), &typed, &options).is_ok());
+}
+
+#[test]
+fn constants_become_defines() {
+    let src = "const SIDES = 2 * 3;
+const NAME = \"uma\";
+let mut count = SIDES + 1;
+func main(): int { println(NAME); return count; }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let mut options = CodegenOptions::new("unused");
+    options.emit_source = true;
+
+    let source = match Codegen::generate(&mut CBackend::default(), &typed, &options).unwrap() {
+        Artifact::Source(source) => source,
+        artifact => panic!("expected source, found {:?}", artifact),
+    };
+
+    assert!(
+        source.contains("#define SIDES 6\n#define NAME UMA_STR(\"uma\")\nint count = (SIDES + 1);")
+    );
+}
+
+#[test]
+fn if_expressions_lower_to_ternaries() {
+    let (typed, _) = check_str(
+        r#"func main(): int { let n = 2; let label = if n > 0 { "pos" } else { "neg" }; println(label); return 0; }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(
+        source.contains(r#"const UmaString label = ((n > 0) ? UMA_STR("pos") : UMA_STR("neg"));"#)
+    );
+}
+
+#[test]
+fn match_lowers_to_a_chain_of_ternaries() {
+    let (typed, _) = check_str(
+        r#"func next(): int { return 2; }
+        func main(): int { let n = 2; let a = match n { 1 => "one", _ => "many" }; let b = match next() { 1 => 1, 2 => 2, _ => 3 }; println(a, b); return 0; }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(
+        source.contains(r#"const UmaString a = (((n == 1) ? UMA_STR("one") : UMA_STR("many")));"#)
+    );
+    // a subject with a call is only worked out once.
+    assert!(source.contains("int uma_match_0;\n"));
+    assert!(source.contains(
+        "const int b = (uma_match_0 = next(), ((uma_match_0 == 1) ? 1 : ((uma_match_0 == 2) ? 2 : 3)));"
+    ));
+}
+
+#[test]
+fn named_arguments_lower_to_positional_calls() {
+    let (typed, _) = check_str(
+        r#"func area(width: int, height: int = 2): int { return width * height; }
+        func main(): int { return area(height: 3, width: 4) + area(5); }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("int area(int width, int height)"));
+    assert!(source.contains("return (area(4, 3) + area(5, 2));"));
+}
+
+#[test]
+fn parameters_keep_their_order() {
+    let (typed, _) = check_str(
+        r#"func pick(a: int, b: float, c: String, d: bool, e: int8): int { return a; }
+        func strncmp(s1: String, s2: String, n: int): int @requires("string.h")
+        func main(): int { return pick(1, 2.5, "c", true, 5) + strncmp("ab", "ac", 1); }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("int pick(int a, double b, UmaString c, bool d, int8_t e)"));
+    assert!(source.contains(r#"strncmp("ab", "ac", 1)"#));
+}
+
+#[test]
+fn generated_c_is_the_same_every_time() {
+    let src = r#"func strlen(s: String): int @requires("string.h")
+        func main(): int {
+            let big = 2i64 ** 40i64;
+            let ok = strlen("uma") == 3;
+            let xs = [1, 2, 3];
+            let root = sqrt(16.0) as int;
+
+            if (ok) { println("{big} {xs[0]} {root}"); }
+            return 0;
+        }"#;
+
+    let generate = || {
+        let (typed, _) = check_str(src, false).unwrap();
+        let mut options = CodegenOptions::new("same");
+        options.emit_source = true;
+
+        match Codegen::generate(&mut CBackend::default(), &typed, &options).unwrap() {
+            Artifact::Source(source) => source,
+            artifact => panic!("expected source, found {:?}", artifact),
+        }
+    };
+
+    let source = generate();
+    assert_eq!(source, generate());
+
+    // the headers are sorted, not in the order they were needed.
+    let headers = source
+        .lines()
+        .take_while(|line| line.starts_with("#include"))
+        .collect::<Vec<_>>();
+    let mut sorted = headers.clone();
+    sorted.sort();
+
+    assert!(headers.len() > 1);
+    assert_eq!(headers, sorted);
+}
+
+#[test]
+fn generated_c_is_indented() {
+    let (typed, _) = check_str(
+        r#"func sign(n: int): int {
+            if (n > 0) {
+                for i in 0..n { println(i); }
+                return 1;
+            } else if (n < 0) {
+                return match n { 0 => 5, _ => 2 };
+            } else {
+                return 0;
+            }
+        }
+        func main(): int { return sign(2); }"#,
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains(
+        "int sign(int n) {
+    if ((n > 0)) {
+        for (int i = 0; i < n; i++) {
+            printf(\"%d\\n\", i);
+        }
+        return 1;
+    } else if ((n < 0)) {
+        return (((n == 0) ? 5 : 2));
+    } else {
+        return 0;
+    }
+}
+"
+    ));
+}
+
+#[test]
+fn statements_point_back_at_the_source() {
+    let (typed, _) = check_str(
+        "func main(): int {\n    let x = 1;\n\n    return x;\n}",
+        false,
+    )
+    .unwrap();
+
+    let source = CBackend::generate_mapped_source(&ir::lower(&typed), &[PathBuf::from("main.uma")]);
+
+    assert!(source.contains(
+        "#line 1 \"main.uma\"
+int main(int uma_argc, char** uma_argv) {
+    uma_set_args(uma_argc, uma_argv);
+#line 2 \"main.uma\"
+    const int x = 1;
+#line 4 \"main.uma\"
+    return x;
+}"
+    ));
+    // without the files there's nothing to point at.
+    assert!(!CBackend::generate_source(&typed).contains("#line"));
+}
+
+#[test]
+fn programs_link_against_the_runtime() {
+    let src = r#"func main(): int {
+            let name = "uma" + "!";
+            println("hi {name}");
+            return 0;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    // the runtime is declared, not defined, in the program's own C.
+    assert!(source.contains("UmaString uma_concat(UmaString a, UmaString b);"));
+    assert!(!source.contains("UmaString uma_concat(UmaString a, UmaString b) {"));
+    assert!(CBackend::runtime().contains("void uma_panic(const char* format, ...) {"));
+
+    let Some(output) = run(built(src, "uma_runtime")) else {
+        return;
+    };
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi uma!\n");
+    let rt = std::env::temp_dir().join("uma_runtime.rt.c");
+    assert!(!rt.exists());
+}
+
+#[test]
+fn standalone_c_compiles_on_its_own() {
+    let src = r#"func main(): int {
+            let name = "uma" + "!";
+            println("hi {name}");
+            return 0;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::standalone(&CBackend::generate_source(&typed));
+
+    let path = std::env::temp_dir().join("uma_standalone");
+    std::fs::write(path.with_extension("c"), source).unwrap();
+
+    let status = Command::new("cc")
+        .arg(path.with_extension("c"))
+        .arg("-o")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&path).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi uma!\n");
+
+    std::fs::remove_file(path.with_extension("c")).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn strings_are_counted_and_let_go_of() {
+    let src = r#"func shout(s: String): String { return s + "!"; }
+        func main(): int {
+            let mut word = "um";
+            word += "a";
+            for i in 0..len(word) { print(word[i], ""); }
+            println(shout(word), len(shout(word)));
+            return 0;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    // what's made for a single statement is let go of right after it.
+    assert!(source.contains(
+        "printf(\"%s %d\\n\", (uma_str_0 = uma_replace(uma_str_0, shout(word))).data, (int)(uma_str_1 = uma_replace(uma_str_1, shout(word))).len);\n    uma_str_0 = uma_release(uma_str_0);\n    uma_str_1 = uma_release(uma_str_1);"
+    ));
+    // and the variables once their scope ends.
+    assert!(source.contains("uma_result = 0;\n    uma_release(word);\n    return uma_result;"));
+
+    let Some(output) = run(built(src, "uma_strings")) else {
+        return;
+    };
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "u m a uma! 4\n");
+}
+
+#[test]
+fn strings_in_arrays_are_let_go_of() {
+    let src = r#"func main(): int {
+            let xs = ["u" + "m", "a"];
+            let mut ys = xs;
+            ys = [xs[1] + "!", "b"];
+            println(xs[0], ys[0]);
+            return 0;
+        }"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    // a copy holds on to the strings, and the ones it replaces are let go of
+    // after the new ones are held.
+    assert!(source
+        .contains("memcpy(ys, xs, sizeof(ys));\n    uma_retain(ys[0]);\n    uma_retain(ys[1]);"));
+    assert!(source.contains(
+        "uma_retain(ys[1]);\n        uma_release(uma_old[0]);\n        uma_release(uma_old[1]);"
+    ));
+    assert!(source.contains(
+        "uma_release(ys[1]);\n    uma_release(ys[0]);\n    uma_release(xs[1]);\n    uma_release(xs[0]);\n    return uma_result;"
+    ));
+
+    let Some(output) = run(built(src, "uma_string_arrays")) else {
+        return;
+    };
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "um a!\n");
+}
+
+#[test]
+fn indexing_is_checked_at_runtime() {
+    let src = "func at(i: int): int {
+    let xs = [1, 2, 3];
+    return xs[0] + xs[i];
+}
+func main(): int { return at(5); }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let output = std::env::temp_dir().join("uma_bounds");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    if let Some(output) = run(generated(&mut CBackend::default(), &typed, &options)) {
+        assert_eq!(output.status.code(), Some(101));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "panic: index 5 out of bounds for length 3 at main.uma:3\n"
+        );
+    }
+
+    let source = CBackend::generate_source(&typed);
+    assert!(source.contains("return (xs[0] + xs[uma_bounds(i, 3, __FILE__, __LINE__)]);"));
+
+    // `--release` takes the checks out.
+    let mut backend = CBackend::new(CompilerOptions {
+        release: true,
+        ..CompilerOptions::default()
+    });
+    let options = CodegenOptions {
+        emit_source: true,
+        ..CodegenOptions::new("unused")
+    };
+
+    match Codegen::generate(&mut backend, &typed, &options).unwrap() {
+        Artifact::Source(source) => assert!(source.contains("return (xs[0] + xs[i]);")),
+        artifact => panic!("expected source, found {:?}", artifact),
+    }
+
+    // a string is checked by the runtime when it is indexed, which says
+    // where as well.
+    let (string, _) = check_str(
+        "func main(): int {\n    let s = \"um\";\n    println(s[5]);\n    return 0;\n}",
+        false,
+    )
+    .unwrap();
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(std::env::temp_dir().join("uma_string_bounds"))
+    };
+
+    if let Some(output) = run(generated(&mut CBackend::default(), &string, &options)) {
+        assert_eq!(output.status.code(), Some(101));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "panic: index 5 is out of bounds for a string of length 2 at main.uma:3\n"
+        );
+    }
+}
+
+#[test]
+fn arithmetic_is_checked_when_asked() {
+    let src = "func halve(n: int, by: uint8): int {
+    let x: uint8 = 9;
+    return n / 2 + (x % by) as int;
+}
+func main(): int {
+    let mut n = 2147483640;
+    n += halve(8, 2) + 40;
+    return halve(4, 0);
+}";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let mut backend = CBackend::new(CompilerOptions {
+        checks: true,
+        ..CompilerOptions::default()
+    });
+    let output = std::env::temp_dir().join("uma_checks");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    if let Some(output) = run(generated(&mut backend, &typed, &options)) {
+        assert_eq!(output.status.code(), Some(101));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "panic: integer overflow in `+` at main.uma:7\n"
+        );
+    }
+
+    let options = CodegenOptions {
+        emit_source: true,
+        ..options
+    };
+    let source = match Codegen::generate(&mut backend, &typed, &options).unwrap() {
+        Artifact::Source(source) => source,
+        artifact => panic!("expected source, found {:?}", artifact),
+    };
+
+    assert!(source.contains(
+        "return ((int)uma_add(((int)uma_div(n, 2, 32, __FILE__, __LINE__)), ((int)((uint8_t)uma_umod(x, by, __FILE__, __LINE__))), 32, __FILE__, __LINE__));"
+    ));
+}
+
+#[test]
+fn dividing_by_zero_panics() {
+    let src = "func div(a: int, b: int): int { return a / b; }
+func main(): int { return div(1, 0); }";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let mut backend = CBackend::new(CompilerOptions {
+        checks: true,
+        ..CompilerOptions::default()
+    });
+    let output = std::env::temp_dir().join("uma_div_zero");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    let Some(output) = run(generated(&mut backend, &typed, &options)) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "panic: division by zero at main.uma:1\n"
+    );
+}
+
+#[test]
+fn failed_asserts_say_where() {
+    let src = "func main(): int {
+    let n = 3;
+    assert(n > 2);
+    assert(n % 2 == 0, \"{n} is odd\");
+    return 0;
+}";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let output = std::env::temp_dir().join("uma_assert");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    if let Some(output) = run(generated(&mut CBackend::default(), &typed, &options)) {
+        assert_eq!(output.status.code(), Some(101));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "panic: assertion `n % 2 == 0` failed at main.uma:4: 3 is odd\n"
+        );
+    }
+
+    // `--release` compiles them out.
+    let mut backend = CBackend::new(CompilerOptions {
+        release: true,
+        ..CompilerOptions::default()
+    });
+    let options = CodegenOptions {
+        emit_source: true,
+        ..CodegenOptions::new("unused")
+    };
+
+    match Codegen::generate(&mut backend, &typed, &options).unwrap() {
+        Artifact::Source(source) => assert!(!source.contains("uma_assert(\"")),
+        artifact => panic!("expected source, found {:?}", artifact),
+    }
+}
+
+#[test]
+fn math_builtins_come_from_math_h() {
+    let src = "func main(): int {
+    let x = 0 - 7;
+    let u: uint8 = 200;
+    println(abs(x), min(3, x), max(u, 9), sqrt(16), round(2.5));
+    return 0;
+}";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let source = CBackend::generate_source(&typed);
+
+    assert!(source.contains("#include <math.h>"));
+    assert!(source.contains(
+        "abs(x), ((int)uma_min(3, x)), ((uint8_t)uma_umax(u, ((uint8_t)9))), sqrt(((double)16)), round(2.5)"
+    ));
+
+    let Some(output) = run(built(src, "uma_math")) else {
+        return;
+    };
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "7 -7 200 4.000000 3.000000\n"
+    );
+}
+
+#[test]
+fn input_is_read_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let src = "func main(): int {
+    let name = input(\"name? \");
+    let n = parse_int(input());
+    println(\"hi {name}, {n + 1}\");
+    let x = parse_float(input());
+    return 0;
+}";
+
+    let (typed, _) = check_str(src, false).unwrap();
+    let output = std::env::temp_dir().join("uma_input");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    let built = generated(&mut CBackend::default(), &typed, &options);
+    let Some(output) = run_with(built, |path| {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(b"Ada\n 41\nnope\n")
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "name? hi Ada, 42\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "panic: cannot parse \"nope\" as a `Float` at main.uma:5\n"
+    );
+}
+
+#[test]
+fn files_are_read_and_written() {
+    let dir = std::env::temp_dir();
+    let file = dir.join("uma_files.txt");
+    let src = format!(
+        "func main(): int {{
+    let path = \"{}\";
+    if (write_file(path, \"one\\n\") == false || append_file(path, \"two\\n\") == false) {{
+        return 1;
+    }}
+    print(read_file(path) + read_file(\"{1}\", \"none\\n\"));
+    let missing = read_file(\"{1}\");
+    return 0;
+}}",
+        file.display(),
+        dir.join("uma_no_such_file.txt").display()
+    );
+
+    let (typed, _) = check_str(&src, false).unwrap();
+    let output = dir.join("uma_files");
+    let options = CodegenOptions {
+        files: vec![PathBuf::from("main.uma")],
+        ..CodegenOptions::new(&output)
+    };
+
+    let Some(output) = run(generated(&mut CBackend::default(), &typed, &options)) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "one\ntwo\nnone\n");
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .ends_with(": No such file or directory at main.uma:7\n"));
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn programs_see_their_arguments() {
+    let src = "func main(): int {
+    for i in 1..arg_count() {
+        println(arg(i));
+    }
+    return arg_count();
+}";
+
+    let built = built(src, "uma_args");
+    let Some(output) = run_with(built, |path| {
+        Command::new(path).args(["a", "b c"]).output().unwrap()
+    }) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a\nb c\n");
+}
+
+#[test]
+fn programs_exit_and_read_the_environment() {
+    let src = r#"func main(): int {
+    set_env("UMA_GREETING", env("UMA_NAME") + "!");
+    print(env("UMA_GREETING"));
+    exit(7);
+}"#;
+
+    let built = built(src, "uma_exit");
+    let Some(output) = run_with(built, |path| {
+        Command::new(path).env("UMA_NAME", "ada").output().unwrap()
+    }) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ada!");
+}
+
+#[test]
+fn programs_need_a_main_to_start_from() {
+    let error = |src| {
+        let err = compile_str(src, &CompileOptions::new("unused")).unwrap_err();
+        let diagnostic = &err.diagnostics[0];
+
+        (diagnostic.code.clone(), diagnostic.message.clone())
+    };
+
+    assert_eq!(
+        error("func helper(): int { return 0; }"),
+        (
+            "MissingMain".to_string(),
+            "No `main` function found".to_string()
+        )
+    );
+    assert_eq!(
+        error("func main(argc: int): int { return argc; }"),
+        (
+            "InvalidMain".to_string(),
+            "`main` can't take arguments".to_string()
+        )
+    );
+    assert_eq!(
+        error("func main(): float { return 1.0; }"),
+        (
+            "InvalidMain".to_string(),
+            "`main` must return an `Int` or nothing, found `Float`".to_string()
+        )
+    );
+
+    // one that returns nothing exits with 0.
+    let Some(output) = run(built("func main() { println(1); }", "uma_void_main")) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn only_declarations_go_at_the_top_level() {
+    let main = "func main(): int {\n    return 0;\n}\n";
+
+    for stmt in [
+        "for i in 0..3 { println(i); }",
+        "{ let x = 1; println(x); }",
+        "println(\"hi\");",
+        "if (true) { println(1); }",
+    ] {
+        let src = format!("{}{}", main, stmt);
+        let err = generate_c(&src).unwrap_err();
+        let diagnostic = &err.diagnostics[0];
+
+        assert_eq!(diagnostic.code, "TopLevelStatement");
+        assert_eq!(diagnostic.span.line, 4, "{}", stmt);
+
+        // they're still fine where they can run, like the REPL.
+        assert!(check_str(&src, false).is_ok());
+    }
+}
+
+#[test]
+fn the_test_harness_runs_one_test() {
+    let src = "func passes() @test { assert(1 + 1 == 2); }
+func fails() @test { assert(1 + 1 == 3); }
+";
+    let tests = test_names(&parse_str(src).unwrap());
+    assert_eq!(tests, ["passes", "fails"]);
+
+    let src = format!("{}{}", src, test_harness(&tests));
+    let Some(passed) = run_with(built(&src, "uma_harness"), |path| {
+        let run = |test: &str| {
+            let output = Command::new(path).arg(test).output();
+            output.unwrap().status.success()
+        };
+
+        (run("0"), run("1"))
+    }) else {
+        return;
+    };
+
+    assert_eq!(passed, (true, false));
+}
+
+#[test]
+fn attributes_change_the_generated_c() {
+    let c = generate_c(
+        "func twice(x: int): int @inline { return x * 2; }
+func answer(): int @export { return 42; }
+func unused(): int { return 0; }
+func main() { println(twice(2)); }",
+    )
+    .unwrap();
+
+    assert!(c.contains("static inline int twice(int x) {"), "{}", c);
+    assert!(c.contains("int answer() {"), "{}", c);
+    assert!(!c.contains("unused"), "{}", c);
+}
+
+#[test]
+fn exported_functions_build_into_a_library() {
+    let (typed, _) = check_str(
+        r#"func add(a: int, b: int): int @export { return a + b + one(); }
+func one(): int { return 1; }
+func greet(name: string): string @export { return "hi " + name; }"#,
+        false,
+    )
+    .unwrap();
+    typeck::check_library(&typed).unwrap();
+
+    let header = CBackend::header(&typed, "sums.h");
+    assert!(header.starts_with("#ifndef UMA_SUMS_H\n#define UMA_SUMS_H\n"));
+    assert!(header.contains("int add(int a, int b);\nUmaString greet(UmaString name);\n"));
+    assert!(!header.contains("one("));
+
+    // only what the exported functions need of the runtime, and declared
+    // for C++ as well.
+    let runtime = header.find("extern \"C\" {").unwrap();
+    assert!(header.find("} UmaString;").unwrap() > runtime);
+    assert!(header.contains("UmaString uma_cstr(const char* data);"));
+    assert!(header.contains("UmaString uma_release(UmaString s);"));
+    assert!(!header.contains("uma_panic"));
+    assert!(!header.contains("#include"));
+
+    let dir = std::env::temp_dir().join("uma_library");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("sums.h"), header).unwrap();
+    std::fs::write(
+        dir.join("host.c"),
+        r#"#include <stdio.h>
+#include "sums.h"
+int main(void) { printf("%d %s", add(1, 2), greet(uma_cstr("c")).data); return 0; }"#,
+    )
+    .unwrap();
+
+    let options = CodegenOptions {
+        library: Some(Library::Static),
+        ..CodegenOptions::new(dir.join("libsums.a"))
+    };
+    let library = match Codegen::generate(&mut CBackend::default(), &typed, &options).unwrap() {
+        Artifact::Library(path) => path,
+        artifact => panic!("expected a library, found {:?}", artifact),
+    };
+
+    let host = dir.join("host");
+    let status = Command::new("cc")
+        .arg(dir.join("host.c"))
+        .arg(&library)
+        .arg("-o")
+        .arg(&host)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&host).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "4 hi c");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn externals_write_through_pointers() {
+    let src = r#"func sscanf(s: String, format: String, ...): int @requires("stdio.h")
+func next(p: Ptr<Int>): int { return *p + 1; }
+func main(): int {
+    let mut n = 0;
+    let mut m = 0;
+    let read = sscanf("12 30", "%d %d", &n, &m);
+    let mut p = &n;
+    let pp = &p;
+    println("{read} {m} {next(p)} {**pp}");
+    return 0;
+}"#;
+
+    let Some(output) = run(built(src, "uma_pointers")) else {
+        return;
+    };
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2 30 13 12\n");
+}
+
+#[test]
+fn opaque_types_are_only_pointed_to() {
+    let src = r#"type FILE @opaque @requires("stdio.h");
+type Counter @opaque;
+func fopen(path: String, mode: String): Ptr<FILE> @requires("stdio.h")
+func fputs(s: String, file: Ptr<FILE>): int @requires("stdio.h")
+func fclose(file: Ptr<FILE>): int @requires("stdio.h")
+func count(counter: Ptr<Counter>): Ptr<Counter> @export { return counter; }
+func main(): int {
+    let file = fopen(arg(1), "w");
+    fputs("opaque", file);
+    return fclose(file);
+}"#;
+
+    let (typed, _) = check_str(src, false).unwrap();
+
+    let source = CBackend::generate_source(&typed);
+    assert!(source.contains("typedef struct Counter Counter;"));
+    assert!(!source.contains("typedef struct FILE"));
+    assert!(source.contains("FILE* const file = fopen("));
+
+    let header = CBackend::header(&typed, "count.h");
+    assert!(header.contains("typedef struct Counter Counter;\n\nCounter* count(Counter* counter);"));
+
+    let written = std::env::temp_dir().join("uma_opaque.txt");
+    let built = built(src, "uma_opaque");
+    let Some(output) = run_with(built, |path| {
+        Command::new(path).arg(&written).output().unwrap()
+    }) else {
+        return;
+    };
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(std::fs::read_to_string(&written).unwrap(), "opaque");
+    std::fs::remove_file(written).unwrap();
+}
+
+#[test]
+fn unwritable_outputs_are_errors() {
+    // a file can't have anything under it.
+    let file = std::env::temp_dir().join(format!("uma_not_a_dir_{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+
+    let options = CompileOptions {
+        backend: CodegenBackend::Wasm,
+        ..CompileOptions::new(file.join("out"))
+    };
+    let err = compile_str("func main(): int { return 0; }", &options).unwrap_err();
+    std::fs::remove_file(&file).unwrap();
+
+    let message = &err.diagnostics[0].message;
+    assert!(
+        message.starts_with(&format!("Couldn't create `{}`", file.display())),
+        "{}",
+        message
+    );
+}
@@ -0,0 +1,113 @@
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+// how long each phase of a build took, for `--timings`. a phase that runs
+// more than once, like lexing every imported file, adds up.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Timings {
+    phases: Vec<(String, Duration)>,
+}
+
+impl Timings {
+    pub fn new() -> Timings {
+        Timings::default()
+    }
+
+    pub fn record(&mut self, phase: &str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| name == phase) {
+            Some((_, total)) => *total += duration,
+            None => self.phases.push((phase.to_string(), duration)),
+        }
+    }
+
+    // runs `f`, recording how long it took as `phase`.
+    pub fn time<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let value = f();
+        self.record(phase, start.elapsed());
+
+        value
+    }
+
+    pub fn extend(&mut self, other: &Timings) {
+        for (phase, duration) in &other.phases {
+            self.record(phase, *duration);
+        }
+    }
+
+    pub fn phases(&self) -> &[(String, Duration)] {
+        &self.phases
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    // the phases in the order they ran, with how long each took and its
+    // share of the whole.
+    pub fn report(&self) -> String {
+        let total = self.total();
+        let width = self
+            .phases
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .chain(["total".len()])
+            .max()
+            .unwrap_or_default();
+
+        let mut out = String::new();
+
+        for (phase, duration) in &self.phases {
+            let share = match total.is_zero() {
+                true => 0.0,
+                false => duration.as_secs_f64() / total.as_secs_f64() * 100.0,
+            };
+
+            out.push_str(&format!(
+                "{:<width$}  {:>10}  {:>5.1}%\n",
+                phase,
+                millis(*duration),
+                share,
+            ));
+        }
+
+        out.push_str(&format!("{:<width$}  {:>10}\n", "total", millis(total)));
+        out
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+// `iter`, adding up the time spent getting each of its items in `total`. the
+// lexer runs as the parser asks for tokens, this tells the two apart.
+pub struct Timed<I> {
+    iter: I,
+    total: Rc<Cell<Duration>>,
+}
+
+impl<I> Timed<I> {
+    pub fn new(iter: I, total: Rc<Cell<Duration>>) -> Timed<I> {
+        Timed { iter, total }
+    }
+}
+
+impl<I: Iterator> Iterator for Timed<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let start = Instant::now();
+        let item = self.iter.next();
+        self.total.set(self.total.get() + start.elapsed());
+
+        item
+    }
+}
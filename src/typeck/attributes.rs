@@ -0,0 +1,140 @@
+use std::mem::discriminant;
+
+use crate::diagnostics::suggest;
+use crate::lexer::Token;
+use crate::parser::Attribute;
+
+use super::{types::FunctionAttribute, TypeError};
+
+// every attribute a function can be given, including the two the parser
+// takes care of itself.
+const ATTRIBUTES: &[&str] = &[
+    "requires",
+    "link",
+    "cfg",
+    "test",
+    "inline",
+    "deprecated",
+    "export",
+];
+
+// the attributes a type can be given, `@requires` is taken care of by the
+// parser.
+const TYPE_ATTRIBUTES: &[&str] = &["requires", "opaque", "cfg"];
+
+// that `attributes` are the ones a type declaration needs.
+pub fn check_type(name: &str, token: &Token, attributes: &[Attribute]) -> Result<(), TypeError> {
+    let mut opaque = false;
+
+    for attribute in attributes {
+        let token = attribute.token.clone();
+
+        match (attribute.name.as_str(), &attribute.value) {
+            ("cfg", Some(_)) => (),
+            ("cfg", None) => {
+                return TypeError::new("`@cfg` needs a flag, like `@cfg(\"linux\")`", token)
+            }
+            ("opaque", None) if opaque => {
+                return TypeError::new("`@opaque` is given more than once", token)
+            }
+            ("opaque", None) => opaque = true,
+            ("opaque", Some(_)) => return TypeError::new("`@opaque` doesn't take a value", token),
+            (name, _) if ATTRIBUTES.contains(&name) => {
+                return TypeError::new(format!("`@{}` can't go on a type", name), token)
+            }
+            (name, _) => {
+                return TypeError::suggested(
+                    format!("Unknown attribute `@{}`", name),
+                    token,
+                    suggest(name, TYPE_ATTRIBUTES.iter().copied()),
+                )
+            }
+        }
+    }
+
+    // there's nothing else a type could be yet.
+    if !opaque {
+        return TypeError::new(
+            format!(
+                "Type `{}` needs `@opaque`, only C's types can be declared",
+                name
+            ),
+            token.clone(),
+        );
+    }
+
+    Ok(())
+}
+
+// what `attributes` ask of `function`, or why they can't go on it.
+pub fn check(
+    function: &str,
+    external: bool,
+    attributes: &[Attribute],
+) -> Result<Vec<FunctionAttribute>, TypeError> {
+    let mut checked: Vec<FunctionAttribute> = vec![];
+
+    for attribute in attributes {
+        let token = attribute.token.clone();
+
+        let attr = match (attribute.name.as_str(), &attribute.value) {
+            // what it asks for was settled before the program was checked.
+            ("cfg", Some(_)) => continue,
+            ("cfg", None) => {
+                return TypeError::new("`@cfg` needs a flag, like `@cfg(\"linux\")`", token)
+            }
+            ("test", None) => FunctionAttribute::Test,
+            ("inline", None) => FunctionAttribute::Inline,
+            ("export", None) => FunctionAttribute::Export,
+            ("deprecated", reason) => FunctionAttribute::Deprecated(reason.clone()),
+            ("test" | "inline" | "export", Some(_)) => {
+                return TypeError::new(format!("`@{}` doesn't take a value", attribute.name), token)
+            }
+            (name, _) => {
+                return TypeError::suggested(
+                    format!("Unknown attribute `@{}`", name),
+                    token,
+                    suggest(name, ATTRIBUTES.iter().copied()),
+                )
+            }
+        };
+
+        if checked
+            .iter()
+            .any(|other| discriminant(other) == discriminant(&attr))
+        {
+            return TypeError::new(
+                format!("`@{}` is given more than once", attribute.name),
+                token,
+            );
+        }
+
+        // an external's body is in C, and `main` is only ever called by it.
+        let misplaced = match attr {
+            FunctionAttribute::Deprecated(_) => None,
+            _ if external => Some("a function with `@requires`".to_string()),
+            FunctionAttribute::Test | FunctionAttribute::Inline if function == "main" => {
+                Some("`main`".to_string())
+            }
+            _ => None,
+        };
+
+        if let Some(function) = misplaced {
+            return TypeError::new(
+                format!("`@{}` can't go on {}", attribute.name, function),
+                token,
+            );
+        }
+
+        // a `static inline` function can't be seen from outside the file.
+        let exclusive = [FunctionAttribute::Inline, FunctionAttribute::Export];
+
+        if exclusive.contains(&attr) && checked.iter().any(|other| exclusive.contains(other)) {
+            return TypeError::new("`@inline` and `@export` can't go together", token);
+        }
+
+        checked.push(attr);
+    }
+
+    Ok(checked)
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use super::{Type, TypedExpr};
+use crate::lexer::TokenKind;
+
+// `expr` worked out to a literal before the program runs, with `consts` the
+// values of the constants it can use. it's `None` for anything that has to
+// wait for the program, like a call, or that C wouldn't get the same answer
+// for, like an `Int` overflowing.
+pub fn fold(expr: &TypedExpr, consts: &HashMap<String, TypedExpr>) -> Option<TypedExpr> {
+    match expr {
+        TypedExpr::Number(_)
+        | TypedExpr::Float(_)
+        | TypedExpr::Bool(_)
+        | TypedExpr::Char(_)
+        | TypedExpr::String(_) => Some(expr.clone()),
+        TypedExpr::Identifier { name, .. } => consts.get(name).cloned(),
+        TypedExpr::Unary { op, operand, .. } => match (&op.kind, fold(operand, consts)?) {
+            (TokenKind::Not, TypedExpr::Bool(value)) => Some(TypedExpr::Bool(!value)),
+            (_, operand) => int(!integer(&operand)?),
+        },
+        TypedExpr::Cast { value, ty } => cast(fold(value, consts)?, ty),
+        TypedExpr::Binary { lhs, op, rhs, .. } => {
+            binary(fold(lhs, consts)?, &op.kind, fold(rhs, consts)?)
+        }
+        TypedExpr::If {
+            condition,
+            consequence,
+            alternative,
+            ..
+        } => match fold(condition, consts)? {
+            TypedExpr::Bool(true) => fold(consequence, consts),
+            TypedExpr::Bool(false) => fold(alternative, consts),
+            _ => None,
+        },
+        TypedExpr::Match {
+            subject,
+            arms,
+            default,
+            ..
+        } => {
+            let subject = fold(subject, consts)?;
+
+            for (pattern, value) in arms {
+                let pattern = fold(pattern, consts)?;
+
+                match binary(subject.clone(), &TokenKind::BinaryEq, pattern)? {
+                    TypedExpr::Bool(true) => return fold(value, consts),
+                    _ => continue,
+                }
+            }
+
+            fold(default, consts)
+        }
+        _ => None,
+    }
+}
+
+// whether C can work `expr` out before the program starts, which a global's
+// value has to be. `**` is a call to
+// `pow`, and strings are joined and compared with calls too.
+pub fn constant(expr: &TypedExpr, consts: &HashMap<String, TypedExpr>) -> bool {
+    match expr {
+        TypedExpr::Number(_)
+        | TypedExpr::Float(_)
+        | TypedExpr::Bool(_)
+        | TypedExpr::Char(_)
+        | TypedExpr::String(_) => true,
+        TypedExpr::Array { elements, .. } => {
+            elements.iter().all(|element| constant(element, consts))
+        }
+        TypedExpr::Unary { operand, .. } | TypedExpr::Cast { value: operand, .. } => {
+            constant(operand, consts)
+        }
+        TypedExpr::Binary { lhs, op, rhs, .. } => {
+            op.kind != TokenKind::Expo
+                && lhs.ty() != Type::String
+                && constant(lhs, consts)
+                && constant(rhs, consts)
+        }
+        TypedExpr::If {
+            condition,
+            consequence,
+            alternative,
+            ..
+        } => [condition, consequence, alternative]
+            .iter()
+            .all(|expr| constant(expr, consts)),
+        // strings are compared with `uma_compare`.
+        TypedExpr::Match {
+            subject,
+            arms,
+            default,
+            ..
+        } => {
+            subject.ty() != Type::String
+                && constant(subject, consts)
+                && constant(default, consts)
+                && arms
+                    .iter()
+                    .all(|(pattern, value)| constant(pattern, consts) && constant(value, consts))
+        }
+        TypedExpr::Identifier { name, .. } => consts.contains_key(name),
+        _ => false,
+    }
+}
+
+fn integer(expr: &TypedExpr) -> Option<i64> {
+    match expr {
+        TypedExpr::Number(num) => num.parse().ok(),
+        _ => None,
+    }
+}
+
+// an `Int` literal, if `num` fits in one.
+fn int(num: i64) -> Option<TypedExpr> {
+    let (min, max) = Type::Int.range()?;
+
+    (min <= num as i128 && num as i128 <= max).then(|| TypedExpr::Number(num.to_string()))
+}
+
+fn float(num: f64) -> Option<TypedExpr> {
+    num.is_finite()
+        .then(|| TypedExpr::Float(format!("{:?}", num)))
+}
+
+fn cast(value: TypedExpr, ty: &Type) -> Option<TypedExpr> {
+    match (&value, ty) {
+        (TypedExpr::Number(_), Type::Int) => Some(value),
+        (TypedExpr::Number(num), Type::Float) => float(num.parse().ok()?),
+        (TypedExpr::Float(num), Type::Int) => {
+            let num: f64 = num.parse().ok()?;
+            int(num.is_finite().then_some(num.trunc() as i64)?)
+        }
+        (TypedExpr::Float(_), Type::Float) => Some(value),
+        (TypedExpr::Bool(b), Type::Int) => int(*b as i64),
+        (TypedExpr::Char(c), Type::Int) => int(*c as i64),
+        // a cast of a literal to one of the sized types is left for C to do.
+        (
+            TypedExpr::Number(_) | TypedExpr::Float(_) | TypedExpr::Bool(_) | TypedExpr::Char(_),
+            ty,
+        ) if ty.is_sized() => Some(TypedExpr::Cast {
+            value: value.into(),
+            ty: ty.clone(),
+        }),
+        _ => None,
+    }
+}
+
+fn binary(lhs: TypedExpr, op: &TokenKind, rhs: TypedExpr) -> Option<TypedExpr> {
+    use TokenKind as TT;
+
+    match (&lhs, &rhs) {
+        (TypedExpr::Number(_), TypedExpr::Number(_)) => {
+            let (l, r) = (integer(&lhs)?, integer(&rhs)?);
+
+            match op {
+                TT::Add => int(l.checked_add(r)?),
+                TT::Sub => int(l.checked_sub(r)?),
+                TT::Multi => int(l.checked_mul(r)?),
+                TT::Div => int(l.checked_div(r)?),
+                TT::Mod => int(l.checked_rem(r)?),
+                TT::Expo => int(l.checked_pow(r.try_into().ok()?)?),
+                TT::BitAnd => int(l & r),
+                TT::BitOr => int(l | r),
+                TT::BitXor => int(l ^ r),
+                // the count wraps to the bits in an `Int`, like every backend.
+                TT::Shl => int(l << (r & (Type::Int.bits()? as i64 - 1))),
+                TT::Shr => int(l >> (r & (Type::Int.bits()? as i64 - 1))),
+                op => compare(l.cmp(&r), op),
+            }
+        }
+        (TypedExpr::Float(l), TypedExpr::Float(r)) => {
+            let (l, r): (f64, f64) = (l.parse().ok()?, r.parse().ok()?);
+
+            match op {
+                TT::Add => float(l + r),
+                TT::Sub => float(l - r),
+                TT::Multi => float(l * r),
+                TT::Div => float(l / r),
+                TT::Expo => float(l.powf(r)),
+                op => compare(l.partial_cmp(&r)?, op),
+            }
+        }
+        (TypedExpr::String(l), TypedExpr::String(r)) => match op {
+            TT::Add => Some(TypedExpr::String(format!("{}{}", l, r))),
+            op => compare(l.cmp(r), op),
+        },
+        (TypedExpr::Char(l), TypedExpr::Char(r)) => compare(l.cmp(r), op),
+        (TypedExpr::Bool(l), TypedExpr::Bool(r)) => match op {
+            TT::And => Some(TypedExpr::Bool(*l && *r)),
+            TT::Or => Some(TypedExpr::Bool(*l || *r)),
+            op => compare(l.cmp(r), op),
+        },
+        _ => None,
+    }
+}
+
+fn compare(ordering: std::cmp::Ordering, op: &TokenKind) -> Option<TypedExpr> {
+    use std::cmp::Ordering::*;
+
+    Some(TypedExpr::Bool(match op {
+        TokenKind::BinaryEq => ordering == Equal,
+        TokenKind::BinaryNeq => ordering != Equal,
+        TokenKind::BinaryGt => ordering == Greater,
+        TokenKind::BinaryGte => ordering != Less,
+        TokenKind::BinaryLt => ordering == Less,
+        TokenKind::BinaryLte => ordering != Greater,
+        _ => return None,
+    }))
+}
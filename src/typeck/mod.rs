@@ -0,0 +1,1901 @@
+mod attributes;
+mod fold;
+mod prelude;
+mod types;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::diagnostics::{suggest, Diagnostic};
+use crate::formatter;
+use crate::lexer::{Span, Token, TokenKind};
+use crate::parser::{Arg, Block, Expr, Param, Part, Pattern, Spanned, Stmt};
+use crate::CompileError;
+
+use self::fold::{constant, fold};
+pub use self::prelude::{interpolation, Builtin};
+pub use self::types::{FunctionAttribute, Type, TypedExpr, TypedPart, TypedStmt};
+
+// what a `Ptr` can point to, for the errors about the ones it can't.
+const POINTEES: &str = "a pointer can only be to a number, `Bool`, `Char` or another pointer";
+
+#[derive(Debug, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub token: Token,
+    // a name that was probably meant instead of the one that was used.
+    pub suggestion: Option<String>,
+}
+
+impl TypeError {
+    pub fn new<T>(message: impl Into<String>, token: Token) -> Result<T, TypeError> {
+        Self::suggested(message, token, None)
+    }
+
+    pub fn suggested<T>(
+        message: impl Into<String>,
+        token: Token,
+        suggestion: Option<String>,
+    ) -> Result<T, TypeError> {
+        Err(TypeError {
+            message: message.into(),
+            token,
+            suggestion,
+        })
+    }
+
+    pub fn at<T>(message: impl Into<String>, span: Span) -> Result<T, TypeError> {
+        Self::new(message, Token::new(TokenKind::None, None, span))
+    }
+
+    // for errors found away from the source, like in a helper, the caller
+    // gives them a location with `or_at`.
+    pub fn unlocated<T>(message: impl Into<String>) -> Result<T, TypeError> {
+        Self::at(message, Span::default())
+    }
+
+    fn or_at(mut self, span: Span) -> TypeError {
+        if self.token.span == Span::default() {
+            self.token.span = span;
+        }
+
+        self
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    name: String,
+    ty: Type,
+    used: bool,
+    is_mut: bool,
+    // its name where it's declared, or the function's for a parameter.
+    span: Span,
+}
+
+// what a call needs to know about the function it calls. the parameters of
+// an external aren't typed, so their types are `None`.
+#[derive(Clone)]
+struct Signature {
+    ty: Type,
+    params: Vec<(Param, Option<Type>)>,
+    is_varadic: bool,
+    attributes: Vec<FunctionAttribute>,
+}
+
+#[derive(Clone)]
+pub struct TypeChecker {
+    functions: HashMap<String, Signature>,
+    // functions with a body in the order they were declared, with their
+    // names' spans, and the ones that have been called, so unused ones can
+    // be reported.
+    defined: Vec<(String, Span)>,
+    called: HashSet<String>,
+    // bindings are kept in declaration order so warnings come out in it.
+    scopes: Vec<Vec<Binding>>,
+    // the values of the constants, which are always global.
+    consts: HashMap<String, TypedExpr>,
+    // the `@opaque` types.
+    types: HashSet<String>,
+    // the globals declared so far, when it's a whole program being checked.
+    // C has to be able to work them out before it starts, where the repl
+    // can declare them again and give them anything.
+    globals: Option<HashSet<String>>,
+    // the function being checked and what it returns, for its `return`s.
+    // nothing at the top level.
+    function: Option<(String, Type)>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeChecker {
+    pub fn new() -> TypeChecker {
+        TypeChecker {
+            functions: HashMap::new(),
+            defined: Vec::new(),
+            called: HashSet::new(),
+            scopes: vec![Vec::new()],
+            consts: HashMap::new(),
+            types: HashSet::new(),
+            globals: None,
+            function: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    // checks a whole program, returning its typed IR along with any warnings.
+    pub fn check(stmts: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<Diagnostic>), TypeError> {
+        let mut checker = Self {
+            globals: Some(HashSet::new()),
+            ..Self::new()
+        };
+        let typed = checker.check_incremental(stmts)?;
+
+        Ok((typed, checker.finish()))
+    }
+
+    // checks `stmts` on top of everything this checker has already seen, so
+    // declarations carry over between calls.
+    pub fn check_incremental(&mut self, stmts: Vec<Stmt>) -> Result<Vec<TypedStmt>, TypeError> {
+        // the types come first, any signature could use them.
+        for stmt in &stmts {
+            if let Stmt::Type {
+                name,
+                attributes,
+                token,
+                ..
+            } = stmt
+            {
+                attributes::check_type(name, token, attributes)?;
+
+                if self.types.contains(name) || Type::from_name(name, &self.types).is_some() {
+                    return TypeError::new(
+                        format!("Type `{}` is already declared", name),
+                        token.clone(),
+                    );
+                }
+
+                self.types.insert(name.clone());
+            }
+        }
+
+        // functions may be called before they're declared, so their
+        // signatures are collected before anything else is checked.
+        for stmt in &stmts {
+            if let Stmt::Function {
+                name,
+                return_type,
+                args,
+                external,
+                attributes,
+                is_varadic,
+                token,
+                ..
+            } = stmt
+            {
+                let ty = self.resolve_or_void(return_type.as_deref())?;
+                let params = args
+                    .iter()
+                    .map(|param| {
+                        let ty = param.ty.as_deref().map(|ty| self.resolve(ty)).transpose();
+                        Ok((param.clone(), ty.map_err(|err| err.or_at(param.span))?))
+                    })
+                    .collect::<Result<_, TypeError>>()?;
+                let attributes = attributes::check(name, external.is_some(), attributes)?;
+                let is_test = attributes.contains(&FunctionAttribute::Test);
+                let is_export = attributes.contains(&FunctionAttribute::Export);
+
+                self.functions.insert(
+                    name.clone(),
+                    Signature {
+                        ty,
+                        params,
+                        is_varadic: *is_varadic,
+                        attributes,
+                    },
+                );
+
+                // `uma test` calls the tests, nothing else is meant to.
+                if is_test && (!args.is_empty() || self.functions[name].ty != Type::Void) {
+                    return TypeError::new(
+                        format!("Test `{}` can't take arguments or return a value", name),
+                        token.clone(),
+                    );
+                }
+
+                // what's exported is called from outside the program.
+                if external.is_none()
+                    && !is_test
+                    && !is_export
+                    && !self.defined.iter().any(|(defined, _)| defined == name)
+                {
+                    self.defined.push((name.clone(), token.span));
+                }
+            }
+        }
+
+        let mut typed = Vec::new();
+        // function bodies can use globals declared after them, so they're
+        // checked once the rest of the top level has been, and put back in
+        // their place after.
+        let mut bodies = Vec::new();
+
+        for stmt in stmts {
+            match stmt {
+                Stmt::Empty => (),
+                Stmt::Function { external: None, .. } => {
+                    bodies.push((typed.len() + bodies.len(), stmt));
+                }
+                stmt => typed.push(self.stmt(stmt)?),
+            }
+        }
+
+        for (i, stmt) in bodies {
+            let stmt = self.stmt(stmt)?;
+            typed.insert(i, stmt);
+        }
+
+        Ok(typed)
+    }
+
+    // warnings raised since the last call.
+    pub fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // warnings for the whole program, including the ones that can only be
+    // raised once everything has been checked.
+    pub fn finish(&mut self) -> Vec<Diagnostic> {
+        self.report_unused(0);
+
+        for (name, span) in self.defined.clone() {
+            if name != "main" && !self.called.contains(&name) && !name.starts_with('_') {
+                self.warn(
+                    "UnusedFunction",
+                    span,
+                    format!("Function `{}` is never called", name),
+                );
+            }
+        }
+
+        self.take_warnings()
+    }
+
+    fn warn(&mut self, code: &str, span: Span, message: String) {
+        self.warnings.push(Diagnostic::warning(code, span, message));
+    }
+
+    fn report_unused(&mut self, scope: usize) {
+        let unused = self.scopes[scope]
+            .iter()
+            .filter(|binding| !binding.used && !binding.name.starts_with('_'))
+            .map(|binding| {
+                let message = format!("Variable `{}` is never used", binding.name);
+                (binding.span, message)
+            })
+            .collect::<Vec<_>>();
+
+        for (span, message) in unused {
+            self.warn("UnusedVariable", span, message);
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Result<Type, TypeError> {
+        match Type::from_name(name, &self.types) {
+            Some(Type::Pointer(ty)) if !ty.is_pointable() => {
+                TypeError::unlocated(format!("Unknown type `{}`, {}", name, POINTEES))
+            }
+            // C only knows the size of what it points to.
+            Some(Type::Opaque(_)) => TypeError::unlocated(format!(
+                "`{0}` can only be used behind a pointer, like `Ptr<{0}>`",
+                name
+            )),
+            Some(ty) => Ok(ty),
+            None => TypeError::unlocated(format!("Unknown type `{}`", name)),
+        }
+    }
+
+    fn resolve_or_void(&self, name: Option<&str>) -> Result<Type, TypeError> {
+        name.map_or(Ok(Type::Void), |name| self.resolve(name))
+    }
+
+    // the innermost binding called `name`, which is used at `span`.
+    fn binding(&mut self, name: &str, span: Span) -> Result<&mut Binding, TypeError> {
+        let position = self.scopes.iter().enumerate().rev().find_map(|(i, scope)| {
+            scope
+                .iter()
+                .rposition(|binding| binding.name == name)
+                .map(|j| (i, j))
+        });
+
+        match position {
+            Some((i, j)) => Ok(&mut self.scopes[i][j]),
+            None => {
+                let candidates = self.scopes.iter().flatten().map(|b| b.name.as_str());
+
+                TypeError::suggested(
+                    format!("Use of undeclared variable `{}`", name),
+                    Token::new(TokenKind::None, None, span),
+                    suggest(name, candidates),
+                )
+            }
+        }
+    }
+
+    // looks a variable up for reading it, which counts as a use.
+    fn lookup(&mut self, name: &str, span: Span) -> Result<Type, TypeError> {
+        let binding = self.binding(name, span)?;
+        binding.used = true;
+
+        Ok(binding.ty.clone())
+    }
+
+    fn declare(&mut self, name: String, ty: Type, is_mut: bool, span: Span) {
+        if self.binding(&name, span).is_ok() {
+            self.warn(
+                "ShadowedBinding",
+                span,
+                format!("`{}` shadows an earlier binding", name),
+            );
+        }
+
+        self.scopes
+            .last_mut()
+            .expect("there is always a global scope")
+            .push(Binding {
+                name,
+                ty,
+                used: false,
+                is_mut,
+                span,
+            });
+    }
+
+    // the value `name` starts out with, checked against the type it's
+    // annotated with.
+    fn initializer(
+        &mut self,
+        name: &str,
+        value: Spanned<Expr>,
+        annotation: Option<&str>,
+    ) -> Result<TypedExpr, TypeError> {
+        let span = value.span;
+        let mut value = self.expr(value)?;
+
+        if let Some(annotation) = annotation {
+            let expected = self.resolve(annotation)?;
+            value = coerce(value, &expected).map_err(|err| err.or_at(span))?;
+
+            let ty = value.ty();
+
+            if ty != expected {
+                return TypeError::at(
+                    format!(
+                        "`{}` is declared as `{}` but is assigned a `{}`",
+                        name, expected, ty
+                    ),
+                    span,
+                );
+            }
+        }
+
+        if value.ty() == Type::Void {
+            return TypeError::at(format!("Cannot assign a `Void` value to `{}`", name), span);
+        }
+
+        Ok(value)
+    }
+
+    // constants are `#define`s in C, which would take over anything else
+    // spelled the same.
+    fn unshadowed(&self, name: &str) -> Result<(), TypeError> {
+        if self.consts.contains_key(name) {
+            return TypeError::unlocated(format!(
+                "`{}` is a constant and can't be declared again",
+                name
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn block(
+        &mut self,
+        block: Block,
+        bindings: Vec<(String, Type, Span)>,
+    ) -> Result<Vec<TypedStmt>, TypeError> {
+        self.scopes.push(
+            bindings
+                .into_iter()
+                .map(|(name, ty, span)| Binding {
+                    name,
+                    ty,
+                    used: false,
+                    is_mut: false,
+                    span,
+                })
+                .collect(),
+        );
+
+        let mut stmts = Vec::new();
+        let mut returned = false;
+
+        for stmt in block.stmts {
+            if stmt == Stmt::Empty {
+                continue;
+            }
+
+            if returned {
+                self.warn(
+                    "UnreachableCode",
+                    location(&stmt),
+                    "Unreachable code after `return`".into(),
+                );
+                returned = false;
+            }
+
+            if let Stmt::Return(..) = stmt {
+                returned = true;
+            }
+
+            match self.stmt(stmt) {
+                Ok(stmt) => stmts.push(stmt),
+                Err(err) => {
+                    self.scopes.pop();
+                    return Err(err);
+                }
+            }
+        }
+
+        self.report_unused(self.scopes.len() - 1);
+        self.scopes.pop();
+
+        Ok(stmts)
+    }
+
+    fn stmt(&mut self, stmt: Stmt) -> Result<TypedStmt, TypeError> {
+        Ok(match stmt {
+            Stmt::Variable {
+                name,
+                value,
+                is_mut,
+                ty: annotation,
+                token,
+            } => {
+                let span = value.span;
+                let value = self.initializer(&name, *value, annotation.as_deref())?;
+                let ty = value.ty();
+
+                self.unshadowed(&name)
+                    .map_err(|err| err.or_at(token.span))?;
+
+                if let Some(globals) = self.globals.as_mut().filter(|_| self.scopes.len() == 1) {
+                    if !globals.insert(name.clone()) {
+                        return TypeError::new(
+                            format!("Global `{}` is declared more than once", name),
+                            token,
+                        );
+                    }
+
+                    if !constant(&value, &self.consts) {
+                        return TypeError::new(
+                            format!("Global `{}` must be initialised with a constant", name),
+                            token,
+                        );
+                    }
+                }
+                self.declare(name.clone(), ty.clone(), is_mut, token.span);
+
+                TypedStmt::Variable {
+                    name,
+                    value,
+                    is_mut,
+                    ty,
+                    span,
+                }
+            }
+            Stmt::Const {
+                name,
+                value,
+                ty: annotation,
+            } => {
+                let span = value.span;
+
+                if self.scopes.len() > 1 {
+                    return TypeError::at(
+                        format!("Constant `{}` must be declared at the top level", name),
+                        span,
+                    );
+                }
+
+                if self.binding(&name, span).is_ok() {
+                    return TypeError::at(format!("`{}` is already declared", name), span);
+                }
+
+                let value = self.initializer(&name, *value, annotation.as_deref())?;
+                let ty = value.ty();
+
+                let Some(value) = fold(&value, &self.consts) else {
+                    return TypeError::at(
+                        format!(
+                            "The value of `{}` can't be worked out at compile time",
+                            name
+                        ),
+                        span,
+                    );
+                };
+
+                self.consts.insert(name.clone(), value.clone());
+                self.declare(name.clone(), ty.clone(), false, span);
+                // constants aren't reported when they're unused.
+                self.binding(&name, span)?.used = true;
+
+                TypedStmt::Const { name, value, ty }
+            }
+            Stmt::Type { name, token, .. } if self.scopes.len() > 1 => {
+                return TypeError::new(
+                    format!("Type `{}` must be declared at the top level", name),
+                    token,
+                );
+            }
+            Stmt::Type { name, external, .. } => TypedStmt::Opaque {
+                name,
+                header: external,
+            },
+            // a function only sees the globals and its own arguments, and
+            // C has nowhere to put one declared inside another.
+            Stmt::Function { name, token, .. } if self.scopes.len() > 1 => {
+                return TypeError::new(
+                    format!("Function `{}` must be declared at the top level", name),
+                    token,
+                );
+            }
+            Stmt::Function {
+                name,
+                args,
+                external: Some(header),
+                links,
+                is_varadic,
+                ..
+            } => TypedStmt::External {
+                name,
+                header,
+                links,
+                arity: args.len(),
+                is_varadic,
+            },
+            // the extra arguments would have nowhere to go in the body.
+            Stmt::Function {
+                name,
+                is_varadic: true,
+                token,
+                ..
+            } => {
+                return TypeError::new(
+                    format!(
+                        "Function `{}` can't take `...`, only a function with `@requires` can",
+                        name
+                    ),
+                    token,
+                );
+            }
+            Stmt::Function {
+                name,
+                return_type,
+                args,
+                body,
+                token,
+                ..
+            } => {
+                self.defaults(&name).map_err(|err| err.or_at(token.span))?;
+
+                let spans = args.iter().map(|param| param.span).collect::<Vec<_>>();
+                let args = args
+                    .into_iter()
+                    .map(|param| match param.ty {
+                        Some(ty) => Ok((
+                            param.name,
+                            self.resolve(&ty).map_err(|err| err.or_at(param.span))?,
+                        )),
+                        None => TypeError::at(
+                            format!("Argument `{}` of `{}` is missing a type", param.name, name),
+                            param.span,
+                        ),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.unshadowed(&name)
+                    .map_err(|err| err.or_at(token.span))?;
+
+                for ((arg, _), span) in args.iter().zip(&spans) {
+                    self.unshadowed(arg).map_err(|err| err.or_at(*span))?;
+                }
+
+                let return_type = self
+                    .resolve_or_void(return_type.as_deref())
+                    .map_err(|err| err.or_at(token.span))?;
+                let outer = self.function.replace((name.clone(), return_type.clone()));
+                let params = args
+                    .iter()
+                    .zip(spans)
+                    .map(|((arg, ty), span)| (arg.clone(), ty.clone(), span))
+                    .collect();
+                let body = self.block(body, params);
+                self.function = outer;
+                let mut body = body?;
+
+                if return_type != Type::Void && !returns(&body) {
+                    // falling off the end of `main` exits successfully, like in C.
+                    if name != "main" || return_type != Type::Int {
+                        return TypeError::new(
+                            format!("Not all paths in `{}` return a value", name),
+                            token,
+                        );
+                    }
+
+                    body.push(TypedStmt::Return(
+                        Some(TypedExpr::Number("0".to_string())),
+                        Span::default(),
+                    ));
+                }
+
+                TypedStmt::Function {
+                    attributes: self.functions[&name].attributes.clone(),
+                    return_type,
+                    body,
+                    name,
+                    args,
+                    span: token.span,
+                }
+            }
+            Stmt::If {
+                condition,
+                consequence,
+                alternative,
+            } => TypedStmt::If {
+                span: condition.span,
+                condition: self.expr(*condition)?,
+                consequence: self.block(consequence, vec![])?,
+                alternative: match alternative {
+                    Some(alt) => Some(Box::new(self.stmt(*alt)?)),
+                    None => None,
+                },
+            },
+            Stmt::For {
+                iterator,
+                start,
+                end,
+                body,
+                token,
+            } => {
+                let span = start.span.to(end.span);
+                let start = self.expr(*start)?;
+                let end = self.expr(*end)?;
+
+                self.unshadowed(&iterator)
+                    .map_err(|err| err.or_at(token.span))?;
+
+                if start.ty() != Type::Int || end.ty() != Type::Int {
+                    return TypeError::at(
+                        format!(
+                            "Range of `{}` must be `Int`, found `{}..{}`",
+                            iterator,
+                            start.ty(),
+                            end.ty()
+                        ),
+                        span,
+                    );
+                }
+
+                TypedStmt::For {
+                    body: self.block(body, vec![(iterator.clone(), Type::Int, token.span)])?,
+                    iterator,
+                    start,
+                    end,
+                    span,
+                }
+            }
+            Stmt::ForEach {
+                iterator,
+                iterable,
+                body,
+                token,
+            } => {
+                let span = iterable.span;
+                let iterable = self.expr(*iterable)?;
+
+                self.unshadowed(&iterator)
+                    .map_err(|err| err.or_at(token.span))?;
+
+                let element = match iterable.ty() {
+                    Type::Array(element, _) => *element,
+                    other => {
+                        return TypeError::at(format!("Cannot iterate over `{}`", other), span)
+                    }
+                };
+
+                TypedStmt::ForEach {
+                    body: self.block(body, vec![(iterator.clone(), element, token.span)])?,
+                    iterator,
+                    iterable,
+                    span,
+                }
+            }
+            Stmt::Block(block, token) => TypedStmt::Block(self.block(block, vec![])?, token.span),
+            Stmt::Assignment { name, value, op } => {
+                // writing to a variable doesn't count as using it, not even
+                // when a compound assignment reads it first.
+                let span = value.span;
+                let binding = self.binding(&name, span)?;
+                let (expected, used) = (binding.ty.clone(), binding.used);
+
+                if !binding.is_mut {
+                    return TypeError::at(
+                        format!(
+                            "Cannot assign to `{}`, it isn't declared with `let mut`",
+                            name
+                        ),
+                        span,
+                    );
+                }
+
+                let value = match &op {
+                    Some(op) => {
+                        let lhs = Spanned::new(Expr::Identifier(name.clone()), op.span);
+                        let value = self.binary(lhs, op.clone(), *value)?;
+
+                        self.binding(&name, span)?.used = used;
+                        value
+                    }
+                    None => coerce(self.expr(*value)?, &expected).map_err(|err| err.or_at(span))?,
+                };
+
+                if value.ty() != expected {
+                    return TypeError::at(
+                        format!(
+                            "Cannot assign `{}` to `{}` of type `{}`",
+                            value.ty(),
+                            name,
+                            expected
+                        ),
+                        span,
+                    );
+                }
+
+                TypedStmt::Assignment {
+                    name,
+                    value,
+                    op,
+                    span,
+                }
+            }
+            Stmt::Return(value, token) => {
+                let span = value.as_ref().map_or(token.span, |value| value.span);
+                let value = value.map(|value| self.expr(*value)).transpose()?;
+
+                // a function's variables are gone once it returns.
+                if let Some(TypedExpr::AddressOf { name, .. }) = &value {
+                    if self.scopes[1..].iter().flatten().any(|b| b.name == *name) {
+                        return TypeError::at(
+                            format!(
+                                "Cannot return the address of `{}`, it's gone once the function returns",
+                                name
+                            ),
+                            span,
+                        );
+                    }
+                }
+
+                let value = match (self.function.clone(), value) {
+                    (Some((name, Type::Void)), Some(_)) => {
+                        return TypeError::at(
+                            format!(
+                                "Cannot return a value from `{}`, it doesn't declare a return type",
+                                name
+                            ),
+                            span,
+                        )
+                    }
+                    (Some((name, expected)), None) if expected != Type::Void => {
+                        return TypeError::at(
+                            format!("`{}` must return a `{}`", name, expected),
+                            span,
+                        )
+                    }
+                    (Some((name, expected)), Some(value)) => {
+                        let value = coerce(value, &expected).map_err(|err| err.or_at(span))?;
+
+                        if value.ty() != expected {
+                            return TypeError::at(
+                                format!(
+                                    "Cannot return `{}` from `{}`, which returns `{}`",
+                                    value.ty(),
+                                    name,
+                                    expected
+                                ),
+                                span,
+                            );
+                        }
+
+                        Some(value)
+                    }
+                    (_, value) => value,
+                };
+
+                TypedStmt::Return(value, span)
+            }
+            Stmt::Expr(expr) => {
+                let span = expr.span;
+                TypedStmt::Expr(self.expr(expr)?, span)
+            }
+            // the files a program imports are merged in before it's checked.
+            Stmt::Import { token, .. } => {
+                return TypeError::new(
+                    "`import` can only be used at the top level of a file",
+                    token,
+                )
+            }
+            Stmt::Empty => {
+                unreachable!("{:?} should never reach the type checker", stmt)
+            }
+        })
+    }
+
+    fn expr(&mut self, expr: Spanned<Expr>) -> Result<TypedExpr, TypeError> {
+        let span = expr.span;
+
+        let typed = match expr.node {
+            Expr::Binary { lhs, op, rhs } => self.binary(*lhs, op, *rhs),
+            Expr::Unary { op, operand } => self.unary(op, *operand),
+            Expr::Cast { value, ty } => self.cast(*value, ty),
+            Expr::If {
+                condition,
+                consequence,
+                alternative,
+            } => self.if_expr(*condition, *consequence, *alternative),
+            Expr::Match { subject, arms } => self.match_expr(*subject, arms, span),
+            Expr::Array(elements) => self.array(elements),
+            Expr::Index { target, index } => self.index(*target, *index),
+            Expr::Identifier(name) => Ok(TypedExpr::Identifier {
+                ty: self.lookup(&name, span)?,
+                name,
+            }),
+            Expr::Number(num) => number(TypedExpr::Number, num),
+            Expr::Float(num) => number(TypedExpr::Float, num),
+            Expr::Bool(value) => Ok(TypedExpr::Bool(value)),
+            Expr::String(value) => Ok(TypedExpr::String(value)),
+            Expr::Char(value) => Ok(TypedExpr::Char(value)),
+            Expr::Interpolation(parts) => self.interpolation(parts),
+            Expr::Call { name, args, token } => self.call(name, args, token),
+        };
+
+        // the innermost expression is the best guess for where an error
+        // without a location happened.
+        typed.map_err(|err| err.or_at(span))
+    }
+
+    fn call(&mut self, name: String, args: Vec<Arg>, token: Token) -> Result<TypedExpr, TypeError> {
+        if let (false, Some(builtin)) = (
+            self.functions.contains_key(&name),
+            Builtin::from_name(&name),
+        ) {
+            if let Some(name) = args.iter().find_map(|arg| arg.name.clone()) {
+                return TypeError::new(
+                    format!("`{}` doesn't take arguments by name", builtin.name()),
+                    name,
+                );
+            }
+
+            let args = args.into_iter().map(|arg| arg.value).collect();
+            return self.builtin(builtin, args, token);
+        }
+
+        let signature = match self.functions.get(&name) {
+            Some(signature) => signature.clone(),
+            None => {
+                let candidates = self
+                    .functions
+                    .keys()
+                    .map(String::as_str)
+                    .chain(Builtin::ALL.iter().map(|builtin| builtin.name()));
+
+                return TypeError::suggested(
+                    format!("Call to undefined function '{}'", name),
+                    token,
+                    suggest(&name, candidates),
+                );
+            }
+        };
+
+        let args = self.arguments(&name, &signature, args, &token)?;
+
+        self.called.insert(name.clone());
+
+        for attribute in &signature.attributes {
+            if let FunctionAttribute::Deprecated(reason) = attribute {
+                let message = match reason {
+                    Some(reason) => format!("`{}` is deprecated: {}", name, reason),
+                    None => format!("`{}` is deprecated", name),
+                };
+
+                self.warnings
+                    .push(Diagnostic::warning("Deprecated", token.span, message));
+            }
+        }
+
+        Ok(TypedExpr::Call {
+            name,
+            args,
+            ty: signature.ty,
+        })
+    }
+
+    // the arguments of a call to `function` in the order of its parameters,
+    // with the ones passed by name put in their place and the defaults of
+    // the ones left out filled in. what doesn't fit a parameter is passed on
+    // through the `...` of a variadic function.
+    fn arguments(
+        &mut self,
+        function: &str,
+        signature: &Signature,
+        args: Vec<Arg>,
+        token: &Token,
+    ) -> Result<Vec<TypedExpr>, TypeError> {
+        let params = &signature.params;
+        let given = args.len();
+
+        let mut slots: Vec<Option<TypedExpr>> = vec![None; params.len()];
+        let mut rest = vec![];
+        let mut named = false;
+
+        for (i, arg) in args.into_iter().enumerate() {
+            let span = arg.value.span;
+
+            let Some(name) = arg.name else {
+                if named {
+                    return TypeError::at(
+                        "Positional arguments have to come before named ones",
+                        span,
+                    );
+                }
+
+                match params.get(i) {
+                    Some(param) => slots[i] = Some(self.argument(function, param, arg.value)?),
+                    None if signature.is_varadic => rest.push(self.expr(arg.value)?),
+                    None => {
+                        return TypeError::at(
+                            format!(
+                                "`{}` takes {} arguments but {} were given",
+                                function,
+                                params.len(),
+                                given
+                            ),
+                            span,
+                        )
+                    }
+                }
+
+                continue;
+            };
+
+            named = true;
+            let param_name = name.text();
+
+            let Some(i) = params
+                .iter()
+                .position(|(param, _)| param.name == param_name)
+            else {
+                let candidates = params.iter().map(|(param, _)| param.name.as_str());
+
+                return TypeError::suggested(
+                    format!("`{}` has no parameter `{}`", function, param_name),
+                    name,
+                    suggest(&param_name, candidates),
+                );
+            };
+
+            if slots[i].is_some() {
+                return TypeError::new(
+                    format!("Argument `{}` is given more than once", param_name),
+                    name,
+                );
+            }
+
+            slots[i] = Some(self.argument(function, &params[i], arg.value)?);
+        }
+
+        let mut args = vec![];
+
+        for (slot, (param, ty)) in slots.into_iter().zip(params) {
+            let arg = match (slot, &param.default) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.default(param, default, ty.as_ref())?,
+                (None, None) => {
+                    return TypeError::new(
+                        format!(
+                            "Missing argument `{}` in call to `{}`",
+                            param.name, function
+                        ),
+                        token.clone(),
+                    )
+                }
+            };
+
+            args.push(arg);
+        }
+
+        args.extend(rest);
+        Ok(args)
+    }
+
+    // `value` passed as `param` of `function`, coerced to its type.
+    fn argument(
+        &mut self,
+        function: &str,
+        (param, ty): &(Param, Option<Type>),
+        value: Spanned<Expr>,
+    ) -> Result<TypedExpr, TypeError> {
+        let span = value.span;
+        let value = self.expr(value)?;
+
+        let Some(ty) = ty else {
+            return Ok(value);
+        };
+
+        let mut value = coerce(value, ty).map_err(|err| err.or_at(span))?;
+
+        if value.ty() == Type::Int && *ty == Type::Float {
+            value = widen(value);
+        }
+
+        if value.ty() != *ty {
+            return TypeError::at(
+                format!(
+                    "Argument `{}` of `{}` must be `{}`, found `{}`",
+                    param.name,
+                    function,
+                    ty,
+                    value.ty()
+                ),
+                span,
+            );
+        }
+
+        Ok(value)
+    }
+
+    // the value of `param` when a call leaves it out. it's copied into every
+    // call, so it has to be a constant.
+    fn default(
+        &mut self,
+        param: &Param,
+        default: &Spanned<Expr>,
+        ty: Option<&Type>,
+    ) -> Result<TypedExpr, TypeError> {
+        let span = default.span;
+        let value = self.expr(default.clone())?;
+
+        let Some(mut value) = fold(&value, &self.consts) else {
+            return TypeError::at(
+                format!(
+                    "The default of `{}` can't be worked out at compile time",
+                    param.name
+                ),
+                span,
+            );
+        };
+
+        if let Some(ty) = ty {
+            value = coerce(value, ty).map_err(|err| err.or_at(span))?;
+
+            if value.ty() == Type::Int && *ty == Type::Float {
+                value = widen(value);
+            }
+
+            if value.ty() != *ty {
+                return TypeError::at(
+                    format!(
+                        "The default of `{}` must be `{}`, found `{}`",
+                        param.name,
+                        ty,
+                        value.ty()
+                    ),
+                    span,
+                );
+            }
+        }
+
+        Ok(value)
+    }
+
+    // the defaults of a function's parameters are checked where they're
+    // written, and come after the parameters without one so every call can
+    // be filled in from the left.
+    fn defaults(&mut self, function: &str) -> Result<(), TypeError> {
+        let signature = self.functions[function].clone();
+        let mut defaulted = None;
+
+        for (param, ty) in &signature.params {
+            match (&param.default, defaulted) {
+                (Some(default), _) => {
+                    self.default(param, default, ty.as_ref())?;
+                    defaulted = Some(&param.name);
+                }
+                (None, Some(before)) => {
+                    return TypeError::unlocated(format!(
+                        "Parameter `{}` of `{}` needs a default, it comes after `{}` which has one",
+                        param.name, function, before
+                    ))
+                }
+                (None, None) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn builtin(
+        &mut self,
+        builtin: Builtin,
+        args: Vec<Spanned<Expr>>,
+        token: Token,
+    ) -> Result<TypedExpr, TypeError> {
+        // what the condition of an `assert` looked like, for when it fails.
+        let source = match (builtin, args.first()) {
+            (Builtin::Assert, Some(condition)) => Some(formatter::format_expr(condition)),
+            _ => None,
+        };
+
+        let mut args = args
+            .into_iter()
+            .map(|arg| self.expr(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // `min` and `max` mix their numbers the way arithmetic does.
+        if let (Builtin::Min | Builtin::Max, [lhs, rhs]) = (builtin, &args[..]) {
+            let lhs = coerce(lhs.clone(), &rhs.ty()).map_err(|err| err.or_at(token.span))?;
+            let rhs = coerce(rhs.clone(), &lhs.ty()).map_err(|err| err.or_at(token.span))?;
+
+            args = match (lhs.ty(), rhs.ty()) {
+                (Type::Int, Type::Float) => vec![widen(lhs), rhs],
+                (Type::Float, Type::Int) => vec![lhs, widen(rhs)],
+                _ => vec![lhs, rhs],
+            };
+        }
+
+        let ty = match builtin.check(&args) {
+            Ok(ty) => ty,
+            Err(message) => return TypeError::new(message, token),
+        };
+
+        if builtin.takes_floats() {
+            args = args
+                .into_iter()
+                .map(|arg| match arg.ty() {
+                    Type::Float => arg,
+                    _ => TypedExpr::Cast {
+                        value: arg.into(),
+                        ty: Type::Float,
+                    },
+                })
+                .collect();
+        }
+
+        if let Some(source) = source {
+            args.insert(1, TypedExpr::String(source));
+        }
+
+        Ok(TypedExpr::Builtin { builtin, args, ty })
+    }
+
+    fn interpolation(&mut self, parts: Vec<Part>) -> Result<TypedExpr, TypeError> {
+        let mut typed = Vec::new();
+
+        for part in parts {
+            match part {
+                Part::Text(text) => typed.push(TypedPart::Text(text)),
+                Part::Expr(expr) => {
+                    let expr = self.expr(*expr)?;
+
+                    if !expr.ty().is_printable() {
+                        return TypeError::unlocated(format!(
+                            "Cannot interpolate a `{}` into a string",
+                            expr.ty()
+                        ));
+                    }
+
+                    typed.push(TypedPart::Expr(expr));
+                }
+            }
+        }
+
+        Ok(TypedExpr::Interpolation(typed))
+    }
+
+    fn array(&mut self, elements: Vec<Spanned<Expr>>) -> Result<TypedExpr, TypeError> {
+        let elements = elements
+            .into_iter()
+            .map(|element| self.expr(element))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let element_ty = match elements.first() {
+            Some(first) => first.ty(),
+            None => return TypeError::unlocated("Cannot infer the type of an empty array"),
+        };
+
+        if let Some(other) = elements.iter().find(|e| e.ty() != element_ty) {
+            return TypeError::unlocated(format!(
+                "Array elements must all be `{}`, found `{}`",
+                element_ty,
+                other.ty()
+            ));
+        }
+
+        if element_ty == Type::Void {
+            return TypeError::unlocated("Array elements cannot be `Void`");
+        }
+
+        Ok(TypedExpr::Array {
+            ty: Type::Array(element_ty.into(), elements.len()),
+            elements,
+        })
+    }
+
+    fn index(
+        &mut self,
+        target: Spanned<Expr>,
+        index: Spanned<Expr>,
+    ) -> Result<TypedExpr, TypeError> {
+        let target = self.expr(target)?;
+        let index = self.expr(index)?;
+
+        // a string is indexed by byte, each one a string of its own.
+        let (ty, kind) = match target.ty() {
+            Type::Array(element, _) => (*element, "Array"),
+            Type::String => (Type::String, "String"),
+            other => return TypeError::unlocated(format!("Cannot index into `{}`", other)),
+        };
+
+        if index.ty() != Type::Int {
+            return TypeError::unlocated(format!(
+                "{} index must be `Int`, found `{}`",
+                kind,
+                index.ty()
+            ));
+        }
+
+        Ok(TypedExpr::Index {
+            target: target.into(),
+            index: index.into(),
+            ty,
+        })
+    }
+
+    fn binary(
+        &mut self,
+        lhs: Spanned<Expr>,
+        op: Token,
+        rhs: Spanned<Expr>,
+    ) -> Result<TypedExpr, TypeError> {
+        let mut lhs = self.expr(lhs)?;
+        let mut rhs = self.expr(rhs)?;
+
+        let is_comparison = matches!(
+            op.kind,
+            TokenKind::BinaryEq
+                | TokenKind::BinaryNeq
+                | TokenKind::BinaryGt
+                | TokenKind::BinaryGte
+                | TokenKind::BinaryLt
+                | TokenKind::BinaryLte
+        );
+
+        let is_equality = matches!(op.kind, TokenKind::BinaryEq | TokenKind::BinaryNeq);
+        let is_logical = matches!(op.kind, TokenKind::And | TokenKind::Or);
+        let is_bitwise = matches!(
+            op.kind,
+            TokenKind::BitAnd
+                | TokenKind::BitOr
+                | TokenKind::BitXor
+                | TokenKind::Shl
+                | TokenKind::Shr
+        );
+
+        // arithmetic and comparisons mixing an `Int` with a `Float` are done
+        // on `Float`s, like in C. `%` and the bitwise operators still need
+        // both sides to be `Int`s.
+        let is_arithmetic = matches!(
+            op.kind,
+            TokenKind::Add | TokenKind::Sub | TokenKind::Multi | TokenKind::Div | TokenKind::Expo
+        );
+
+        if is_arithmetic || is_comparison || is_bitwise || op.kind == TokenKind::Mod {
+            lhs = coerce(lhs, &rhs.ty())?;
+            rhs = coerce(rhs, &lhs.ty())?;
+        }
+
+        if is_arithmetic || is_comparison {
+            match (lhs.ty(), rhs.ty()) {
+                (Type::Int, Type::Float) => lhs = widen(lhs),
+                (Type::Float, Type::Int) => rhs = widen(rhs),
+                _ => (),
+            }
+        }
+
+        let (l, r) = (lhs.ty(), rhs.ty());
+
+        if l != r {
+            return TypeError::new(format!("Mismatched types `{}` and `{}`", l, r), op);
+        }
+
+        let ty = match (&l, &op.kind) {
+            (Type::Float | Type::Float32, TokenKind::Mod) => {
+                return TypeError::new(
+                    "Operator `%` only works on `Int`s, `Float`s need `fmod` from `math.h`",
+                    op,
+                )
+            }
+            (l, _) if l.is_numeric() && is_comparison => Type::Bool,
+            (l, _) if l.is_integer() && is_bitwise => l.clone(),
+            (l, _) if l.is_numeric() && !is_logical && !is_bitwise => l.clone(),
+            (Type::String | Type::Bool, _) if is_equality => Type::Bool,
+            (Type::Char, _) if is_comparison => Type::Bool,
+            (Type::String, TokenKind::Add) => Type::String,
+            (Type::Bool, _) if is_logical => Type::Bool,
+            _ => {
+                return TypeError::new(
+                    format!("Operator `{}` cannot be applied to `{}`", op.repr(), l),
+                    op,
+                )
+            }
+        };
+
+        Ok(TypedExpr::Binary {
+            lhs: lhs.into(),
+            op,
+            rhs: rhs.into(),
+            ty,
+        })
+    }
+
+    fn unary(&mut self, op: Token, operand: Spanned<Expr>) -> Result<TypedExpr, TypeError> {
+        match op.kind {
+            TokenKind::BitAnd => return self.address_of(op, operand),
+            TokenKind::Multi => return self.deref(op, operand),
+            _ => (),
+        }
+
+        let operand = self.expr(operand)?;
+
+        // `~` takes an integer and `!` a `Bool`.
+        let allowed = match op.kind {
+            TokenKind::Not => operand.ty() == Type::Bool,
+            _ => operand.ty().is_integer(),
+        };
+
+        if !allowed {
+            return TypeError::new(
+                format!(
+                    "Operator `{}` cannot be applied to `{}`",
+                    op.repr(),
+                    operand.ty()
+                ),
+                op,
+            );
+        }
+
+        Ok(TypedExpr::Unary {
+            ty: operand.ty(),
+            op,
+            operand: operand.into(),
+        })
+    }
+
+    // `&name`, a pointer to a variable. it has to be one declared `mut`, since
+    // what it's passed to can change it.
+    fn address_of(&mut self, op: Token, operand: Spanned<Expr>) -> Result<TypedExpr, TypeError> {
+        let Expr::Identifier(name) = operand.node else {
+            return TypeError::new("Only the address of a variable can be taken", op);
+        };
+
+        let binding = self.binding(&name, operand.span)?;
+        binding.used = true;
+
+        let (ty, is_mut) = (binding.ty.clone(), binding.is_mut);
+
+        if !is_mut {
+            return TypeError::new(
+                format!(
+                    "Cannot take the address of `{}`, it isn't declared with `let mut`",
+                    name
+                ),
+                op,
+            );
+        }
+
+        if !ty.is_pointable() {
+            return TypeError::new(
+                format!("Cannot take the address of `{}`, {}", name, POINTEES),
+                op,
+            );
+        }
+
+        Ok(TypedExpr::AddressOf {
+            ty: Type::Pointer(ty.into()),
+            name,
+        })
+    }
+
+    // `*pointer`, the value it points to. a `Ptr<Void>` could be to anything
+    // and C keeps what's in an `@opaque` type to itself, so they can only be
+    // passed along.
+    fn deref(&mut self, op: Token, operand: Spanned<Expr>) -> Result<TypedExpr, TypeError> {
+        let pointer = self.expr(operand)?;
+
+        match pointer.ty() {
+            Type::Pointer(ty) if !matches!(*ty, Type::Void | Type::Opaque(_)) => {
+                Ok(TypedExpr::Deref {
+                    ty: *ty,
+                    pointer: pointer.into(),
+                })
+            }
+            ty => TypeError::new(format!("Cannot dereference a `{}`", ty), op),
+        }
+    }
+
+    // both branches have to give the same type, with the same conversions
+    // as the two sides of `+`.
+    fn if_expr(
+        &mut self,
+        condition: Spanned<Expr>,
+        consequence: Spanned<Expr>,
+        alternative: Spanned<Expr>,
+    ) -> Result<TypedExpr, TypeError> {
+        let span = alternative.span;
+
+        let condition = self.expr(condition)?;
+        let mut consequence = self.expr(consequence)?;
+        let mut alternative = self.expr(alternative)?;
+
+        consequence = coerce(consequence, &alternative.ty()).map_err(|err| err.or_at(span))?;
+        alternative = coerce(alternative, &consequence.ty()).map_err(|err| err.or_at(span))?;
+
+        match (consequence.ty(), alternative.ty()) {
+            (Type::Int, Type::Float) => consequence = widen(consequence),
+            (Type::Float, Type::Int) => alternative = widen(alternative),
+            _ => (),
+        }
+
+        let ty = consequence.ty();
+
+        if ty != alternative.ty() {
+            return TypeError::at(
+                format!(
+                    "`if` and `else` have mismatched types `{}` and `{}`",
+                    ty,
+                    alternative.ty()
+                ),
+                span,
+            );
+        }
+
+        Ok(TypedExpr::If {
+            condition: condition.into(),
+            consequence: consequence.into(),
+            alternative: alternative.into(),
+            ty,
+        })
+    }
+
+    // the patterns have to be of the subject's type, and without a `_` every
+    // value it can have has to be covered, which only `Bool` can do.
+    fn match_expr(
+        &mut self,
+        subject: Spanned<Expr>,
+        arms: Vec<(Pattern, Spanned<Expr>)>,
+        span: Span,
+    ) -> Result<TypedExpr, TypeError> {
+        let subject_span = subject.span;
+        let subject = self.expr(subject)?;
+        let ty = subject.ty();
+
+        if !(ty.is_numeric() || matches!(ty, Type::Bool | Type::Char | Type::String)) {
+            return TypeError::at(format!("Cannot match on a `{}`", ty), subject_span);
+        }
+
+        let mut cases: Vec<(TypedExpr, TypedExpr, Span)> = vec![];
+        let mut default = None;
+
+        for (pattern, value) in arms {
+            let value_span = value.span;
+            let value = self.expr(value)?;
+
+            let literal = match pattern {
+                _ if default.is_some() => {
+                    let span = match pattern {
+                        Pattern::Literal(literal) => literal.span,
+                        Pattern::Wildcard(span) => span,
+                    };
+
+                    self.warnings.push(Diagnostic::warning(
+                        "UnreachablePattern",
+                        span,
+                        "This arm is never reached, the `_` before it matches everything",
+                    ));
+                    continue;
+                }
+                Pattern::Wildcard(_) => {
+                    default = Some((value, value_span));
+                    continue;
+                }
+                Pattern::Literal(literal) => literal,
+            };
+
+            let pattern_span = literal.span;
+            let mut pattern = self.expr(literal)?;
+            pattern = coerce(pattern, &ty).map_err(|err| err.or_at(pattern_span))?;
+
+            if pattern.ty() == Type::Int && ty == Type::Float {
+                pattern = widen(pattern);
+            }
+
+            if pattern.ty() != ty {
+                return TypeError::at(
+                    format!("Cannot match a `{}` against a `{}`", ty, pattern.ty()),
+                    pattern_span,
+                );
+            }
+
+            if cases.iter().any(|(case, ..)| *case == pattern) {
+                self.warnings.push(Diagnostic::warning(
+                    "UnreachablePattern",
+                    pattern_span,
+                    "This arm is never reached, an earlier arm has the same pattern",
+                ));
+                continue;
+            }
+
+            cases.push((pattern, value, value_span));
+        }
+
+        let covered = |b| cases.iter().any(|(case, ..)| *case == TypedExpr::Bool(b));
+
+        let (default, default_span) = match default {
+            Some(default) => default,
+            None if covered(true) && covered(false) => {
+                let (_, value, span) = cases.pop().expect("both `Bool`s are covered");
+                (value, span)
+            }
+            None => {
+                return TypeError::at(
+                    format!("This `match` doesn't cover every `{}`, add a `_` arm", ty),
+                    span,
+                )
+            }
+        };
+
+        // the arms have one type between them. a number that isn't a literal
+        // decides it over the literals, and an `Int` widens to a `Float` when
+        // another arm is one.
+        let mut values: Vec<_> = cases
+            .iter()
+            .map(|(_, value, span)| (value.clone(), *span))
+            .chain([(default.clone(), default_span)])
+            .collect();
+
+        let mut arm_ty = values
+            .iter()
+            .map(|(value, _)| value)
+            .find(|value| {
+                value.ty().is_numeric()
+                    && !matches!(value, TypedExpr::Number(_) | TypedExpr::Float(_))
+            })
+            .unwrap_or(&values[0].0)
+            .ty();
+
+        if arm_ty == Type::Int && values.iter().any(|(value, _)| value.ty() == Type::Float) {
+            arm_ty = Type::Float;
+        }
+
+        for (value, span) in &mut values {
+            let mut coerced = coerce(value.clone(), &arm_ty).map_err(|err| err.or_at(*span))?;
+
+            if coerced.ty() == Type::Int && arm_ty == Type::Float {
+                coerced = widen(coerced);
+            }
+
+            if coerced.ty() != arm_ty {
+                return TypeError::at(
+                    format!(
+                        "`match` arms have mismatched types `{}` and `{}`",
+                        arm_ty,
+                        coerced.ty()
+                    ),
+                    *span,
+                );
+            }
+
+            *value = coerced;
+        }
+
+        let default = values.pop().expect("there's always a default").0;
+
+        Ok(TypedExpr::Match {
+            subject: subject.into(),
+            arms: cases
+                .into_iter()
+                .zip(values)
+                .map(|((pattern, ..), (value, _))| (pattern, value))
+                .collect(),
+            default: default.into(),
+            ty: arm_ty,
+        })
+    }
+
+    // numbers convert to any other number, and a `Bool` to an integer.
+    fn cast(&mut self, value: Spanned<Expr>, token: Token) -> Result<TypedExpr, TypeError> {
+        let value = self.expr(value)?;
+        let name = token.text();
+
+        let Some(ty) = Type::from_name(&name, &self.types) else {
+            return TypeError::new(format!("Unknown type `{}`", name), token);
+        };
+
+        let from = value.ty();
+
+        // a `Char` casts to the number of its code point.
+        let is_number = from.is_numeric() && ty.is_numeric();
+        let is_code = matches!(from, Type::Bool | Type::Char) && ty.is_integer();
+
+        if !(is_number || is_code) {
+            return TypeError::new(format!("Cannot cast `{}` to `{}`", from, ty), token);
+        }
+
+        Ok(TypedExpr::Cast {
+            value: value.into(),
+            ty,
+        })
+    }
+}
+
+// a literal `value` given the type `ty` it's used as, `1` in `x + 1` takes the
+// type of `x`. the branches of an `if` and the arms of a `match` are coerced
+// on their own, and anything else is left as it is.
+fn coerce(value: TypedExpr, ty: &Type) -> Result<TypedExpr, TypeError> {
+    if let TypedExpr::If {
+        condition,
+        consequence,
+        alternative,
+        ty: old,
+    } = value
+    {
+        let consequence = coerce(*consequence, ty)?;
+        let alternative = coerce(*alternative, ty)?;
+
+        // both branches have to change for the `if` to.
+        let ty = if consequence.ty() == alternative.ty() {
+            consequence.ty()
+        } else {
+            old
+        };
+
+        return Ok(TypedExpr::If {
+            condition,
+            consequence: consequence.into(),
+            alternative: alternative.into(),
+            ty,
+        });
+    }
+
+    if let TypedExpr::Match {
+        subject,
+        arms,
+        default,
+        ty: old,
+    } = value
+    {
+        let arms = arms
+            .into_iter()
+            .map(|(pattern, value)| Ok((pattern, coerce(value, ty)?)))
+            .collect::<Result<Vec<_>, TypeError>>()?;
+        let default = coerce(*default, ty)?;
+
+        // like an `if`, every arm has to change.
+        let ty = if arms.iter().all(|(_, value)| value.ty() == default.ty()) {
+            default.ty()
+        } else {
+            old
+        };
+
+        return Ok(TypedExpr::Match {
+            subject,
+            arms,
+            default: default.into(),
+            ty,
+        });
+    }
+
+    match (&value, ty.range()) {
+        (TypedExpr::Number(num), Some((min, max))) if *ty != Type::Int => {
+            if !num.parse::<i128>().is_ok_and(|n| min <= n && n <= max) {
+                return TypeError::unlocated(format!("Integer `{}` doesn't fit in `{}`", num, ty));
+            }
+        }
+        (TypedExpr::Float(_), _) if *ty == Type::Float32 => (),
+        _ => return Ok(value),
+    }
+
+    Ok(TypedExpr::Cast {
+        value: value.into(),
+        ty: ty.clone(),
+    })
+}
+
+// a number as it's written, made into a literal by `literal`. one with a
+// suffix keeps the type it names wherever it's used, so `1u8` isn't coerced
+// like `1` is. the lexer only lets through a suffix there is.
+fn number(literal: fn(String) -> TypedExpr, text: String) -> Result<TypedExpr, TypeError> {
+    let Some(at) = text.find(['i', 'u', 'f']) else {
+        return Ok(literal(text));
+    };
+
+    let (num, suffix) = text.split_at(at);
+
+    let Some(ty) = Type::from_suffix(suffix) else {
+        return TypeError::unlocated(format!("Unknown suffix `{}` on a number", suffix));
+    };
+
+    // `1f64` is written like an integer, and C would take it as one.
+    let mut num = num.to_string();
+
+    if !ty.is_integer() && !num.contains(['.', 'e', 'E']) {
+        num.push_str(".0");
+    }
+
+    let value = literal(num);
+    coerce(value.clone(), &ty)?;
+
+    Ok(TypedExpr::Cast {
+        value: value.into(),
+        ty,
+    })
+}
+
+// `value`, an `Int`, converted to a `Float`.
+fn widen(value: TypedExpr) -> TypedExpr {
+    TypedExpr::Cast {
+        value: value.into(),
+        ty: Type::Float,
+    }
+}
+
+// whether running `stmts` always ends in a `return`. loops might not run at
+// all, so returning inside one doesn't count.
+// whether `program` has a `main` to start from. it takes nothing, the
+// program's arguments come from `arg`, and it gives back an exit status or
+// nothing at all. code that's checked bit by bit, like in the repl or an
+// editor, doesn't need one, so it's checked apart from the rest.
+pub fn check_main(program: &[TypedStmt]) -> Result<(), CompileError> {
+    declarations(program)?;
+
+    let main = program.iter().find_map(|stmt| match stmt {
+        TypedStmt::Function {
+            name,
+            return_type,
+            args,
+            span,
+            ..
+        } if name == "main" => Some((return_type, args, *span)),
+        _ => None,
+    });
+
+    let err = match main {
+        None => Diagnostic::error("MissingMain", Span::default(), "No `main` function found")
+            .with_note("a program starts running at `func main(): int { ... }`"),
+        Some((_, args, span)) if !args.is_empty() => {
+            Diagnostic::error("InvalidMain", span, "`main` can't take arguments")
+                .with_note("the program's arguments come from `arg_count()` and `arg(i)`")
+        }
+        Some((ty, _, span)) if !matches!(ty, Type::Int | Type::Void) => Diagnostic::error(
+            "InvalidMain",
+            span,
+            format!("`main` must return an `Int` or nothing, found `{}`", ty),
+        )
+        .with_note("the `Int` it returns is the program's exit status"),
+        Some(_) => return Ok(()),
+    };
+
+    Err(err.into())
+}
+
+// whether `program` can be built into a library, which whatever links
+// against it is the `main` of.
+pub fn check_library(program: &[TypedStmt]) -> Result<(), CompileError> {
+    declarations(program)?;
+
+    let main = program.iter().find_map(|stmt| match stmt {
+        TypedStmt::Function { name, span, .. } if name == "main" => Some(*span),
+        _ => None,
+    });
+
+    match main {
+        Some(span) => Err(
+            Diagnostic::error("LibraryMain", span, "A library can't have a `main`")
+                .with_note("what it exports is called from C, build it without `--lib` to run it")
+                .into(),
+        ),
+        None => Ok(()),
+    }
+}
+
+// a compiled program is only its declarations, there's nowhere for anything
+// else at the top level to run. the REPL runs them as they're entered.
+fn declarations(program: &[TypedStmt]) -> Result<(), CompileError> {
+    let stmt = program.iter().find_map(|stmt| match stmt {
+        TypedStmt::If { span, .. }
+        | TypedStmt::For { span, .. }
+        | TypedStmt::ForEach { span, .. }
+        | TypedStmt::Block(_, span)
+        | TypedStmt::Assignment { span, .. }
+        | TypedStmt::Return(_, span)
+        | TypedStmt::Expr(_, span) => Some(*span),
+        _ => None,
+    });
+
+    match stmt {
+        Some(span) => Err(Diagnostic::error(
+            "TopLevelStatement",
+            span,
+            "Only declarations can go at the top level of a program",
+        )
+        .with_note("statements run inside a function, move it into `main`")
+        .into()),
+        None => Ok(()),
+    }
+}
+
+// roughly where `stmt` starts, for the warnings about a statement as a whole.
+fn location(stmt: &Stmt) -> Span {
+    match stmt {
+        Stmt::Variable { token, .. }
+        | Stmt::Function { token, .. }
+        | Stmt::For { token, .. }
+        | Stmt::ForEach { token, .. }
+        | Stmt::Block(_, token)
+        | Stmt::Import { token, .. }
+        | Stmt::Type { token, .. }
+        | Stmt::Return(_, token) => token.span,
+        Stmt::Const { value, .. } | Stmt::Assignment { value, .. } => value.span,
+        Stmt::If { condition, .. } => condition.span,
+        Stmt::Expr(expr) => expr.span,
+        Stmt::Empty => Span::default(),
+    }
+}
+
+fn returns(stmts: &[TypedStmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        TypedStmt::Return(..) => true,
+        // nothing after an `exit` runs, so it's as good as a `return`.
+        TypedStmt::Expr(
+            TypedExpr::Builtin {
+                builtin: Builtin::Exit,
+                ..
+            },
+            _,
+        ) => true,
+        TypedStmt::Block(body, _) => returns(body),
+        TypedStmt::If {
+            consequence,
+            alternative: Some(alt),
+            ..
+        } => returns(consequence) && returns(std::slice::from_ref(&**alt)),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests;
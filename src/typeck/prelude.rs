@@ -0,0 +1,438 @@
+use super::{Type, TypedExpr, TypedPart};
+
+// functions every program can call without declaring them. a function the
+// program declares with the same name takes their place.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Builtin {
+    Print,
+    Println,
+    // the length of a string in bytes, or of an array.
+    Len,
+    // stops the program when a condition doesn't hold. the checker puts the
+    // condition's source right after it, for the message.
+    Assert,
+    // the basics of `<math.h>`. `abs`, `min` and `max` give back the type
+    // they're given, the rest work on `Float`s.
+    Abs,
+    Min,
+    Max,
+    Sqrt,
+    Pow,
+    Floor,
+    Ceil,
+    Round,
+    // a line from stdin, after printing the prompt it's given if it's given
+    // one, and the numbers in a string.
+    Input,
+    ParseInt,
+    ParseFloat,
+    // a whole file as a string, which stops the program if it can't be read,
+    // and writing one out, or adding to its end, which say whether it worked.
+    ReadFile,
+    WriteFile,
+    AppendFile,
+    // how many arguments the program was started with, and the one at an
+    // index. the first is the program itself, like in C.
+    ArgCount,
+    Arg,
+    // ends the program with a status, and never comes back.
+    Exit,
+    // an environment variable, empty if it isn't set, and setting one.
+    Env,
+    SetEnv,
+}
+
+impl Builtin {
+    // every one of them, for suggesting a name when a call's misspelt.
+    pub const ALL: [Builtin; 23] = [
+        Builtin::Print,
+        Builtin::Println,
+        Builtin::Len,
+        Builtin::Assert,
+        Builtin::Abs,
+        Builtin::Min,
+        Builtin::Max,
+        Builtin::Sqrt,
+        Builtin::Pow,
+        Builtin::Floor,
+        Builtin::Ceil,
+        Builtin::Round,
+        Builtin::Input,
+        Builtin::ParseInt,
+        Builtin::ParseFloat,
+        Builtin::ReadFile,
+        Builtin::WriteFile,
+        Builtin::AppendFile,
+        Builtin::ArgCount,
+        Builtin::Arg,
+        Builtin::Exit,
+        Builtin::Env,
+        Builtin::SetEnv,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Builtin> {
+        match name {
+            "print" => Some(Builtin::Print),
+            "println" => Some(Builtin::Println),
+            "len" => Some(Builtin::Len),
+            "assert" => Some(Builtin::Assert),
+            "abs" => Some(Builtin::Abs),
+            "min" => Some(Builtin::Min),
+            "max" => Some(Builtin::Max),
+            "sqrt" => Some(Builtin::Sqrt),
+            "pow" => Some(Builtin::Pow),
+            "floor" => Some(Builtin::Floor),
+            "ceil" => Some(Builtin::Ceil),
+            "round" => Some(Builtin::Round),
+            "input" => Some(Builtin::Input),
+            "parse_int" => Some(Builtin::ParseInt),
+            "parse_float" => Some(Builtin::ParseFloat),
+            "read_file" => Some(Builtin::ReadFile),
+            "write_file" => Some(Builtin::WriteFile),
+            "append_file" => Some(Builtin::AppendFile),
+            "arg_count" => Some(Builtin::ArgCount),
+            "arg" => Some(Builtin::Arg),
+            "exit" => Some(Builtin::Exit),
+            "env" => Some(Builtin::Env),
+            "set_env" => Some(Builtin::SetEnv),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+            Builtin::Len => "len",
+            Builtin::Assert => "assert",
+            Builtin::Abs => "abs",
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Sqrt => "sqrt",
+            Builtin::Pow => "pow",
+            Builtin::Floor => "floor",
+            Builtin::Ceil => "ceil",
+            Builtin::Round => "round",
+            Builtin::Input => "input",
+            Builtin::ParseInt => "parse_int",
+            Builtin::ParseFloat => "parse_float",
+            Builtin::ReadFile => "read_file",
+            Builtin::WriteFile => "write_file",
+            Builtin::AppendFile => "append_file",
+            Builtin::ArgCount => "arg_count",
+            Builtin::Arg => "arg",
+            Builtin::Exit => "exit",
+            Builtin::Env => "env",
+            Builtin::SetEnv => "set_env",
+        }
+    }
+
+    pub fn is_math(&self) -> bool {
+        matches!(
+            self,
+            Builtin::Abs
+                | Builtin::Min
+                | Builtin::Max
+                | Builtin::Sqrt
+                | Builtin::Pow
+                | Builtin::Floor
+                | Builtin::Ceil
+                | Builtin::Round
+        )
+    }
+
+    // whether the numbers this is given are converted to `Float`s first.
+    pub fn takes_floats(&self) -> bool {
+        self.is_math() && !matches!(self, Builtin::Abs | Builtin::Min | Builtin::Max)
+    }
+
+    // whether this comes down to a `printf`.
+    pub fn prints(&self) -> bool {
+        matches!(self, Builtin::Print | Builtin::Println)
+    }
+
+    // the type of a call with `args`, or why it can't be made.
+    pub fn check(&self, args: &[TypedExpr]) -> Result<Type, String> {
+        if *self == Builtin::Len {
+            return match args {
+                [arg] if matches!(arg.ty(), Type::String | Type::Array(..)) => Ok(Type::Int),
+                [arg] => Err(format!("Cannot take the `len` of a `{}`", arg.ty())),
+                args => Err(format!(
+                    "`len` takes one argument but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if *self == Builtin::Assert {
+            return match args {
+                [condition, ..] if condition.ty() != Type::Bool => Err(format!(
+                    "`assert` takes a `Bool` condition, found `{}`",
+                    condition.ty()
+                )),
+                [_, message] if message.ty() != Type::String => Err(format!(
+                    "`assert`'s message must be a `String`, found `{}`",
+                    message.ty()
+                )),
+                [_] | [_, _] => Ok(Type::Void),
+                args => Err(format!(
+                    "`assert` takes a condition and an optional message but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if self.is_math() {
+            return self.check_math(args);
+        }
+
+        if *self == Builtin::Input {
+            return match args {
+                [prompt] if prompt.ty() != Type::String => Err(format!(
+                    "`input`'s prompt must be a `String`, found `{}`",
+                    prompt.ty()
+                )),
+                [] | [_] => Ok(Type::String),
+                args => Err(format!(
+                    "`input` takes an optional prompt but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if matches!(self, Builtin::ParseInt | Builtin::ParseFloat) {
+            return match args {
+                [arg] if arg.ty() != Type::String => Err(format!(
+                    "`{}` takes a `String`, found `{}`",
+                    self.name(),
+                    arg.ty()
+                )),
+                [_] if *self == Builtin::ParseInt => Ok(Type::Int),
+                [_] => Ok(Type::Float),
+                args => Err(format!(
+                    "`{}` takes one argument but {} were given",
+                    self.name(),
+                    args.len()
+                )),
+            };
+        }
+
+        // with a `fallback`, it's what `read_file` gives instead of
+        // panicking when the file can't be read.
+        if *self == Builtin::ReadFile {
+            return match args {
+                [path, ..] if path.ty() != Type::String => Err(format!(
+                    "`read_file` takes a `String` path, found `{}`",
+                    path.ty()
+                )),
+                [_, fallback] if fallback.ty() != Type::String => Err(format!(
+                    "`read_file`'s fallback must be a `String`, found `{}`",
+                    fallback.ty()
+                )),
+                [_] | [_, _] => Ok(Type::String),
+                args => Err(format!(
+                    "`read_file` takes a path and an optional fallback but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if matches!(self, Builtin::WriteFile | Builtin::AppendFile) {
+            return match args {
+                [path, _] if path.ty() != Type::String => Err(format!(
+                    "`{}` takes a `String` path, found `{}`",
+                    self.name(),
+                    path.ty()
+                )),
+                [_, contents] if contents.ty() != Type::String => Err(format!(
+                    "`{}` takes `String` contents, found `{}`",
+                    self.name(),
+                    contents.ty()
+                )),
+                [_, _] => Ok(Type::Bool),
+                args => Err(format!(
+                    "`{}` takes a path and its contents but {} were given",
+                    self.name(),
+                    args.len()
+                )),
+            };
+        }
+
+        if *self == Builtin::ArgCount {
+            return match args {
+                [] => Ok(Type::Int),
+                args => Err(format!(
+                    "`arg_count` takes no arguments but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if *self == Builtin::Arg {
+            return match args {
+                [index] if !index.ty().is_integer() => Err(format!(
+                    "`arg` takes an integer index, found `{}`",
+                    index.ty()
+                )),
+                [_] => Ok(Type::String),
+                args => Err(format!(
+                    "`arg` takes one argument but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if *self == Builtin::Exit {
+            return match args {
+                [status] if !status.ty().is_integer() => Err(format!(
+                    "`exit` takes an integer status, found `{}`",
+                    status.ty()
+                )),
+                [_] => Ok(Type::Void),
+                args => Err(format!(
+                    "`exit` takes one argument but {} were given",
+                    args.len()
+                )),
+            };
+        }
+
+        if matches!(self, Builtin::Env | Builtin::SetEnv) {
+            let arity = if *self == Builtin::Env { 1 } else { 2 };
+
+            return match args.iter().find(|arg| arg.ty() != Type::String) {
+                _ if args.len() != arity => Err(format!(
+                    "`{}` takes {} but {} were given",
+                    self.name(),
+                    if arity == 1 {
+                        "a name"
+                    } else {
+                        "a name and a value"
+                    },
+                    args.len()
+                )),
+                Some(arg) => Err(format!(
+                    "`{}` takes `String`s, found `{}`",
+                    self.name(),
+                    arg.ty()
+                )),
+                None if *self == Builtin::Env => Ok(Type::String),
+                None => Ok(Type::Void),
+            };
+        }
+
+        for arg in args {
+            if !arg.ty().is_printable() {
+                return Err(format!("`{}` can't print a `{}`", self.name(), arg.ty()));
+            }
+        }
+
+        Ok(Type::Void)
+    }
+
+    fn check_math(&self, args: &[TypedExpr]) -> Result<Type, String> {
+        let arity = match self {
+            Builtin::Min | Builtin::Max | Builtin::Pow => 2,
+            _ => 1,
+        };
+
+        if args.len() != arity {
+            return Err(format!(
+                "`{}` takes {} but {} were given",
+                self.name(),
+                if arity == 1 {
+                    "one argument"
+                } else {
+                    "two arguments"
+                },
+                args.len()
+            ));
+        }
+
+        if let Some(arg) = args.iter().find(|arg| !arg.ty().is_numeric()) {
+            return Err(format!(
+                "`{}` takes numbers, found a `{}`",
+                self.name(),
+                arg.ty()
+            ));
+        }
+
+        match args {
+            _ if self.takes_floats() => Ok(Type::Float),
+            [lhs, rhs] if lhs.ty() != rhs.ty() => Err(format!(
+                "`{}` takes two numbers of the same type, found `{}` and `{}`",
+                self.name(),
+                lhs.ty(),
+                rhs.ty()
+            )),
+            args => Ok(args[0].ty()),
+        }
+    }
+
+    // the arguments of the `printf` call this comes down to, the format
+    // string first. arguments are separated by spaces and interpolated
+    // strings are spliced into the format, `println` ends the line.
+    pub fn printf(&self, args: &[TypedExpr]) -> Vec<TypedExpr> {
+        let mut format = String::new();
+        let mut values = vec![];
+
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                format.push(' ');
+            }
+
+            match arg {
+                TypedExpr::Interpolation(parts) => {
+                    let (spliced, args) = interpolation(parts);
+
+                    format.push_str(&spliced);
+                    values.extend(args.into_iter().cloned());
+                }
+                arg => {
+                    format.push_str(spec(&arg.ty()));
+                    values.push(arg.clone());
+                }
+            }
+        }
+
+        if *self == Builtin::Println {
+            format.push('\n');
+        }
+
+        values.insert(0, TypedExpr::String(format));
+        values
+    }
+}
+
+// the printf conversion for a value of type `ty`. the integers smaller than an
+// `int` are promoted to one when they're passed to printf.
+pub fn spec(ty: &Type) -> &'static str {
+    match ty {
+        Type::Int64 => "%lld",
+        Type::UInt64 => "%llu",
+        Type::UInt32 => "%u",
+        ty if ty.is_integer() => "%d",
+        Type::Float | Type::Float32 => "%f",
+        Type::Char => "%c",
+        // a `Bool` is passed as the string `true` or `false`.
+        _ => "%s",
+    }
+}
+
+// a printf format string for an interpolated string, and the values that go
+// with it.
+pub fn interpolation(parts: &[TypedPart]) -> (String, Vec<&TypedExpr>) {
+    let mut format = String::new();
+    let mut values = vec![];
+
+    for part in parts {
+        match part {
+            TypedPart::Text(text) => format.push_str(&text.replace('%', "%%")),
+            TypedPart::Expr(expr) => {
+                format.push_str(spec(&expr.ty()));
+                values.push(expr);
+            }
+        }
+    }
+
+    (format, values)
+}
@@ -0,0 +1,1519 @@
+use super::*;
+
+use crate::lexer::{Lexer, Span};
+use crate::parser::Parser;
+
+// checked bit by bit like the repl does, so a global can be given anything.
+fn check(src: &str) -> Result<Vec<TypedStmt>, TypeError> {
+    let tokens = Lexer::new(src).lex().unwrap();
+
+    TypeChecker::new().check_incremental(Parser::new(tokens).parse().unwrap())
+}
+
+fn warnings(src: &str) -> Vec<String> {
+    let tokens = Lexer::new(src).lex().unwrap();
+    let (_, warnings) = TypeChecker::check(Parser::new(tokens).parse().unwrap()).unwrap();
+
+    warnings
+        .into_iter()
+        .map(|warning| warning.message)
+        .collect()
+}
+
+#[test]
+fn variable_types() {
+    let typed = check(
+        r#"
+            let greet = "Hello, World!";
+            let mut x = 35 + 34;
+            let pi = 3.14;
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        typed,
+        vec![
+            TypedStmt::Variable {
+                name: String::from("greet"),
+                value: TypedExpr::String(String::from("Hello, World!")),
+                is_mut: false,
+                ty: Type::String,
+                span: Span::new(25, 40, 2, 25),
+            },
+            TypedStmt::Variable {
+                name: String::from("x"),
+                value: TypedExpr::Binary {
+                    lhs: TypedExpr::Number(String::from("35")).into(),
+                    op: Token::new(TokenKind::Add, None, Span::new(69, 70, 3, 28)),
+                    rhs: TypedExpr::Number(String::from("34")).into(),
+                    ty: Type::Int,
+                },
+                is_mut: true,
+                ty: Type::Int,
+                span: Span::new(66, 73, 3, 25),
+            },
+            TypedStmt::Variable {
+                name: String::from("pi"),
+                value: TypedExpr::Float(String::from("3.14")),
+                is_mut: false,
+                ty: Type::Float,
+                span: Span::new(96, 100, 4, 22),
+            },
+        ]
+    )
+}
+
+#[test]
+fn call_return_type() {
+    let typed = check(
+        r#"
+            func main(): int {
+                let x = sum(1, 2);
+            }
+
+            func sum(x: int, y: int): int {
+                return x + y;
+            }
+        "#,
+    )
+    .unwrap();
+
+    match &typed[0] {
+        TypedStmt::Function { body, .. } => match &body[0] {
+            TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Int),
+            stmt => panic!("expected a variable, found: {:?}", stmt),
+        },
+        stmt => panic!("expected a function, found: {:?}", stmt),
+    }
+}
+
+#[test]
+fn for_iterator_is_scoped() {
+    let result = check(
+        r#"
+            func main() {
+                for i in 0..10 {
+                    let x = i;
+                }
+
+                let y = i;
+            }
+        "#,
+    );
+
+    assert_eq!(
+        result.err().unwrap().message,
+        "Use of undeclared variable `i`"
+    );
+}
+
+#[test]
+fn array_types() {
+    let typed = check(
+        r#"
+            let grid = [[1, 2], [3, 4]];
+            let x = grid[1][0];
+        "#,
+    )
+    .unwrap();
+
+    match (&typed[0], &typed[1]) {
+        (TypedStmt::Variable { ty: grid, .. }, TypedStmt::Variable { ty: x, .. }) => {
+            assert_eq!(
+                *grid,
+                Type::Array(Type::Array(Type::Int.into(), 2).into(), 2)
+            );
+            assert_eq!(*x, Type::Int);
+        }
+        stmts => panic!("expected two variables, found: {:?}", stmts),
+    }
+}
+
+#[test]
+fn for_each_element_type() {
+    let typed = check(
+        r#"
+            for row in [[1.5, 2.5]] {
+                let x = row[0];
+            }
+        "#,
+    )
+    .unwrap();
+
+    match &typed[0] {
+        TypedStmt::ForEach { body, .. } => match &body[0] {
+            TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Float),
+            stmt => panic!("expected a variable, found: {:?}", stmt),
+        },
+        stmt => panic!("expected a for loop, found: {:?}", stmt),
+    }
+}
+
+#[test]
+fn index_non_array() {
+    let result = check(
+        r#"
+            let x = 1;
+            let y = x[0];
+        "#,
+    );
+
+    assert_eq!(result.err().unwrap().message, "Cannot index into `Int`");
+}
+
+#[test]
+fn mismatched_types() {
+    let result = check(
+        r#"
+            let x = 1 + "2.5";
+        "#,
+    );
+
+    assert_eq!(
+        result.err().unwrap(),
+        TypeError {
+            message: String::from("Mismatched types `Int` and `String`"),
+            token: Token::new(TokenKind::Add, None, Span::new(23, 24, 2, 23)),
+            suggestion: None,
+        }
+    );
+}
+
+#[test]
+fn string_operators() {
+    let typed = check(
+        r#"
+            let greeting = "Hello, " + "World!";
+            let same = greeting == "Hello, World!";
+        "#,
+    )
+    .unwrap();
+
+    match (&typed[0], &typed[1]) {
+        (TypedStmt::Variable { ty: greeting, .. }, TypedStmt::Variable { ty: same, .. }) => {
+            assert_eq!(*greeting, Type::String);
+            assert_eq!(*same, Type::Bool);
+        }
+        stmts => panic!("expected two variables, found: {:?}", stmts),
+    }
+
+    assert_eq!(
+        check(r#"let x = "a" - "b";"#).err().unwrap().message,
+        "Operator `-` cannot be applied to `String`"
+    );
+}
+
+#[test]
+fn undefined_function() {
+    let result = check(
+        r#"
+            let x = nope();
+        "#,
+    );
+
+    assert_eq!(
+        result.err().unwrap().token,
+        Token::new(
+            TokenKind::Identifier,
+            Some(String::from("nope")),
+            Span::new(21, 25, 2, 21)
+        )
+    );
+}
+
+#[test]
+fn void_variable() {
+    let result = check(
+        r#"
+            func printf(fmt, ...) @requires("stdio.h")
+
+            let x = printf("hi");
+        "#,
+    );
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn variadic_functions_need_a_header() {
+    let err = check("func sum(first: Int, ...): Int { return first; }").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "Function `sum` can't take `...`, only a function with `@requires` can"
+    );
+    assert_eq!(err.token.span, Span::new(5, 8, 1, 6));
+}
+
+#[test]
+fn unused_bindings() {
+    assert_eq!(
+        warnings(
+            r#"
+                func helper(a: int, _b: int): int {
+                    let unused = 1;
+                    let mut written = 2;
+                    written = 3;
+
+                    return a;
+                }
+
+                func main(): int {
+                    return 0;
+                }
+            "#
+        ),
+        vec![
+            "Variable `unused` is never used",
+            "Variable `written` is never used",
+            "Function `helper` is never called",
+        ]
+    );
+}
+
+#[test]
+fn unreachable_and_shadowed() {
+    assert_eq!(
+        warnings(
+            r#"
+                func main(): int {
+                    let x = 1;
+
+                    for i in 0..x {
+                        let x = i;
+                        return x;
+                        x;
+                    }
+
+                    return 0;
+                }
+            "#
+        ),
+        vec![
+            "`x` shadows an earlier binding",
+            "Unreachable code after `return`",
+        ]
+    );
+}
+
+#[test]
+fn warnings_point_at_the_names() {
+    let src = "func helper(unused: Int) {}
+func main(): int {
+    for i in 0..3 {}
+    for i in 0..3 { let i = 1; return 0; i; }
+    return 0;
+}";
+    let tokens = Lexer::new(src).lex().unwrap();
+    let (_, warnings) = TypeChecker::check(Parser::new(tokens).parse().unwrap()).unwrap();
+
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|warning| (
+                warning.code.as_str(),
+                warning.span.line,
+                warning.span.column
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("UnusedVariable", 1, 13),
+            ("UnusedVariable", 3, 9),
+            ("ShadowedBinding", 4, 25),
+            ("UnreachableCode", 4, 42),
+            ("UnusedVariable", 4, 9),
+            ("UnusedFunction", 1, 6),
+        ]
+    );
+
+    // so does a parameter without a type.
+    let err = check("func f(x: Int,\n    y) {}").err().unwrap();
+    assert_eq!(err.message, "Argument `y` of `f` is missing a type");
+    assert_eq!(err.token.span, Span::new(19, 20, 2, 5));
+}
+
+#[test]
+fn undeclared_variables_point_at_the_name() {
+    let err = check("func f(): int { return y; }").err().unwrap();
+    assert_eq!(err.message, "Use of undeclared variable `y`");
+    assert_eq!(err.token.span, Span::new(23, 24, 1, 24));
+
+    let err = check("let z = xy + 1;").err().unwrap();
+    assert_eq!(err.message, "Use of undeclared variable `xy`");
+    assert_eq!(err.token.span, Span::new(8, 10, 1, 9));
+}
+
+#[test]
+fn suggestions() {
+    let result = check(
+        r#"
+            func print(s: string) {}
+
+            func main(): int {
+                let count = 1;
+                prnt("x");
+
+                return 0;
+            }
+        "#,
+    );
+    assert_eq!(
+        result.err().unwrap().suggestion,
+        Some(String::from("print"))
+    );
+
+    // the prelude's functions are suggested too.
+    let result = check(r#"func main(): int { prnt("x"); return 0; }"#);
+    assert_eq!(
+        result.err().unwrap().suggestion,
+        Some(String::from("print"))
+    );
+
+    let result = check("let count = 1; let total = cuont + 1;");
+    assert_eq!(
+        result.err().unwrap().suggestion,
+        Some(String::from("count"))
+    );
+
+    let result = check("let count = 1; let total = x + 1;");
+    assert_eq!(result.err().unwrap().suggestion, None);
+}
+
+#[test]
+fn missing_return() {
+    let err = check(
+        r#"
+            func sign(x: int): int {
+                if (x < 0) {
+                    return 0 - 1;
+                } else if (x > 0) {
+                    return 1;
+                }
+            }
+        "#,
+    )
+    .err()
+    .unwrap();
+
+    assert_eq!(err.message, "Not all paths in `sign` return a value");
+    assert_eq!(err.token.span, Span::new(18, 22, 2, 18));
+
+    assert!(check(
+        r#"
+            func sign(x: int): int {
+                if (x < 0) {
+                    return 0 - 1;
+                } else {
+                    for i in 0..x {
+                        return 1;
+                    }
+                }
+            }
+        "#,
+    )
+    .is_err());
+
+    assert!(check(
+        r#"
+            func sign(x: int): int {
+                if (x < 0) {
+                    return 0 - 1;
+                } else if (x > 0) {
+                    return 1;
+                } else {
+                    return 0;
+                }
+            }
+        "#,
+    )
+    .is_ok());
+}
+
+#[test]
+fn main_returns_zero() {
+    let typed = check("func main(): int { let mut x = 1; x = x + 1; }").unwrap();
+
+    match &typed[0] {
+        TypedStmt::Function { body, .. } => assert_eq!(
+            body.last(),
+            Some(&TypedStmt::Return(
+                Some(TypedExpr::Number(String::from("0"))),
+                Span::default()
+            ))
+        ),
+        stmt => panic!("expected a function, found {:?}", stmt),
+    }
+}
+
+#[test]
+fn prelude() {
+    let typed = check(r#"println("x =", 1, 2.5);"#).unwrap();
+
+    match &typed[0] {
+        TypedStmt::Expr(TypedExpr::Builtin { builtin, args, ty }, _) => {
+            assert_eq!(*builtin, Builtin::Println);
+            assert_eq!(
+                builtin.printf(args)[0],
+                TypedExpr::String(String::from("%s %d %f\n"))
+            );
+            assert_eq!(*ty, Type::Void);
+        }
+        stmt => panic!("expected a builtin call, found {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("println([1, 2]);").err().unwrap().message,
+        "`println` can't print a `[Int; 2]`"
+    );
+
+    // a declaration of the same name takes the place of the builtin.
+    let typed = check(
+        r#"func println(fmt, ...) @requires("stdio.h")
+println("hi");"#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        typed[1],
+        TypedStmt::Expr(TypedExpr::Call { .. }, _)
+    ));
+}
+
+#[test]
+fn interpolated_strings() {
+    let typed = check(r#"let n = 2; let s = "{n} of {"them"} at 100%";"#).unwrap();
+
+    let parts = match &typed[1] {
+        TypedStmt::Variable {
+            value: TypedExpr::Interpolation(parts),
+            ty: Type::String,
+            ..
+        } => parts,
+        stmt => panic!("expected an interpolated string, found {:?}", stmt),
+    };
+
+    let (format, values) = interpolation(parts);
+    assert_eq!(format, "%d of %s at 100%%");
+    assert_eq!(values.len(), 2);
+
+    assert!(check(r#"let xs = [1]; let s = "{xs}";"#).is_err());
+}
+
+#[test]
+fn booleans() {
+    let typed = check("let a = true; let b = 1 < 2 && a != false || 2.5 >= 1.5;").unwrap();
+
+    match &typed[1] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Bool),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x = 1 && 2;").err().unwrap().message,
+        "Operator `&&` cannot be applied to `Int`"
+    );
+    assert_eq!(
+        check("let x = true + false;").err().unwrap().message,
+        "Operator `+` cannot be applied to `Bool`"
+    );
+}
+
+#[test]
+fn modulo() {
+    match &check("let x = 7 % 2;").unwrap()[0] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Int),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    let err = check("let x = 7.5 % 2.0;").err().unwrap();
+
+    assert_eq!(err.token.kind, TokenKind::Mod);
+    assert!(err.message.contains("`fmod`"));
+}
+
+#[test]
+fn compound_assignment() {
+    let typed = check("let mut s = \"a\"; s += \"b\";").unwrap();
+
+    match &typed[1] {
+        TypedStmt::Assignment {
+            value: TypedExpr::Binary { lhs, ty, .. },
+            op: Some(op),
+            ..
+        } => {
+            assert_eq!(op.kind, TokenKind::Add);
+            assert_eq!(*ty, Type::String);
+            assert!(matches!(**lhs, TypedExpr::Identifier { .. }));
+        }
+        stmt => panic!("expected a compound assignment, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x = 1; x += 1;").err().unwrap().message,
+        "Cannot assign to `x`, it isn't declared with `let mut`"
+    );
+    assert_eq!(
+        check("let mut x = 1; x -= \"a\";").err().unwrap().message,
+        "Mismatched types `Int` and `String`"
+    );
+    assert!(check("func f(n: int): int { n = 2; return n; }").is_err());
+}
+
+#[test]
+fn bitwise() {
+    match &check("let x = ~1 << 2 | 3 & 4 ^ 5 >> 1;").unwrap()[0] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Int),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x = 1.5 & 2.5;").err().unwrap().message,
+        "Operator `&` cannot be applied to `Float`"
+    );
+    assert_eq!(
+        check("let x = ~true;").err().unwrap().message,
+        "Operator `~` cannot be applied to `Bool`"
+    );
+}
+
+#[test]
+fn logical_not() {
+    match &check("let x = !(1 < 2) || !true;").unwrap()[0] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Bool),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x = !1;").err().unwrap().message,
+        "Operator `!` cannot be applied to `Int`"
+    );
+    assert!(check("println(true, 1 == 2, \"{1 < 2}\");").is_ok());
+}
+
+#[test]
+fn annotations() {
+    match &check("let b: bool = 1 < 2;").unwrap()[0] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Bool),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x: Int = \"three\";").err().unwrap().message,
+        "`x` is declared as `Int` but is assigned a `String`"
+    );
+    assert_eq!(
+        check("let x: Nope = 3;").err().unwrap().message,
+        "Unknown type `Nope`"
+    );
+}
+
+#[test]
+fn int_to_float_widening() {
+    let typed = check("let n = 2; let x = n * 1.5; let big = 3 > 2.5;").unwrap();
+
+    match &typed[1] {
+        TypedStmt::Variable {
+            value: TypedExpr::Binary { lhs, ty, .. },
+            ..
+        } => {
+            assert_eq!(*ty, Type::Float);
+            assert!(matches!(
+                **lhs,
+                TypedExpr::Cast {
+                    ty: Type::Float,
+                    ..
+                }
+            ));
+        }
+        stmt => panic!("expected a binary expression, found: {:?}", stmt),
+    }
+
+    match &typed[2] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Bool),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x = 5 % 2.0;").err().unwrap().message,
+        "Mismatched types `Int` and `Float`"
+    );
+    assert_eq!(
+        check("let mut n = 1; n += 0.5;").err().unwrap().message,
+        "Cannot assign `Float` to `n` of type `Int`"
+    );
+}
+
+#[test]
+fn sized_types() {
+    let typed = check("let x: Int8 = 5; let y = x + 1; let z: float32 = 1.5;").unwrap();
+
+    match &typed[1] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Int8),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    match &typed[2] {
+        TypedStmt::Variable { value, .. } => {
+            assert!(matches!(
+                value,
+                TypedExpr::Cast {
+                    ty: Type::Float32,
+                    ..
+                }
+            ))
+        }
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let x: UInt8 = 300;").err().unwrap().message,
+        "Integer `300` doesn't fit in `UInt8`"
+    );
+    assert_eq!(
+        check("let x: Int8 = 5; let y = x + 300;")
+            .err()
+            .unwrap()
+            .message,
+        "Integer `300` doesn't fit in `Int8`"
+    );
+    assert_eq!(
+        check("let x: Int8 = 5; let n = 1; let y = x + n;")
+            .err()
+            .unwrap()
+            .message,
+        "Mismatched types `Int8` and `Int`"
+    );
+}
+
+#[test]
+fn casts() {
+    let typed = check("let f = 2.5; let n = f as Int + 1; let b = (1 < 2) as UInt8;").unwrap();
+
+    match &typed[1] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::Int),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    match &typed[2] {
+        TypedStmt::Variable { ty, .. } => assert_eq!(*ty, Type::UInt8),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    assert_eq!(
+        check("let s = \"1\" as Int;").err().unwrap().message,
+        "Cannot cast `String` to `Int`"
+    );
+    assert_eq!(
+        check("let b = 1 as Bool;").err().unwrap().message,
+        "Cannot cast `Int` to `Bool`"
+    );
+    assert_eq!(
+        check("let x = 1 as Nope;").err().unwrap().message,
+        "Unknown type `Nope`"
+    );
+}
+
+#[test]
+fn number_suffixes() {
+    let typed = check("let n = 100u64; let f = 1.5f; let d = 2f64; let x = 1e3;").unwrap();
+
+    let types: Vec<_> = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found: {:?}", stmt),
+        })
+        .collect();
+
+    assert_eq!(
+        types,
+        vec![Type::UInt64, Type::Float32, Type::Float, Type::Float]
+    );
+
+    match &typed[2] {
+        TypedStmt::Variable { value, .. } => assert_eq!(
+            *value,
+            TypedExpr::Cast {
+                value: TypedExpr::Float(String::from("2.0")).into(),
+                ty: Type::Float,
+            }
+        ),
+        stmt => panic!("expected a variable, found: {:?}", stmt),
+    }
+
+    // a suffix pins the type, where a plain literal would be coerced.
+    assert_eq!(
+        check("let x: UInt8 = 1; let y = x + 1u16;")
+            .err()
+            .unwrap()
+            .message,
+        "Mismatched types `UInt8` and `UInt16`"
+    );
+    assert_eq!(
+        check("let x = 300u8;").err().unwrap().message,
+        "Integer `300` doesn't fit in `UInt8`"
+    );
+}
+
+#[test]
+fn characters() {
+    let typed =
+        check("let c: Char = 'a'; let after = c > 'Z'; let code = c as UInt8; let s = \"{c}\";")
+            .unwrap();
+
+    let types: Vec<_> = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found: {:?}", stmt),
+        })
+        .collect();
+
+    assert_eq!(
+        types,
+        vec![Type::Char, Type::Bool, Type::UInt8, Type::String]
+    );
+
+    assert_eq!(
+        check("let c = 'a' + 1;").err().unwrap().message,
+        "Mismatched types `Char` and `Int`"
+    );
+    assert_eq!(
+        check("let c = 97 as Char;").err().unwrap().message,
+        "Cannot cast `Int` to `Char`"
+    );
+    assert_eq!(
+        check("let s: String = 'a';").err().unwrap().message,
+        "`s` is declared as `String` but is assigned a `Char`"
+    );
+}
+
+#[test]
+fn errors_point_at_expressions() {
+    let err = check(r#"let x: Int = "three";"#).unwrap_err();
+    assert_eq!(err.token.span, Span::new(13, 20, 1, 14));
+
+    let err = check("let mut x = 1; x = 1.5 < 2;").unwrap_err();
+    assert_eq!(err.token.span, Span::new(19, 26, 1, 20));
+}
+
+#[test]
+fn block_scoping() {
+    let result = check(
+        r#"
+            func main(): int {
+                {
+                    let x = 1;
+                    x;
+                }
+
+                return x;
+            }
+        "#,
+    );
+
+    assert_eq!(
+        result.err().unwrap().message,
+        "Use of undeclared variable `x`"
+    );
+
+    assert_eq!(
+        warnings("let x = 1; { let x = 2; x; } x;"),
+        vec!["`x` shadows an earlier binding"]
+    );
+
+    let err = check(
+        r#"
+            func main(): int {
+                let x = 1;
+                func inner(): int { return x; }
+                return inner();
+            }
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "Function `inner` must be declared at the top level"
+    );
+    assert_eq!(err.token.span.line, 4);
+}
+
+#[test]
+fn functions_see_later_globals() {
+    let typed = check(
+        r#"
+            func main(): int {
+                return limit;
+            }
+
+            let limit = 10;
+        "#,
+    )
+    .unwrap();
+
+    assert!(matches!(typed[0], TypedStmt::Function { .. }));
+    assert!(matches!(typed[1], TypedStmt::Variable { .. }));
+}
+
+#[test]
+fn constants() {
+    let typed = check(
+        r#"
+            const SIDES = 2 * 3;
+            const TAU = 3.14 * 2.0;
+            const NAME = "uma" + "!";
+            const BIG = SIDES << 4 > 90 && true;
+            const SMALL: UInt8 = ~0 as UInt8;
+            const PICKED = if SIDES > 5 { "big" } else { "small" };
+        "#,
+    )
+    .unwrap();
+
+    let values = typed
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            TypedStmt::Const { value, .. } => Some(value),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        values,
+        vec![
+            TypedExpr::Number("6".into()),
+            TypedExpr::Float("6.28".into()),
+            TypedExpr::String("uma!".into()),
+            TypedExpr::Bool(true),
+            TypedExpr::Cast {
+                value: TypedExpr::Number("-1".into()).into(),
+                ty: Type::UInt8,
+            },
+            TypedExpr::String("big".into()),
+        ]
+    );
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("func two(): int { return 2; }\nconst N = two();"),
+        "The value of `N` can't be worked out at compile time"
+    );
+    assert_eq!(
+        message("const N = 2147483647 + 1;"),
+        "The value of `N` can't be worked out at compile time"
+    );
+    assert_eq!(
+        message("func main() { const N = 1; }"),
+        "Constant `N` must be declared at the top level"
+    );
+    assert_eq!(
+        message("const N = 1;\nfunc f(N: int) {}"),
+        "`N` is a constant and can't be declared again"
+    );
+    assert_eq!(
+        message("let N = 1;\nconst N = 2;"),
+        "`N` is already declared"
+    );
+}
+
+#[test]
+fn if_expressions() {
+    let typed = check(
+        r#"
+            let label = if 1 > 0 { "pos" } else { "neg" };
+            let half = if true { 1 } else { 0.5 };
+            let small: Int8 = if false { 1 } else { 2 };
+        "#,
+    )
+    .unwrap();
+
+    let types = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found {:?}", stmt),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(types, vec![Type::String, Type::Float, Type::Int8]);
+
+    let err = check(r#"let x = if true { 1 } else { "one" };"#).unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "`if` and `else` have mismatched types `Int` and `String`"
+    );
+    assert_eq!(err.token.span, Span::new(29, 34, 1, 30));
+}
+
+#[test]
+fn match_expressions() {
+    let typed = check(
+        r#"
+            let n = 2;
+            let label = match n { 1 => "one", 2 => "two", _ => "many" };
+            let half = match n { 1 => 0.5, _ => 1 };
+            let small: Int8 = match n > 1 { true => 1, false => 2 };
+        "#,
+    )
+    .unwrap();
+
+    let types = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found {:?}", stmt),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        vec![Type::Int, Type::String, Type::Float, Type::Int8]
+    );
+
+    let err = check("let n = 1; let x = match n { 1 => 1 };").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "This `match` doesn't cover every `Int`, add a `_` arm"
+    );
+    assert_eq!(err.token.span, Span::new(19, 37, 1, 20));
+
+    let err = check(r#"let n = 1; let x = match n { "one" => 1, _ => 2 };"#).unwrap_err();
+
+    assert_eq!(err.message, "Cannot match a `Int` against a `String`");
+    assert_eq!(err.token.span, Span::new(29, 34, 1, 30));
+
+    let err = check(r#"let n = 1; let x = match n { 1 => 1, _ => "two" };"#).unwrap_err();
+    assert_eq!(
+        err.message,
+        "`match` arms have mismatched types `Int` and `String`"
+    );
+
+    assert_eq!(
+        warnings("let n = 1; println(match n { 1 => 1, 1 => 2, _ => 3, 4 => 4 });"),
+        vec![
+            "This arm is never reached, an earlier arm has the same pattern",
+            "This arm is never reached, the `_` before it matches everything",
+        ]
+    );
+}
+
+#[test]
+fn default_and_named_arguments() {
+    let typed = check(
+        r#"
+            func greet(name: String, greeting: String = "Hello", times: Float = 2): Int {
+                return 0;
+            }
+
+            greet("Ana");
+            greet(times: 3, name: "Bo");
+        "#,
+    )
+    .unwrap();
+
+    let args = typed
+        .iter()
+        .filter_map(|stmt| match stmt {
+            TypedStmt::Expr(TypedExpr::Call { args, .. }, _) => Some(args.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let float = |num: &str| TypedExpr::Cast {
+        value: TypedExpr::Number(num.to_string()).into(),
+        ty: Type::Float,
+    };
+
+    assert_eq!(
+        args,
+        vec![
+            vec![
+                TypedExpr::String("Ana".to_string()),
+                TypedExpr::String("Hello".to_string()),
+                float("2"),
+            ],
+            vec![
+                TypedExpr::String("Bo".to_string()),
+                TypedExpr::String("Hello".to_string()),
+                float("3"),
+            ],
+        ]
+    );
+
+    let errors = [
+        ("greet();", "Missing argument `name` in call to `greet`"),
+        (
+            r#"greet("a", "b", 1, 2);"#,
+            "`greet` takes 3 arguments but 4 were given",
+        ),
+        (r#"greet(nam: "a");"#, "`greet` has no parameter `nam`"),
+        (
+            r#"greet("a", name: "b");"#,
+            "Argument `name` is given more than once",
+        ),
+        (
+            r#"greet(name: "a", "b");"#,
+            "Positional arguments have to come before named ones",
+        ),
+        (
+            "greet(1);",
+            "Argument `name` of `greet` must be `String`, found `Int`",
+        ),
+        (
+            r#"println(x: "a");"#,
+            "`println` doesn't take arguments by name",
+        ),
+    ];
+
+    for (call, message) in errors {
+        let src = format!(
+            r#"func greet(name: String, greeting: String = "Hello", times: Float = 2): Int {{ return 0; }} {}"#,
+            call
+        );
+
+        assert_eq!(check(&src).unwrap_err().message, message, "{}", call);
+    }
+
+    let err = check("func f(x: Int = 1, y: Int): Int { return x; }").unwrap_err();
+    assert_eq!(
+        err.message,
+        "Parameter `y` of `f` needs a default, it comes after `x` which has one"
+    );
+
+    let err = check("let n = 1; func f(x: Int = n): Int { return x; }").unwrap_err();
+    assert_eq!(
+        err.message,
+        "The default of `x` can't be worked out at compile time"
+    );
+    assert_eq!(err.token.span, Span::new(27, 28, 1, 28));
+}
+
+#[test]
+fn strings_have_a_length_and_can_be_indexed() {
+    let typed =
+        check(r#"let s = "uma"; let n = len(s); let c = s[0]; let m = len([1, 2]);"#).unwrap();
+
+    let types = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found {:?}", stmt),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        vec![Type::String, Type::Int, Type::String, Type::Int]
+    );
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("let n = len(1);"),
+        "Cannot take the `len` of a `Int`"
+    );
+    assert_eq!(
+        message(r#"let n = len("a", "b");"#),
+        "`len` takes one argument but 2 were given"
+    );
+    assert_eq!(
+        message(r#"let c = "uma"[true];"#),
+        "String index must be `Int`, found `Bool`"
+    );
+}
+
+#[test]
+fn assert_keeps_its_condition_source() {
+    let typed = check(r#"let n = 2; assert(n*2 == 4, "doubled");"#).unwrap();
+
+    match &typed[1] {
+        TypedStmt::Expr(TypedExpr::Builtin { builtin, args, .. }, _) => {
+            assert_eq!(*builtin, Builtin::Assert);
+            assert_eq!(args[1], TypedExpr::String("n * 2 == 4".to_string()));
+            assert_eq!(args.len(), 3);
+        }
+        stmt => panic!("expected an assert, found {:?}", stmt),
+    }
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("assert(1);"),
+        "`assert` takes a `Bool` condition, found `Int`"
+    );
+    assert_eq!(
+        message("assert(true, 1);"),
+        "`assert`'s message must be a `String`, found `Int`"
+    );
+    assert_eq!(
+        message("assert();"),
+        "`assert` takes a condition and an optional message but 0 were given"
+    );
+}
+
+#[test]
+fn math_builtins() {
+    let typed = check(
+        r#"
+            let small: Int8 = 3;
+            let a = abs(small);
+            let b = min(small, 2);
+            let c = max(1, 2.5);
+            let d = sqrt(9);
+            let e = pow(2, 0.5);
+        "#,
+    )
+    .unwrap();
+
+    let types = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found {:?}", stmt),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        types,
+        vec![
+            Type::Int8,
+            Type::Int8,
+            Type::Int8,
+            Type::Float,
+            Type::Float,
+            Type::Float
+        ]
+    );
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("let x = sqrt(1, 2);"),
+        "`sqrt` takes one argument but 2 were given"
+    );
+    assert_eq!(
+        message("let x = max(1);"),
+        "`max` takes two arguments but 1 were given"
+    );
+    assert_eq!(
+        message(r#"let x = abs("a");"#),
+        "`abs` takes numbers, found a `String`"
+    );
+
+    let err = check("let a: Int8 = 1; let b: Int16 = 2; let x = min(a, b);").unwrap_err();
+    assert_eq!(
+        err.message,
+        "`min` takes two numbers of the same type, found `Int8` and `Int16`"
+    );
+}
+
+#[test]
+fn reading_input() {
+    let typed = check(
+        r#"
+            let name = input("name? ");
+            let n = parse_int(input());
+            let x = parse_float(name);
+        "#,
+    )
+    .unwrap();
+
+    let types = typed
+        .iter()
+        .map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => ty.clone(),
+            stmt => panic!("expected a variable, found {:?}", stmt),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(types, vec![Type::String, Type::Int, Type::Float]);
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("let x = input(1);"),
+        "`input`'s prompt must be a `String`, found `Int`"
+    );
+    assert_eq!(
+        message(r#"let x = input("a", "b");"#),
+        "`input` takes an optional prompt but 2 were given"
+    );
+    assert_eq!(
+        message("let x = parse_int(1);"),
+        "`parse_int` takes a `String`, found `Int`"
+    );
+    assert_eq!(
+        message("let x = parse_float();"),
+        "`parse_float` takes one argument but 0 were given"
+    );
+}
+
+#[test]
+fn file_builtins() {
+    let typed = check(
+        r#"
+            let text = read_file("in.txt");
+            let ok = write_file("out.txt", text);
+            let maybe = read_file("maybe.txt", "");
+        "#,
+    )
+    .unwrap();
+
+    assert!(matches!(
+        &typed[1],
+        TypedStmt::Variable { ty: Type::Bool, .. }
+    ));
+    assert!(matches!(
+        &typed[2],
+        TypedStmt::Variable {
+            ty: Type::String,
+            ..
+        }
+    ));
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("let x = read_file(1);"),
+        "`read_file` takes a `String` path, found `Int`"
+    );
+    assert_eq!(
+        message(r#"let x = read_file("in.txt", false);"#),
+        "`read_file`'s fallback must be a `String`, found `Bool`"
+    );
+    assert_eq!(
+        message(r#"let x = append_file("log.txt", 1);"#),
+        "`append_file` takes `String` contents, found `Int`"
+    );
+    assert_eq!(
+        message(r#"let x = write_file("out.txt");"#),
+        "`write_file` takes a path and its contents but 1 were given"
+    );
+}
+
+#[test]
+fn program_arguments() {
+    let typed = check("let n = arg_count(); let first = arg(n - 1);").unwrap();
+
+    assert!(matches!(
+        &typed[1],
+        TypedStmt::Variable {
+            ty: Type::String,
+            ..
+        }
+    ));
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message(r#"let x = arg("0");"#),
+        "`arg` takes an integer index, found `String`"
+    );
+    assert_eq!(
+        message("let x = arg_count(1);"),
+        "`arg_count` takes no arguments but 1 were given"
+    );
+}
+
+#[test]
+fn exit_counts_as_returning() {
+    assert!(check("func f(n: int): int { if (n > 0) { return n; } exit(1); }").is_ok());
+
+    let message = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        message("exit(1.5);"),
+        "`exit` takes an integer status, found `Float`"
+    );
+    assert_eq!(
+        message(r#"let x = env("A", "B");"#),
+        "`env` takes a name but 2 were given"
+    );
+    assert_eq!(
+        message(r#"set_env("A", 1);"#),
+        "`set_env` takes `String`s, found `Int`"
+    );
+}
+
+#[test]
+fn tests_are_never_unused() {
+    assert!(warnings("func works() @test { assert(1 == 1); }").is_empty());
+
+    for src in [
+        "func works(x: int) @test {}",
+        "func works(): int @test { return 1; }",
+    ] {
+        assert_eq!(
+            check(src).unwrap_err().message,
+            "Test `works` can't take arguments or return a value"
+        );
+    }
+}
+
+#[test]
+fn attributes_are_checked() {
+    let error = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        error("func f() @inlined {}"),
+        "Unknown attribute `@inlined`"
+    );
+    assert_eq!(
+        check("func f() @inlined {}").unwrap_err().suggestion,
+        Some("inline".to_string())
+    );
+    assert_eq!(
+        error(r#"func f() @inline("x") {}"#),
+        "`@inline` doesn't take a value"
+    );
+    assert_eq!(
+        error("func f() @export @export {}"),
+        "`@export` is given more than once"
+    );
+    assert_eq!(
+        error(r#"func puts(s: string) @requires("stdio.h") @inline"#),
+        "`@inline` can't go on a function with `@requires`"
+    );
+    assert_eq!(error("func main() @test {}"), "`@test` can't go on `main`");
+    assert_eq!(
+        error("func f() @export @inline {}"),
+        "`@inline` and `@export` can't go together"
+    );
+    assert_eq!(
+        error("func f() @cfg {}"),
+        "`@cfg` needs a flag, like `@cfg(\"linux\")`"
+    );
+}
+
+#[test]
+fn deprecated_functions_warn_where_called() {
+    assert_eq!(
+        warnings(
+            r#"
+            func old(): int @deprecated("use `new`") { return 1; }
+            func older(): int @deprecated { return 0; }
+            func main() { println(old() + older()); }
+            "#
+        ),
+        ["`old` is deprecated: use `new`", "`older` is deprecated"]
+    );
+}
+
+#[test]
+fn exported_functions_are_never_unused() {
+    assert!(warnings("func answer(): int @export { return 42; }").is_empty());
+}
+
+#[test]
+fn pointers() {
+    let stmts = check("let mut x = 1; let p = &x; let y = *p;").unwrap();
+    let types = stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            TypedStmt::Variable { ty, .. } => Some(ty.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(types, ["Int", "Ptr<Int>", "Int"]);
+
+    let error = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        error("let x = 1; let p = &x;"),
+        "Cannot take the address of `x`, it isn't declared with `let mut`"
+    );
+    assert_eq!(
+        error("let p = &(1 + 2);"),
+        "Only the address of a variable can be taken"
+    );
+    assert_eq!(
+        error(r#"let mut s = "hi"; let p = &s;"#),
+        "Cannot take the address of `s`, a pointer can only be to a number, `Bool`, `Char` or another pointer"
+    );
+    assert_eq!(error("let x = *1;"), "Cannot dereference a `Int`");
+    assert_eq!(
+        error("func f(p: Ptr<Void>): int { return *p; }"),
+        "Cannot dereference a `Ptr<Void>`"
+    );
+    assert_eq!(
+        error("func f(): Ptr<Int> { let mut x = 1; return &x; }"),
+        "Cannot return the address of `x`, it's gone once the function returns"
+    );
+    assert!(error("func f(p: Ptr<String>) {}").starts_with("Unknown type `Ptr<String>`"));
+}
+
+#[test]
+fn opaque_types() {
+    let src = r#"type FILE @opaque @requires("stdio.h");
+    func fopen(path: String, mode: String): Ptr<FILE> @requires("stdio.h")
+    let file = fopen("x", "r");"#;
+
+    let stmts = check(src).unwrap();
+    assert_eq!(
+        stmts[0],
+        TypedStmt::Opaque {
+            name: String::from("FILE"),
+            header: Some(String::from("stdio.h")),
+        }
+    );
+    assert!(matches!(
+        &stmts[2],
+        TypedStmt::Variable { ty: Type::Pointer(ty), .. } if **ty == Type::Opaque(String::from("FILE"))
+    ));
+
+    let error = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        error("type File;"),
+        "Type `File` needs `@opaque`, only C's types can be declared"
+    );
+    assert_eq!(
+        error("type File @opaque; func f(file: File) {}"),
+        "`File` can only be used behind a pointer, like `Ptr<File>`"
+    );
+    assert_eq!(
+        error("type File @opaque; func f(file: Ptr<File>) { let x = *file; }"),
+        "Cannot dereference a `Ptr<File>`"
+    );
+    assert_eq!(error("type Int @opaque;"), "Type `Int` is already declared");
+    assert_eq!(
+        error("type File @opaque @test;"),
+        "`@test` can't go on a type"
+    );
+    assert_eq!(
+        error("func f() { type File @opaque; }"),
+        "Type `File` must be declared at the top level"
+    );
+}
+
+#[test]
+fn returns_match_the_return_type() {
+    let error = |src| check(src).unwrap_err().message;
+
+    assert_eq!(
+        error(r#"func f(): int { return "s"; }"#),
+        "Cannot return `String` from `f`, which returns `Int`"
+    );
+    assert_eq!(
+        error("func f(): String { return 1; }"),
+        "Cannot return `Int` from `f`, which returns `String`"
+    );
+    assert_eq!(
+        error("func f() { return 1; }"),
+        "Cannot return a value from `f`, it doesn't declare a return type"
+    );
+    assert_eq!(
+        error("func f(): int { if (true) { return; } return 1; }"),
+        "`f` must return a `Int`"
+    );
+
+    // a literal takes the type it's returned as, and a bare `return` leaves
+    // a function without one.
+    assert!(check("func f(): int64 { return 1; }").is_ok());
+    assert!(check("func f(x: int) { if (x > 1) { return; } println(x); }").is_ok());
+}
@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use super::Builtin;
+use crate::lexer::{Span, Token};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    // a C `int` and `double`.
+    Int,
+    Float,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Float32,
+    Bool,
+    // a single unicode character, a C `char` when it's ASCII.
+    Char,
+    String,
+    Array(Box<Type>, usize),
+    // `Ptr<Int>`, a C `int*`. `Ptr<Void>` is a `void*`, which can only be
+    // passed along.
+    Pointer(Box<Type>),
+    // a C type declared with `type FILE @opaque;`, that the program can
+    // only point to.
+    Opaque(String),
+    Void,
+}
+
+impl Type {
+    // `opaque` are the names of the `@opaque` types declared so far.
+    pub fn from_name(name: &str, opaque: &HashSet<String>) -> Option<Type> {
+        match name {
+            "int" | "Int" => Some(Type::Int),
+            "float" | "Float" | "float64" | "Float64" => Some(Type::Float),
+            "int8" | "Int8" => Some(Type::Int8),
+            "int16" | "Int16" => Some(Type::Int16),
+            "int32" | "Int32" => Some(Type::Int32),
+            "int64" | "Int64" => Some(Type::Int64),
+            "uint8" | "UInt8" => Some(Type::UInt8),
+            "uint16" | "UInt16" => Some(Type::UInt16),
+            "uint32" | "UInt32" => Some(Type::UInt32),
+            "uint64" | "UInt64" => Some(Type::UInt64),
+            "float32" | "Float32" => Some(Type::Float32),
+            "bool" | "Bool" => Some(Type::Bool),
+            "char" | "Char" => Some(Type::Char),
+            "string" | "String" => Some(Type::String),
+            "void" | "Void" => Some(Type::Void),
+            name if opaque.contains(name) => Some(Type::Opaque(name.to_string())),
+            name => {
+                let inner = name
+                    .strip_prefix("Ptr<")
+                    .or_else(|| name.strip_prefix("ptr<"))?
+                    .strip_suffix('>')?;
+
+                Some(Type::Pointer(Type::from_name(inner, opaque)?.into()))
+            }
+        }
+    }
+
+    // the type a number's suffix pins it to, `u8` in `255u8`.
+    pub fn from_suffix(suffix: &str) -> Option<Type> {
+        match suffix {
+            "i8" => Some(Type::Int8),
+            "i16" => Some(Type::Int16),
+            "i32" => Some(Type::Int32),
+            "i64" => Some(Type::Int64),
+            "u8" => Some(Type::UInt8),
+            "u16" => Some(Type::UInt16),
+            "u32" => Some(Type::UInt32),
+            "u64" => Some(Type::UInt64),
+            "f" | "f32" => Some(Type::Float32),
+            "f64" => Some(Type::Float),
+            _ => None,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || matches!(self, Type::Float | Type::Float32)
+    }
+
+    // what `print` and interpolated strings can show, a `Bool` as `true` or
+    // `false`.
+    pub fn is_printable(&self) -> bool {
+        self.is_numeric() || matches!(self, Type::Bool | Type::Char | Type::String)
+    }
+
+    // whether a `Ptr` can point to one. a string is let go of and an array
+    // copied behind the scenes, which a pointer to them would get around.
+    pub fn is_pointable(&self) -> bool {
+        match self {
+            Type::String | Type::Array(..) => false,
+            Type::Pointer(ty) => ty.is_pointable(),
+            _ => true,
+        }
+    }
+
+    // one of the types with the size in its name, `Int8` but not `Int`.
+    pub fn is_sized(&self) -> bool {
+        *self != Type::Int && self.is_integer() || *self == Type::Float32
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.range().is_some()
+    }
+
+    // how many bits an integer type is stored in.
+    pub fn bits(&self) -> Option<u32> {
+        let (min, max) = self.range()?;
+
+        Some((max + 1).trailing_zeros() + (min < 0) as u32)
+    }
+
+    // the smallest and largest values an integer type holds.
+    pub fn range(&self) -> Option<(i128, i128)> {
+        Some(match self {
+            Type::Int | Type::Int32 => (i32::MIN as i128, i32::MAX as i128),
+            Type::Int8 => (i8::MIN as i128, i8::MAX as i128),
+            Type::Int16 => (i16::MIN as i128, i16::MAX as i128),
+            Type::Int64 => (i64::MIN as i128, i64::MAX as i128),
+            Type::UInt8 => (0, u8::MAX as i128),
+            Type::UInt16 => (0, u16::MAX as i128),
+            Type::UInt32 => (0, u32::MAX as i128),
+            Type::UInt64 => (0, u64::MAX as i128),
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::Int8 => write!(f, "Int8"),
+            Type::Int16 => write!(f, "Int16"),
+            Type::Int32 => write!(f, "Int32"),
+            Type::Int64 => write!(f, "Int64"),
+            Type::UInt8 => write!(f, "UInt8"),
+            Type::UInt16 => write!(f, "UInt16"),
+            Type::UInt32 => write!(f, "UInt32"),
+            Type::UInt64 => write!(f, "UInt64"),
+            Type::Float32 => write!(f, "Float32"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Char => write!(f, "Char"),
+            Type::String => write!(f, "String"),
+            Type::Array(element, len) => write!(f, "[{}; {}]", element, len),
+            Type::Pointer(ty) => write!(f, "Ptr<{}>", ty),
+            Type::Opaque(name) => write!(f, "{}", name),
+            Type::Void => write!(f, "Void"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypedExpr {
+    Binary {
+        lhs: Box<TypedExpr>,
+        op: Token,
+        rhs: Box<TypedExpr>,
+        ty: Type,
+    },
+    Unary {
+        op: Token,
+        operand: Box<TypedExpr>,
+        ty: Type,
+    },
+    Call {
+        name: String,
+        args: Vec<TypedExpr>,
+        ty: Type,
+    },
+    // `value` converted to `ty`, written out with `as` or for `Int`s widened
+    // to `Float` and literals that are used as one of the sized types.
+    Cast {
+        value: Box<TypedExpr>,
+        ty: Type,
+    },
+    // a call to a function from the prelude.
+    Builtin {
+        builtin: Builtin,
+        args: Vec<TypedExpr>,
+        ty: Type,
+    },
+    If {
+        condition: Box<TypedExpr>,
+        consequence: Box<TypedExpr>,
+        alternative: Box<TypedExpr>,
+        ty: Type,
+    },
+    // the first arm whose pattern is equal to `subject` gives the value, and
+    // `default` does when none are. a `match` on `true` and `false` without
+    // a `_` has its last arm as the default.
+    Match {
+        subject: Box<TypedExpr>,
+        arms: Vec<(TypedExpr, TypedExpr)>,
+        default: Box<TypedExpr>,
+        ty: Type,
+    },
+    Array {
+        elements: Vec<TypedExpr>,
+        ty: Type,
+    },
+    Index {
+        target: Box<TypedExpr>,
+        index: Box<TypedExpr>,
+        ty: Type,
+    },
+    Identifier {
+        name: String,
+        ty: Type,
+    },
+    // `&name`, a pointer to the variable `name`.
+    AddressOf {
+        name: String,
+        ty: Type,
+    },
+    // `*pointer`, what it points at.
+    Deref {
+        pointer: Box<TypedExpr>,
+        ty: Type,
+    },
+    Number(String),
+    Float(String),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Interpolation(Vec<TypedPart>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypedPart {
+    Text(String),
+    Expr(TypedExpr),
+}
+
+impl TypedExpr {
+    pub fn ty(&self) -> Type {
+        match self {
+            TypedExpr::Binary { ty, .. }
+            | TypedExpr::Unary { ty, .. }
+            | TypedExpr::Cast { ty, .. }
+            | TypedExpr::Call { ty, .. }
+            | TypedExpr::Builtin { ty, .. }
+            | TypedExpr::If { ty, .. }
+            | TypedExpr::Match { ty, .. }
+            | TypedExpr::Array { ty, .. }
+            | TypedExpr::Index { ty, .. }
+            | TypedExpr::Identifier { ty, .. }
+            | TypedExpr::AddressOf { ty, .. }
+            | TypedExpr::Deref { ty, .. } => ty.clone(),
+            TypedExpr::Number(_) => Type::Int,
+            TypedExpr::Float(_) => Type::Float,
+            TypedExpr::Bool(_) => Type::Bool,
+            TypedExpr::Char(_) => Type::Char,
+            TypedExpr::String(_) | TypedExpr::Interpolation(_) => Type::String,
+        }
+    }
+}
+
+// what a function's attributes ask for, once they've been checked.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FunctionAttribute {
+    // run by `uma test`.
+    Test,
+    // made a `static inline` function in C.
+    Inline,
+    // warned about wherever it's called, with why when it says.
+    Deprecated(Option<String>),
+    // kept under its own name even when nothing calls it.
+    Export,
+}
+
+// the statements that can end up as code carry the span they were written
+// at, so a backend can point back at the source. it's the default for the
+// ones the checker adds itself, like the `return 0` at the end of `main`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypedStmt {
+    Variable {
+        name: String,
+        value: TypedExpr,
+        is_mut: bool,
+        ty: Type,
+        span: Span,
+    },
+    // `value` is always a literal, the constant already worked out.
+    Const {
+        name: String,
+        value: TypedExpr,
+        ty: Type,
+    },
+    Function {
+        name: String,
+        return_type: Type,
+        args: Vec<(String, Type)>,
+        body: Vec<TypedStmt>,
+        attributes: Vec<FunctionAttribute>,
+        span: Span,
+    },
+    // `arity` only counts the arguments before a `...`.
+    External {
+        name: String,
+        header: String,
+        links: Vec<String>,
+        arity: usize,
+        is_varadic: bool,
+    },
+    // an `@opaque` type, declared by `header` or by nothing it's told of.
+    Opaque {
+        name: String,
+        header: Option<String>,
+    },
+    If {
+        condition: TypedExpr,
+        consequence: Vec<TypedStmt>,
+        alternative: Option<Box<TypedStmt>>,
+        span: Span,
+    },
+    For {
+        iterator: String,
+        start: TypedExpr,
+        end: TypedExpr,
+        body: Vec<TypedStmt>,
+        span: Span,
+    },
+    ForEach {
+        iterator: String,
+        iterable: TypedExpr,
+        body: Vec<TypedStmt>,
+        span: Span,
+    },
+    Block(Vec<TypedStmt>, Span),
+    // `value` is the whole new value, `x + 1` for `x += 1`, with `op` set
+    // when it was written as a compound assignment.
+    Assignment {
+        name: String,
+        value: TypedExpr,
+        op: Option<Token>,
+        span: Span,
+    },
+    // nothing for a bare `return`.
+    Return(Option<TypedExpr>, Span),
+    Expr(TypedExpr, Span),
+}
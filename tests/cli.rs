@@ -0,0 +1,103 @@
+use std::{
+    env, fs,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+// diagnostics go to stderr, so the C written to stdout with `-o -` can be
+// piped straight into a C compiler.
+#[test]
+fn warnings_stay_out_of_emitted_c() {
+    let input = env::temp_dir().join(format!("uma-cli-{}.uma", std::process::id()));
+    fs::write(
+        &input,
+        "func main(): int {\n    let unused = 1;\n    return 0;\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_uma"))
+        .args(["build", "--emit=c", "-o", "-"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    fs::remove_file(&input).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("Variable `unused` is never used"),
+        "{}",
+        stderr
+    );
+    assert!(!stdout.contains("warning"), "{}", stdout);
+
+    // where there's a C compiler, it has to take the output as it is.
+    let Ok(mut cc) = Command::new("cc")
+        .args(["-x", "c", "-fsyntax-only", "-"])
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return;
+    };
+
+    cc.stdin
+        .take()
+        .unwrap()
+        .write_all(stdout.as_bytes())
+        .unwrap();
+    assert!(cc.wait().unwrap().success());
+}
+
+// a check that fails in a program run with the JIT panics the way the built
+// program would.
+#[cfg(feature = "jit")]
+#[test]
+fn jit_panics_like_the_runtime() {
+    let input = env::temp_dir().join(format!("uma-jit-{}.uma", std::process::id()));
+    fs::write(
+        &input,
+        "func main(): void {\n    let xs = [1, 2];\n    println(\"before\");\n    println(xs[arg_count() + 1]);\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_uma"))
+        .args(["run", "--backend=jit"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    fs::remove_file(&input).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert_eq!(output.status.code(), Some(101), "{}", stderr);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "before\n");
+    assert!(
+        stderr.contains("panic: index 2 out of bounds for length 2 at "),
+        "{}",
+        stderr
+    );
+}
+
+// `uma test` runs the tests in place of `main`, which doesn't leave what
+// only `main` calls unused.
+#[test]
+fn tests_leave_what_main_calls_alone() {
+    let input = env::temp_dir().join(format!("uma-test-{}.uma", std::process::id()));
+    fs::write(
+        &input,
+        "func greet(): void { println(\"hi\"); }\n\nfunc main(): int {\n    greet();\n    return 0;\n}\n\nfunc adds() @test {\n    assert(1 + 1 == 2);\n}\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_uma"))
+        .arg("test")
+        .arg(&input)
+        .output()
+        .unwrap();
+    fs::remove_file(&input).unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("never called"), "{}", stderr);
+}
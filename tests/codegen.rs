@@ -0,0 +1,158 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+use uma::{interpreter::Interpreter, Artifact, CompileOptions};
+
+// every program in `tests/codegen` is compiled to C and compared with the
+// `.c` next to it. where there's a C compiler it's also run, and what it
+// prints compared with the `.out`. `UMA_BLESS=1` writes over them instead.
+#[test]
+fn codegen_snapshots() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/codegen");
+    let bless = env::var_os("UMA_BLESS").is_some();
+
+    let mut programs = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "uma"))
+        .collect::<Vec<_>>();
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    let mut failures = vec![];
+
+    for program in &programs {
+        let name = program.file_stem().unwrap().to_string_lossy();
+        let source = fs::read_to_string(program).unwrap();
+
+        let c = match uma::generate_c(&source) {
+            Ok(c) => c,
+            Err(err) => panic!("{} doesn't compile: {:?}", name, err.diagnostics),
+        };
+
+        if let Some(failure) = compare(&program.with_extension("c"), &c, bless) {
+            failures.push(format!("{}.c {}", name, failure));
+        }
+
+        // the interpreter has to print the same as the built program, and it
+        // doesn't need a C compiler to.
+        let interpreted = interpret(&source, &name);
+
+        if let Some(failure) = compare(&program.with_extension("out"), &interpreted, false) {
+            failures.push(format!("{}.out with the interpreter {}", name, failure));
+        }
+
+        #[cfg(feature = "jit")]
+        if let Some(failure) = compare(&program.with_extension("out"), &jit(program), false) {
+            failures.push(format!("{}.out with the JIT {}", name, failure));
+        }
+
+        let Some(stdout) = run(&source, &name) else {
+            continue;
+        };
+
+        if let Some(failure) = compare(&program.with_extension("out"), &stdout, bless) {
+            failures.push(format!("{}.out {}", name, failure));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{}\n\nrun with UMA_BLESS=1 to accept the changes",
+        failures.join("\n\n")
+    );
+}
+
+// why `actual` isn't what's in `expected`, or nothing when it is.
+fn compare(expected: &PathBuf, actual: &str, bless: bool) -> Option<String> {
+    if bless {
+        fs::write(expected, actual).unwrap();
+        return None;
+    }
+
+    match fs::read_to_string(expected) {
+        Ok(expected) if expected == actual => None,
+        Ok(expected) => Some(diff(&expected, actual)),
+        Err(_) => Some("is missing".to_string()),
+    }
+}
+
+// the first line the two differ on.
+fn diff(expected: &str, actual: &str) -> String {
+    let (expected, actual) = (expected.lines(), actual.lines());
+    let lines = expected
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(actual.map(Some).chain(std::iter::repeat(None)))
+        .take_while(|lines| *lines != (None, None));
+
+    for (line, (expected, actual)) in lines.enumerate() {
+        if expected != actual {
+            return format!(
+                "differs on line {}:\n  expected: {}\n  actual:   {}",
+                line + 1,
+                expected.unwrap_or("<end>"),
+                actual.unwrap_or("<end>"),
+            );
+        }
+    }
+
+    "differs in its line endings".to_string()
+}
+
+// what the program prints, or nothing when there's no C compiler to build
+// it with.
+fn run(source: &str, name: &str) -> Option<String> {
+    let output = env::temp_dir().join(format!("uma_codegen_{}", name));
+
+    let path = match uma::compile_str(source, &CompileOptions::new(&output)) {
+        Ok(Artifact::Executable(path)) => path,
+        Ok(artifact) => panic!("expected an executable, found {:?}", artifact),
+        Err(err) if err.diagnostics[0].message == "No C compiler found" => return None,
+        Err(err) => panic!("{} doesn't build: {:?}", name, err.diagnostics),
+    };
+
+    let output = Command::new(&path).output().unwrap();
+    fs::remove_file(path).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{} failed: {}",
+        name,
+        output.status
+    );
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+// what the program prints when it's run with the interpreter instead.
+fn interpret(source: &str, name: &str) -> String {
+    let (typed, _) = uma::check_str(source, false).unwrap();
+    let mut interpreter = Interpreter::new();
+
+    if let Err(err) = interpreter
+        .run(&typed)
+        .and_then(|_| interpreter.call("main", vec![]))
+    {
+        panic!("{} fails in the interpreter: {}", name, err.message);
+    }
+
+    interpreter.take_output()
+}
+
+// what the program prints when it's run with `uma run --backend=jit`,
+// without the checks the built program is left without too.
+#[cfg(feature = "jit")]
+fn jit(program: &PathBuf) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_uma"))
+        .args(["run", "--backend=jit", "--checks=off"])
+        .arg(program)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{} fails with the JIT: {}",
+        program.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
@@ -0,0 +1,106 @@
+#include <stdio.h>
+
+// the runtime every program is linked against. the generated code only
+// needs these declarations, the definitions are compiled on their own.
+#include <stdarg.h>
+#include <stddef.h>
+
+// a `String`. `data` always ends with a NUL, so it can be handed to C as it
+// is. `refs` counts the references to a string on the heap, and is NULL for
+// one that's never freed, like a literal.
+typedef struct {
+    long long len;
+    const char* data;
+    long long* refs;
+} UmaString;
+
+// a string literal, which lives as long as the program does.
+#define UMA_STR(s) ((UmaString){sizeof(s) - 1, s, NULL})
+
+// prints the message, formatted like printf, and exits with status 101.
+void uma_panic(const char* format, ...);
+// what a failed `assert` panics with. `source` is the condition as it was
+// written, and `message` is NULL when it wasn't given one.
+void uma_assert(const char* source, const char* message, const char* file, int line);
+// `malloc`, except it panics instead of returning NULL.
+void* uma_alloc(size_t size);
+// `index`, if it's in bounds of an array of `len` elements. `file` and `line`
+// are where the indexing was written.
+int uma_bounds(long long index, int len, const char* file, int line);
+
+// the arithmetic on signed integers of `bits` bits, which panics instead of
+// overflowing or dividing by zero.
+long long uma_add(long long a, long long b, int bits, const char* file, int line);
+long long uma_sub(long long a, long long b, int bits, const char* file, int line);
+long long uma_mul(long long a, long long b, int bits, const char* file, int line);
+long long uma_div(long long a, long long b, int bits, const char* file, int line);
+long long uma_mod(long long a, long long b, int bits, const char* file, int line);
+// and on unsigned ones, which only have dividing by zero to worry about.
+unsigned long long uma_udiv(unsigned long long a, unsigned long long b, const char* file, int line);
+unsigned long long uma_umod(unsigned long long a, unsigned long long b, const char* file, int line);
+
+// `min` and `max` on integers, which `<math.h>` only has for floats.
+long long uma_min(long long a, long long b);
+long long uma_max(long long a, long long b);
+unsigned long long uma_umin(unsigned long long a, unsigned long long b);
+unsigned long long uma_umax(unsigned long long a, unsigned long long b);
+
+// a string C made, which is never freed.
+UmaString uma_cstr(const char* data);
+// another reference to `s`.
+UmaString uma_retain(UmaString s);
+// drops a reference to `s`, freeing it with the last one. it returns an
+// empty string to put in its place.
+UmaString uma_release(UmaString s);
+// drops a reference to `old`, returning `next` to put in its place.
+UmaString uma_replace(UmaString old, UmaString next);
+// less than, equal to or more than 0 as `a` sorts before, with or after `b`.
+int uma_compare(UmaString a, UmaString b);
+// the byte at `index`, as a string of its own. `file` and `line` are where the
+// indexing was written.
+UmaString uma_string_at(UmaString s, long long index, const char* file, int line);
+UmaString uma_concat(UmaString a, UmaString b);
+// a line from stdin, without its newline, after `prompt` if it isn't NULL.
+// it's empty once there's nothing left to read.
+UmaString uma_input(const char* prompt);
+// the number `s` spells out, panicking if it doesn't.
+int uma_parse_int(UmaString s, const char* file, int line);
+double uma_parse_float(UmaString s, const char* file, int line);
+// everything in the file at `path`, panicking if it can't be read.
+UmaString uma_read_file(const char* path, const char* file, int line);
+// the same, except it gives `fallback` instead of panicking.
+UmaString uma_read_file_or(const char* path, UmaString fallback);
+// writes `contents` to the file at `path`, replacing what was there or adding
+// to its end if `append` is set. it returns 0 if it couldn't.
+int uma_write_file(const char* path, UmaString contents, int append);
+// keeps the program's arguments for `arg_count` and `arg`, it's the first
+// thing `main` does.
+void uma_set_args(int argc, char** argv);
+int uma_arg_count(void);
+// the argument at `index`, if there's one there.
+UmaString uma_arg(long long index, const char* file, int line);
+// a copy of the environment variable `name`, empty if it isn't set.
+UmaString uma_env(const char* name);
+void uma_set_env(const char* name, const char* value, const char* file, int line);
+UmaString uma_format(const char* format, ...);
+
+UmaString greet(UmaString name);
+int main(int uma_argc, char** uma_argv);
+
+UmaString greet(UmaString name) {
+    return uma_concat(UMA_STR("hello "), name);
+}
+
+int main(int uma_argc, char** uma_argv) {
+    UmaString uma_str_0 = {0};
+    int uma_result;
+    uma_set_args(uma_argc, uma_argv);
+    UmaString word = UMA_STR("um");
+    word = uma_replace(word, uma_concat(word, UMA_STR("a")));
+    printf("%s %d\n", (uma_str_0 = uma_replace(uma_str_0, greet(word))).data, (int)word.len);
+    uma_str_0 = uma_release(uma_str_0);
+    printf("%s!\n", word.data);
+    uma_result = 0;
+    uma_release(word);
+    return uma_result;
+}